@@ -0,0 +1,112 @@
+/*!
+Fuzzes a toy target for a few iterations, then exports the genealogy of the corpus.
+
+Usage: `corpus_genealogy <out.dot> <out.json> [iterations]`
+
+Render the graph with e.g. `dot -Tsvg out.dot -o out.svg`.
+*/
+use std::{env, fs, process};
+
+use libafl::{
+    corpus::{CorpusGenealogy, InMemoryCorpus},
+    events::SimpleEventManager,
+    executors::{ExitKind, InProcessExecutor},
+    feedback_or,
+    feedbacks::{CrashFeedback, MaxMapFeedback, ProvenanceFeedback},
+    fuzzer::{Fuzzer, StdFuzzer},
+    generators::RandPrintablesGenerator,
+    inputs::{BytesInput, HasTargetBytes},
+    monitors::SimpleMonitor,
+    mutators::{havoc_mutations, StdScheduledMutator},
+    observers::StdMapObserver,
+    schedulers::QueueScheduler,
+    stages::StdMutationalStage,
+    state::{HasCorpus, StdState},
+    Error,
+};
+use libafl_bolts::{current_nanos, nonzero, rands::StdRand, tuples::tuple_list, AsSlice};
+
+/// The number of fuzzing iterations if none is given
+const DEFAULT_ITERATIONS: u64 = 1000;
+
+pub fn main() -> Result<(), Error> {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 3 {
+        eprintln!("Usage: {} <out.dot> <out.json> [iterations]", args[0]);
+        process::exit(1);
+    }
+    let iterations = match args.get(3) {
+        Some(iterations) => iterations
+            .parse()
+            .map_err(|err| Error::illegal_argument(format!("Invalid iterations: {err}")))?,
+        None => DEFAULT_ITERATIONS,
+    };
+
+    // Coverage map with explicit assignments due to the lack of instrumentation
+    let mut signals = [0_u8; 16];
+    let signals_len = signals.len();
+    let signals_ptr = signals.as_mut_ptr();
+    let signals_set = |idx: usize| unsafe { signals_ptr.add(idx).write(1) };
+
+    // A toy target that never crashes, the deeper the branch the longer the lineage
+    let mut harness = |input: &BytesInput| {
+        let target = input.target_bytes();
+        let buf = target.as_slice();
+        signals_set(0);
+        for (idx, expected) in b"genealogy".iter().enumerate().take(signals_len - 1) {
+            if buf.get(idx) != Some(expected) {
+                break;
+            }
+            signals_set(idx + 1);
+        }
+        ExitKind::Ok
+    };
+
+    let observer = unsafe { StdMapObserver::from_mut_ptr("signals", signals_ptr, signals_len) };
+
+    // The provenance feedback never makes an input interesting, it only records where it comes from
+    let mut feedback = feedback_or!(MaxMapFeedback::new(&observer), ProvenanceFeedback::new());
+    let mut objective = CrashFeedback::new();
+
+    let mut state = StdState::new(
+        StdRand::with_seed(current_nanos()),
+        InMemoryCorpus::new(),
+        InMemoryCorpus::new(),
+        &mut feedback,
+        &mut objective,
+    )?;
+
+    let mon = SimpleMonitor::new(|s| println!("{s}"));
+    let mut mgr = SimpleEventManager::new(mon);
+    let scheduler = QueueScheduler::new();
+    let mut fuzzer = StdFuzzer::new(scheduler, feedback, objective);
+
+    let mut executor = InProcessExecutor::new(
+        &mut harness,
+        tuple_list!(observer),
+        &mut fuzzer,
+        &mut state,
+        &mut mgr,
+    )?;
+
+    // The initial inputs are the roots of the genealogy
+    let mut generator = RandPrintablesGenerator::new(nonzero!(32));
+    state.generate_initial_inputs(&mut fuzzer, &mut executor, &mut generator, &mut mgr, 8)?;
+
+    // The provenance records the havoc mutations applied to each new entry, in order
+    let mutator = StdScheduledMutator::new(havoc_mutations());
+    let mut stages = tuple_list!(StdMutationalStage::new(mutator));
+
+    fuzzer.fuzz_loop_for(&mut stages, &mut executor, &mut state, &mut mgr, iterations)?;
+
+    let genealogy = CorpusGenealogy::new(state.corpus())?;
+    fs::write(&args[1], genealogy.to_dot())?;
+    fs::write(&args[2], genealogy.to_json()?)?;
+    println!(
+        "Wrote the genealogy of {} entries to {} and {}",
+        genealogy.nodes().len(),
+        args[1],
+        args[2]
+    );
+    Ok(())
+}
@@ -0,0 +1,36 @@
+/*!
+Exports a [`SqliteCorpus`] to a directory, in the layout of the `OnDiskCorpus`.
+
+Usage: `sqlite_export <corpus.db> <out_dir> [--no-metadata]`
+
+The metadata of each testcase is written as pretty JSON to a `.<testcase>.metadata` file,
+unless `--no-metadata` is given.
+*/
+use std::{env, process};
+
+use libafl::{
+    corpus::{ondisk::OnDiskMetadataFormat, Corpus, SqliteCorpus},
+    inputs::BytesInput,
+    Error,
+};
+
+pub fn main() -> Result<(), Error> {
+    let args: Vec<String> = env::args().collect();
+    let (db_path, out_dir, with_metadata) = match args.as_slice() {
+        [_, db_path, out_dir] => (db_path, out_dir, true),
+        [_, db_path, out_dir, flag] if flag == "--no-metadata" => (db_path, out_dir, false),
+        _ => {
+            eprintln!("Usage: {} <corpus.db> <out_dir> [--no-metadata]", args[0]);
+            process::exit(1);
+        }
+    };
+
+    let corpus = SqliteCorpus::<BytesInput>::new(db_path)?;
+    let meta_format = with_metadata.then_some(OnDiskMetadataFormat::JsonPretty);
+    corpus.export_to_dir(out_dir, meta_format.as_ref())?;
+    println!(
+        "Exported {} testcases from {db_path} to {out_dir}",
+        corpus.count_all()
+    );
+    Ok(())
+}
@@ -0,0 +1,467 @@
+//! The [`DedupCorpus`] wraps any other [`Corpus`] and makes sure each input is stored only once.
+//!
+//! Testcases are addressed by the hash of their (serialized) input.
+//! Adding a testcase whose input is already part of the corpus returns the [`CorpusId`] of the existing entry,
+//! and merges the new metadata into it, instead of creating a byte-identical duplicate.
+//! The fuzzer asks [`Corpus::duplicate_of`] before adding an input, so duplicates never reach the scheduler.
+
+use alloc::vec::Vec;
+use core::{
+    cell::{Ref, RefCell, RefMut},
+    mem,
+};
+
+use hashbrown::HashMap;
+use libafl_bolts::hash_std;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::{Corpus, CorpusId, HasTestcase, Testcase},
+    Error, HasMetadata,
+};
+
+/// A [`Corpus`] wrapper, deduplicating [`Testcase`]s by their input.
+///
+/// Call [`Corpus::add`] with an input already present in an enabled entry (or [`Corpus::add_disabled`] with one
+/// present in a disabled entry), and the [`CorpusId`] of the existing [`Testcase`] will be returned.
+/// Metadata of the new [`Testcase`] is moved to the existing one, unless metadata of the same type is already present.
+/// The merged metadata of enabled entries is written back through [`Corpus::replace`], so on-disk corpora store it too.
+#[derive(Default, Serialize, Deserialize, Clone, Debug)]
+pub struct DedupCorpus<C> {
+    inner: C,
+    /// Maps the hash of each input to the [`CorpusId`]s storing an input with this hash
+    ids_by_hash: HashMap<u64, Vec<CorpusId>>,
+    /// Maps each [`CorpusId`] back to the hash of its input, for removals
+    hashes_by_id: HashMap<CorpusId, u64>,
+}
+
+impl<C> DedupCorpus<C>
+where
+    C: Corpus,
+    C::Input: Serialize + Clone,
+{
+    /// Creates a new [`DedupCorpus`], wrapping the given `inner` corpus.
+    ///
+    /// All [`Testcase`]s already present in `inner` (enabled and disabled) are indexed,
+    /// which requires loading each of their inputs once.
+    pub fn new(inner: C) -> Result<Self, Error> {
+        let mut corpus = Self {
+            inner,
+            ids_by_hash: HashMap::default(),
+            hashes_by_id: HashMap::default(),
+        };
+        for nth in 0..corpus.inner.count_all() {
+            let id = corpus.inner.nth_from_all(nth);
+            let hash = {
+                let mut testcase = corpus.inner.get_from_all(id)?.borrow_mut();
+                hash_std(&Self::serialize_input(testcase.load_input(&corpus.inner)?)?)
+            };
+            // If `inner` already contains duplicates, the first one is found first
+            corpus.register(id, hash);
+        }
+        Ok(corpus)
+    }
+
+    /// Returns the [`CorpusId`] of the [`Testcase`] storing the given input, if any, enabled or disabled
+    pub fn id_of(&self, input: &C::Input) -> Result<Option<CorpusId>, Error> {
+        let bytes = Self::serialize_input(input)?;
+        let hash = hash_std(&bytes);
+        if let Some(id) = self.find(hash, &bytes, true)? {
+            return Ok(Some(id));
+        }
+        self.find(hash, &bytes, false)
+    }
+
+    /// Fetch the inner corpus
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Serializes the input, for hashing and comparing it
+    fn serialize_input(input: &C::Input) -> Result<Vec<u8>, Error> {
+        Ok(postcard::to_allocvec(input)?)
+    }
+
+    /// Serializes the input of the given [`Testcase`], which has to be loaded
+    fn serialize_testcase(testcase: &Testcase<C::Input>) -> Result<Vec<u8>, Error> {
+        let Some(input) = testcase.input() else {
+            return Err(Error::illegal_argument(
+                "No input available for testcase. Could not deduplicate it.",
+            ));
+        };
+        Self::serialize_input(input)
+    }
+
+    /// Looks for an enabled (or disabled) entry storing exactly the serialized input `bytes`
+    fn find(&self, hash: u64, bytes: &[u8], enabled: bool) -> Result<Option<CorpusId>, Error> {
+        let Some(ids) = self.ids_by_hash.get(&hash) else {
+            return Ok(None);
+        };
+        for &id in ids {
+            if self.inner.get(id).is_ok() != enabled {
+                continue;
+            }
+            // Compare the inputs themselves, a hash collision must not drop a distinct input
+            let mut testcase = self.inner.get_from_all(id)?.borrow_mut();
+            if Self::serialize_input(testcase.load_input(&self.inner)?)? == bytes {
+                return Ok(Some(id));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Moves the metadata of `testcase` into the existing entry `id`
+    fn merge_into(&mut self, id: CorpusId, testcase: &mut Testcase<C::Input>) -> Result<(), Error> {
+        let merged = {
+            let mut existing = self.inner.get_from_all(id)?.borrow_mut();
+            let metadata_count = existing.metadata_map().len();
+            existing
+                .metadata_map_mut()
+                .merge_missing(mem::take(testcase.metadata_map_mut()));
+            let mut changed = existing.metadata_map().len() != metadata_count;
+            if existing.exec_time().is_none() && testcase.exec_time().is_some() {
+                *existing.exec_time_mut() = *testcase.exec_time();
+                changed = true;
+            }
+            if !changed {
+                return Ok(());
+            }
+            self.inner.load_input_into(&mut existing)?;
+            existing.clone()
+        };
+        // Disabled entries cannot be replaced, their merged metadata stays in memory
+        if self.inner.get(id).is_ok() {
+            self.inner.replace(id, merged)?;
+        }
+        Ok(())
+    }
+
+    /// Records the hash of a newly added [`Testcase`]
+    fn register(&mut self, id: CorpusId, hash: u64) {
+        self.ids_by_hash.entry(hash).or_default().push(id);
+        self.hashes_by_id.insert(id, hash);
+    }
+
+    /// Forgets the hash of a removed or replaced [`Testcase`]
+    fn unregister(&mut self, id: CorpusId) {
+        if let Some(hash) = self.hashes_by_id.remove(&id) {
+            if let Some(ids) = self.ids_by_hash.get_mut(&hash) {
+                ids.retain(|other| *other != id);
+                if ids.is_empty() {
+                    self.ids_by_hash.remove(&hash);
+                }
+            }
+        }
+    }
+}
+
+impl<C> Corpus for DedupCorpus<C>
+where
+    C: Corpus,
+    C::Input: Serialize + Clone,
+{
+    type Input = C::Input;
+
+    /// Returns the number of all enabled entries
+    #[inline]
+    fn count(&self) -> usize {
+        self.inner.count()
+    }
+
+    /// Returns the number of all disabled entries
+    fn count_disabled(&self) -> usize {
+        self.inner.count_disabled()
+    }
+
+    /// Returns the number of elements including disabled entries
+    #[inline]
+    fn count_all(&self) -> usize {
+        self.inner.count_all()
+    }
+
+    /// Add an enabled testcase to the corpus and return its index.
+    /// If the input is already present in an enabled entry, the existing index is returned instead.
+    fn add(&mut self, mut testcase: Testcase<Self::Input>) -> Result<CorpusId, Error> {
+        let bytes = Self::serialize_testcase(&testcase)?;
+        let hash = hash_std(&bytes);
+        if let Some(id) = self.find(hash, &bytes, true)? {
+            self.merge_into(id, &mut testcase)?;
+            return Ok(id);
+        }
+        let id = self.inner.add(testcase)?;
+        self.register(id, hash);
+        Ok(id)
+    }
+
+    /// Add a disabled testcase to the corpus and return its index.
+    /// If the input is already present in a disabled entry, the existing index is returned instead.
+    fn add_disabled(&mut self, mut testcase: Testcase<Self::Input>) -> Result<CorpusId, Error> {
+        let bytes = Self::serialize_testcase(&testcase)?;
+        let hash = hash_std(&bytes);
+        if let Some(id) = self.find(hash, &bytes, false)? {
+            self.merge_into(id, &mut testcase)?;
+            return Ok(id);
+        }
+        let id = self.inner.add_disabled(testcase)?;
+        self.register(id, hash);
+        Ok(id)
+    }
+
+    /// Replaces the testcase at the given idx
+    fn replace(
+        &mut self,
+        id: CorpusId,
+        testcase: Testcase<Self::Input>,
+    ) -> Result<Testcase<Self::Input>, Error> {
+        let hash = match testcase.input() {
+            Some(input) => Some(hash_std(&Self::serialize_input(input)?)),
+            None => None,
+        };
+        let entry = self.inner.replace(id, testcase)?;
+        self.unregister(id);
+        if let Some(hash) = hash {
+            self.register(id, hash);
+        }
+        Ok(entry)
+    }
+
+    /// Removes an entry from the corpus, returning it if it was present; considers both enabled and disabled testcases
+    fn remove(&mut self, id: CorpusId) -> Result<Testcase<Self::Input>, Error> {
+        let testcase = self.inner.remove(id)?;
+        self.unregister(id);
+        Ok(testcase)
+    }
+
+    /// Returns the id of the enabled entry storing the same input
+    fn duplicate_of(&self, input: &Self::Input) -> Result<Option<CorpusId>, Error> {
+        let bytes = Self::serialize_input(input)?;
+        self.find(hash_std(&bytes), &bytes, true)
+    }
+
+    /// Get by id; considers only enabled testcases
+    #[inline]
+    fn get(&self, id: CorpusId) -> Result<&RefCell<Testcase<Self::Input>>, Error> {
+        self.inner.get(id)
+    }
+
+    /// Get by id; considers both enabled and disabled testcases
+    #[inline]
+    fn get_from_all(&self, id: CorpusId) -> Result<&RefCell<Testcase<Self::Input>>, Error> {
+        self.inner.get_from_all(id)
+    }
+
+    /// Current testcase scheduled
+    #[inline]
+    fn current(&self) -> &Option<CorpusId> {
+        self.inner.current()
+    }
+
+    /// Current testcase scheduled (mutable)
+    #[inline]
+    fn current_mut(&mut self) -> &mut Option<CorpusId> {
+        self.inner.current_mut()
+    }
+
+    #[inline]
+    fn next(&self, id: CorpusId) -> Option<CorpusId> {
+        self.inner.next(id)
+    }
+
+    /// Peek the next free corpus id
+    #[inline]
+    fn peek_free_id(&self) -> CorpusId {
+        self.inner.peek_free_id()
+    }
+
+    #[inline]
+    fn prev(&self, id: CorpusId) -> Option<CorpusId> {
+        self.inner.prev(id)
+    }
+
+    #[inline]
+    fn first(&self) -> Option<CorpusId> {
+        self.inner.first()
+    }
+
+    #[inline]
+    fn last(&self) -> Option<CorpusId> {
+        self.inner.last()
+    }
+
+    /// Get the nth corpus id; considers only enabled testcases
+    #[inline]
+    fn nth(&self, nth: usize) -> CorpusId {
+        self.inner.nth(nth)
+    }
+
+    /// Get the nth corpus id; considers both enabled and disabled testcases
+    #[inline]
+    fn nth_from_all(&self, nth: usize) -> CorpusId {
+        self.inner.nth_from_all(nth)
+    }
+
+    #[inline]
+    fn load_input_into(&self, testcase: &mut Testcase<Self::Input>) -> Result<(), Error> {
+        self.inner.load_input_into(testcase)
+    }
+
+    #[inline]
+    fn store_input_from(&self, testcase: &Testcase<Self::Input>) -> Result<(), Error> {
+        self.inner.store_input_from(testcase)
+    }
+}
+
+impl<C> HasTestcase for DedupCorpus<C>
+where
+    C: Corpus,
+    C::Input: Serialize + Clone,
+{
+    fn testcase(&self, id: CorpusId) -> Result<Ref<Testcase<C::Input>>, Error> {
+        Ok(self.get(id)?.borrow())
+    }
+
+    fn testcase_mut(&self, id: CorpusId) -> Result<RefMut<Testcase<C::Input>>, Error> {
+        Ok(self.get(id)?.borrow_mut())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "std")]
+    use std::{env, fs};
+
+    use libafl_bolts::rands::StdRand;
+
+    use super::DedupCorpus;
+    #[cfg(feature = "std")]
+    use crate::corpus::InMemoryOnDiskCorpus;
+    use crate::{
+        corpus::{Corpus, InMemoryCorpus, SchedulerTestcaseMetadata, Testcase},
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::ConstFeedback,
+        fuzzer::{ExecuteInputResult, ExecutionProcessor, StdFuzzer},
+        inputs::BytesInput,
+        schedulers::QueueScheduler,
+        state::{HasCorpus, StdState},
+        HasMetadata,
+    };
+
+    #[test]
+    fn test_dedup_corpus() {
+        let mut corpus = DedupCorpus::new(InMemoryCorpus::<BytesInput>::new()).unwrap();
+
+        let id = corpus
+            .add(Testcase::new(BytesInput::new(b"dedup".to_vec())))
+            .unwrap();
+
+        let mut duplicate = Testcase::new(BytesInput::new(b"dedup".to_vec()));
+        duplicate.add_metadata(SchedulerTestcaseMetadata::new(3));
+        assert_eq!(corpus.add(duplicate).unwrap(), id);
+        assert_eq!(corpus.count(), 1);
+        assert_eq!(
+            corpus
+                .get(id)
+                .unwrap()
+                .borrow()
+                .metadata::<SchedulerTestcaseMetadata>()
+                .unwrap()
+                .depth(),
+            3
+        );
+
+        let other = corpus
+            .add(Testcase::new(BytesInput::new(b"other".to_vec())))
+            .unwrap();
+        assert_ne!(other, id);
+
+        // A disabled entry is never merged into an enabled one
+        let disabled = corpus
+            .add_disabled(Testcase::new(BytesInput::new(b"other".to_vec())))
+            .unwrap();
+        assert_ne!(disabled, other);
+        assert_eq!(
+            corpus
+                .add_disabled(Testcase::new(BytesInput::new(b"other".to_vec())))
+                .unwrap(),
+            disabled
+        );
+        assert_eq!(corpus.count_disabled(), 1);
+
+        corpus.remove(id).unwrap();
+        assert!(corpus
+            .id_of(&BytesInput::new(b"dedup".to_vec()))
+            .unwrap()
+            .is_none());
+        let readded = corpus
+            .add(Testcase::new(BytesInput::new(b"dedup".to_vec())))
+            .unwrap();
+        assert_ne!(readded, id);
+        assert_eq!(corpus.count(), 2);
+    }
+
+    #[test]
+    fn test_dedup_corpus_fuzzer() {
+        let mut feedback = ConstFeedback::new(true);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            DedupCorpus::new(InMemoryCorpus::<BytesInput>::new()).unwrap(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), feedback, objective);
+        let mut manager = NopEventManager::new();
+
+        let input = BytesInput::new(b"dedup".to_vec());
+        let (res, id) = fuzzer
+            .evaluate_execution(
+                &mut state,
+                &mut manager,
+                input.clone(),
+                &(),
+                &ExitKind::Ok,
+                false,
+            )
+            .unwrap();
+        assert_eq!(res, ExecuteInputResult::Corpus);
+        assert!(id.is_some());
+
+        // The duplicate is neither added nor reported to the scheduler or the other clients
+        let (res, id) = fuzzer
+            .evaluate_execution(&mut state, &mut manager, input, &(), &ExitKind::Ok, false)
+            .unwrap();
+        assert_eq!(res, ExecuteInputResult::None);
+        assert!(id.is_none());
+        assert_eq!(state.corpus().count(), 1);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_dedup_corpus_on_disk() {
+        let dir = env::temp_dir().join("libafl_dedup_corpus_on_disk");
+        drop(fs::remove_dir_all(&dir));
+        let mut corpus =
+            DedupCorpus::new(InMemoryOnDiskCorpus::<BytesInput>::new(&dir).unwrap()).unwrap();
+
+        let id = corpus
+            .add(Testcase::new(BytesInput::new(b"dedup".to_vec())))
+            .unwrap();
+        let filename = corpus.get(id).unwrap().borrow().filename().clone().unwrap();
+        let metadata_path = dir.join(format!(".{filename}.metadata"));
+        let before = fs::read_to_string(&metadata_path).unwrap();
+
+        let mut duplicate = Testcase::new(BytesInput::new(b"dedup".to_vec()));
+        duplicate.add_metadata(SchedulerTestcaseMetadata::new(3));
+        assert_eq!(corpus.add(duplicate).unwrap(), id);
+
+        // The merged metadata made it to disk
+        let after = fs::read_to_string(&metadata_path).unwrap();
+        assert_ne!(before, after);
+        assert_eq!(fs::read(dir.join(&filename)).unwrap(), b"dedup".to_vec());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,349 @@
+//! The genealogy of a [`Corpus`]: which entry was derived from which, by which mutations.
+//!
+//! The lineage is read from the [`ProvenanceMetadata`] attached by the [`crate::feedbacks::ProvenanceFeedback`],
+//! falling back to the parent set by the scheduler for entries without one. Export it as a DOT graph or as JSON.
+//! The `corpus_genealogy` example fuzzes a toy target and writes both.
+
+use alloc::{
+    borrow::Cow,
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::{Corpus, CorpusId},
+    feedbacks::ProvenanceMetadata,
+    Error, HasMetadata,
+};
+
+/// A corpus entry in the [`CorpusGenealogy`]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenealogyNode {
+    /// The id of this entry
+    pub id: CorpusId,
+    /// The entry it was derived from, `None` for the roots
+    pub parent_id: Option<CorpusId>,
+    /// The entries derived from this one
+    pub children: Vec<CorpusId>,
+    /// The filename of this entry, if any
+    pub filename: Option<String>,
+    /// How this entry was found, if the [`crate::feedbacks::ProvenanceFeedback`] recorded it
+    pub provenance: Option<ProvenanceMetadata>,
+    /// The mutations applied to the parent, in the order they were applied.
+    ///
+    /// Taken from [`ProvenanceMetadata::mutations`], or the mutator of the stage if it does not report them.
+    pub mutations: Vec<Cow<'static, str>>,
+}
+
+/// The tree of the entries of a [`Corpus`], from their parents to their children.
+///
+/// An entry whose parent is not in the corpus anymore is a root.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CorpusGenealogy {
+    nodes: Vec<GenealogyNode>,
+}
+
+impl CorpusGenealogy {
+    /// Collects the genealogy of the enabled entries of `corpus`.
+    pub fn new<C>(corpus: &C) -> Result<Self, Error>
+    where
+        C: Corpus,
+    {
+        let mut nodes = Vec::with_capacity(corpus.count());
+        let mut positions = BTreeMap::new();
+        for id in corpus.ids() {
+            let testcase = corpus.get(id)?.borrow();
+            let provenance = testcase.metadata::<ProvenanceMetadata>().ok().cloned();
+            let parent_id = match &provenance {
+                Some(provenance) => provenance.parent_id(),
+                None => testcase.parent_id(),
+            };
+            let mutations = match &provenance {
+                Some(provenance) if !provenance.mutations().is_empty() => {
+                    provenance.mutations().to_vec()
+                }
+                _ => provenance
+                    .as_ref()
+                    .and_then(ProvenanceMetadata::mutator)
+                    .map(|mutator| Cow::Owned(mutator.to_string()))
+                    .into_iter()
+                    .collect(),
+            };
+            positions.insert(id, nodes.len());
+            nodes.push(GenealogyNode {
+                id,
+                parent_id,
+                children: Vec::new(),
+                filename: testcase.filename().clone(),
+                provenance,
+                mutations,
+            });
+        }
+
+        for idx in 0..nodes.len() {
+            let Some(parent_id) = nodes[idx].parent_id else {
+                continue;
+            };
+            if let Some(&parent) = positions.get(&parent_id) {
+                let id = nodes[idx].id;
+                nodes[parent].children.push(id);
+            } else {
+                nodes[idx].parent_id = None;
+            }
+        }
+
+        Ok(Self { nodes })
+    }
+
+    /// All entries, in corpus order
+    #[must_use]
+    pub fn nodes(&self) -> &[GenealogyNode] {
+        &self.nodes
+    }
+
+    /// The entries without a parent in the corpus
+    pub fn roots(&self) -> impl Iterator<Item = &GenealogyNode> {
+        self.nodes.iter().filter(|node| node.parent_id.is_none())
+    }
+
+    /// The genealogy as a graphviz DOT digraph, edges go from the parents to their children.
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph genealogy {\n");
+        for node in &self.nodes {
+            let mut label = node.id.to_string();
+            if let Some(filename) = &node.filename {
+                label.push('\n');
+                label.push_str(filename);
+            }
+            if let Some(provenance) = &node.provenance {
+                if let Some(stage) = provenance.stage() {
+                    label.push('\n');
+                    label.push_str(stage);
+                }
+                if let Some(client_id) = provenance.client_id() {
+                    write!(label, "\nfrom client {}", client_id.0).unwrap();
+                }
+            }
+            if !node.mutations.is_empty() {
+                label.push('\n');
+                label.push_str(&node.mutations.join(", "));
+            }
+            writeln!(dot, "  {} [label=\"{}\"];", node.id, escape_dot(&label)).unwrap();
+        }
+        for node in &self.nodes {
+            for child in &node.children {
+                writeln!(dot, "  {} -> {child};", node.id).unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// The genealogy as JSON, a list of the entries with their parent and children.
+    #[cfg(feature = "std")]
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(self)
+            .map_err(|err| Error::serialize(format!("Failed to json-ify the genealogy: {err:?}")))
+    }
+}
+
+fn escape_dot(label: &str) -> String {
+    let mut escaped = String::with_capacity(label.len());
+    for c in label.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::Cow, vec::Vec};
+
+    use libafl_bolts::{rands::StdRand, tuples::tuple_list, ClientId, Named};
+
+    use super::CorpusGenealogy;
+    use crate::{
+        corpus::{Corpus, CorpusId, InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::{ExitKind, InProcessExecutor},
+        feedback_or,
+        feedbacks::{ConstFeedback, ProvenanceFeedback, ProvenanceMetadata},
+        fuzzer::Fuzzer,
+        inputs::BytesInput,
+        mutators::{
+            mutations::{BitFlipMutator, ByteFlipMutator},
+            StdScheduledMutator,
+        },
+        schedulers::QueueScheduler,
+        stages::{MutationalStage, StdMutationalStage},
+        state::{HasCorpus, StdState},
+        Error, HasMetadata, StdFuzzer,
+    };
+
+    #[test]
+    fn test_corpus_genealogy() {
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        let seed = corpus.add(Testcase::new(BytesInput::new(vec![0]))).unwrap();
+
+        let mut mutant = Testcase::new(BytesInput::new(vec![1]));
+        mutant.add_metadata(ProvenanceMetadata::default());
+        ProvenanceMetadata::enter_stage(
+            &mut mutant,
+            "mutational:0",
+            Some(Cow::Borrowed("havoc")),
+            Some(seed),
+        );
+        ProvenanceMetadata::record_mutations(&mut mutant, || {
+            vec![
+                Cow::Borrowed("BitFlipMutator"),
+                Cow::Borrowed("ByteIncMutator"),
+            ]
+        });
+        let mutant = corpus.add(mutant).unwrap();
+
+        let mut received = Testcase::new(BytesInput::new(vec![2]));
+        received.add_metadata(ProvenanceMetadata::default());
+        ProvenanceMetadata::enter_transfer(&mut received, ClientId(3));
+        // The parent set by the scheduler is ignored when the provenance is known
+        received.set_parent_id(seed);
+        let received = corpus.add(received).unwrap();
+
+        let mut generated = Testcase::new(BytesInput::new(vec![3]));
+        generated.add_metadata(ProvenanceMetadata::default());
+        ProvenanceMetadata::enter_stage(&mut generated, "generation", None, None);
+        let generated = corpus.add(generated).unwrap();
+
+        // The provenance is forgotten even if the stage fails
+        let mut failed = Testcase::new(BytesInput::new(vec![6]));
+        failed.add_metadata(ProvenanceMetadata::default());
+        assert!(
+            ProvenanceMetadata::with_stage(&mut failed, "generation", None, None, |_| {
+                Err::<(), _>(Error::unknown("The stage failed"))
+            })
+            .is_err()
+        );
+        assert_eq!(
+            failed.metadata::<ProvenanceMetadata>().unwrap(),
+            &ProvenanceMetadata::default()
+        );
+
+        let mut unknown = Testcase::new(BytesInput::new(vec![4]));
+        unknown.set_parent_id(mutant);
+        let unknown = corpus.add(unknown).unwrap();
+
+        let mut orphan = Testcase::new(BytesInput::new(vec![5]));
+        orphan.set_parent_id(CorpusId(42));
+        let orphan = corpus.add(orphan).unwrap();
+
+        let genealogy = CorpusGenealogy::new(&corpus).unwrap();
+        let roots: Vec<_> = genealogy.roots().map(|node| node.id).collect();
+        assert_eq!(roots, [seed, received, generated, orphan]);
+        assert_eq!(genealogy.nodes()[0].children, [mutant]);
+        assert_eq!(genealogy.nodes()[1].children, [unknown]);
+        assert_eq!(genealogy.nodes()[4].parent_id, Some(mutant));
+
+        let provenance = genealogy.nodes()[1].provenance.as_ref().unwrap();
+        assert_eq!(provenance.parent_id(), Some(seed));
+        assert_eq!(provenance.stage(), Some("mutational:0"));
+        assert_eq!(provenance.mutator(), Some("havoc"));
+        assert_eq!(
+            genealogy.nodes()[1].mutations,
+            ["BitFlipMutator", "ByteIncMutator"]
+        );
+        assert_eq!(
+            genealogy.nodes()[2]
+                .provenance
+                .as_ref()
+                .unwrap()
+                .client_id(),
+            Some(ClientId(3))
+        );
+
+        let dot = genealogy.to_dot();
+        assert!(dot.contains(&format!("  {seed} -> {mutant};")));
+        assert!(dot.contains("mutational:0\\nBitFlipMutator, ByteIncMutator"));
+        assert!(dot.contains("from client 3"));
+        assert!(dot.contains("generation"));
+
+        #[cfg(feature = "std")]
+        {
+            let json = genealogy.to_json().unwrap();
+            let parsed: CorpusGenealogy = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed, genealogy);
+        }
+    }
+
+    #[test]
+    fn test_corpus_genealogy_from_stage() {
+        let mut feedback = feedback_or!(ConstFeedback::new(true), ProvenanceFeedback::new());
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let seed = state
+            .corpus_mut()
+            .add(Testcase::new(BytesInput::new(vec![0; 4])))
+            .unwrap();
+
+        let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), feedback, objective);
+        let mut mgr = NopEventManager::new();
+        let mut harness = |_input: &BytesInput| ExitKind::Ok;
+        let mut executor = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )
+        .unwrap();
+
+        let mutator =
+            StdScheduledMutator::new(tuple_list!(BitFlipMutator::new(), ByteFlipMutator::new()));
+        let stage = StdMutationalStage::new(mutator);
+        let stage_name = stage.name().clone();
+        let mutator_name = stage.mutator().name().clone();
+        let mut stages = tuple_list!(stage);
+        fuzzer
+            .fuzz_one(&mut stages, &mut executor, &mut state, &mut mgr)
+            .unwrap();
+        assert!(state.corpus().count() > 1);
+
+        let genealogy = CorpusGenealogy::new(state.corpus()).unwrap();
+        let roots: Vec<_> = genealogy.roots().map(|node| node.id).collect();
+        assert_eq!(roots, [seed]);
+        let mutants: Vec<_> = genealogy.nodes()[1..].iter().map(|node| node.id).collect();
+        assert_eq!(genealogy.nodes()[0].children, mutants);
+        for node in &genealogy.nodes()[1..] {
+            assert_eq!(node.parent_id, Some(seed));
+            let provenance = node.provenance.as_ref().unwrap();
+            assert_eq!(provenance.stage(), Some(&*stage_name));
+            assert_eq!(provenance.mutator(), Some(&*mutator_name));
+            assert!(!node.mutations.is_empty());
+            assert_eq!(node.mutations, provenance.mutations());
+            assert!(node
+                .mutations
+                .iter()
+                .all(|name| name == "BitFlipMutator" || name == "ByteFlipMutator"));
+        }
+        // The provenance of the state is reset after the stage
+        assert_eq!(
+            state.metadata::<ProvenanceMetadata>().unwrap(),
+            &ProvenanceMetadata::default()
+        );
+    }
+}
@@ -6,6 +6,12 @@ pub use testcase::{HasTestcase, SchedulerTestcaseMetadata, Testcase};
 pub mod inmemory;
 pub use inmemory::InMemoryCorpus;
 
+pub mod dedup;
+pub use dedup::DedupCorpus;
+
+pub mod genealogy;
+pub use genealogy::{CorpusGenealogy, GenealogyNode};
+
 #[cfg(feature = "std")]
 pub mod inmemory_ondisk;
 #[cfg(feature = "std")]
@@ -21,6 +27,11 @@ pub mod cached;
 #[cfg(feature = "std")]
 pub use cached::CachedOnDiskCorpus;
 
+#[cfg(feature = "sqlite_corpus")]
+pub mod sqlite;
+#[cfg(feature = "sqlite_corpus")]
+pub use sqlite::SqliteCorpus;
+
 #[cfg(all(feature = "cmin", unix))]
 pub mod minimizer;
 use core::{cell::RefCell, fmt};
@@ -189,6 +200,14 @@ pub trait Corpus: Sized {
     /// Method to store the input of this `Testcase` to persistent storage, if necessary.
     fn store_input_from(&self, testcase: &Testcase<Self::Input>) -> Result<(), Error>;
 
+    /// Returns the id of the enabled entry already storing `input`, for corpora deduplicating their entries.
+    ///
+    /// The fuzzer checks this before adding an interesting input, so that an input already present
+    /// is neither reported to the scheduler nor sent to other clients again.
+    fn duplicate_of(&self, _input: &Self::Input) -> Result<Option<CorpusId>, Error> {
+        Ok(None)
+    }
+
     /// Loads the `Input` for a given [`CorpusId`] from the [`Corpus`], and returns the clone.
     fn cloned_input_for_id(&self, id: CorpusId) -> Result<Self::Input, Error>
     where
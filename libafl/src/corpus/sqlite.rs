@@ -0,0 +1,522 @@
+//! The [`SqliteCorpus`] stores all [`Testcase`]s in a single embedded `SQLite` database.
+//!
+//! Contrary to the [`crate::corpus::OnDiskCorpus`], no file (and no `.metadata` sidecar) is created per [`Testcase`].
+//! This keeps the number of inodes constant, even for corpora with millions of entries.
+//! The inputs are only loaded from the database when they are being used.
+//! Use [`SqliteCorpus::export_to_dir`] to get back the plain directory layout of the [`crate::corpus::OnDiskCorpus`],
+//! the `sqlite_export` example does so from the command line.
+
+use alloc::{string::String, vec::Vec};
+use core::{
+    cell::{OnceCell, Ref, RefCell, RefMut},
+    time::Duration,
+};
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "gzip")]
+use libafl_bolts::compress::GzipCompressor;
+use libafl_bolts::serdeany::SerdeAnyMap;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::{
+        ondisk::{OnDiskMetadata, OnDiskMetadataFormat},
+        Corpus, CorpusId, HasTestcase, InMemoryCorpus, Testcase,
+    },
+    inputs::Input,
+    Error, HasMetadata,
+};
+
+/// How long a connection waits for a lock held by another fuzzer instance before giving up
+const SQLITE_BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Converts a [`rusqlite::Error`] into a [`libafl_bolts::Error`]
+#[allow(clippy::needless_pass_by_value)]
+fn sqlite_error(err: rusqlite::Error) -> Error {
+    Error::illegal_state(format!("SQLite error: {err}"))
+}
+
+/// A corpus storing all [`Testcase`]s, their metadata, and their enabled/disabled state in a `SQLite` database.
+///
+/// The [`Testcase`]s themselves are kept in memory, but their inputs are evicted after they have been written.
+/// The database connection is (re)opened lazily, so the corpus can be serialized together with the state.
+#[derive(Default, Serialize, Deserialize, Debug)]
+pub struct SqliteCorpus<I> {
+    inner: InMemoryCorpus<I>,
+    db_path: PathBuf,
+    #[serde(skip)]
+    conn: OnceCell<Connection>,
+}
+
+impl<I> Clone for SqliteCorpus<I>
+where
+    I: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            db_path: self.db_path.clone(),
+            conn: OnceCell::new(),
+        }
+    }
+}
+
+impl<I> Corpus for SqliteCorpus<I>
+where
+    I: Input,
+{
+    type Input = I;
+
+    /// Returns the number of all enabled entries
+    #[inline]
+    fn count(&self) -> usize {
+        self.inner.count()
+    }
+
+    /// Returns the number of all disabled entries
+    fn count_disabled(&self) -> usize {
+        self.inner.count_disabled()
+    }
+
+    /// Returns the number of elements including disabled entries
+    #[inline]
+    fn count_all(&self) -> usize {
+        self.inner.count_all()
+    }
+
+    /// Add an enabled testcase to the corpus and return its index
+    #[inline]
+    fn add(&mut self, testcase: Testcase<I>) -> Result<CorpusId, Error> {
+        let id = self.inner.add(testcase)?;
+        let testcase = &mut self.inner.get(id).unwrap().borrow_mut();
+        self.save_testcase(testcase, id, false)?;
+        *testcase.input_mut() = None;
+        Ok(id)
+    }
+
+    /// Add a disabled testcase to the corpus and return its index
+    #[inline]
+    fn add_disabled(&mut self, testcase: Testcase<I>) -> Result<CorpusId, Error> {
+        let id = self.inner.add_disabled(testcase)?;
+        let testcase = &mut self.inner.get_from_all(id).unwrap().borrow_mut();
+        self.save_testcase(testcase, id, true)?;
+        *testcase.input_mut() = None;
+        Ok(id)
+    }
+
+    /// Replaces the testcase at the given idx
+    #[inline]
+    fn replace(&mut self, id: CorpusId, testcase: Testcase<I>) -> Result<Testcase<I>, Error> {
+        let entry = self.inner.replace(id, testcase)?;
+        self.remove_testcase(&entry)?;
+        let testcase = &mut self.inner.get(id).unwrap().borrow_mut();
+        self.save_testcase(testcase, id, false)?;
+        *testcase.input_mut() = None;
+        Ok(entry)
+    }
+
+    /// Removes an entry from the corpus, returning it if it was present; considers both enabled and disabled corpus
+    #[inline]
+    fn remove(&mut self, id: CorpusId) -> Result<Testcase<I>, Error> {
+        let entry = self.inner.remove(id)?;
+        self.remove_testcase(&entry)?;
+        Ok(entry)
+    }
+
+    /// Get by id; considers only enabled testcases
+    #[inline]
+    fn get(&self, id: CorpusId) -> Result<&RefCell<Testcase<I>>, Error> {
+        self.inner.get(id)
+    }
+
+    /// Get by id; considers both enabled and disabled testcases
+    #[inline]
+    fn get_from_all(&self, id: CorpusId) -> Result<&RefCell<Testcase<I>>, Error> {
+        self.inner.get_from_all(id)
+    }
+
+    /// Current testcase scheduled
+    #[inline]
+    fn current(&self) -> &Option<CorpusId> {
+        self.inner.current()
+    }
+
+    /// Current testcase scheduled (mutable)
+    #[inline]
+    fn current_mut(&mut self) -> &mut Option<CorpusId> {
+        self.inner.current_mut()
+    }
+
+    #[inline]
+    fn next(&self, id: CorpusId) -> Option<CorpusId> {
+        self.inner.next(id)
+    }
+
+    /// Peek the next free corpus id
+    #[inline]
+    fn peek_free_id(&self) -> CorpusId {
+        self.inner.peek_free_id()
+    }
+
+    #[inline]
+    fn prev(&self, id: CorpusId) -> Option<CorpusId> {
+        self.inner.prev(id)
+    }
+
+    #[inline]
+    fn first(&self) -> Option<CorpusId> {
+        self.inner.first()
+    }
+
+    #[inline]
+    fn last(&self) -> Option<CorpusId> {
+        self.inner.last()
+    }
+
+    /// Get the nth corpus id; considers only enabled testcases
+    #[inline]
+    fn nth(&self, nth: usize) -> CorpusId {
+        self.inner.nth(nth)
+    }
+    /// Get the nth corpus id; considers both enabled and disabled testcases
+    #[inline]
+    fn nth_from_all(&self, nth: usize) -> CorpusId {
+        self.inner.nth_from_all(nth)
+    }
+
+    fn load_input_into(&self, testcase: &mut Testcase<Self::Input>) -> Result<(), Error> {
+        if testcase.input_mut().is_none() {
+            let Some(name) = testcase.filename().as_ref() else {
+                return Err(Error::illegal_argument(
+                    "No name set for testcase. Could not load inputs.",
+                ));
+            };
+            let bytes: Vec<u8> = self
+                .conn()?
+                .query_row(
+                    "SELECT input FROM testcases WHERE name = ?1",
+                    params![name],
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(sqlite_error)?
+                .ok_or_else(|| {
+                    Error::key_not_found(format!("Testcase {name} not found in the database"))
+                })?;
+            testcase.set_input(postcard::from_bytes(&bytes)?);
+        }
+        Ok(())
+    }
+
+    fn store_input_from(&self, testcase: &Testcase<Self::Input>) -> Result<(), Error> {
+        let Some(name) = testcase.filename() else {
+            return Err(Error::illegal_argument(
+                "No name set for testcase. Could not store input to the database.",
+            ));
+        };
+        let Some(input) = testcase.input() else {
+            return Err(Error::illegal_argument(
+                "No input available for testcase. Could not store anything.",
+            ));
+        };
+        self.conn()?
+            .execute(
+                "UPDATE testcases SET input = ?1 WHERE name = ?2",
+                params![postcard::to_allocvec(input)?, name],
+            )
+            .map_err(sqlite_error)?;
+        Ok(())
+    }
+}
+
+impl<I> HasTestcase for SqliteCorpus<I>
+where
+    I: Input,
+{
+    fn testcase(&self, id: CorpusId) -> Result<Ref<Testcase<I>>, Error> {
+        Ok(self.get(id)?.borrow())
+    }
+
+    fn testcase_mut(&self, id: CorpusId) -> Result<RefMut<Testcase<I>>, Error> {
+        Ok(self.get(id)?.borrow_mut())
+    }
+}
+
+impl<I> SqliteCorpus<I>
+where
+    I: Input,
+{
+    /// Creates a [`SqliteCorpus`] backed by the database at `db_path`.
+    ///
+    /// The database (and its parent directories) will be created, if it does not exist yet.
+    /// All [`Testcase`]s already stored in an existing database are added to the corpus,
+    /// keeping their metadata and their enabled/disabled state. Their inputs are loaded on demand.
+    pub fn new<P>(db_path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let db_path = db_path.as_ref();
+        if let Some(parent) = db_path.parent() {
+            match fs::create_dir_all(parent) {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        let mut corpus = Self {
+            inner: InMemoryCorpus::new(),
+            db_path: db_path.into(),
+            conn: OnceCell::new(),
+        };
+        corpus.load_existing()?;
+        Ok(corpus)
+    }
+
+    /// Adds all [`Testcase`]s stored in the database to the in-memory index, without their inputs
+    fn load_existing(&mut self) -> Result<(), Error> {
+        let rows = {
+            let mut stmt = self
+                .conn()?
+                .prepare(
+                    "SELECT name, metadata, exec_time_ns, disabled FROM testcases ORDER BY rowid",
+                )
+                .map_err(sqlite_error)?;
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, Vec<u8>>(1)?,
+                        row.get::<_, Option<i64>>(2)?,
+                        row.get::<_, bool>(3)?,
+                    ))
+                })
+                .map_err(sqlite_error)?;
+            rows.collect::<Result<Vec<_>, _>>().map_err(sqlite_error)?
+        };
+
+        for (name, metadata, exec_time_ns, disabled) in rows {
+            let mut testcase = Testcase::default();
+            *testcase.filename_mut() = Some(name);
+            *testcase.metadata_map_mut() = postcard::from_bytes::<SerdeAnyMap>(&metadata)?;
+            if let Some(exec_time_ns) = exec_time_ns.and_then(|ns| u64::try_from(ns).ok()) {
+                testcase.set_exec_time(Duration::from_nanos(exec_time_ns));
+            }
+            testcase.set_disabled(disabled);
+            if disabled {
+                self.inner.add_disabled(testcase)?;
+            } else {
+                self.inner.add(testcase)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes all [`Testcase`]s of this corpus to `dir`, using the same layout as the [`crate::corpus::OnDiskCorpus`].
+    ///
+    /// Each input is written to its own file, named after the testcase.
+    /// If `meta_format` is set, the metadata is written to a `.<testcase>.metadata` file next to it.
+    /// Disabled testcases are exported as well.
+    pub fn export_to_dir<P>(
+        &self,
+        dir: P,
+        meta_format: Option<&OnDiskMetadataFormat>,
+    ) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+
+        let mut stmt = self
+            .conn()?
+            .prepare("SELECT name, input, metadata, exec_time_ns FROM testcases ORDER BY rowid")
+            .map_err(sqlite_error)?;
+        let mut rows = stmt.query([]).map_err(sqlite_error)?;
+        while let Some(row) = rows.next().map_err(sqlite_error)? {
+            let name: String = row.get(0).map_err(sqlite_error)?;
+            let input: Vec<u8> = row.get(1).map_err(sqlite_error)?;
+            postcard::from_bytes::<I>(&input)?.to_file(dir.join(&name))?;
+
+            let Some(meta_format) = meta_format else {
+                continue;
+            };
+            let metadata: Vec<u8> = row.get(2).map_err(sqlite_error)?;
+            let exec_time_ns: Option<i64> = row.get(3).map_err(sqlite_error)?;
+            let ondisk_meta = OnDiskMetadata {
+                metadata: &postcard::from_bytes(&metadata)?,
+                exec_time: &exec_time_ns
+                    .and_then(|ns| u64::try_from(ns).ok())
+                    .map(Duration::from_nanos),
+            };
+
+            let json_error =
+                |err| Error::serialize(format!("Failed to json-ify metadata: {err:?}"));
+
+            let serialized = match meta_format {
+                OnDiskMetadataFormat::Postcard => postcard::to_allocvec(&ondisk_meta)?,
+                OnDiskMetadataFormat::Json => {
+                    serde_json::to_vec(&ondisk_meta).map_err(json_error)?
+                }
+                OnDiskMetadataFormat::JsonPretty => {
+                    serde_json::to_vec_pretty(&ondisk_meta).map_err(json_error)?
+                }
+                #[cfg(feature = "gzip")]
+                OnDiskMetadataFormat::JsonGzip => GzipCompressor::new()
+                    .compress(&serde_json::to_vec_pretty(&ondisk_meta).map_err(json_error)?),
+            };
+            File::create(dir.join(format!(".{name}.metadata")))?.write_all(&serialized)?;
+        }
+        Ok(())
+    }
+
+    /// Inserts the [`Testcase`] into the database, picking a unique name for it
+    fn save_testcase(
+        &self,
+        testcase: &mut Testcase<I>,
+        id: CorpusId,
+        disabled: bool,
+    ) -> Result<(), Error> {
+        let Some(input) = testcase.input() else {
+            return Err(Error::illegal_argument(
+                "No input available for testcase. Could not store anything.",
+            ));
+        };
+        let name_orig = match testcase.filename() {
+            Some(name) => name.clone(),
+            None => input.generate_name(Some(id)),
+        };
+        let input = postcard::to_allocvec(input)?;
+        let metadata = postcard::to_allocvec(testcase.metadata_map())?;
+        let exec_time_ns = testcase
+            .exec_time()
+            .map(|t| i64::try_from(t.as_nanos()))
+            .transpose()?;
+
+        let conn = self.conn()?;
+        let mut name = name_orig.clone();
+        let mut ctr = 2;
+        // Names are unique, like files in a directory. Retry with a suffix if this one is taken.
+        while conn
+            .execute(
+                "INSERT OR IGNORE INTO testcases (name, input, metadata, exec_time_ns, disabled)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![name, input, metadata, exec_time_ns, disabled],
+            )
+            .map_err(sqlite_error)?
+            == 0
+        {
+            name = format!("{name_orig}-{ctr}");
+            ctr += 1;
+        }
+
+        *testcase.filename_mut() = Some(name);
+        Ok(())
+    }
+
+    /// Deletes the [`Testcase`] from the database
+    fn remove_testcase(&self, testcase: &Testcase<I>) -> Result<(), Error> {
+        if let Some(name) = testcase.filename() {
+            self.conn()?
+                .execute("DELETE FROM testcases WHERE name = ?1", params![name])
+                .map_err(sqlite_error)?;
+        }
+        Ok(())
+    }
+}
+
+impl<I> SqliteCorpus<I> {
+    /// Returns the connection to the database, opening it (and creating the schema) if needed
+    fn conn(&self) -> Result<&Connection, Error> {
+        if let Some(conn) = self.conn.get() {
+            return Ok(conn);
+        }
+        let conn = Connection::open(&self.db_path).map_err(sqlite_error)?;
+        conn.busy_timeout(SQLITE_BUSY_TIMEOUT)
+            .map_err(sqlite_error)?;
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA synchronous = NORMAL;
+             CREATE TABLE IF NOT EXISTS testcases (
+                 name TEXT NOT NULL UNIQUE,
+                 input BLOB NOT NULL,
+                 metadata BLOB NOT NULL,
+                 exec_time_ns INTEGER,
+                 disabled INTEGER NOT NULL DEFAULT 0
+             );",
+        )
+        .map_err(sqlite_error)?;
+        Ok(self.conn.get_or_init(|| conn))
+    }
+
+    /// Path to the database file associated with this corpus
+    #[must_use]
+    pub fn db_path(&self) -> &PathBuf {
+        &self.db_path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use libafl_bolts::HasLen;
+
+    use super::SqliteCorpus;
+    use crate::{
+        corpus::{ondisk::OnDiskMetadataFormat, Corpus, Testcase},
+        inputs::BytesInput,
+    };
+
+    #[test]
+    fn test_sqlite_corpus() {
+        let tmp = env::temp_dir().join(format!("libafl_sqlite_corpus_{}", std::process::id()));
+        _ = fs::remove_dir_all(&tmp);
+        let db_path = tmp.join("corpus.sqlite");
+
+        let mut corpus = SqliteCorpus::<BytesInput>::new(&db_path).unwrap();
+        let id = corpus
+            .add(Testcase::new(BytesInput::new(b"hello".to_vec())))
+            .unwrap();
+        let dup_id = corpus
+            .add(Testcase::new(BytesInput::new(b"hello".to_vec())))
+            .unwrap();
+        corpus
+            .add_disabled(Testcase::new(BytesInput::new(b"disabled".to_vec())))
+            .unwrap();
+        assert_eq!(corpus.count(), 2);
+        assert_eq!(corpus.count_disabled(), 1);
+        assert_ne!(
+            corpus.get(id).unwrap().borrow().filename(),
+            corpus.get(dup_id).unwrap().borrow().filename()
+        );
+
+        // A testcase without an input can't be stored
+        let mut unloaded = Testcase::new(BytesInput::new(b"unloaded".to_vec()));
+        unloaded.input_mut().take();
+        assert!(corpus.add(unloaded).is_err());
+
+        corpus.remove(dup_id).unwrap();
+        drop(corpus);
+
+        let reopened = SqliteCorpus::<BytesInput>::new(&db_path).unwrap();
+        assert_eq!(reopened.count(), 1);
+        assert_eq!(reopened.count_disabled(), 1);
+        let first = reopened.first().unwrap();
+        let mut testcase = reopened.get(first).unwrap().borrow_mut();
+        assert_eq!(testcase.load_input(&reopened).unwrap().len(), 5);
+        drop(testcase);
+
+        let export_dir = tmp.join("export");
+        reopened
+            .export_to_dir(&export_dir, Some(&OnDiskMetadataFormat::JsonPretty))
+            .unwrap();
+        assert_eq!(fs::read_dir(&export_dir).unwrap().count(), 4);
+
+        fs::remove_dir_all(tmp).unwrap();
+    }
+}
@@ -2,15 +2,13 @@ use alloc::vec::Vec;
 use core::{fmt::Debug, marker::PhantomData};
 
 #[cfg(feature = "llmp_compression")]
-use libafl_bolts::{compress::GzipCompressor, llmp::LLMP_FLAG_COMPRESSED};
+use libafl_bolts::compress::LlmpCompressor;
 use libafl_bolts::{
     llmp::{Flags, LlmpBrokerInner, LlmpHook, LlmpMsgHookResult, Tag},
     shmem::ShMemProvider,
     ClientId, Error,
 };
 
-#[cfg(feature = "llmp_compression")]
-use crate::events::COMPRESS_THRESHOLD;
 use crate::{
     events::{BrokerEventResult, Event, _LLMP_TAG_TO_MAIN},
     inputs::Input,
@@ -19,7 +17,7 @@ use crate::{
 /// An LLMP-backed event manager for scalable multi-processed fuzzing
 pub struct CentralizedLlmpHook<I> {
     #[cfg(feature = "llmp_compression")]
-    compressor: GzipCompressor,
+    compressor: LlmpCompressor,
     phantom: PhantomData<I>,
 }
 
@@ -45,8 +43,8 @@ where
             #[cfg(feature = "llmp_compression")]
             let compressed;
             #[cfg(feature = "llmp_compression")]
-            let event_bytes = if *_msg_flags & LLMP_FLAG_COMPRESSED == LLMP_FLAG_COMPRESSED {
-                compressed = compressor.decompress(msg)?;
+            let event_bytes = if LlmpCompressor::is_compressed(*_msg_flags) {
+                compressed = compressor.decompress(*_msg_flags, msg)?;
                 &compressed
             } else {
                 &*msg
@@ -83,7 +81,7 @@ where
     pub fn new() -> Result<Self, Error> {
         Ok(Self {
             #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::with_threshold(COMPRESS_THRESHOLD),
+            compressor: LlmpCompressor::decompressor(),
             phantom: PhantomData,
         })
     }
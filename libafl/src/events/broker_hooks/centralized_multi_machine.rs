@@ -6,8 +6,6 @@ use std::{
     vec::Vec,
 };
 
-#[cfg(feature = "llmp_compression")]
-use libafl_bolts::llmp::LLMP_FLAG_COMPRESSED;
 use libafl_bolts::{
     llmp::{Flags, LlmpBrokerInner, LlmpHook, LlmpMsgHookResult, Tag, LLMP_FLAG_FROM_MM},
     ownedref::OwnedRef,
@@ -143,10 +141,10 @@ where
     ) -> Result<(Flags, Vec<u8>), Error> {
         let serialized = postcard::to_allocvec(&event)?;
 
-        match state_lock.compressor().maybe_compress(&serialized) {
-            Some(comp_buf) => Ok((LLMP_FLAG_COMPRESSED, comp_buf)),
-            None => Ok((Flags(0), serialized)),
-        }
+        Ok(state_lock
+            .compressor()
+            .maybe_compress(&serialized)?
+            .unwrap_or((Flags(0), serialized)))
     }
 
     #[cfg(not(feature = "llmp_compression"))]
@@ -254,12 +252,10 @@ where
                     MultiMachineMsg::LlmpMsg(msg) => {
                         let msg = msg.into_owned().unwrap().into_vec();
                         #[cfg(feature = "llmp_compression")]
-                        match state_wr_lock.compressor().maybe_compress(msg.as_ref()) {
-                            Some(comp_buf) => Ok((
-                                _LLMP_TAG_TO_MAIN,
-                                LLMP_FLAG_COMPRESSED | LLMP_FLAG_FROM_MM,
-                                comp_buf,
-                            )),
+                        match state_wr_lock.compressor().maybe_compress(msg.as_ref())? {
+                            Some((comp_flags, comp_buf)) => {
+                                Ok((_LLMP_TAG_TO_MAIN, comp_flags | LLMP_FLAG_FROM_MM, comp_buf))
+                            }
                             None => Ok((_LLMP_TAG_TO_MAIN, LLMP_FLAG_FROM_MM, msg)),
                         }
                         #[cfg(not(feature = "llmp_compression"))]
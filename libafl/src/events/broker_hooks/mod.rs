@@ -3,15 +3,13 @@ use alloc::vec::Vec;
 use core::marker::PhantomData;
 
 #[cfg(feature = "llmp_compression")]
-use libafl_bolts::{compress::GzipCompressor, llmp::LLMP_FLAG_COMPRESSED};
+use libafl_bolts::compress::LlmpCompressor;
 use libafl_bolts::{
     llmp::{Flags, LlmpBrokerInner, LlmpHook, LlmpMsgHookResult, Tag},
     shmem::ShMemProvider,
     ClientId,
 };
 
-#[cfg(feature = "llmp_compression")]
-use crate::events::llmp::COMPRESS_THRESHOLD;
 use crate::{
     events::{llmp::LLMP_TAG_EVENT_TO_BOTH, BrokerEventResult, Event},
     inputs::Input,
@@ -36,7 +34,7 @@ pub use centralized_multi_machine::*;
 pub struct StdLlmpEventHook<I, MT> {
     monitor: MT,
     #[cfg(feature = "llmp_compression")]
-    compressor: GzipCompressor,
+    compressor: LlmpCompressor,
     phantom: PhantomData<I>,
 }
 
@@ -66,8 +64,8 @@ where
             #[cfg(feature = "llmp_compression")]
             let compressed;
             #[cfg(feature = "llmp_compression")]
-            let event_bytes = if *msg_flags & LLMP_FLAG_COMPRESSED == LLMP_FLAG_COMPRESSED {
-                compressed = compressor.decompress(msg)?;
+            let event_bytes = if LlmpCompressor::is_compressed(*msg_flags) {
+                compressed = compressor.decompress(*msg_flags, msg)?;
                 &compressed
             } else {
                 &*msg
@@ -98,7 +96,7 @@ where
         Ok(Self {
             monitor,
             #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::with_threshold(COMPRESS_THRESHOLD),
+            compressor: LlmpCompressor::decompressor(),
             phantom: PhantomData,
         })
     }
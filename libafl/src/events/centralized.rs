@@ -13,8 +13,8 @@ use std::{marker::PhantomData, process};
 
 #[cfg(feature = "llmp_compression")]
 use libafl_bolts::{
-    compress::GzipCompressor,
-    llmp::{LLMP_FLAG_COMPRESSED, LLMP_FLAG_INITIALIZED},
+    compress::{LlmpCompression, LlmpCompressor},
+    llmp::LLMP_FLAG_INITIALIZED,
 };
 use libafl_bolts::{
     llmp::{LlmpClient, LlmpClientDescription, Tag},
@@ -36,6 +36,7 @@ use crate::{
         HasCustomBufHandlers, HasEventManagerId, LogSeverity, ProgressReporter,
     },
     executors::{Executor, HasObservers},
+    feedbacks::ProvenanceMetadata,
     fuzzer::{EvaluatorObservers, ExecutionProcessor},
     inputs::{Input, NopInput, UsesInput},
     observers::{ObserversTuple, TimeObserver},
@@ -58,7 +59,7 @@ where
     /// The centralized LLMP client for inter process communication
     client: LlmpClient<SP>,
     #[cfg(feature = "llmp_compression")]
-    compressor: GzipCompressor,
+    compressor: LlmpCompressor,
     time_ref: Option<Handle<TimeObserver>>,
     hooks: EMH,
     is_main: bool,
@@ -84,6 +85,8 @@ impl
 #[derive(Debug)]
 pub struct CentralizedEventManagerBuilder {
     is_main: bool,
+    #[cfg(feature = "llmp_compression")]
+    compression: LlmpCompression,
 }
 
 impl Default for CentralizedEventManagerBuilder {
@@ -96,13 +99,27 @@ impl CentralizedEventManagerBuilder {
     /// The constructor
     #[must_use]
     pub fn new() -> Self {
-        Self { is_main: false }
+        Self {
+            is_main: false,
+            #[cfg(feature = "llmp_compression")]
+            compression: LlmpCompression::default(),
+        }
     }
 
     /// Make this a main evaluator node
     #[must_use]
-    pub fn is_main(self, is_main: bool) -> Self {
-        Self { is_main }
+    pub fn is_main(mut self, is_main: bool) -> Self {
+        self.is_main = is_main;
+        self
+    }
+
+    /// Set the algorithm used to compress the messages sent to the centralized broker.
+    /// Incoming messages are decompressed with whichever algorithm they were compressed with.
+    #[cfg(feature = "llmp_compression")]
+    #[must_use]
+    pub fn compression(mut self, compression: LlmpCompression) -> Self {
+        self.compression = compression;
+        self
     }
 
     /// Creates a new [`CentralizedEventManager`].
@@ -124,7 +141,7 @@ impl CentralizedEventManagerBuilder {
             hooks,
             client,
             #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::with_threshold(COMPRESS_THRESHOLD),
+            compressor: LlmpCompressor::new(self.compression, COMPRESS_THRESHOLD),
             time_ref: time_obs,
             is_main: self.is_main,
             phantom: PhantomData,
@@ -156,7 +173,7 @@ impl CentralizedEventManagerBuilder {
             hooks,
             client,
             #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::with_threshold(COMPRESS_THRESHOLD),
+            compressor: LlmpCompressor::new(self.compression, COMPRESS_THRESHOLD),
             time_ref: time_obs,
             is_main: self.is_main,
             phantom: PhantomData,
@@ -185,7 +202,7 @@ impl CentralizedEventManagerBuilder {
             hooks,
             client: LlmpClient::on_existing_from_env(shmem_provider, env_name)?,
             #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::with_threshold(COMPRESS_THRESHOLD),
+            compressor: LlmpCompressor::new(self.compression, COMPRESS_THRESHOLD),
             time_ref: time_obs,
             is_main: self.is_main,
             phantom: PhantomData,
@@ -213,7 +230,7 @@ impl CentralizedEventManagerBuilder {
             hooks,
             client: LlmpClient::existing_client_from_description(shmem_provider, description)?,
             #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::with_threshold(COMPRESS_THRESHOLD),
+            compressor: LlmpCompressor::new(self.compression, COMPRESS_THRESHOLD),
             time_ref: time_obs,
             is_main: self.is_main,
             phantom: PhantomData,
@@ -498,11 +515,11 @@ where
         let serialized = postcard::to_allocvec(event)?;
         let flags = LLMP_FLAG_INITIALIZED;
 
-        match self.compressor.maybe_compress(&serialized) {
-            Some(comp_buf) => {
+        match self.compressor.maybe_compress(&serialized)? {
+            Some((comp_flags, comp_buf)) => {
                 self.client.send_buf_with_flags(
                     _LLMP_TAG_TO_MAIN,
-                    flags | LLMP_FLAG_COMPRESSED,
+                    flags | comp_flags,
                     &comp_buf,
                 )?;
             }
@@ -555,8 +572,8 @@ where
             #[cfg(feature = "llmp_compression")]
             let compressed;
             #[cfg(feature = "llmp_compression")]
-            let event_bytes = if _flags & LLMP_FLAG_COMPRESSED == LLMP_FLAG_COMPRESSED {
-                compressed = self.compressor.decompress(msg)?;
+            let event_bytes = if LlmpCompressor::is_compressed(_flags) {
+                compressed = self.compressor.decompress(_flags, msg)?;
                 &compressed
             } else {
                 msg
@@ -609,7 +626,7 @@ where
                     event_name
                 );
 
-                let res =
+                let res = ProvenanceMetadata::with_transfer(state, client_id, |state| {
                     if client_config.match_with(&self.configuration()) && observers_buf.is_some() {
                         let observers: E::Observers =
                             postcard::from_bytes(observers_buf.as_ref().unwrap())?;
@@ -629,7 +646,7 @@ where
                             &observers,
                             &exit_kind,
                             false,
-                        )?
+                        )
                     } else {
                         #[cfg(feature = "scalability_introspection")]
                         {
@@ -646,8 +663,9 @@ where
                             self,
                             input.clone(),
                             false,
-                        )?
-                    };
+                        )
+                    }
+                })?;
 
                 if let Some(item) = res.1 {
                     let event = Event::NewTestcase {
@@ -0,0 +1,352 @@
+//! The [`JsonLogEventHook`] appends every event passing through an event manager to a JSON-lines file.
+//!
+//! Each line is an [`EventLogRecord`], with a timestamp, the client id, and the kind of the event.
+//! Use [`rebuild_from_event_log`] to restore the corpus and the objectives of a campaign from its logs.
+
+use alloc::{format, string::String};
+use core::{marker::PhantomData, time::Duration};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+use libafl_bolts::{current_time, ClientId};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::Corpus,
+    events::{Event, EventManagerHook},
+    inputs::Input,
+    state::{HasSolutions, State},
+    Error,
+};
+
+/// Whether an event was fired by this client or received from another one
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventDirection {
+    /// The event was fired by the logging client
+    Fired,
+    /// The event was received from another client
+    Received,
+}
+
+/// A single line of the event log, see [`JsonLogEventHook`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(bound = "I: serde::de::DeserializeOwned")]
+pub struct EventLogRecord<I>
+where
+    I: Input,
+{
+    /// The time the event was logged
+    pub time: Duration,
+    /// The client that fired the event
+    pub client_id: ClientId,
+    /// Whether the event was fired or received by the logging client
+    pub direction: EventDirection,
+    /// The kind of the event, see [`Event::name`]
+    pub kind: String,
+    /// The input of a [`Event::Objective`] fired by the logging client, if it could be loaded.
+    /// The event itself does not carry the input, so this is always `None` for objectives received
+    /// from other clients: only the log of the client that found an objective holds its input.
+    pub objective_input: Option<I>,
+    /// The event
+    pub event: Event<I>,
+}
+
+/// The borrowed counterpart of [`EventLogRecord`], used for writing
+#[derive(Serialize)]
+#[serde(bound = "I: Serialize")]
+struct EventLogEntry<'a, I>
+where
+    I: Input,
+{
+    time: Duration,
+    client_id: ClientId,
+    direction: EventDirection,
+    kind: &'a str,
+    objective_input: Option<&'a I>,
+    event: &'a Event<I>,
+}
+
+/// An [`EventManagerHook`] appending each event received or fired by the event manager
+/// to a JSON-lines file, one [`EventLogRecord`] per line.
+///
+/// Use one log file per client, concurrent appends of large lines may interleave.
+/// Received events are logged in [`EventManagerHook::pre_exec`], whether or not another hook discards them.
+#[derive(Debug)]
+pub struct JsonLogEventHook<S> {
+    path: PathBuf,
+    file: File,
+    phantom: PhantomData<S>,
+}
+
+impl<S> JsonLogEventHook<S> {
+    /// Create a new [`JsonLogEventHook`], appending to the file at `path`
+    pub fn new<P>(path: P) -> Result<Self, Error>
+    where
+        P: Into<PathBuf>,
+    {
+        let path = path.into();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self {
+            path,
+            file,
+            phantom: PhantomData,
+        })
+    }
+
+    /// The path of the log file
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl<S> JsonLogEventHook<S>
+where
+    S: State + HasSolutions,
+    S::Solutions: Corpus<Input = S::Input>,
+{
+    /// Append an event to the log
+    fn write_entry(
+        &mut self,
+        state: &S,
+        client_id: ClientId,
+        direction: EventDirection,
+        event: &Event<S::Input>,
+    ) -> Result<(), Error> {
+        // The objective was added to the local solutions right before the event got fired
+        let objective =
+            if direction == EventDirection::Fired && matches!(event, Event::Objective { .. }) {
+                match state.solutions().last() {
+                    Some(id) => {
+                        let mut testcase = state.solutions().get(id)?.borrow_mut();
+                        state.solutions().load_input_into(&mut *testcase)?;
+                        testcase.input().clone()
+                    }
+                    None => None,
+                }
+            } else {
+                None
+            };
+
+        let entry = EventLogEntry {
+            time: current_time(),
+            client_id,
+            direction,
+            kind: event.name(),
+            objective_input: objective.as_ref(),
+            event,
+        };
+        let mut line = serde_json::to_vec(&entry)
+            .map_err(|err| Error::serialize(format!("Failed to serialize event: {err}")))?;
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        Ok(())
+    }
+}
+
+impl<S> EventManagerHook<S> for JsonLogEventHook<S>
+where
+    S: State + HasSolutions,
+    S::Solutions: Corpus<Input = S::Input>,
+{
+    fn pre_exec(
+        &mut self,
+        state: &mut S,
+        client_id: ClientId,
+        event: &Event<S::Input>,
+    ) -> Result<bool, Error> {
+        self.write_entry(state, client_id, EventDirection::Received, event)?;
+        Ok(true)
+    }
+
+    fn on_fire(
+        &mut self,
+        state: &mut S,
+        client_id: ClientId,
+        event: &Event<S::Input>,
+    ) -> Result<(), Error> {
+        self.write_entry(state, client_id, EventDirection::Fired, event)
+    }
+}
+
+/// The number of files written by [`rebuild_from_event_log`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EventLogReplayStats {
+    /// The number of inputs written to the corpus directory
+    pub corpus: usize,
+    /// The number of inputs written to the objectives directory
+    pub objectives: usize,
+}
+
+/// Replay an event log written by [`JsonLogEventHook`],
+/// writing the input of each [`Event::NewTestcase`] to `corpus_dir`,
+/// and the input of each [`Event::Objective`] to `objectives_dir`.
+///
+/// Inputs are named using [`Input::generate_name`]; inputs already present in the directories are skipped,
+/// so the logs of multiple clients can be replayed into the same directories.
+/// Objectives received from other clients carry no input, replay the logs of all clients to restore all objectives.
+pub fn rebuild_from_event_log<I, P, C, O>(
+    log: P,
+    corpus_dir: C,
+    objectives_dir: O,
+) -> Result<EventLogReplayStats, Error>
+where
+    I: Input,
+    P: AsRef<Path>,
+    C: AsRef<Path>,
+    O: AsRef<Path>,
+{
+    let (corpus_dir, objectives_dir) = (corpus_dir.as_ref(), objectives_dir.as_ref());
+    fs::create_dir_all(corpus_dir)?;
+    fs::create_dir_all(objectives_dir)?;
+
+    let mut stats = EventLogReplayStats::default();
+    let reader = BufReader::new(File::open(log)?);
+    for (line_no, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: EventLogRecord<I> = serde_json::from_str(&line).map_err(|err| {
+            Error::serialize(format!(
+                "Invalid event log record in line {}: {err}",
+                line_no + 1
+            ))
+        })?;
+
+        let (input, dir, count) = match (record.event, record.objective_input) {
+            (Event::NewTestcase { input, .. }, _) => (input, corpus_dir, &mut stats.corpus),
+            (Event::Objective { .. }, Some(input)) => {
+                (input, objectives_dir, &mut stats.objectives)
+            }
+            _ => continue,
+        };
+        if write_new_input(&input, dir)? {
+            *count += 1;
+        }
+    }
+    Ok(stats)
+}
+
+/// Write the input to `dir`, unless an input of the same name exists. Returns `true` if the input was written.
+fn write_new_input<I>(input: &I, dir: &Path) -> Result<bool, Error>
+where
+    I: Input,
+{
+    let path = dir.join(input.generate_name(None));
+    if path.exists() {
+        return Ok(false);
+    }
+    input.to_file(path)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{marker::PhantomData, time::Duration};
+    use std::{env, fs};
+
+    use libafl_bolts::{tuples::tuple_list, ClientId};
+
+    use super::{
+        rebuild_from_event_log, EventDirection, EventLogRecord, EventLogReplayStats,
+        JsonLogEventHook,
+    };
+    use crate::{
+        corpus::{Corpus, Testcase},
+        events::{Event, EventConfig, EventFirer, EventManagerHook, SimpleEventManager},
+        executors::ExitKind,
+        inputs::BytesInput,
+        monitors::NopMonitor,
+        state::{HasSolutions, StdState},
+    };
+
+    #[test]
+    fn test_event_log_replay() {
+        let dir = env::temp_dir().join(format!("libafl_event_log_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("events.jsonl");
+
+        let mut state = StdState::nop::<BytesInput>().unwrap();
+        let mut hook = JsonLogEventHook::new(&log).unwrap();
+
+        let testcase = Event::NewTestcase {
+            input: BytesInput::new(b"testcase".to_vec()),
+            observers_buf: None,
+            exit_kind: ExitKind::Ok,
+            corpus_size: 1,
+            client_config: EventConfig::AlwaysUnique,
+            time: Duration::ZERO,
+            forward_id: None,
+            #[cfg(all(unix, feature = "multi_machine"))]
+            node_id: None,
+        };
+        assert!(hook.pre_exec(&mut state, ClientId(2), &testcase).unwrap());
+
+        state
+            .solutions_mut()
+            .add(Testcase::new(BytesInput::new(b"crash".to_vec())))
+            .unwrap();
+        let objective = Event::Objective {
+            objective_size: 1,
+            time: Duration::ZERO,
+        };
+        hook.on_fire(&mut state, ClientId(1), &objective).unwrap();
+        let log_line = Event::Log {
+            severity_level: crate::events::LogSeverity::Info,
+            message: "hello".into(),
+            phantom: PhantomData,
+        };
+        hook.on_fire(&mut state, ClientId(1), &log_line).unwrap();
+        // Receiving the same testcase again must not create a duplicate
+        hook.pre_exec(&mut state, ClientId(3), &testcase).unwrap();
+
+        let stats = rebuild_from_event_log::<BytesInput, _, _, _>(
+            &log,
+            dir.join("queue"),
+            dir.join("crashes"),
+        )
+        .unwrap();
+        assert_eq!(
+            stats,
+            EventLogReplayStats {
+                corpus: 1,
+                objectives: 1
+            }
+        );
+        assert_eq!(fs::read_to_string(&log).unwrap().lines().count(), 4);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_event_log_simple_event_manager() {
+        let dir = env::temp_dir().join(format!("libafl_event_log_simple_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("events.jsonl");
+
+        let mut state = StdState::nop::<BytesInput>().unwrap();
+        let hook = JsonLogEventHook::new(&log).unwrap();
+        let mut mgr = SimpleEventManager::with_hooks(NopMonitor::new(), tuple_list!(hook));
+        mgr.fire(
+            &mut state,
+            Event::Objective {
+                objective_size: 0,
+                time: Duration::ZERO,
+            },
+        )
+        .unwrap();
+
+        let log_content = fs::read_to_string(&log).unwrap();
+        let record: EventLogRecord<BytesInput> =
+            serde_json::from_str(log_content.lines().next().unwrap()).unwrap();
+        assert_eq!(record.direction, EventDirection::Fired);
+        assert_eq!(record.kind, "Objective");
+        assert_eq!(log_content.lines().count(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
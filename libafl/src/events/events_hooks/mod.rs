@@ -4,6 +4,12 @@
 //! other clients
 use libafl_bolts::ClientId;
 
+/// A hook logging all events to a JSON-lines file
+#[cfg(feature = "std")]
+pub mod event_log;
+#[cfg(feature = "std")]
+pub use event_log::{rebuild_from_event_log, JsonLogEventHook};
+
 use crate::{events::Event, state::State, Error};
 
 /// The `broker_hooks` that are run before and after the event manager calls `handle_in_client`
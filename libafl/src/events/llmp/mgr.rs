@@ -1,6 +1,8 @@
 //! An [`crate::events::EventManager`] that forwards all events to other attached fuzzers on shared maps or via tcp,
 //! using low-level message passing, [`libafl_bolts::llmp`].
 
+#[cfg(feature = "llmp_compression")]
+use alloc::borrow::Cow;
 #[cfg(feature = "std")]
 use alloc::string::ToString;
 use alloc::{boxed::Box, vec::Vec};
@@ -10,8 +12,8 @@ use std::net::TcpStream;
 
 #[cfg(feature = "llmp_compression")]
 use libafl_bolts::{
-    compress::GzipCompressor,
-    llmp::{LLMP_FLAG_COMPRESSED, LLMP_FLAG_INITIALIZED},
+    compress::{LlmpCompression, LlmpCompressor},
+    llmp::LLMP_FLAG_INITIALIZED,
 };
 use libafl_bolts::{
     current_time,
@@ -28,7 +30,13 @@ use libafl_bolts::{
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "llmp_compression")]
-use crate::events::llmp::COMPRESS_THRESHOLD;
+use crate::{
+    events::llmp::COMPRESS_THRESHOLD,
+    monitors::{
+        AggregatorOps, UserStats, UserStatsValue, LLMP_COMPRESSION_RATIO_STATS_NAME,
+        LLMP_COMPRESSION_TIME_STATS_NAME,
+    },
+};
 use crate::{
     events::{
         llmp::{LLMP_TAG_EVENT_TO_BOTH, _LLMP_TAG_EVENT_TO_BROKER},
@@ -37,6 +45,7 @@ use crate::{
         EventRestarter, HasCustomBufHandlers, HasEventManagerId, ProgressReporter,
     },
     executors::{Executor, HasObservers},
+    feedbacks::ProvenanceMetadata,
     fuzzer::{Evaluator, EvaluatorObservers, ExecutionProcessor},
     inputs::{NopInput, UsesInput},
     observers::{ObserversTuple, TimeObserver},
@@ -44,6 +53,10 @@ use crate::{
     Error, HasMetadata,
 };
 
+/// The minimum time between two reports of the compression stats of a client
+#[cfg(feature = "llmp_compression")]
+const COMPRESSION_STATS_INTERVAL: Duration = Duration::from_secs(30);
+
 /// An [`EventManager`] that forwards all events to other attached fuzzers on shared maps or via tcp,
 /// using low-level message passing, `llmp`.
 pub struct LlmpEventManager<EMH, S, SP>
@@ -63,7 +76,10 @@ where
     /// The custom buf handler
     custom_buf_handlers: Vec<Box<CustomBufHandlerFn<S>>>,
     #[cfg(feature = "llmp_compression")]
-    compressor: GzipCompressor,
+    compressor: LlmpCompressor,
+    /// When the compression stats were last sent
+    #[cfg(feature = "llmp_compression")]
+    last_compression_stats: Duration,
     /// The configuration defines this specific fuzzer.
     /// A node will not re-use the observer values sent over LLMP
     /// from nodes with other configurations.
@@ -90,6 +106,8 @@ pub struct LlmpEventManagerBuilder<EMH> {
     throttle: Option<Duration>,
     hooks: EMH,
     always_interesting: bool,
+    #[cfg(feature = "llmp_compression")]
+    compression: LlmpCompression,
 }
 
 impl Default for LlmpEventManagerBuilder<()> {
@@ -106,6 +124,8 @@ impl LlmpEventManagerBuilder<()> {
             throttle: None,
             hooks: (),
             always_interesting: false,
+            #[cfg(feature = "llmp_compression")]
+            compression: LlmpCompression::default(),
         }
     }
 
@@ -115,6 +135,8 @@ impl LlmpEventManagerBuilder<()> {
             throttle: self.throttle,
             hooks,
             always_interesting: self.always_interesting,
+            #[cfg(feature = "llmp_compression")]
+            compression: self.compression,
         }
     }
 
@@ -125,6 +147,8 @@ impl LlmpEventManagerBuilder<()> {
             throttle: self.throttle,
             hooks: self.hooks,
             always_interesting,
+            #[cfg(feature = "llmp_compression")]
+            compression: self.compression,
         }
     }
 }
@@ -137,6 +161,15 @@ impl<EMH> LlmpEventManagerBuilder<EMH> {
         self
     }
 
+    /// Set the algorithm used to compress outgoing messages.
+    /// Incoming messages are decompressed with whichever algorithm they were compressed with.
+    #[cfg(feature = "llmp_compression")]
+    #[must_use]
+    pub fn compression(mut self, compression: LlmpCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
     /// Create a manager from a raw LLMP client
     pub fn build_from_client<S, SP>(
         self,
@@ -155,7 +188,9 @@ impl<EMH> LlmpEventManagerBuilder<EMH> {
             always_interesting: self.always_interesting,
             llmp,
             #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::with_threshold(COMPRESS_THRESHOLD),
+            compressor: LlmpCompressor::new(self.compression, COMPRESS_THRESHOLD),
+            #[cfg(feature = "llmp_compression")]
+            last_compression_stats: Duration::ZERO,
             configuration,
             serialization_time: Duration::ZERO,
             deserialization_time: Duration::ZERO,
@@ -189,7 +224,9 @@ impl<EMH> LlmpEventManagerBuilder<EMH> {
             always_interesting: self.always_interesting,
             llmp,
             #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::with_threshold(COMPRESS_THRESHOLD),
+            compressor: LlmpCompressor::new(self.compression, COMPRESS_THRESHOLD),
+            #[cfg(feature = "llmp_compression")]
+            last_compression_stats: Duration::ZERO,
             configuration,
             serialization_time: Duration::ZERO,
             deserialization_time: Duration::ZERO,
@@ -223,7 +260,9 @@ impl<EMH> LlmpEventManagerBuilder<EMH> {
             always_interesting: self.always_interesting,
             llmp,
             #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::with_threshold(COMPRESS_THRESHOLD),
+            compressor: LlmpCompressor::new(self.compression, COMPRESS_THRESHOLD),
+            #[cfg(feature = "llmp_compression")]
+            last_compression_stats: Duration::ZERO,
             configuration,
             serialization_time: Duration::ZERO,
             deserialization_time: Duration::ZERO,
@@ -255,7 +294,9 @@ impl<EMH> LlmpEventManagerBuilder<EMH> {
             always_interesting: self.always_interesting,
             llmp,
             #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::with_threshold(COMPRESS_THRESHOLD),
+            compressor: LlmpCompressor::new(self.compression, COMPRESS_THRESHOLD),
+            #[cfg(feature = "llmp_compression")]
+            last_compression_stats: Duration::ZERO,
             configuration,
             serialization_time: Duration::ZERO,
             deserialization_time: Duration::ZERO,
@@ -425,41 +466,45 @@ where
                 #[cfg(feature = "std")]
                 log::debug!("[{}] Received new Testcase {evt_name} from {client_id:?} ({client_config:?}, forward {forward_id:?})", std::process::id());
 
-                if self.always_interesting {
-                    let item = fuzzer.add_input(state, executor, self, input)?;
-                    log::debug!("Added received Testcase as item #{item}");
-                } else {
-                    let res = if client_config.match_with(&self.configuration)
-                        && observers_buf.is_some()
-                    {
-                        let start = current_time();
-                        let observers: E::Observers =
-                            postcard::from_bytes(observers_buf.as_ref().unwrap())?;
-                        {
-                            self.deserialization_time = current_time() - start;
-                        }
-                        #[cfg(feature = "scalability_introspection")]
-                        {
-                            state.scalability_monitor_mut().testcase_with_observers += 1;
-                        }
-                        fuzzer
-                            .evaluate_execution(state, self, input, &observers, &exit_kind, false)?
+                ProvenanceMetadata::with_transfer(state, client_id, |state| {
+                    if self.always_interesting {
+                        let item = fuzzer.add_input(state, executor, self, input)?;
+                        log::debug!("Added received Testcase as item #{item}");
                     } else {
-                        #[cfg(feature = "scalability_introspection")]
+                        let res = if client_config.match_with(&self.configuration)
+                            && observers_buf.is_some()
                         {
-                            state.scalability_monitor_mut().testcase_without_observers += 1;
+                            let start = current_time();
+                            let observers: E::Observers =
+                                postcard::from_bytes(observers_buf.as_ref().unwrap())?;
+                            {
+                                self.deserialization_time = current_time() - start;
+                            }
+                            #[cfg(feature = "scalability_introspection")]
+                            {
+                                state.scalability_monitor_mut().testcase_with_observers += 1;
+                            }
+                            fuzzer.evaluate_execution(
+                                state, self, input, &observers, &exit_kind, false,
+                            )?
+                        } else {
+                            #[cfg(feature = "scalability_introspection")]
+                            {
+                                state.scalability_monitor_mut().testcase_without_observers += 1;
+                            }
+                            fuzzer.evaluate_input_with_observers::<E>(
+                                state, executor, self, input, false,
+                            )?
+                        };
+                        if let Some(item) = res.1 {
+                            *state.imported_mut() += 1;
+                            log::debug!("Added received Testcase {evt_name} as item #{item}");
+                        } else {
+                            log::debug!("Testcase {evt_name} was discarded");
                         }
-                        fuzzer.evaluate_input_with_observers::<E>(
-                            state, executor, self, input, false,
-                        )?
-                    };
-                    if let Some(item) = res.1 {
-                        *state.imported_mut() += 1;
-                        log::debug!("Added received Testcase {evt_name} as item #{item}");
-                    } else {
-                        log::debug!("Testcase {evt_name} was discarded");
                     }
-                }
+                    Ok(())
+                })?;
             }
             Event::CustomBuf { tag, buf } => {
                 for handler in &mut self.custom_buf_handlers {
@@ -491,6 +536,44 @@ impl<EMH, S: State, SP: ShMemProvider> LlmpEventManager<EMH, S, SP> {
     pub fn send_exiting(&mut self) -> Result<(), Error> {
         self.llmp.sender_mut().send_exiting()
     }
+
+    /// The compressor for outgoing messages, keeping track of the compression stats
+    #[cfg(feature = "llmp_compression")]
+    #[must_use]
+    pub fn compressor(&self) -> &LlmpCompressor {
+        &self.compressor
+    }
+
+    /// Send the compression ratio and the time spent compressing as [`UserStats`],
+    /// see [`crate::monitors::ClientStats::llmp_compression_ratio`].
+    /// Called by [`EventFirer::fire`] for at most one heartbeat every [`COMPRESSION_STATS_INTERVAL`].
+    #[cfg(feature = "llmp_compression")]
+    fn fire_compression_stats(&mut self, state: &mut S) -> Result<(), Error>
+    where
+        EMH: EventManagerHooksTuple<S>,
+    {
+        let ratio = self.compressor.compression_ratio();
+        let time = self.compressor.compression_time();
+        self.fire(
+            state,
+            Event::UpdateUserStats {
+                name: Cow::Borrowed(LLMP_COMPRESSION_RATIO_STATS_NAME),
+                value: UserStats::new(UserStatsValue::Float(ratio), AggregatorOps::Avg),
+                phantom: PhantomData,
+            },
+        )?;
+        self.fire(
+            state,
+            Event::UpdateUserStats {
+                name: Cow::Borrowed(LLMP_COMPRESSION_TIME_STATS_NAME),
+                value: UserStats::new(
+                    UserStatsValue::Float(time.as_secs_f64()),
+                    AggregatorOps::Sum,
+                ),
+                phantom: PhantomData,
+            },
+        )
+    }
 }
 
 impl<EMH, S, SP> UsesState for LlmpEventManager<EMH, S, SP>
@@ -503,6 +586,7 @@ where
 
 impl<EMH, S, SP> EventFirer for LlmpEventManager<EMH, S, SP>
 where
+    EMH: EventManagerHooksTuple<S>,
     S: State,
     SP: ShMemProvider,
 {
@@ -517,17 +601,19 @@ where
     #[cfg(feature = "llmp_compression")]
     fn fire(
         &mut self,
-        _state: &mut Self::State,
+        state: &mut Self::State,
         event: Event<<Self::State as UsesInput>::Input>,
     ) -> Result<(), Error> {
+        self.hooks
+            .on_fire_all(state, self.llmp.sender().id(), &event)?;
         let serialized = postcard::to_allocvec(&event)?;
         let flags = LLMP_FLAG_INITIALIZED;
 
-        match self.compressor.maybe_compress(&serialized) {
-            Some(comp_buf) => {
+        match self.compressor.maybe_compress(&serialized)? {
+            Some((comp_flags, comp_buf)) => {
                 self.llmp.send_buf_with_flags(
                     LLMP_TAG_EVENT_TO_BOTH,
-                    flags | LLMP_FLAG_COMPRESSED,
+                    flags | comp_flags,
                     &comp_buf,
                 )?;
             }
@@ -537,15 +623,33 @@ where
         }
         self.last_sent = current_time();
 
+        // Piggyback the compression stats on the heartbeats, once every `COMPRESSION_STATS_INTERVAL`
+        #[cfg(not(feature = "introspection"))]
+        let is_heartbeat = matches!(event, Event::UpdateExecStats { .. });
+        #[cfg(feature = "introspection")]
+        let is_heartbeat = matches!(
+            event,
+            Event::UpdateExecStats { .. } | Event::UpdatePerfMonitor { .. }
+        );
+        if is_heartbeat
+            && self.last_sent.saturating_sub(self.last_compression_stats)
+                >= COMPRESSION_STATS_INTERVAL
+        {
+            self.last_compression_stats = self.last_sent;
+            self.fire_compression_stats(state)?;
+        }
+
         Ok(())
     }
 
     #[cfg(not(feature = "llmp_compression"))]
     fn fire(
         &mut self,
-        _state: &mut Self::State,
+        state: &mut Self::State,
         event: Event<<Self::State as UsesInput>::Input>,
     ) -> Result<(), Error> {
+        self.hooks
+            .on_fire_all(state, self.llmp.sender().id(), &event)?;
         let serialized = postcard::to_allocvec(&event)?;
         self.llmp.send_buf(LLMP_TAG_EVENT_TO_BOTH, &serialized)?;
         Ok(())
@@ -617,8 +721,8 @@ where
             #[cfg(feature = "llmp_compression")]
             let compressed;
             #[cfg(feature = "llmp_compression")]
-            let event_bytes = if flags & LLMP_FLAG_COMPRESSED == LLMP_FLAG_COMPRESSED {
-                compressed = self.compressor.decompress(msg)?;
+            let event_bytes = if LlmpCompressor::is_compressed(flags) {
+                compressed = self.compressor.decompress(flags, msg)?;
                 &compressed
             } else {
                 msg
@@ -672,6 +776,7 @@ where
 
 impl<EMH, S, SP> ProgressReporter for LlmpEventManager<EMH, S, SP>
 where
+    EMH: EventManagerHooksTuple<S>,
     S: State + HasExecutions + HasMetadata + HasLastReportTime,
     SP: ShMemProvider,
 {
@@ -5,8 +5,8 @@ use core::{marker::PhantomData, time::Duration};
 
 #[cfg(feature = "llmp_compression")]
 use libafl_bolts::{
-    compress::GzipCompressor,
-    llmp::{LLMP_FLAG_COMPRESSED, LLMP_FLAG_INITIALIZED},
+    compress::{LlmpCompression, LlmpCompressor},
+    llmp::LLMP_FLAG_INITIALIZED,
 };
 use libafl_bolts::{
     llmp::{LlmpClient, LlmpClientDescription, Tag},
@@ -18,6 +18,7 @@ use serde::Deserialize;
 use crate::{
     events::{CustomBufEventResult, CustomBufHandlerFn, Event, EventFirer},
     executors::{Executor, HasObservers},
+    feedbacks::ProvenanceMetadata,
     fuzzer::{EvaluatorObservers, ExecutionProcessor},
     inputs::{Input, InputConverter, NopInput, NopInputConverter, UsesInput},
     state::{HasExecutions, NopState, State, Stoppable, UsesState},
@@ -98,7 +99,7 @@ where
     /// The custom buf handler
     custom_buf_handlers: Vec<Box<CustomBufHandlerFn<S>>>,
     #[cfg(feature = "llmp_compression")]
-    compressor: GzipCompressor,
+    compressor: LlmpCompressor,
     converter: Option<IC>,
     converter_back: Option<ICB>,
     phantom: PhantomData<S>,
@@ -124,21 +125,35 @@ impl
 #[derive(Debug, Clone, Default)]
 pub struct LlmpEventConverterBuilder {
     throttle: Option<Duration>,
+    #[cfg(feature = "llmp_compression")]
+    compression: LlmpCompression,
 }
 
 impl LlmpEventConverterBuilder {
     #[must_use]
     /// Constructor
     pub fn new() -> Self {
-        Self { throttle: None }
+        Self {
+            throttle: None,
+            #[cfg(feature = "llmp_compression")]
+            compression: LlmpCompression::default(),
+        }
     }
 
     #[must_use]
     /// Sets the `throttle`
-    pub fn throttle(self, throttle: Duration) -> Self {
-        Self {
-            throttle: Some(throttle),
-        }
+    pub fn throttle(mut self, throttle: Duration) -> Self {
+        self.throttle = Some(throttle);
+        self
+    }
+
+    /// Set the algorithm used to compress outgoing messages.
+    /// Incoming messages are decompressed with whichever algorithm they were compressed with.
+    #[cfg(feature = "llmp_compression")]
+    #[must_use]
+    pub fn compression(mut self, compression: LlmpCompression) -> Self {
+        self.compression = compression;
+        self
     }
 
     /// Create a event converter from a raw llmp client
@@ -160,7 +175,7 @@ impl LlmpEventConverterBuilder {
             last_sent: Duration::from_secs(0),
             llmp,
             #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::with_threshold(COMPRESS_THRESHOLD),
+            compressor: LlmpCompressor::new(self.compression, COMPRESS_THRESHOLD),
             converter,
             converter_back,
             phantom: PhantomData,
@@ -190,7 +205,7 @@ impl LlmpEventConverterBuilder {
             last_sent: Duration::from_secs(0),
             llmp,
             #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::with_threshold(COMPRESS_THRESHOLD),
+            compressor: LlmpCompressor::new(self.compression, COMPRESS_THRESHOLD),
             converter,
             converter_back,
             phantom: PhantomData,
@@ -220,7 +235,7 @@ impl LlmpEventConverterBuilder {
             last_sent: Duration::from_secs(0),
             llmp,
             #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::with_threshold(COMPRESS_THRESHOLD),
+            compressor: LlmpCompressor::new(self.compression, COMPRESS_THRESHOLD),
             converter,
             converter_back,
             phantom: PhantomData,
@@ -308,13 +323,12 @@ where
                     return Ok(());
                 };
 
-                let res = fuzzer.evaluate_input_with_observers::<E>(
-                    state,
-                    executor,
-                    manager,
-                    converter.convert(input)?,
-                    false,
-                )?;
+                let input = converter.convert(input)?;
+                let res = ProvenanceMetadata::with_transfer(state, client_id, |state| {
+                    fuzzer.evaluate_input_with_observers::<E>(
+                        state, executor, manager, input, false,
+                    )
+                })?;
 
                 if let Some(item) = res.1 {
                     log::info!("Added received Testcase as item #{item}");
@@ -369,8 +383,8 @@ where
             #[cfg(feature = "llmp_compression")]
             let compressed;
             #[cfg(feature = "llmp_compression")]
-            let event_bytes = if _flags & LLMP_FLAG_COMPRESSED == LLMP_FLAG_COMPRESSED {
-                compressed = self.compressor.decompress(msg)?;
+            let event_bytes = if LlmpCompressor::is_compressed(_flags) {
+                compressed = self.compressor.decompress(_flags, msg)?;
                 &compressed
             } else {
                 msg
@@ -453,11 +467,11 @@ where
         let serialized = postcard::to_allocvec(&converted_event)?;
         let flags = LLMP_FLAG_INITIALIZED;
 
-        match self.compressor.maybe_compress(&serialized) {
-            Some(comp_buf) => {
+        match self.compressor.maybe_compress(&serialized)? {
+            Some((comp_flags, comp_buf)) => {
                 self.llmp.send_buf_with_flags(
                     LLMP_TAG_EVENT_TO_BOTH,
-                    flags | LLMP_FLAG_COMPRESSED,
+                    flags | comp_flags,
                     &comp_buf,
                 )?;
             }
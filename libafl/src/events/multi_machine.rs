@@ -14,7 +14,7 @@ use std::{
 
 use enumflags2::{bitflags, BitFlags};
 #[cfg(feature = "llmp_compression")]
-use libafl_bolts::compress::GzipCompressor;
+use libafl_bolts::compress::LlmpCompressor;
 use libafl_bolts::{current_time, ownedref::OwnedRef, Error};
 use serde::{Deserialize, Serialize};
 use tokio::{
@@ -131,7 +131,7 @@ pub struct TcpMultiMachineState<A> {
     children: HashMap<NodeId, TcpStream>, // The children who connected during the fuzzing session.
     old_msgs: Vec<Vec<u8>>,
     #[cfg(feature = "llmp_compression")]
-    compressor: GzipCompressor,
+    compressor: LlmpCompressor,
 }
 
 /// The tree descriptor for the
@@ -240,7 +240,7 @@ where
             children: HashMap::default(),
             old_msgs: Vec::new(),
             #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::new(),
+            compressor: LlmpCompressor::with_threshold(0),
         }));
 
         let rt =
@@ -357,8 +357,8 @@ where
 
     /// The compressor
     #[cfg(feature = "llmp_compression")]
-    pub fn compressor(&mut self) -> &GzipCompressor {
-        &self.compressor
+    pub fn compressor(&mut self) -> &mut LlmpCompressor {
+        &mut self.compressor
     }
 
     /// Read a [`TcpMultiMachineMsg`] from a stream.
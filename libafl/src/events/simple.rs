@@ -25,8 +25,8 @@ use super::{CustomBufEventResult, CustomBufHandlerFn, HasCustomBufHandlers, Prog
 use crate::events::EVENTMGR_SIGHANDLER_STATE;
 use crate::{
     events::{
-        BrokerEventResult, Event, EventFirer, EventManager, EventManagerId, EventProcessor,
-        EventRestarter, HasEventManagerId,
+        BrokerEventResult, Event, EventFirer, EventManager, EventManagerHooksTuple, EventManagerId,
+        EventProcessor, EventRestarter, HasEventManagerId,
     },
     inputs::UsesInput,
     monitors::Monitor,
@@ -46,12 +46,16 @@ const _ENV_FUZZER_RECEIVER: &str = "_AFL_ENV_FUZZER_RECEIVER";
 const _ENV_FUZZER_BROKER_CLIENT_INITIAL: &str = "_AFL_ENV_FUZZER_BROKER_CLIENT";
 
 /// A simple, single-threaded event manager that just logs
-pub struct SimpleEventManager<MT, S>
+///
+/// Its [`crate::events::EventManagerHook`]s only see the fired events, as there are no other clients to receive events from.
+pub struct SimpleEventManager<MT, S, EMH = ()>
 where
     S: UsesInput + Stoppable,
 {
     /// The monitor
     monitor: MT,
+    /// The hooks called for each fired event
+    hooks: EMH,
     /// The events that happened since the last `handle_in_broker`
     events: Vec<Event<S::Input>>,
     /// The custom buf handler
@@ -59,7 +63,7 @@ where
     phantom: PhantomData<S>,
 }
 
-impl<MT, S> Debug for SimpleEventManager<MT, S>
+impl<MT, S, EMH> Debug for SimpleEventManager<MT, S, EMH>
 where
     MT: Debug,
     S: UsesInput + Stoppable,
@@ -73,15 +77,16 @@ where
     }
 }
 
-impl<MT, S> UsesState for SimpleEventManager<MT, S>
+impl<MT, S, EMH> UsesState for SimpleEventManager<MT, S, EMH>
 where
     S: State,
 {
     type State = S;
 }
 
-impl<MT, S> EventFirer for SimpleEventManager<MT, S>
+impl<MT, S, EMH> EventFirer for SimpleEventManager<MT, S, EMH>
 where
+    EMH: EventManagerHooksTuple<S>,
     MT: Monitor,
     S: State,
 {
@@ -91,9 +96,10 @@ where
 
     fn fire(
         &mut self,
-        _state: &mut Self::State,
+        state: &mut Self::State,
         event: Event<<Self::State as UsesInput>::Input>,
     ) -> Result<(), Error> {
+        self.hooks.on_fire_all(state, ClientId(0), &event)?;
         match Self::handle_in_broker(&mut self.monitor, &event)? {
             BrokerEventResult::Forward => self.events.push(event),
             BrokerEventResult::Handled => (),
@@ -102,15 +108,17 @@ where
     }
 }
 
-impl<MT, S> EventRestarter for SimpleEventManager<MT, S>
+impl<MT, S, EMH> EventRestarter for SimpleEventManager<MT, S, EMH>
 where
+    EMH: EventManagerHooksTuple<S>,
     MT: Monitor,
     S: State,
 {
 }
 
-impl<E, MT, S, Z> EventProcessor<E, Z> for SimpleEventManager<MT, S>
+impl<E, EMH, MT, S, Z> EventProcessor<E, Z> for SimpleEventManager<MT, S, EMH>
 where
+    EMH: EventManagerHooksTuple<S>,
     MT: Monitor,
     S: State,
 {
@@ -132,14 +140,15 @@ where
     }
 }
 
-impl<E, MT, S, Z> EventManager<E, Z> for SimpleEventManager<MT, S>
+impl<E, EMH, MT, S, Z> EventManager<E, Z> for SimpleEventManager<MT, S, EMH>
 where
+    EMH: EventManagerHooksTuple<S>,
     MT: Monitor,
     S: State + HasExecutions + HasLastReportTime + HasMetadata,
 {
 }
 
-impl<MT, S> HasCustomBufHandlers for SimpleEventManager<MT, S>
+impl<MT, S, EMH> HasCustomBufHandlers for SimpleEventManager<MT, S, EMH>
 where
     MT: Monitor, //CE: CustomEvent<I, OT>,
     S: State,
@@ -155,14 +164,15 @@ where
     }
 }
 
-impl<MT, S> ProgressReporter for SimpleEventManager<MT, S>
+impl<MT, S, EMH> ProgressReporter for SimpleEventManager<MT, S, EMH>
 where
+    EMH: EventManagerHooksTuple<S>,
     MT: Monitor,
     S: State + HasExecutions + HasMetadata + HasLastReportTime,
 {
 }
 
-impl<MT, S> HasEventManagerId for SimpleEventManager<MT, S>
+impl<MT, S, EMH> HasEventManagerId for SimpleEventManager<MT, S, EMH>
 where
     MT: Monitor,
     S: UsesInput + Stoppable,
@@ -191,8 +201,20 @@ where
 {
     /// Creates a new [`SimpleEventManager`].
     pub fn new(monitor: MT) -> Self {
+        SimpleEventManager::with_hooks(monitor, ())
+    }
+}
+
+impl<MT, S, EMH> SimpleEventManager<MT, S, EMH>
+where
+    MT: Monitor, //TODO CE: CustomEvent,
+    S: UsesInput + Stoppable,
+{
+    /// Creates a new [`SimpleEventManager`], calling the `hooks` for each fired event.
+    pub fn with_hooks(monitor: MT, hooks: EMH) -> Self {
         Self {
             monitor,
+            hooks,
             events: vec![],
             custom_buf_handlers: vec![],
             phantom: PhantomData,
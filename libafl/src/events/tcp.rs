@@ -48,6 +48,7 @@ use crate::{
         ProgressReporter,
     },
     executors::{Executor, HasObservers},
+    feedbacks::ProvenanceMetadata,
     fuzzer::{EvaluatorObservers, ExecutionProcessor},
     inputs::{Input, UsesInput},
     monitors::Monitor,
@@ -621,24 +622,24 @@ where
             } => {
                 log::info!("Received new Testcase from {client_id:?} ({client_config:?}, forward {forward_id:?})");
 
-                let _res = if client_config.match_with(&self.configuration)
-                    && observers_buf.is_some()
-                {
-                    let observers: E::Observers =
-                        postcard::from_bytes(observers_buf.as_ref().unwrap())?;
-                    #[cfg(feature = "scalability_introspection")]
-                    {
-                        state.scalability_monitor_mut().testcase_with_observers += 1;
-                    }
-                    fuzzer.evaluate_execution(state, self, input, &observers, &exit_kind, false)?
-                } else {
-                    #[cfg(feature = "scalability_introspection")]
-                    {
-                        state.scalability_monitor_mut().testcase_without_observers += 1;
+                let _res = ProvenanceMetadata::with_transfer(state, client_id, |state| {
+                    if client_config.match_with(&self.configuration) && observers_buf.is_some() {
+                        let observers: E::Observers =
+                            postcard::from_bytes(observers_buf.as_ref().unwrap())?;
+                        #[cfg(feature = "scalability_introspection")]
+                        {
+                            state.scalability_monitor_mut().testcase_with_observers += 1;
+                        }
+                        fuzzer.evaluate_execution(state, self, input, &observers, &exit_kind, false)
+                    } else {
+                        #[cfg(feature = "scalability_introspection")]
+                        {
+                            state.scalability_monitor_mut().testcase_without_observers += 1;
+                        }
+                        fuzzer
+                            .evaluate_input_with_observers::<E>(state, executor, self, input, false)
                     }
-                    fuzzer
-                        .evaluate_input_with_observers::<E>(state, executor, self, input, false)?
-                };
+                })?;
                 if let Some(item) = _res.1 {
                     *state.imported_mut() += 1;
                     log::info!("Added received Testcase as item #{item}");
@@ -704,9 +705,10 @@ where
 
     fn fire(
         &mut self,
-        _state: &mut Self::State,
+        state: &mut Self::State,
         event: Event<<Self::State as UsesInput>::Input>,
     ) -> Result<(), Error> {
+        self.hooks.on_fire_all(state, self.client_id, &event)?;
         let serialized = postcard::to_allocvec(&event)?;
 
         #[cfg(feature = "tcp_compression")]
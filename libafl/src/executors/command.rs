@@ -1,5 +1,5 @@
 //! The command executor executes a sub program for each run
-use alloc::vec::Vec;
+use alloc::{string::ToString, vec::Vec};
 use core::{
     fmt::{self, Debug, Formatter},
     marker::PhantomData,
@@ -16,15 +16,16 @@ use std::{
     os::unix::ffi::OsStrExt,
     path::{Path, PathBuf},
     process::{Child, Command, Stdio},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 #[cfg(target_os = "linux")]
 use libafl_bolts::core_affinity::CoreId;
 use libafl_bolts::{
     fs::{get_unique_std_input_file, InputFile},
+    shmem::{ShMem, ShMemProvider, UnixShMem, UnixShMemProvider},
     tuples::{Handle, MatchName, RefIndexable},
-    AsSlice,
+    AsSlice, AsSliceMut,
 };
 #[cfg(target_os = "linux")]
 use libc::STDIN_FILENO;
@@ -48,7 +49,10 @@ use typed_builder::TypedBuilder;
 use super::HasTimeout;
 use crate::{
     corpus::Corpus,
-    executors::{hooks::ExecutorHooksTuple, Executor, ExitKind, HasObservers},
+    executors::{
+        hooks::ExecutorHooksTuple, resource_limits::ResourceLimits, Executor, ExitKind,
+        HasObservers,
+    },
     inputs::{HasTargetBytes, Input, UsesInput},
     observers::{ObserversTuple, StdErrObserver, StdOutObserver},
     state::{HasCorpus, HasExecutions, State, UsesState},
@@ -56,9 +60,20 @@ use crate::{
     Error,
 };
 
+/// The environment variable holding the id of the shared memory used for [`InputLocation::SharedMemory`].
+/// Its size is in the variable of the same name, suffixed with `_SIZE`.
+pub const SHM_INPUT_ENV_VAR: &str = "__LIBAFL_SHM_INPUT_ID";
+
+/// The size of the header preceding the input in the shared memory: the input length, as native endian `u32`
+pub const SHM_INPUT_HDR_SIZE: usize = 4;
+
+/// The initial size of the shared memory used for [`InputLocation::SharedMemory`]
+const SHM_INPUT_SIZE_DEFAULT: usize = 1024 * 1024;
+
 /// How to deliver input to an external program
 /// `StdIn`: The target reads from stdin
 /// `File`: The target reads from the specified [`InputFile`]
+/// `SharedMemory`: The target reads from shared memory
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub enum InputLocation {
     /// Mutate a commandline argument to deliver an input
@@ -75,6 +90,53 @@ pub enum InputLocation {
         /// The file to write input to. The target should read input from this location.
         out_file: InputFile,
     },
+    /// Deliver the input via shared memory, avoiding filesystem I/O.
+    ///
+    /// The id of the shared memory is passed in the [`SHM_INPUT_ENV_VAR`] environment variable.
+    /// It starts with a [`SHM_INPUT_HDR_SIZE`] bytes header holding the input length, followed by the input.
+    /// Targets can read it using `libafl_targets`' `shmem_input` module.
+    SharedMemory,
+}
+
+/// The shared memory an input is delivered through, for [`InputLocation::SharedMemory`]
+#[derive(Debug)]
+struct ShMemInput {
+    provider: UnixShMemProvider,
+    shmem: UnixShMem,
+}
+
+impl ShMemInput {
+    /// Creates the shared memory, and passes it to the `command`
+    fn new(command: &mut Command) -> Result<Self, Error> {
+        let mut provider = UnixShMemProvider::new()?;
+        let shmem = provider.new_shmem(SHM_INPUT_SIZE_DEFAULT)?;
+        let shmem_input = Self { provider, shmem };
+        shmem_input.set_env(command);
+        Ok(shmem_input)
+    }
+
+    fn set_env(&self, command: &mut Command) {
+        command.env(SHM_INPUT_ENV_VAR, self.shmem.id().to_string());
+        command.env(
+            format!("{SHM_INPUT_ENV_VAR}_SIZE"),
+            self.shmem.len().to_string(),
+        );
+    }
+
+    /// Writes the input to the shared memory.
+    /// If it is too small, a larger one is allocated, and passed to the `command` instead.
+    fn write(&mut self, command: &mut Command, input: &[u8]) -> Result<(), Error> {
+        let len = u32::try_from(input.len())?;
+        let size = SHM_INPUT_HDR_SIZE + input.len();
+        if size > self.shmem.len() {
+            self.shmem = self.provider.new_shmem(size.next_power_of_two())?;
+            self.set_env(command);
+        }
+        let map = self.shmem.as_slice_mut();
+        map[..SHM_INPUT_HDR_SIZE].copy_from_slice(&len.to_ne_bytes());
+        map[SHM_INPUT_HDR_SIZE..size].copy_from_slice(input);
+        Ok(())
+    }
 }
 
 /// A simple Configurator that takes the most common parameters
@@ -91,8 +153,12 @@ pub struct StdCommandConfigurator {
     timeout: Duration,
     /// true: input gets delivered via stdink
     input_location: InputLocation,
+    /// The shared memory for [`InputLocation::SharedMemory`]
+    input_shmem: Option<ShMemInput>,
     /// The Command to execute
     command: Command,
+    /// The limits the child is killed for going over
+    resource_limits: Option<ResourceLimits>,
 }
 
 impl<I> CommandConfigurator<I> for StdCommandConfigurator
@@ -167,6 +233,14 @@ where
                 out_file.write_buf(input.target_bytes().as_slice())?;
                 Ok(self.command.spawn()?)
             }
+            InputLocation::SharedMemory => {
+                let shmem_input = self
+                    .input_shmem
+                    .as_mut()
+                    .ok_or_else(|| Error::illegal_state("No shared memory set up for the input"))?;
+                shmem_input.write(&mut self.command, input.target_bytes().as_slice())?;
+                Ok(self.command.spawn()?)
+            }
         }
     }
 
@@ -176,6 +250,10 @@ where
     fn exec_timeout_mut(&mut self) -> &mut Duration {
         &mut self.timeout
     }
+
+    fn resource_limits(&self) -> Option<&ResourceLimits> {
+        self.resource_limits.as_ref()
+    }
 }
 
 /// Linux specific [`CommandConfigurator`] that leverages `ptrace`
@@ -213,6 +291,12 @@ where
             unistd::{alarm, dup2, execve, fork, pipe, write, ForkResult},
         };
 
+        if self.input_location == InputLocation::SharedMemory {
+            return Err(Error::unsupported(
+                "Shared memory input delivery is not supported by the PTraceCommandConfigurator",
+            ));
+        }
+
         match unsafe { fork() } {
             Ok(ForkResult::Parent { child }) => Ok(child),
             Ok(ForkResult::Child) => {
@@ -249,6 +333,7 @@ where
                     InputLocation::File { out_file } => {
                         out_file.write_buf(input.target_bytes().as_slice()).unwrap();
                     }
+                    InputLocation::SharedMemory => unreachable!(),
                 }
 
                 ptrace::traceme().unwrap();
@@ -301,6 +386,7 @@ impl CommandExecutor<(), (), ()> {
     /// * `arg_input_arg` for input delivered _as_ a command line argument
     /// * `arg_input_file` for input via a file of a specific name
     /// * `arg_input_file_std` for a file with default name (at the right location in the arguments)
+    /// * `arg_input_shmem` for input via shared memory
     #[must_use]
     pub fn builder() -> CommandExecutorBuilder {
         CommandExecutorBuilder::new()
@@ -348,18 +434,26 @@ where
 
         let mut child = self.configurer.spawn_child(input)?;
 
-        let exit_kind = child
-            .wait_timeout(self.configurer.exec_timeout())
-            .expect("waiting on child failed")
-            .map(|status| self.configurer.exit_kind_from_status(&status))
-            .unwrap_or_else(|| {
-                // if this fails, there is not much we can do. let's hope it failed because the process finished
-                // in the meantime.
-                drop(child.kill());
-                // finally, try to wait to properly clean up system resources.
-                drop(child.wait());
-                ExitKind::Timeout
-            });
+        let exit_kind = match self.configurer.resource_limits() {
+            Some(limits) if !limits.is_empty() => {
+                wait_child_limited(&mut child, self.configurer.exec_timeout(), limits)?
+                    .map(|status| self.configurer.exit_kind_from_status(&status))
+            }
+            _ => child
+                .wait_timeout(self.configurer.exec_timeout())
+                .expect("waiting on child failed")
+                .map_or(Err(ExitKind::Timeout), |status| {
+                    Ok(self.configurer.exit_kind_from_status(&status))
+                }),
+        }
+        .unwrap_or_else(|exit_kind| {
+            // if this fails, there is not much we can do. let's hope it failed because the process finished
+            // in the meantime.
+            drop(child.kill());
+            // finally, try to wait to properly clean up system resources.
+            drop(child.wait());
+            exit_kind
+        });
 
         self.observers
             .post_exec_child_all(state, input, &exit_kind)?;
@@ -390,6 +484,35 @@ where
     }
 }
 
+/// Waits for `child` until `timeout`, checking it against the resource `limits` in between.
+///
+/// Returns the exit status, or the [`ExitKind`] to report if the child has to be killed:
+/// [`ExitKind::Timeout`] when it ran for too long, [`ExitKind::Oom`] when it went over a limit.
+fn wait_child_limited(
+    child: &mut Child,
+    timeout: Duration,
+    limits: &ResourceLimits,
+) -> Result<Result<std::process::ExitStatus, ExitKind>, Error> {
+    use wait_timeout::ChildExt;
+
+    let deadline = Instant::now() + timeout;
+    #[allow(clippy::cast_possible_wrap)]
+    let pid = child.id() as i32;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if let Some(status) = child.wait_timeout(remaining.min(limits.poll_interval()))? {
+            return Ok(Ok(status));
+        }
+        if let Some(breach) = limits.check(pid) {
+            log::info!("Killing child {pid}: {breach}");
+            return Ok(Err(ExitKind::Oom));
+        }
+        if Instant::now() >= deadline {
+            return Ok(Err(ExitKind::Timeout));
+        }
+    }
+}
+
 impl<EM, OT, S, T, Z> Executor<EM, Z> for CommandExecutor<OT, S, T>
 where
     EM: UsesState<State = S>,
@@ -540,6 +663,7 @@ pub struct CommandExecutorBuilder {
     cwd: Option<PathBuf>,
     envs: Vec<(OsString, OsString)>,
     timeout: Duration,
+    resource_limits: Option<ResourceLimits>,
 }
 
 impl Default for CommandExecutorBuilder {
@@ -562,6 +686,7 @@ impl CommandExecutorBuilder {
             envs: vec![],
             timeout: Duration::from_secs(5),
             debug_child: false,
+            resource_limits: None,
         }
     }
 
@@ -631,6 +756,13 @@ impl CommandExecutorBuilder {
         self
     }
 
+    /// Sets the input mode to [`InputLocation::SharedMemory`].
+    /// The shared memory grows as needed, to fit larger inputs.
+    pub fn arg_input_shmem(&mut self) -> &mut Self {
+        self.input(InputLocation::SharedMemory);
+        self
+    }
+
     /// Adds an argument to the program's commandline.
     pub fn arg<O: AsRef<OsStr>>(&mut self, arg: O) -> &mut CommandExecutorBuilder {
         self.args.push(arg.as_ref().to_owned());
@@ -692,6 +824,13 @@ impl CommandExecutorBuilder {
         self
     }
 
+    /// Sets the [`ResourceLimits`] of the child.
+    /// A child going over them is killed, and the run is reported as [`ExitKind::Oom`].
+    pub fn resource_limits(&mut self, limits: ResourceLimits) -> &mut CommandExecutorBuilder {
+        self.resource_limits = Some(limits);
+        self
+    }
+
     /// Builds the `CommandExecutor`
     pub fn build<OT, S>(
         &self,
@@ -713,7 +852,9 @@ impl CommandExecutorBuilder {
             InputLocation::StdIn => {
                 command.stdin(Stdio::piped());
             }
-            InputLocation::File { .. } | InputLocation::Arg { .. } => {
+            InputLocation::File { .. }
+            | InputLocation::Arg { .. }
+            | InputLocation::SharedMemory => {
                 command.stdin(Stdio::null());
             }
         }
@@ -739,13 +880,21 @@ impl CommandExecutorBuilder {
             command.stderr(Stdio::piped());
         }
 
+        let input_shmem = if self.input_location == InputLocation::SharedMemory {
+            Some(ShMemInput::new(&mut command)?)
+        } else {
+            None
+        };
+
         let configurator = StdCommandConfigurator {
             debug_child: self.debug_child,
             stdout_observer: self.stdout.clone(),
             stderr_observer: self.stderr.clone(),
             input_location: self.input_location.clone(),
+            input_shmem,
             timeout: self.timeout,
             command,
+            resource_limits: self.resource_limits,
         };
         Ok(
             <StdCommandConfigurator as CommandConfigurator<S::Input>>::into_executor::<OT, S>(
@@ -817,6 +966,12 @@ pub trait CommandConfigurator<I, C = Child>: Sized {
     /// Set the timeout duration for execution of the child process.
     fn exec_timeout_mut(&mut self) -> &mut Duration;
 
+    /// The [`ResourceLimits`] of the child process, if any.
+    /// A child going over them is killed, and the run is reported as [`ExitKind::Oom`].
+    fn resource_limits(&self) -> Option<&ResourceLimits> {
+        None
+    }
+
     /// Maps the exit status of the child process to an `ExitKind`.
     #[inline]
     fn exit_kind_from_status(&self, status: &std::process::ExitStatus) -> ExitKind {
@@ -882,11 +1037,13 @@ fn waitpid_filtered(pid: Pid, options: Option<WaitPidFlag>) -> Result<WaitStatus
 
 #[cfg(test)]
 mod tests {
+    use libafl_bolts::HasLen;
+
     use crate::{
         events::SimpleEventManager,
         executors::{
-            command::{CommandExecutor, InputLocation},
-            Executor,
+            command::{CommandExecutor, InputLocation, SHM_INPUT_ENV_VAR, SHM_INPUT_HDR_SIZE},
+            Executor, ExitKind, ResourceLimits,
         },
         fuzzer::NopFuzzer,
         inputs::BytesInput,
@@ -917,4 +1074,66 @@ mod tests {
             )
             .unwrap();
     }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_shmem_input() {
+        let mut mgr = SimpleEventManager::new(SimpleMonitor::new(|status| {
+            log::info!("{status}");
+        }));
+
+        let mut executor = CommandExecutor::builder();
+        executor
+            .program("sh")
+            .arg("-c")
+            .arg(format!("test -n \"${SHM_INPUT_ENV_VAR}\""))
+            .arg_input_shmem();
+        let mut executor = executor.build(()).unwrap();
+
+        // Larger than the initial shared memory
+        let input = BytesInput::new(vec![0x41; 2 * 1024 * 1024]);
+        let exit_kind = executor
+            .run_target(
+                &mut NopFuzzer::new(),
+                &mut NopState::new(),
+                &mut mgr,
+                &input,
+            )
+            .unwrap();
+        assert_eq!(exit_kind, ExitKind::Ok);
+
+        let shmem = &executor.configurer.input_shmem.as_ref().unwrap().shmem;
+        assert!(shmem.len() >= SHM_INPUT_HDR_SIZE + input.len());
+        assert_eq!(shmem[..SHM_INPUT_HDR_SIZE], 0x0020_0000_u32.to_ne_bytes());
+        assert_eq!(shmem[SHM_INPUT_HDR_SIZE], 0x41);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_resource_limits() {
+        if !std::path::Path::new("/proc/self/stat").exists() {
+            return;
+        }
+        let mut mgr = SimpleEventManager::new(SimpleMonitor::new(|status| {
+            log::info!("{status}");
+        }));
+
+        let mut executor = CommandExecutor::builder();
+        executor
+            .program("sh")
+            .arg("-c")
+            .arg("sleep 2 & sleep 2 & wait")
+            .resource_limits(ResourceLimits::new().with_max_children(1));
+        let mut executor = executor.build(()).unwrap();
+
+        let exit_kind = executor
+            .run_target(
+                &mut NopFuzzer::new(),
+                &mut NopState::new(),
+                &mut mgr,
+                &BytesInput::new(vec![]),
+            )
+            .unwrap();
+        assert_eq!(exit_kind, ExitKind::Oom);
+    }
 }
@@ -4,6 +4,7 @@ use alloc::{borrow::ToOwned, string::ToString, vec::Vec};
 use core::{
     fmt::{self, Debug, Formatter},
     marker::PhantomData,
+    slice,
     time::Duration,
 };
 use std::{
@@ -16,6 +17,7 @@ use std::{
     },
     path::Path,
     process::{Child, Command, Stdio},
+    time::Instant,
 };
 
 use libafl_bolts::{
@@ -37,13 +39,13 @@ use nix::{
     unistd::Pid,
 };
 
-use super::HasTimeout;
+use super::{BatchExecutor, HasTimeout};
 #[cfg(feature = "regex")]
 use crate::observers::{
     get_asan_runtime_flags, get_asan_runtime_flags_with_log_path, AsanBacktraceObserver,
 };
 use crate::{
-    executors::{Executor, ExitKind, HasObservers},
+    executors::{resource_limits::ResourceLimits, Executor, ExitKind, HasObservers},
     inputs::{
         BytesInput, HasTargetBytes, Input, NopTargetBytesConverter, TargetBytesConverter, UsesInput,
     },
@@ -77,6 +79,9 @@ const FS_NEW_OPT_SHDMEM_FUZZ: i32 = 2_u32 as i32;
 const FS_NEW_OPT_AUTODTCT: i32 = 0x00000800_u32 as i32;
 #[allow(clippy::cast_possible_wrap)]
 const FS_OPT_AUTODTCT: i32 = 0x10000000_u32 as i32;
+/// Batched mode, a `LibAFL` extension only spoken by `libafl_targets`' forkserver
+#[allow(clippy::cast_possible_wrap)]
+const FS_NEW_OPT_BATCH: i32 = 0x00010000_u32 as i32;
 
 #[allow(clippy::cast_possible_wrap)]
 const FS_ERROR_MAP_SIZE: i32 = 1_u32 as i32;
@@ -123,6 +128,16 @@ fn report_error_and_exit(status: i32) -> Result<(), Error> {
 
 /// The length of header bytes which tells shmem size
 const SHMEM_FUZZ_HDR_SIZE: usize = 4;
+/// The env var holding the coverage map the observers look at
+const SHM_ENV_VAR: &str = "__AFL_SHM_ID";
+
+/// The env var telling the target how many children a batch can hold.
+/// Set by the [`ForkserverExecutor`] in batched mode, see [`ForkserverExecutorBuilder::batch_size`].
+pub const FS_BATCH_SIZE_ENV_VAR: &str = "__LIBAFL_FS_BATCH_SIZE";
+/// The env var holding the shared memory id of the coverage maps of a batch, one per child
+pub const FS_BATCH_MAP_ENV_VAR: &str = "__LIBAFL_FS_BATCH_MAP_ID";
+/// The env var holding the shared memory id of the inputs of a batch, one per child
+pub const FS_BATCH_INPUT_ENV_VAR: &str = "__LIBAFL_FS_BATCH_INPUT_ID";
 const MAX_INPUT_SIZE_DEFAULT: usize = 1024 * 1024;
 const MIN_INPUT_SIZE_DEFAULT: usize = 1;
 
@@ -604,10 +619,147 @@ impl Forkserver {
     }
 }
 
+/// The shared memory of the batched mode, with one slot per child in flight
+#[derive(Debug)]
+struct ForkserverBatch<SHM> {
+    /// The maximum number of children in flight
+    size: usize,
+    /// The coverage maps of the children, `map_size` bytes each
+    maps: SHM,
+    map_size: usize,
+    /// The inputs of the children, each prefixed by its length
+    inputs: SHM,
+    input_stride: usize,
+    /// The coverage map the observers look at, the results are copied there
+    observed_map: SHM,
+    /// The children of the last batch
+    pids: Vec<Pid>,
+    /// The results of the last batch
+    exit_kinds: Vec<ExitKind>,
+}
+
+/// Writes `input` to `slot`, prefixed by its length, the way the target reads shared memory testcases.
+/// The input is truncated to `max_input_size`, or zero-padded to `min_input_size`, like AFL++ does.
+fn write_shmem_testcase(
+    slot: &mut [u8],
+    input: &[u8],
+    min_input_size: usize,
+    max_input_size: usize,
+) {
+    let input_size = input.len().clamp(min_input_size, max_input_size);
+    let copied = input.len().min(input_size);
+    #[allow(clippy::cast_possible_truncation)]
+    slot[..SHMEM_FUZZ_HDR_SIZE].copy_from_slice(&(input_size as u32).to_ne_bytes());
+    let data = &mut slot[SHMEM_FUZZ_HDR_SIZE..SHMEM_FUZZ_HDR_SIZE + input_size];
+    data[..copied].copy_from_slice(&input[..copied]);
+    data[copied..].fill(0);
+}
+
+/// Reads the status of the next child the forkserver reports, or `None` once `deadline` has passed.
+///
+/// With resource `limits`, the running children in `pids` are checked in between,
+/// the ones going over a limit are killed with `SIGKILL` and flagged in `breached`.
+fn read_st_limited(
+    forkserver: &mut Forkserver,
+    limits: Option<&ResourceLimits>,
+    deadline: Instant,
+    pids: &[Pid],
+    breached: &mut [bool],
+) -> Result<Option<i32>, Error> {
+    let Some(limits) = limits.filter(|limits| !limits.is_empty()) else {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        return forkserver.read_st_timed(&remaining.into());
+    };
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let poll = remaining.min(limits.poll_interval());
+        if let Some(status) = forkserver.read_st_timed(&poll.into())? {
+            return Ok(Some(status));
+        }
+        if remaining == poll {
+            return Ok(None);
+        }
+        for (&pid, breached) in pids.iter().zip(breached.iter_mut()) {
+            if *breached {
+                continue;
+            }
+            if let Some(breach) = limits.check(pid.as_raw()) {
+                log::info!("Killing child {pid}: {breach}");
+                let _ = kill(pid, Signal::SIGKILL);
+                *breached = true;
+            }
+        }
+    }
+}
+
+/// Reads the status of each child of a batch, in the order the forkserver reports them, and turns them into exit kinds.
+///
+/// All children started together, so they share the deadline. Once it passed, all children that did not report
+/// yet are killed, and the status of each of them tells if it finished in time, or got killed.
+fn read_batch_exit_kinds(
+    forkserver: &mut Forkserver,
+    limits: Option<&ResourceLimits>,
+    timeout: Duration,
+    crash_exitcode: Option<i8>,
+    pids: &[Pid],
+) -> Result<Vec<ExitKind>, Error> {
+    let deadline = Instant::now() + timeout;
+    let mut breached = vec![false; pids.len()];
+    let mut killed = false;
+    let mut exit_kinds = Vec::with_capacity(pids.len());
+    for idx in 0..pids.len() {
+        let status = if killed {
+            None
+        } else {
+            read_st_limited(
+                forkserver,
+                limits,
+                deadline,
+                &pids[idx..],
+                &mut breached[idx..],
+            )?
+        };
+        let (status, timed_out) = if let Some(status) = status {
+            (status, false)
+        } else {
+            if !killed {
+                for &pid in &pids[idx..] {
+                    let _ = kill(pid, forkserver.kill_signal);
+                }
+                killed = true;
+            }
+            let status = forkserver.read_st().map_err(|err| {
+                Error::unknown(format!("Could not kill timed-out child: {err:?}"))
+            })?;
+            // Children reported after a hanging one may have finished before the deadline
+            let timed_out = libc::WIFSIGNALED(status)
+                && libc::WTERMSIG(status) == forkserver.kill_signal as libc::c_int;
+            (status, timed_out)
+        };
+
+        let exitcode_is_crash = crash_exitcode
+            .is_some_and(|crash_exitcode| (libc::WEXITSTATUS(status) as i8) == crash_exitcode);
+        let exit_kind = if breached[idx] {
+            ExitKind::Oom
+        } else if timed_out {
+            ExitKind::Timeout
+        } else if libc::WIFSIGNALED(status) || exitcode_is_crash {
+            ExitKind::Crash
+        } else {
+            ExitKind::Ok
+        };
+        exit_kinds.push(exit_kind);
+    }
+    Ok(exit_kinds)
+}
+
 /// This [`Executor`] can run binaries compiled for AFL/AFL++ that make use of a forkserver.
 ///
 /// Shared memory feature is also available, but you have to set things up in your code.
 /// Please refer to AFL++'s docs. <https://github.com/AFLplusplus/AFLplusplus/blob/stable/instrumentation/README.persistent_mode.md>
+///
+/// Targets built with `libafl_targets`' forkserver also support a batched mode, see [`ForkserverExecutorBuilder::batch_size`],
+/// in which several children run at the same time. Use it through the [`BatchExecutor`] trait.
 pub struct ForkserverExecutor<TC, OT, S, SP>
 where
     SP: ShMemProvider,
@@ -628,6 +780,8 @@ where
     asan_obs: Handle<AsanBacktraceObserver>,
     timeout: TimeSpec,
     crash_exitcode: Option<i8>,
+    batch: Option<ForkserverBatch<SP::ShMem>>,
+    resource_limits: Option<ResourceLimits>,
 }
 
 impl<TC, OT, S, SP> Debug for ForkserverExecutor<TC, OT, S, SP>
@@ -646,6 +800,8 @@ where
             .field("forkserver", &self.forkserver)
             .field("observers", &self.observers)
             .field("map", &self.map)
+            .field("batch", &self.batch)
+            .field("resource_limits", &self.resource_limits)
             .finish_non_exhaustive()
     }
 }
@@ -697,6 +853,11 @@ where
         self.map_size
     }
 
+    /// The number of children that can run at the same time, `1` unless in batched mode
+    pub fn batch_size(&self) -> usize {
+        self.batch.as_ref().map_or(1, |batch| batch.size)
+    }
+
     /// Execute input and increase the execution counter.
     #[inline]
     fn execute_input(&mut self, state: &mut S, input: &TC::Input) -> Result<ExitKind, Error>
@@ -705,9 +866,112 @@ where
     {
         *state.executions_mut() += 1;
 
+        if self.batch.is_some() {
+            let exit_kind = self.execute_batch_uncounted(slice::from_ref(input))?[0];
+            self.load_batch_result_at(0)?;
+            return Ok(exit_kind);
+        }
+
         self.execute_input_uncounted(input)
     }
 
+    /// Runs all `inputs` in their own child at the same time, and returns their [`ExitKind`]s in the same order.
+    /// The coverage of each child stays in its slot, until [`Self::load_batch_result_at`] copies it to the observed map.
+    fn execute_batch_uncounted(&mut self, inputs: &[TC::Input]) -> Result<Vec<ExitKind>, Error> {
+        let Some(batch) = self.batch.as_mut() else {
+            return Err(Error::illegal_state(
+                "The forkserver is not in batched mode, set a `batch_size` in the builder",
+            ));
+        };
+        if inputs.len() > batch.size {
+            return Err(Error::illegal_argument(format!(
+                "Got {} inputs, but the batch size is {}",
+                inputs.len(),
+                batch.size
+            )));
+        }
+        batch.pids.clear();
+        batch.exit_kinds.clear();
+        if inputs.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        for (idx, input) in inputs.iter().enumerate() {
+            let input_bytes = self.target_bytes_converter.to_target_bytes(input);
+            let slot = &mut batch.inputs.as_slice_mut()
+                [idx * batch.input_stride..(idx + 1) * batch.input_stride];
+            write_shmem_testcase(
+                slot,
+                input_bytes.as_slice(),
+                self.min_input_size,
+                self.max_input_size,
+            );
+            batch.maps.as_slice_mut()[idx * batch.map_size..(idx + 1) * batch.map_size].fill(0);
+        }
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        if let Err(err) = self.forkserver.write_ctl(inputs.len() as i32) {
+            return Err(Error::unknown(format!(
+                "Unable to request new processes from fork server (OOM?): {err:?}"
+            )));
+        }
+        for _ in inputs {
+            let pid = self.forkserver.read_st().map_err(|err| {
+                Error::unknown(format!(
+                    "Unable to request new processes from fork server (OOM?): {err:?}"
+                ))
+            })?;
+            if pid <= 0 {
+                return Err(Error::unknown(
+                    "Fork server is misbehaving (OOM?)".to_string(),
+                ));
+            }
+            batch.pids.push(Pid::from_raw(pid));
+        }
+
+        batch.exit_kinds = read_batch_exit_kinds(
+            &mut self.forkserver,
+            self.resource_limits.as_ref(),
+            self.timeout.into(),
+            self.crash_exitcode,
+            &batch.pids,
+        )?;
+
+        Ok(batch.exit_kinds.clone())
+    }
+
+    /// Copies the coverage of the `idx`th child of the last batch to the coverage map the observers look at.
+    fn load_batch_result_at(&mut self, idx: usize) -> Result<(), Error> {
+        let Some(batch) = self.batch.as_mut() else {
+            return Err(Error::illegal_state(
+                "The forkserver is not in batched mode, set a `batch_size` in the builder",
+            ));
+        };
+        let Some(&exit_kind) = batch.exit_kinds.get(idx) else {
+            return Err(Error::illegal_argument(format!(
+                "The last batch had no input {idx}"
+            )));
+        };
+
+        let observed_map = batch.observed_map.as_slice_mut();
+        let len = observed_map.len().min(batch.map_size);
+        observed_map[..len].copy_from_slice(
+            &batch.maps.as_slice()[idx * batch.map_size..idx * batch.map_size + len],
+        );
+
+        #[cfg(feature = "regex")]
+        if exit_kind == ExitKind::Crash {
+            let pid = batch.pids[idx].as_raw();
+            if let Some(asan_observer) = self.observers.get_mut(&self.asan_obs) {
+                asan_observer.parse_asan_output_from_asan_log_file(pid)?;
+            }
+        }
+        #[cfg(not(feature = "regex"))]
+        let _ = exit_kind;
+
+        Ok(())
+    }
+
     /// Execute input, but side-step the execution counter.
     #[inline]
     fn execute_input_uncounted(&mut self, input: &TC::Input) -> Result<ExitKind, Error> {
@@ -769,14 +1033,24 @@ where
 
         self.forkserver.set_child_pid(Pid::from_raw(pid));
 
-        if let Some(status) = self.forkserver.read_st_timed(&self.timeout)? {
+        let deadline = Instant::now() + Duration::from(self.timeout);
+        let mut breached = [false];
+        if let Some(status) = read_st_limited(
+            &mut self.forkserver,
+            self.resource_limits.as_ref(),
+            deadline,
+            &[Pid::from_raw(pid)],
+            &mut breached,
+        )? {
             self.forkserver.set_status(status);
             let exitcode_is_crash = if let Some(crash_exitcode) = self.crash_exitcode {
                 (libc::WEXITSTATUS(self.forkserver().status()) as i8) == crash_exitcode
             } else {
                 false
             };
-            if libc::WIFSIGNALED(self.forkserver().status()) || exitcode_is_crash {
+            if breached[0] {
+                exit_kind = ExitKind::Oom;
+            } else if libc::WIFSIGNALED(self.forkserver().status()) || exitcode_is_crash {
                 exit_kind = ExitKind::Crash;
                 #[cfg(feature = "regex")]
                 if let Some(asan_observer) = self.observers.get_mut(&self.asan_obs) {
@@ -793,7 +1067,11 @@ where
                     "Could not kill timed-out child: {err:?}"
                 )));
             }
-            exit_kind = ExitKind::Timeout;
+            exit_kind = if breached[0] {
+                ExitKind::Oom
+            } else {
+                ExitKind::Timeout
+            };
         }
 
         if !libc::WIFSTOPPED(self.forkserver().status()) {
@@ -827,6 +1105,8 @@ pub struct ForkserverExecutorBuilder<'a, TC, SP> {
     #[cfg(feature = "regex")]
     asan_obs: Option<Handle<AsanBacktraceObserver>>,
     crash_exitcode: Option<i8>,
+    batch_size: Option<usize>,
+    resource_limits: Option<ResourceLimits>,
     target_bytes_converter: TC,
 }
 
@@ -848,7 +1128,7 @@ where
         TC: TargetBytesConverter,
         SP: ShMemProvider,
     {
-        let (forkserver, input_file, map) = self.build_helper()?;
+        let (forkserver, input_file, map, batch) = self.build_helper()?;
 
         let target = self.program.take().unwrap();
         log::info!(
@@ -898,6 +1178,8 @@ where
                 .unwrap_or(AsanBacktraceObserver::default().handle()),
             crash_exitcode: self.crash_exitcode,
             target_bytes_converter: self.target_bytes_converter,
+            batch,
+            resource_limits: self.resource_limits,
         })
     }
 
@@ -916,7 +1198,7 @@ where
         S::Input: Input + HasTargetBytes,
         SP: ShMemProvider,
     {
-        let (forkserver, input_file, map) = self.build_helper()?;
+        let (forkserver, input_file, map, batch) = self.build_helper()?;
 
         let target = self.program.take().unwrap();
         log::info!(
@@ -964,11 +1246,24 @@ where
                 .unwrap_or(AsanBacktraceObserver::default().handle()),
             crash_exitcode: self.crash_exitcode,
             target_bytes_converter: self.target_bytes_converter,
+            batch,
+            resource_limits: self.resource_limits,
         })
     }
 
     #[allow(clippy::pedantic)]
-    fn build_helper(&mut self) -> Result<(Forkserver, InputFile, Option<SP::ShMem>), Error>
+    #[allow(clippy::type_complexity)]
+    fn build_helper(
+        &mut self,
+    ) -> Result<
+        (
+            Forkserver,
+            InputFile,
+            Option<SP::ShMem>,
+            Option<ForkserverBatch<SP::ShMem>>,
+        ),
+        Error,
+    >
     where
         SP: ShMemProvider,
     {
@@ -995,6 +1290,11 @@ where
             }
         };
 
+        let batch = match self.batch_size {
+            None => None,
+            Some(size) => Some(self.create_batch(size)?),
+        };
+
         let mut forkserver = match &self.program {
             Some(t) => Forkserver::with_kill_signal(
                 t.clone(),
@@ -1032,7 +1332,54 @@ where
         } else {
             self.initialize_forkserver(version_status, map.as_ref(), &mut forkserver)?;
         }
-        Ok((forkserver, input_file, map))
+
+        if batch.is_some() && !self.uses_shmem_testcase {
+            return Err(Error::illegal_state(
+                "Batched mode needs a target reading its input from shared memory, like with `__AFL_FUZZ_TESTCASE_BUF`",
+            ));
+        }
+        Ok((forkserver, input_file, map, batch))
+    }
+
+    /// Creates the shared memory of the batched mode, and passes it to the target in env vars.
+    fn create_batch(&mut self, size: usize) -> Result<ForkserverBatch<SP::ShMem>, Error> {
+        if self.is_persistent {
+            return Err(Error::illegal_argument(
+                "Batched mode does not support persistent targets",
+            ));
+        }
+        let Some(map_size) = self.map_size else {
+            return Err(Error::illegal_argument(
+                "Batched mode needs the coverage map size, set it with `coverage_map_size`",
+            ));
+        };
+        let Some(provider) = &mut self.shmem_provider else {
+            return Err(Error::illegal_argument(
+                "Batched mode needs a `shmem_provider` for the coverage maps and inputs of the children",
+            ));
+        };
+
+        let maps = provider.new_shmem(size * map_size)?;
+        maps.write_to_env(FS_BATCH_MAP_ENV_VAR)?;
+        let input_stride = self.max_input_size + SHMEM_FUZZ_HDR_SIZE;
+        let inputs = provider.new_shmem(size * input_stride)?;
+        inputs.write_to_env(FS_BATCH_INPUT_ENV_VAR)?;
+        let observed_map = provider.existing_from_env(SHM_ENV_VAR)?;
+        self.envs.push((
+            FS_BATCH_SIZE_ENV_VAR.into(),
+            OsString::from(size.to_string()),
+        ));
+
+        Ok(ForkserverBatch {
+            size,
+            maps,
+            map_size,
+            inputs,
+            input_stride,
+            observed_map,
+            pids: Vec::with_capacity(size),
+            exit_kinds: Vec::with_capacity(size),
+        })
     }
 
     fn is_old_forkserver(version_status: i32) -> bool {
@@ -1126,6 +1473,12 @@ where
             }
         }
 
+        if self.batch_size.is_some() && status & FS_NEW_OPT_BATCH == 0 {
+            return Err(Error::illegal_state(
+                "Batched mode requested, but the target does not support it. Build it with `libafl_targets`' forkserver.",
+            ));
+        }
+
         let aflx = forkserver.read_st().map_err(|err| {
             Error::illegal_state(format!("Reading from forkserver failed: {err:?}"))
         })?;
@@ -1147,6 +1500,12 @@ where
         map: Option<&SP::ShMem>,
         forkserver: &mut Forkserver,
     ) -> Result<(), Error> {
+        if self.batch_size.is_some() {
+            return Err(Error::illegal_state(
+                "Batched mode requested, but the target uses the old fork server model",
+            ));
+        }
+
         if status & FS_OPT_ENABLED == FS_OPT_ENABLED && status & FS_OPT_MAPSIZE == FS_OPT_MAPSIZE {
             let fsrv_map_size = fs_opt_get_mapsize(status);
             self.set_map_size(fsrv_map_size)?;
@@ -1459,6 +1818,25 @@ where
         self
     }
 
+    /// Run up to `batch_size` children at the same time, each with its own coverage map and input.
+    ///
+    /// Only targets built with `libafl_targets`' forkserver, reading their input from shared memory, support this.
+    /// It needs a `shmem_provider`, the `coverage_map_size`, and the coverage map in `__AFL_SHM_ID`:
+    /// the coverage of each child is copied there when its result is loaded, see [`BatchExecutor`].
+    #[must_use]
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = Some(batch_size.max(1));
+        self
+    }
+
+    /// Sets the [`ResourceLimits`] of the children.
+    /// A child going over them is killed, and the run is reported as [`ExitKind::Oom`].
+    #[must_use]
+    pub fn resource_limits(mut self, limits: ResourceLimits) -> Self {
+        self.resource_limits = Some(limits);
+        self
+    }
+
     /// Determine if the asan observer is present (always false if feature "regex" is disabled)
     #[cfg(feature = "regex")]
     pub fn has_asan_obs(&self) -> bool {
@@ -1502,6 +1880,8 @@ impl<'a> ForkserverExecutorBuilder<'a, NopTargetBytesConverter<BytesInput>, Unix
             #[cfg(feature = "regex")]
             asan_obs: None,
             crash_exitcode: None,
+            batch_size: None,
+            resource_limits: None,
             target_bytes_converter: NopTargetBytesConverter::new(),
         }
     }
@@ -1535,6 +1915,8 @@ impl<'a, TC> ForkserverExecutorBuilder<'a, TC, UnixShMemProvider> {
             #[cfg(feature = "regex")]
             asan_obs: self.asan_obs,
             crash_exitcode: self.crash_exitcode,
+            batch_size: self.batch_size,
+            resource_limits: self.resource_limits,
             target_bytes_converter: self.target_bytes_converter,
         }
     }
@@ -1568,6 +1950,8 @@ impl<'a, TC, SP> ForkserverExecutorBuilder<'a, TC, SP> {
             #[cfg(feature = "regex")]
             asan_obs: self.asan_obs,
             crash_exitcode: self.crash_exitcode,
+            batch_size: self.batch_size,
+            resource_limits: self.resource_limits,
             target_bytes_converter,
         }
     }
@@ -1602,6 +1986,38 @@ where
     }
 }
 
+impl<EM, TC, OT, S, SP, Z> BatchExecutor<EM, Z> for ForkserverExecutor<TC, OT, S, SP>
+where
+    OT: ObserversTuple<S::Input, S>,
+    SP: ShMemProvider,
+    S: State + HasExecutions,
+    TC: TargetBytesConverter<Input = S::Input>,
+    EM: UsesState<State = S>,
+    Z: UsesState<State = S>,
+{
+    #[inline]
+    fn batch_size(&self) -> usize {
+        self.batch_size()
+    }
+
+    fn run_batch(
+        &mut self,
+        _fuzzer: &mut Z,
+        state: &mut Self::State,
+        _mgr: &mut EM,
+        inputs: &[Self::Input],
+    ) -> Result<Vec<ExitKind>, Error> {
+        *state.executions_mut() += inputs.len() as u64;
+
+        self.execute_batch_uncounted(inputs)
+    }
+
+    #[inline]
+    fn load_batch_result(&mut self, idx: usize) -> Result<(), Error> {
+        self.load_batch_result_at(idx)
+    }
+}
+
 impl<TC, OT, S, SP> HasTimeout for ForkserverExecutor<TC, OT, S, SP>
 where
     SP: ShMemProvider,
@@ -1646,21 +2062,121 @@ where
 
 #[cfg(test)]
 mod tests {
-    use std::ffi::OsString;
+    use alloc::vec::Vec;
+    use core::time::Duration;
+    use std::{env, ffi::OsString};
 
     use libafl_bolts::{
         shmem::{ShMem, ShMemProvider, UnixShMemProvider},
         tuples::tuple_list,
         AsSliceMut,
     };
+    use nix::unistd::Pid;
     use serial_test::serial;
 
     use crate::{
-        executors::forkserver::{ForkserverExecutor, FAILED_TO_START_FORKSERVER_MSG},
+        executors::{
+            forkserver::{
+                read_batch_exit_kinds, write_shmem_testcase, Forkserver, ForkserverExecutor,
+                FAILED_TO_START_FORKSERVER_MSG, FORKSRV_FD,
+            },
+            ExitKind,
+        },
         observers::{ConstMapObserver, HitcountsMapObserver},
         Error,
     };
 
+    /// Tells [`fake_batch_forkserver`] to act as a forkserver
+    const FAKE_FORKSERVER_ENV: &str = "LIBAFL_TEST_FAKE_BATCH_FORKSERVER";
+
+    /// Acts as a batched forkserver when spawned by [`test_batch_hang`], does nothing otherwise.
+    /// In each batch, the first child hangs, the third one crashes, and the others exit right away.
+    #[test]
+    fn fake_batch_forkserver() {
+        if env::var_os(FAKE_FORKSERVER_ENV).is_none() {
+            return;
+        }
+        unsafe {
+            loop {
+                let mut count = [0_u8; 4];
+                if libc::read(FORKSRV_FD, count.as_mut_ptr().cast(), 4) != 4 {
+                    libc::_exit(0);
+                }
+                let pids: Vec<i32> = (0..u32::from_ne_bytes(count))
+                    .map(|idx| match libc::fork() {
+                        0 => match idx {
+                            0 => loop {
+                                libc::pause();
+                            },
+                            2 => libc::abort(),
+                            _ => libc::_exit(0),
+                        },
+                        pid => pid,
+                    })
+                    .collect();
+                for pid in &pids {
+                    libc::write(FORKSRV_FD + 1, (&raw const *pid).cast(), 4);
+                }
+                for &pid in &pids {
+                    let mut status = 0;
+                    libc::waitpid(pid, &raw mut status, 0);
+                    libc::write(FORKSRV_FD + 1, (&raw const status).cast(), 4);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[serial]
+    #[cfg_attr(miri, ignore)]
+    fn test_batch_hang() {
+        let mut shmem_provider = UnixShMemProvider::new().unwrap();
+        let shmem = shmem_provider.new_shmem(1024).unwrap();
+        shmem.write_to_env("__AFL_SHM_ID").unwrap();
+
+        let mut forkserver = Forkserver::new(
+            env::current_exe().unwrap().into(),
+            vec![
+                OsString::from("executors::forkserver::tests::fake_batch_forkserver"),
+                OsString::from("--exact"),
+                OsString::from("--test-threads=1"),
+            ],
+            vec![(FAKE_FORKSERVER_ENV.into(), "1".into())],
+            0,
+            false,
+            0,
+            false,
+            false,
+            false,
+            Some(1024),
+            false,
+        )
+        .unwrap();
+
+        forkserver.write_ctl(4).unwrap();
+        let pids: Vec<Pid> = (0..4)
+            .map(|_| Pid::from_raw(forkserver.read_st().unwrap()))
+            .collect();
+        // The children after the hanging one finish in time, even though they are reported after the deadline
+        let exit_kinds = read_batch_exit_kinds(
+            &mut forkserver,
+            None,
+            Duration::from_millis(200),
+            None,
+            &pids,
+        )
+        .unwrap();
+        assert_eq!(
+            exit_kinds,
+            [
+                ExitKind::Timeout,
+                ExitKind::Ok,
+                ExitKind::Crash,
+                ExitKind::Ok
+            ]
+        );
+    }
+
     #[test]
     #[serial]
     #[cfg_attr(miri, ignore)]
@@ -1701,4 +2217,17 @@ mod tests {
         };
         assert!(result);
     }
+
+    #[test]
+    fn test_write_shmem_testcase() {
+        let mut slot = [0xff_u8; 12];
+        write_shmem_testcase(&mut slot, b"abcdefghij", 1, 6);
+        assert_eq!(u32::from_ne_bytes(slot[..4].try_into().unwrap()), 6);
+        assert_eq!(&slot[4..10], b"abcdef");
+
+        let mut slot = [0xff_u8; 12];
+        write_shmem_testcase(&mut slot, b"ab", 4, 6);
+        assert_eq!(u32::from_ne_bytes(slot[..4].try_into().unwrap()), 4);
+        assert_eq!(&slot[4..8], b"ab\0\0");
+    }
 }
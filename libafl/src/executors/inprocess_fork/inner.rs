@@ -6,6 +6,7 @@ use core::{
     sync::atomic::{compiler_fence, Ordering},
     time::Duration,
 };
+use std::thread;
 
 use libafl_bolts::{
     os::unix_signals::Signal,
@@ -13,7 +14,10 @@ use libafl_bolts::{
     tuples::{tuple_list, Merge, RefIndexable},
 };
 use nix::{
-    sys::wait::{waitpid, WaitStatus},
+    sys::{
+        signal::{kill, Signal as NixSignal},
+        wait::{waitpid, WaitPidFlag, WaitStatus},
+    },
     unistd::Pid,
 };
 
@@ -26,6 +30,7 @@ use crate::{
             inprocess_fork::{InChildProcessHooks, FORK_EXECUTOR_GLOBAL_DATA},
             ExecutorHooksTuple,
         },
+        resource_limits::{PidFd, ResourceLimits},
         ExitKind, HasObservers,
     },
     inputs::UsesInput,
@@ -43,6 +48,7 @@ pub struct GenericInProcessForkExecutorInner<HT, OT, S, SP, EM, Z> {
     pub(super) itimerspec: libc::itimerspec,
     #[cfg(all(unix, not(target_os = "linux")))]
     pub(super) itimerval: Itimerval,
+    pub(super) resource_limits: Option<ResourceLimits>,
     pub(super) phantom: PhantomData<(S, EM, Z)>,
 }
 
@@ -58,6 +64,7 @@ where
             .field("observers", &self.observers)
             .field("shmem_provider", &self.shmem_provider)
             .field("itimerspec", &self.itimerspec)
+            .field("resource_limits", &self.resource_limits)
             .finish_non_exhaustive()
     }
 
@@ -69,6 +76,7 @@ where
             .field("observers", &self.observers)
             .field("shmem_provider", &self.shmem_provider)
             .field("itimerval", &self.itimerval)
+            .field("resource_limits", &self.resource_limits)
             .finish_non_exhaustive();
     }
 }
@@ -179,7 +187,13 @@ where
         // log::trace!("from parent {} child is {}", std::process::id(), child);
         self.shmem_provider.post_fork(false)?;
 
-        let res = waitpid(child, None)?;
+        let res = match &self.resource_limits {
+            Some(limits) if !limits.is_empty() => match wait_child_limited(child, limits)? {
+                Some(res) => res,
+                None => return Ok(ExitKind::Oom),
+            },
+            _ => waitpid(child, None)?,
+        };
         log::trace!("{res:#?}");
         match res {
             WaitStatus::Signaled(_, signal, _) => match signal {
@@ -208,6 +222,36 @@ where
     }
 }
 
+/// Waits for `child`, checking it against the resource `limits` each time the poll interval passes without it exiting.
+/// Returns `None` if the child went over a limit, it is killed and reaped then.
+/// The child enforces its timeout itself, like without limits.
+///
+/// Blocks on a pidfd of the child; on kernels without pidfds, polls `waitpid` instead.
+fn wait_child_limited(child: Pid, limits: &ResourceLimits) -> Result<Option<WaitStatus>, Error> {
+    let pidfd = PidFd::open(child.as_raw());
+    loop {
+        if let Some(pidfd) = &pidfd {
+            if pidfd.wait_exit(limits.poll_interval())? {
+                return Ok(Some(waitpid(child, None)?));
+            }
+        } else {
+            let res = waitpid(child, Some(WaitPidFlag::WNOHANG))?;
+            if res != WaitStatus::StillAlive {
+                return Ok(Some(res));
+            }
+        }
+        if let Some(breach) = limits.check(child.as_raw()) {
+            log::info!("Killing child {child}: {breach}");
+            let _ = kill(child, NixSignal::SIGKILL);
+            waitpid(child, None)?;
+            return Ok(None);
+        }
+        if pidfd.is_none() {
+            thread::sleep(limits.poll_interval());
+        }
+    }
+}
+
 impl<HT, OT, S, SP, EM, Z> GenericInProcessForkExecutorInner<HT, OT, S, SP, EM, Z>
 where
     HT: ExecutorHooksTuple<S>,
@@ -274,6 +318,7 @@ where
             observers,
             hooks,
             itimerspec,
+            resource_limits: None,
             phantom: PhantomData,
         })
     }
@@ -301,6 +346,7 @@ where
             observers,
             hooks,
             itimerval,
+            resource_limits: None,
             phantom: PhantomData,
         })
     }
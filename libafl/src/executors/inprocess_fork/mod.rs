@@ -17,7 +17,8 @@ use crate::{
     events::{EventFirer, EventRestarter},
     executors::{
         hooks::inprocess_fork::InProcessForkExecutorGlobalData,
-        inprocess_fork::inner::GenericInProcessForkExecutorInner, Executor, ExitKind, HasObservers,
+        inprocess_fork::inner::GenericInProcessForkExecutorInner, resource_limits::ResourceLimits,
+        Executor, ExitKind, HasObservers,
     },
     feedbacks::Feedback,
     fuzzer::HasObjective,
@@ -233,6 +234,25 @@ where {
     }
 }
 
+impl<H, HT, OT, S, SP, EM, Z> GenericInProcessForkExecutor<'_, H, HT, OT, S, SP, EM, Z>
+where
+    H: FnMut(&S::Input) -> ExitKind + ?Sized,
+    OT: ObserversTuple<S::Input, S>,
+    S: UsesInput,
+    SP: ShMemProvider,
+    HT: ExecutorHooksTuple<S>,
+    EM: UsesState<State = S>,
+    Z: UsesState<State = S>,
+{
+    /// Sets the [`ResourceLimits`] of the child.
+    /// A child going over them is killed, and the run is reported as [`ExitKind::Oom`].
+    #[must_use]
+    pub fn with_resource_limits(mut self, limits: ResourceLimits) -> Self {
+        self.inner.resource_limits = Some(limits);
+        self
+    }
+}
+
 impl<H, HT, OT, S, SP, EM, Z> HasObservers
     for GenericInProcessForkExecutor<'_, H, HT, OT, S, SP, EM, Z>
 where
@@ -420,6 +440,7 @@ mod tests {
                 shmem_provider: provider,
                 observers: tuple_list!(),
                 itimerspec,
+                resource_limits: None,
                 phantom: PhantomData,
             },
         };
@@ -431,6 +452,7 @@ mod tests {
                 shmem_provider: provider,
                 observers: tuple_list!(),
                 itimerval: itimerspec,
+                resource_limits: None,
                 phantom: PhantomData,
             },
         };
@@ -442,4 +464,61 @@ mod tests {
             .run_target(&mut fuzzer, &mut state, &mut mgr, &input)
             .unwrap();
     }
+
+    #[test]
+    #[serial]
+    #[cfg_attr(miri, ignore)]
+    #[cfg(target_os = "linux")]
+    fn test_inprocessfork_resource_limits() {
+        use core::marker::PhantomData;
+        use std::process::Command;
+
+        use libafl_bolts::shmem::{ShMemProvider, StdShMemProvider};
+        use libc::{itimerspec, timespec};
+
+        use crate::{
+            events::SimpleEventManager,
+            executors::{
+                hooks::inprocess_fork::InChildProcessHooks,
+                inprocess_fork::GenericInProcessForkExecutor, ResourceLimits,
+            },
+            fuzzer::NopFuzzer,
+            state::NopState,
+        };
+
+        let provider = StdShMemProvider::new().unwrap();
+        let timespec = timespec {
+            tv_sec: 5,
+            tv_nsec: 0,
+        };
+        let itimerspec = itimerspec {
+            it_interval: timespec,
+            it_value: timespec,
+        };
+
+        let mut harness = |_buf: &NopInput| {
+            Command::new("sleep").arg("2").status().unwrap();
+            ExitKind::Ok
+        };
+        let mut in_process_fork_executor = GenericInProcessForkExecutor {
+            harness_fn: &mut harness,
+            inner: GenericInProcessForkExecutorInner {
+                hooks: tuple_list!(InChildProcessHooks::nop()),
+                shmem_provider: provider,
+                observers: tuple_list!(),
+                itimerspec,
+                resource_limits: None,
+                phantom: PhantomData,
+            },
+        }
+        .with_resource_limits(ResourceLimits::new().with_max_children(0));
+        let input = NopInput {};
+        let mut fuzzer = NopFuzzer::new();
+        let mut state = NopState::new();
+        let mut mgr = SimpleEventManager::printing();
+        let exit_kind = in_process_fork_executor
+            .run_target(&mut fuzzer, &mut state, &mut mgr, &input)
+            .unwrap();
+        assert_eq!(exit_kind, ExitKind::Oom);
+    }
 }
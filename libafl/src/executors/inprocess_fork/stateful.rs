@@ -17,8 +17,8 @@ use nix::unistd::{fork, ForkResult};
 use crate::{
     events::{EventFirer, EventRestarter},
     executors::{
-        hooks::ExecutorHooksTuple, inprocess_fork::GenericInProcessForkExecutorInner, Executor,
-        ExitKind, HasObservers,
+        hooks::ExecutorHooksTuple, inprocess_fork::GenericInProcessForkExecutorInner,
+        resource_limits::ResourceLimits, Executor, ExitKind, HasObservers,
     },
     feedbacks::Feedback,
     fuzzer::HasObjective,
@@ -194,6 +194,14 @@ where
         })
     }
 
+    /// Sets the [`ResourceLimits`] of the child.
+    /// A child going over them is killed, and the run is reported as [`ExitKind::Oom`].
+    #[must_use]
+    pub fn with_resource_limits(mut self, limits: ResourceLimits) -> Self {
+        self.inner.resource_limits = Some(limits);
+        self
+    }
+
     /// Retrieve the harness function.
     #[inline]
     pub fn harness(&self) -> &H {
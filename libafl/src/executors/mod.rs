@@ -16,8 +16,17 @@ pub use inprocess_fork::InProcessForkExecutor;
 #[cfg(unix)]
 use libafl_bolts::os::unix_signals::Signal;
 use libafl_bolts::tuples::RefIndexable;
+pub use multi_differential::MultiDiffExecutor;
+#[cfg(all(feature = "std", unix, feature = "multipart_inputs"))]
+pub use network::{NetworkExecutor, NetworkProtocol, NetworkServer};
+#[cfg(all(feature = "std", unix))]
+pub use persistent::{PersistentCoverageMap, PersistentExecutor};
+#[cfg(all(feature = "std", unix))]
+pub use resource_limits::{ResourceBreach, ResourceLimits};
 use serde::{Deserialize, Serialize};
 pub use shadow::ShadowExecutor;
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub use snapshot::{MappingTracker, MemorySnapshot, SnapshotExecutor};
 pub use with_observers::WithObservers;
 
 use crate::{state::UsesState, Error};
@@ -29,12 +38,21 @@ pub mod differential;
 #[cfg(all(feature = "std", feature = "fork", unix))]
 pub mod forkserver;
 pub mod inprocess;
+pub mod multi_differential;
+#[cfg(all(feature = "std", unix, feature = "multipart_inputs"))]
+pub mod network;
+#[cfg(all(feature = "std", unix))]
+pub mod persistent;
+#[cfg(all(feature = "std", unix))]
+pub mod resource_limits;
 
 /// The module for inproc fork executor
 #[cfg(all(feature = "std", unix))]
 pub mod inprocess_fork;
 
 pub mod shadow;
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub mod snapshot;
 
 pub mod with_observers;
 
@@ -132,6 +150,32 @@ where
     ) -> Result<ExitKind, Error>;
 }
 
+/// An [`Executor`] that can run several inputs at the same time.
+///
+/// The results are kept aside until they are loaded one by one, so that the observers
+/// can be evaluated for each input, like after a normal run.
+pub trait BatchExecutor<EM, Z>: Executor<EM, Z> + HasObservers
+where
+    EM: UsesState<State = Self::State>,
+    Z: UsesState<State = Self::State>,
+{
+    /// The maximum number of inputs in one batch
+    fn batch_size(&self) -> usize;
+
+    /// Runs all `inputs` at the same time, and returns their [`ExitKind`]s in the same order.
+    fn run_batch(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut Self::State,
+        mgr: &mut EM,
+        inputs: &[Self::Input],
+    ) -> Result<Vec<ExitKind>, Error>;
+
+    /// Loads what was observed while running the `idx`th input of the last batch into the observers.
+    /// Call it between the observers' `pre_exec_all` and `post_exec_all`.
+    fn load_batch_result(&mut self, idx: usize) -> Result<(), Error>;
+}
+
 /// A trait that allows to get/set an `Executor`'s timeout thresold
 pub trait HasTimeout {
     /// Get a timeout
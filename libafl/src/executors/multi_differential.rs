@@ -0,0 +1,471 @@
+//! Executor for differential fuzzing across any number of implementations.
+//!
+//! It wraps a tuple of executors that will be run after each other with the same input.
+//! In comparison to the [`crate::executors::DiffExecutor`], it is not limited to two executors.
+//! Use it with the [`crate::feedbacks::MultiDiffFeedback`] to find out which implementations disagree.
+
+use alloc::vec::Vec;
+use core::{
+    cell::UnsafeCell,
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    ptr,
+    time::Duration,
+};
+
+use libafl_bolts::{
+    ownedref::OwnedMutPtr,
+    tuples::{MatchName, RefIndexable},
+};
+use serde::{Deserialize, Serialize};
+
+use super::HasTimeout;
+use crate::{
+    executors::{DiffExitKind, Executor, ExitKind, HasObservers},
+    inputs::UsesInput,
+    observers::ObserversTuple,
+    state::{State, UsesState},
+    Error,
+};
+
+/// A tuple of executors, run after each other by the [`MultiDiffExecutor`]
+pub trait DiffExecutorsTuple {
+    /// The pointers to the observers of all executors, see [`ObserversPtrsTuple`]
+    type ObserversPtrs: ObserversPtrsTuple;
+
+    /// Get pointers to the observers of all executors
+    fn observers_ptrs(&self) -> Self::ObserversPtrs;
+
+    /// Set the timeout of all executors
+    fn set_timeout_all(&mut self, timeout: Duration);
+
+    /// Append the timeouts of all executors to `timeouts`
+    fn timeouts_all(&self, timeouts: &mut Vec<Duration>);
+}
+
+impl DiffExecutorsTuple for () {
+    type ObserversPtrs = ();
+
+    fn observers_ptrs(&self) -> Self::ObserversPtrs {}
+
+    fn set_timeout_all(&mut self, _timeout: Duration) {}
+
+    fn timeouts_all(&self, _timeouts: &mut Vec<Duration>) {}
+}
+
+impl<Head, Tail> DiffExecutorsTuple for (Head, Tail)
+where
+    Head: HasObservers + HasTimeout,
+    Head::Observers: MatchName,
+    Tail: DiffExecutorsTuple,
+{
+    type ObserversPtrs = (OwnedMutPtr<Head::Observers>, Tail::ObserversPtrs);
+
+    fn observers_ptrs(&self) -> Self::ObserversPtrs {
+        (
+            OwnedMutPtr::Ptr(ptr::from_ref(&*self.0.observers()).cast_mut()),
+            self.1.observers_ptrs(),
+        )
+    }
+
+    fn set_timeout_all(&mut self, timeout: Duration) {
+        self.0.set_timeout(timeout);
+        self.1.set_timeout_all(timeout);
+    }
+
+    fn timeouts_all(&self, timeouts: &mut Vec<Duration>) {
+        timeouts.push(self.0.timeout());
+        self.1.timeouts_all(timeouts);
+    }
+}
+
+/// A tuple of executors that can all run the same input
+pub trait RunDiffExecutorsTuple<EM, Z, S>: DiffExecutorsTuple
+where
+    S: UsesInput,
+{
+    /// Run all executors with the same input, appending their [`ExitKind`]s to `exit_kinds`
+    fn run_target_all(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut S,
+        mgr: &mut EM,
+        input: &S::Input,
+        exit_kinds: &mut Vec<ExitKind>,
+    ) -> Result<(), Error>;
+}
+
+impl<EM, Z, S> RunDiffExecutorsTuple<EM, Z, S> for ()
+where
+    S: UsesInput,
+{
+    fn run_target_all(
+        &mut self,
+        _fuzzer: &mut Z,
+        _state: &mut S,
+        _mgr: &mut EM,
+        _input: &S::Input,
+        _exit_kinds: &mut Vec<ExitKind>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<EM, Head, S, Tail, Z> RunDiffExecutorsTuple<EM, Z, S> for (Head, Tail)
+where
+    EM: UsesState<State = S>,
+    Head: Executor<EM, Z, State = S> + HasObservers + HasTimeout,
+    Head::Observers: ObserversTuple<S::Input, S>,
+    S: State,
+    Tail: RunDiffExecutorsTuple<EM, Z, S>,
+    Z: UsesState<State = S>,
+{
+    fn run_target_all(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut S,
+        mgr: &mut EM,
+        input: &S::Input,
+        exit_kinds: &mut Vec<ExitKind>,
+    ) -> Result<(), Error> {
+        self.0.observers_mut().pre_exec_all(state, input)?;
+        let exit_kind = self.0.run_target(fuzzer, state, mgr, input)?;
+        self.0
+            .observers_mut()
+            .post_exec_all(state, input, &exit_kind)?;
+        exit_kinds.push(exit_kind);
+        self.1.run_target_all(fuzzer, state, mgr, input, exit_kinds)
+    }
+}
+
+/// A [`MultiDiffExecutor`] wraps a tuple of executors, and runs all of them with the same input.
+///
+/// If their [`ExitKind`]s differ, it reports an [`ExitKind::Diff`], with the most common [`ExitKind`] as `primary`,
+/// and the first one deviating from it as `secondary`.
+/// The observers of all executors are accessible through its observers, so their names must be unique.
+pub struct MultiDiffExecutor<ET, S>
+where
+    ET: DiffExecutorsTuple,
+{
+    executors: ET,
+    observers: UnsafeCell<MultiProxyObserversTuple<ET::ObserversPtrs>>,
+    exit_kinds: Vec<ExitKind>,
+    phantom: PhantomData<S>,
+}
+
+impl<ET, S> Debug for MultiDiffExecutor<ET, S>
+where
+    ET: DiffExecutorsTuple + Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultiDiffExecutor")
+            .field("executors", &self.executors)
+            .field("exit_kinds", &self.exit_kinds)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<ET, S> MultiDiffExecutor<ET, S>
+where
+    ET: DiffExecutorsTuple,
+{
+    /// Create a new `MultiDiffExecutor`, wrapping the given tuple of `executors`.
+    pub fn new(executors: ET) -> Self {
+        Self {
+            executors,
+            observers: UnsafeCell::new(MultiProxyObserversTuple { ptrs: None }),
+            exit_kinds: Vec::new(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Retrieve the executors wrapped by this `MultiDiffExecutor`.
+    pub fn executors(&mut self) -> &mut ET {
+        &mut self.executors
+    }
+
+    /// The [`ExitKind`] of each executor during the last run, in order
+    #[must_use]
+    pub fn exit_kinds(&self) -> &[ExitKind] {
+        &self.exit_kinds
+    }
+}
+
+/// Find the most common [`ExitKind`], and the first one deviating from it
+fn diff_exit_kinds(exit_kinds: &[ExitKind]) -> Option<(ExitKind, ExitKind)> {
+    let majority = *exit_kinds.iter().max_by_key(|exit_kind| {
+        // On ties, prefer the earlier executors
+        (
+            exit_kinds.iter().filter(|other| other == exit_kind).count(),
+            core::cmp::Reverse(exit_kinds.iter().position(|other| other == *exit_kind)),
+        )
+    })?;
+    let deviating = *exit_kinds
+        .iter()
+        .find(|exit_kind| **exit_kind != majority)?;
+    Some((majority, deviating))
+}
+
+impl<EM, ET, S, Z> Executor<EM, Z> for MultiDiffExecutor<ET, S>
+where
+    EM: UsesState<State = S>,
+    ET: RunDiffExecutorsTuple<EM, Z, S>,
+    S: State,
+    Z: UsesState<State = S>,
+{
+    fn run_target(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut Self::State,
+        mgr: &mut EM,
+        input: &Self::Input,
+    ) -> Result<ExitKind, Error> {
+        self.exit_kinds.clear();
+        self.executors
+            .run_target_all(fuzzer, state, mgr, input, &mut self.exit_kinds)?;
+        match diff_exit_kinds(&self.exit_kinds) {
+            // We found a diff in the exit codes!
+            Some((majority, deviating)) => Ok(ExitKind::Diff {
+                primary: DiffExitKind::from(majority),
+                secondary: DiffExitKind::from(deviating),
+            }),
+            None => Ok(self.exit_kinds.first().copied().unwrap_or(ExitKind::Ok)),
+        }
+    }
+}
+
+impl<ET, S> HasTimeout for MultiDiffExecutor<ET, S>
+where
+    ET: DiffExecutorsTuple,
+{
+    #[inline]
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.executors.set_timeout_all(timeout);
+    }
+
+    #[inline]
+    fn timeout(&self) -> Duration {
+        let mut timeouts = Vec::new();
+        self.executors.timeouts_all(&mut timeouts);
+        assert!(
+            timeouts.windows(2).all(|pair| pair[0] == pair[1]),
+            "The wrapped executors have different timeouts!"
+        );
+        timeouts.first().copied().unwrap_or_default()
+    }
+}
+
+impl<ET, S> UsesState for MultiDiffExecutor<ET, S>
+where
+    ET: DiffExecutorsTuple,
+    S: State,
+{
+    type State = S;
+}
+
+impl<ET, S> HasObservers for MultiDiffExecutor<ET, S>
+where
+    ET: DiffExecutorsTuple,
+    S: State,
+{
+    type Observers = MultiProxyObserversTuple<ET::ObserversPtrs>;
+
+    #[inline]
+    fn observers(&self) -> RefIndexable<&Self::Observers, Self::Observers> {
+        unsafe {
+            self.observers.get().as_mut().unwrap().ptrs = Some(self.executors.observers_ptrs());
+            RefIndexable::from(self.observers.get().as_ref().unwrap())
+        }
+    }
+
+    #[inline]
+    fn observers_mut(&mut self) -> RefIndexable<&mut Self::Observers, Self::Observers> {
+        self.observers.get_mut().ptrs = Some(self.executors.observers_ptrs());
+        RefIndexable::from(self.observers.get_mut())
+    }
+}
+
+/// A tuple of pointers to the observers of each executor of a [`MultiDiffExecutor`]
+pub trait ObserversPtrsTuple {
+    /// Match for a name in the observers of all executors, and return the borrowed value
+    fn match_observer_name<T>(&self, name: &str) -> Option<&T>;
+
+    /// Match for a name in the observers of all executors, and return the mut borrowed value
+    fn match_observer_name_mut<T>(&mut self, name: &str) -> Option<&mut T>;
+}
+
+impl ObserversPtrsTuple for () {
+    fn match_observer_name<T>(&self, _name: &str) -> Option<&T> {
+        None
+    }
+
+    fn match_observer_name_mut<T>(&mut self, _name: &str) -> Option<&mut T> {
+        None
+    }
+}
+
+impl<Head, Tail> ObserversPtrsTuple for (OwnedMutPtr<Head>, Tail)
+where
+    Head: MatchName,
+    Tail: ObserversPtrsTuple,
+{
+    #[allow(deprecated)]
+    fn match_observer_name<T>(&self, name: &str) -> Option<&T> {
+        if let Some(t) = self.0.as_ref().match_name::<T>(name) {
+            Some(t)
+        } else {
+            self.1.match_observer_name::<T>(name)
+        }
+    }
+
+    #[allow(deprecated)]
+    fn match_observer_name_mut<T>(&mut self, name: &str) -> Option<&mut T> {
+        if let Some(t) = self.0.as_mut().match_name_mut::<T>(name) {
+            Some(t)
+        } else {
+            self.1.match_observer_name_mut::<T>(name)
+        }
+    }
+}
+
+/// Proxy the observers of the executors of a [`MultiDiffExecutor`]
+///
+/// The observers are run by the [`MultiDiffExecutor`] around the execution of each executor,
+/// so the `*_all` methods of this proxy don't do anything.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(bound = "PT: serde::Serialize + serde::de::DeserializeOwned")]
+pub struct MultiProxyObserversTuple<PT> {
+    ptrs: Option<PT>,
+}
+
+impl<I, PT, S> ObserversTuple<I, S> for MultiProxyObserversTuple<PT>
+where
+    PT: ObserversPtrsTuple,
+{
+    fn pre_exec_all(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn post_exec_all(
+        &mut self,
+        _state: &mut S,
+        _input: &I,
+        _exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn pre_exec_child_all(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn post_exec_child_all(
+        &mut self,
+        _state: &mut S,
+        _input: &I,
+        _exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<PT> MatchName for MultiProxyObserversTuple<PT>
+where
+    PT: ObserversPtrsTuple,
+{
+    fn match_name<T>(&self, name: &str) -> Option<&T> {
+        self.ptrs.as_ref()?.match_observer_name::<T>(name)
+    }
+
+    fn match_name_mut<T>(&mut self, name: &str) -> Option<&mut T> {
+        self.ptrs.as_mut()?.match_observer_name_mut::<T>(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(all(feature = "std", unix))]
+    use libafl_bolts::{rands::StdRand, tuples::tuple_list};
+
+    use super::diff_exit_kinds;
+    #[cfg(all(feature = "std", unix))]
+    use super::MultiDiffExecutor;
+    use crate::executors::ExitKind;
+    #[cfg(all(feature = "std", unix))]
+    use crate::{
+        corpus::InMemoryCorpus,
+        events::NopEventManager,
+        executors::{CommandExecutor, DiffExitKind, Executor},
+        feedbacks::ConstFeedback,
+        fuzzer::NopFuzzer,
+        inputs::BytesInput,
+        state::StdState,
+    };
+
+    #[test]
+    fn test_diff_exit_kinds() {
+        assert_eq!(diff_exit_kinds(&[]), None);
+        assert_eq!(diff_exit_kinds(&[ExitKind::Ok, ExitKind::Ok]), None);
+        assert_eq!(
+            diff_exit_kinds(&[ExitKind::Ok, ExitKind::Crash, ExitKind::Ok]),
+            Some((ExitKind::Ok, ExitKind::Crash))
+        );
+        assert_eq!(
+            diff_exit_kinds(&[ExitKind::Timeout, ExitKind::Crash, ExitKind::Crash]),
+            Some((ExitKind::Crash, ExitKind::Timeout))
+        );
+        // Without a majority, the first executor wins
+        assert_eq!(
+            diff_exit_kinds(&[ExitKind::Crash, ExitKind::Ok]),
+            Some((ExitKind::Crash, ExitKind::Ok))
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "std", unix))]
+    #[cfg_attr(miri, ignore)]
+    fn test_multi_diff_executor() {
+        let build = |program: &str, args: &[&str]| {
+            CommandExecutor::builder()
+                .program(program)
+                .args(args)
+                .build(())
+                .unwrap()
+        };
+        let mut executor = MultiDiffExecutor::new(tuple_list!(
+            build("true", &[]),
+            build("sh", &["-c", "kill -SEGV $$"]),
+            build("true", &[]),
+        ));
+
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let exit_kind = executor
+            .run_target(
+                &mut NopFuzzer::new(),
+                &mut state,
+                &mut NopEventManager::new(),
+                &BytesInput::new(b"test".to_vec()),
+            )
+            .unwrap();
+        assert_eq!(
+            executor.exit_kinds(),
+            &[ExitKind::Ok, ExitKind::Crash, ExitKind::Ok]
+        );
+        assert_eq!(
+            exit_kind,
+            ExitKind::Diff {
+                primary: DiffExitKind::Ok,
+                secondary: DiffExitKind::Crash
+            }
+        );
+    }
+}
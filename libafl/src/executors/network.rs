@@ -0,0 +1,1019 @@
+//! The network executor sends inputs to a (stateful) server over TCP or UDP.
+//!
+//! Each part of a [`MultipartInput`] is sent as one message, in order, and the responses are captured
+//! in a [`ResponseObserver`], or turned into protocol states by a [`ProtocolStateObserver`]. The server is launched as a plain [`Command`], or forked by an
+//! AFL-style forkserver, using an already initialized [`crate::executors::ForkserverExecutor`].
+use alloc::{borrow::ToOwned, vec::Vec};
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    ops::IndexMut,
+    time::Duration,
+};
+use std::{
+    ffi::{OsStr, OsString},
+    io::{self, ErrorKind, Read, Write},
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, UdpSocket},
+    os::unix::process::ExitStatusExt,
+    path::{Path, PathBuf},
+    process::{Child, Command, ExitStatus, Stdio},
+    thread,
+    time::Instant,
+};
+
+#[cfg(feature = "fork")]
+use libafl_bolts::shmem::ShMemProvider;
+use libafl_bolts::{
+    tuples::{Handle, RefIndexable},
+    AsSlice,
+};
+#[cfg(feature = "fork")]
+use nix::{
+    sys::{
+        signal::{kill, Signal},
+        time::TimeSpec,
+    },
+    unistd::Pid,
+};
+
+use super::HasTimeout;
+#[cfg(feature = "fork")]
+use crate::{executors::ForkserverExecutor, inputs::TargetBytesConverter};
+use crate::{
+    executors::{Executor, ExitKind, HasObservers},
+    inputs::{HasTargetBytes, MultipartInput, UsesInput},
+    observers::{ObserversTuple, ProtocolStateObserver, ResponseObserver},
+    state::{HasExecutions, State, UsesState},
+    Error,
+};
+
+/// The transport used to talk to the server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NetworkProtocol {
+    /// Open one TCP connection per execution, and send each message over it
+    #[default]
+    Tcp,
+    /// Send each message as one UDP datagram
+    Udp,
+}
+
+/// A server the [`NetworkExecutor`] talks to
+pub trait NetworkServer {
+    /// Launches a new instance of the server
+    fn start(&mut self) -> Result<(), Error>;
+
+    /// Checks if the running instance terminated.
+    /// Returns [`ExitKind::Crash`] if it crashed, and [`ExitKind::Ok`] if it exited on its own.
+    fn poll_exit(&mut self) -> Result<Option<ExitKind>, Error>;
+
+    /// Stops the running instance
+    fn stop(&mut self) -> Result<(), Error>;
+}
+
+/// A [`NetworkServer`] spawning a new process from a [`Command`] for each instance
+#[derive(Debug)]
+pub struct CommandServer {
+    command: Command,
+    child: Option<Child>,
+}
+
+impl CommandServer {
+    /// Creates a new [`CommandServer`], spawning the given `command`
+    #[must_use]
+    pub fn new(command: Command) -> Self {
+        Self {
+            command,
+            child: None,
+        }
+    }
+
+    fn exit_kind_from_status(status: ExitStatus) -> ExitKind {
+        if status.signal().is_some() {
+            ExitKind::Crash
+        } else {
+            ExitKind::Ok
+        }
+    }
+}
+
+impl NetworkServer for CommandServer {
+    fn start(&mut self) -> Result<(), Error> {
+        self.stop()?;
+        self.child = Some(self.command.spawn()?);
+        Ok(())
+    }
+
+    fn poll_exit(&mut self) -> Result<Option<ExitKind>, Error> {
+        let Some(child) = &mut self.child else {
+            return Ok(None);
+        };
+        let exit_kind = child.try_wait()?.map(Self::exit_kind_from_status);
+        if exit_kind.is_some() {
+            self.child = None;
+        }
+        Ok(exit_kind)
+    }
+
+    fn stop(&mut self) -> Result<(), Error> {
+        if let Some(mut child) = self.child.take() {
+            // if this fails, the process most likely finished in the meantime
+            drop(child.kill());
+            child.wait()?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for CommandServer {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+/// Lets the forkserver fork a new server instance. The coverage map is set up by the [`ForkserverExecutor`].
+///
+/// The [`NetworkExecutor`] only runs its own observers: the observers of the [`ForkserverExecutor`]
+/// are never reset, nor run after an execution. Pass the map observer(s) to the [`NetworkExecutor`],
+/// for example an observer on the same shared memory map, so that the map is cleared before each execution.
+#[cfg(feature = "fork")]
+impl<TC, OT, S, SP> NetworkServer for ForkserverExecutor<TC, OT, S, SP>
+where
+    OT: ObserversTuple<S::Input, S>,
+    S: UsesInput,
+    SP: ShMemProvider,
+    TC: TargetBytesConverter,
+{
+    fn start(&mut self) -> Result<(), Error> {
+        let forkserver = self.forkserver_mut();
+        let last_run_timed_out = forkserver.last_run_timed_out_raw();
+        forkserver.set_last_run_timed_out(false);
+        forkserver.write_ctl(last_run_timed_out).map_err(|err| {
+            Error::unknown(format!(
+                "Unable to request new process from fork server (OOM?): {err:?}"
+            ))
+        })?;
+        let pid = forkserver.read_st().map_err(|err| {
+            Error::unknown(format!(
+                "Unable to request new process from fork server (OOM?): {err:?}"
+            ))
+        })?;
+        if pid <= 0 {
+            return Err(Error::unknown("Fork server is misbehaving (OOM?)"));
+        }
+        forkserver.set_child_pid(Pid::from_raw(pid));
+        Ok(())
+    }
+
+    fn poll_exit(&mut self) -> Result<Option<ExitKind>, Error> {
+        let forkserver = self.forkserver_mut();
+        let Some(status) = forkserver.read_st_timed(&TimeSpec::new(0, 0))? else {
+            return Ok(None);
+        };
+        forkserver.set_status(status);
+        forkserver.reset_child_pid();
+        if libc::WIFSIGNALED(status) {
+            Ok(Some(ExitKind::Crash))
+        } else {
+            Ok(Some(ExitKind::Ok))
+        }
+    }
+
+    fn stop(&mut self) -> Result<(), Error> {
+        let forkserver = self.forkserver_mut();
+        let _ = kill(forkserver.child_pid(), Signal::SIGKILL);
+        let status = forkserver
+            .read_st()
+            .map_err(|err| Error::unknown(format!("Could not kill server: {err:?}")))?;
+        forkserver.set_status(status);
+        // Tell the forkserver the child was killed, like after a timeout
+        forkserver.set_last_run_timed_out(true);
+        forkserver.reset_child_pid();
+        Ok(())
+    }
+}
+
+/// The outcome of sending a message to the server
+enum Exchange {
+    /// The server responded, and kept the connection open
+    Response(Vec<u8>),
+    /// The server closed the connection (or went away), after sending the given, possibly empty, response
+    Closed(Vec<u8>),
+    /// The server did not respond within the message timeout
+    Silent,
+}
+
+/// An open channel to the server, for a single execution
+enum Connection {
+    Tcp(TcpStream),
+    Udp(UdpSocket),
+}
+
+/// The size of the buffer used to receive responses
+const RECV_BUF_SIZE: usize = 65536;
+
+impl Connection {
+    /// Sends a message, and returns the response
+    fn exchange(&mut self, message: &[u8]) -> Result<Exchange, Error> {
+        let mut buf = vec![0; RECV_BUF_SIZE];
+        let mut response = Vec::new();
+        match self {
+            Connection::Tcp(stream) => {
+                if let Err(err) = stream.write_all(message) {
+                    return gone_or_err(err, response);
+                }
+                // Read until the server closes the connection, or stays silent for the message timeout
+                loop {
+                    match stream.read(&mut buf) {
+                        Ok(0) => return Ok(Exchange::Closed(response)),
+                        Ok(len) => response.extend_from_slice(&buf[..len]),
+                        Err(err) if is_timeout(&err) => return Ok(responded(response)),
+                        Err(err) => return gone_or_err(err, response),
+                    }
+                }
+            }
+            Connection::Udp(socket) => {
+                if let Err(err) = socket.send(message) {
+                    return gone_or_err(err, response);
+                }
+                // Collect datagrams until the server stays silent for the message timeout
+                loop {
+                    match socket.recv(&mut buf) {
+                        Ok(len) => response.extend_from_slice(&buf[..len]),
+                        Err(err) if is_timeout(&err) => return Ok(responded(response)),
+                        Err(err) => return gone_or_err(err, response),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The outcome of a message, once the server stopped sending
+fn responded(response: Vec<u8>) -> Exchange {
+    if response.is_empty() {
+        Exchange::Silent
+    } else {
+        Exchange::Response(response)
+    }
+}
+
+fn is_timeout(err: &io::Error) -> bool {
+    matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+}
+
+/// Errors hinting at a server that went away end the execution, others are returned
+fn gone_or_err(err: io::Error, response: Vec<u8>) -> Result<Exchange, Error> {
+    match err.kind() {
+        ErrorKind::BrokenPipe
+        | ErrorKind::ConnectionReset
+        | ErrorKind::ConnectionAborted
+        | ErrorKind::ConnectionRefused
+        | ErrorKind::UnexpectedEof => Ok(Exchange::Closed(response)),
+        _ => Err(err.into()),
+    }
+}
+
+/// The time between two attempts to reach a starting server
+const STARTUP_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The time between two checks if a server that closed the connection terminated
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// An [`Executor`] sending each part of a [`MultipartInput`] as a message to a server, over TCP or UDP.
+///
+/// Each execution opens a new TCP connection (or UDP socket), sends the messages in order, and reads
+/// the response to each message until the server closes the connection or stays silent for the message timeout.
+/// The server is (re)started for each execution by default, so that each input starts from a clean state.
+/// A crashed server is always restarted before the next execution.
+///
+/// If the server does not respond to a message at all, the execution ends with [`ExitKind::Timeout`],
+/// and the server is restarted, unless responses are optional (see [`NetworkExecutorBuilder::require_responses`]).
+/// Once the server closed the connection, the executor waits for it to terminate for a short grace period
+/// (see [`NetworkExecutorBuilder::exit_grace_period`]), to catch crashes while it handled the last message.
+///
+/// Construct it with [`NetworkExecutor::builder()`].
+pub struct NetworkExecutor<T, OT, S> {
+    server: T,
+    observers: OT,
+    protocol: NetworkProtocol,
+    address: SocketAddr,
+    startup_timeout: Duration,
+    message_timeout: Duration,
+    exit_grace_period: Duration,
+    restart_each_run: bool,
+    require_responses: bool,
+    response_observer: Option<Handle<ResponseObserver>>,
+    state_observer: Option<Handle<ProtocolStateObserver>>,
+    /// If an instance of the server is running
+    running: bool,
+    phantom: PhantomData<S>,
+}
+
+impl NetworkExecutor<(), (), ()> {
+    /// Creates a builder for a new [`NetworkExecutor`].
+    ///
+    /// It mimics the api of [`Command`] to launch the server, specifically, you will use
+    /// `arg`, `args`, `env`, and so on. Use [`NetworkExecutorBuilder::build_with_server`]
+    /// to launch it in a different way, for example using a forkserver.
+    #[must_use]
+    pub fn builder() -> NetworkExecutorBuilder {
+        NetworkExecutorBuilder::new()
+    }
+}
+
+impl<T, OT, S> Debug for NetworkExecutor<T, OT, S>
+where
+    T: Debug,
+    OT: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NetworkExecutor")
+            .field("server", &self.server)
+            .field("observers", &self.observers)
+            .field("protocol", &self.protocol)
+            .field("address", &self.address)
+            .field("startup_timeout", &self.startup_timeout)
+            .field("message_timeout", &self.message_timeout)
+            .field("exit_grace_period", &self.exit_grace_period)
+            .field("restart_each_run", &self.restart_each_run)
+            .field("require_responses", &self.require_responses)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, OT, S> NetworkExecutor<T, OT, S>
+where
+    T: NetworkServer,
+{
+    /// The server this executor talks to
+    pub fn server(&self) -> &T {
+        &self.server
+    }
+
+    /// The server this executor talks to (mutable)
+    pub fn server_mut(&mut self) -> &mut T {
+        &mut self.server
+    }
+
+    /// Starts the server, if it is not running, and waits until it accepts messages.
+    ///
+    /// Returns the exit kind if the server terminated before that.
+    fn ensure_server(&mut self) -> Result<Result<Connection, ExitKind>, Error> {
+        if self.running && self.server.poll_exit()?.is_some() {
+            self.running = false;
+        }
+        if !self.running {
+            self.server.start()?;
+            self.running = true;
+        }
+
+        let start = Instant::now();
+        loop {
+            if let Some(connection) = self.try_connect()? {
+                return Ok(Ok(connection));
+            }
+            if let Some(exit_kind) = self.server.poll_exit()? {
+                self.running = false;
+                return Ok(Err(exit_kind));
+            }
+            if start.elapsed() > self.startup_timeout {
+                return Err(Error::illegal_state(format!(
+                    "The server did not listen on {} within {:?}",
+                    self.address, self.startup_timeout
+                )));
+            }
+            thread::sleep(STARTUP_POLL_INTERVAL);
+        }
+    }
+
+    /// Waits for the server to terminate, for at most the exit grace period
+    fn wait_for_exit(&mut self) -> Result<Option<ExitKind>, Error> {
+        let start = Instant::now();
+        loop {
+            if let Some(exit_kind) = self.server.poll_exit()? {
+                return Ok(Some(exit_kind));
+            }
+            if start.elapsed() >= self.exit_grace_period {
+                return Ok(None);
+            }
+            thread::sleep(EXIT_POLL_INTERVAL);
+        }
+    }
+
+    /// Tries to open a channel to the server
+    fn try_connect(&self) -> Result<Option<Connection>, Error> {
+        match self.protocol {
+            NetworkProtocol::Tcp => {
+                let Ok(stream) = TcpStream::connect_timeout(&self.address, self.message_timeout)
+                else {
+                    return Ok(None);
+                };
+                stream.set_nodelay(true)?;
+                stream.set_read_timeout(Some(self.message_timeout))?;
+                stream.set_write_timeout(Some(self.message_timeout))?;
+                Ok(Some(Connection::Tcp(stream)))
+            }
+            NetworkProtocol::Udp => {
+                // UDP has no handshake, we consider the server ready once it bound the address.
+                // This only works for local servers.
+                if UdpSocket::bind(self.address).is_ok() {
+                    return Ok(None);
+                }
+                let local: SocketAddr = if self.address.is_ipv4() {
+                    (Ipv4Addr::UNSPECIFIED, 0).into()
+                } else {
+                    (Ipv6Addr::UNSPECIFIED, 0).into()
+                };
+                let socket = UdpSocket::bind(local)?;
+                socket.connect(self.address)?;
+                socket.set_read_timeout(Some(self.message_timeout))?;
+                Ok(Some(Connection::Udp(socket)))
+            }
+        }
+    }
+
+    /// Sends all parts of the input, and returns the responses and the exit kind of the server
+    fn send_messages<I>(
+        &mut self,
+        input: &MultipartInput<I>,
+    ) -> Result<(Vec<Vec<u8>>, ExitKind), Error>
+    where
+        I: HasTargetBytes,
+    {
+        let mut responses = Vec::with_capacity(input.parts().len());
+        let mut connection = match self.ensure_server()? {
+            Ok(connection) => connection,
+            Err(exit_kind) => return Ok((responses, exit_kind)),
+        };
+
+        let mut closed = false;
+        let mut silent = false;
+        for part in input.parts() {
+            match connection.exchange(part.target_bytes().as_slice())? {
+                Exchange::Response(response) => responses.push(response),
+                Exchange::Closed(response) => {
+                    if !response.is_empty() {
+                        responses.push(response);
+                    }
+                    closed = true;
+                    break;
+                }
+                Exchange::Silent => {
+                    responses.push(Vec::new());
+                    if self.require_responses {
+                        silent = true;
+                        break;
+                    }
+                }
+            }
+        }
+        drop(connection);
+
+        // A server closing the connection may be about to crash, give it some time to do so
+        let exited = if closed {
+            self.wait_for_exit()?
+        } else {
+            self.server.poll_exit()?
+        };
+        let exit_kind = match exited {
+            Some(exit_kind) => {
+                self.running = false;
+                exit_kind
+            }
+            None if silent => {
+                // The server hangs, don't talk to it again
+                self.server.stop()?;
+                self.running = false;
+                ExitKind::Timeout
+            }
+            None => ExitKind::Ok,
+        };
+        if self.running && self.restart_each_run {
+            self.server.stop()?;
+            self.running = false;
+        }
+        Ok((responses, exit_kind))
+    }
+}
+
+impl<EM, I, OT, S, T, Z> Executor<EM, Z> for NetworkExecutor<T, OT, S>
+where
+    EM: UsesState<State = S>,
+    S: State + HasExecutions + UsesInput<Input = MultipartInput<I>>,
+    I: HasTargetBytes,
+    T: NetworkServer,
+    OT: ObserversTuple<S::Input, S>,
+    Z: UsesState<State = S>,
+{
+    fn run_target(
+        &mut self,
+        _fuzzer: &mut Z,
+        state: &mut Self::State,
+        _mgr: &mut EM,
+        input: &Self::Input,
+    ) -> Result<ExitKind, Error> {
+        *state.executions_mut() += 1;
+        self.observers.pre_exec_child_all(state, input)?;
+
+        let (responses, exit_kind) = self.send_messages(input)?;
+
+        if let Some(h) = &self.response_observer {
+            let mut observers = RefIndexable::from(&mut self.observers);
+            let obs = observers.index_mut(h);
+            for response in &responses {
+                obs.observe_response(response);
+            }
+        }
+        if let Some(h) = &self.state_observer {
+            let mut observers = RefIndexable::from(&mut self.observers);
+            let obs = observers.index_mut(h);
+            for response in &responses {
+                obs.observe_response(response);
+            }
+        }
+        self.observers
+            .post_exec_child_all(state, input, &exit_kind)?;
+        Ok(exit_kind)
+    }
+}
+
+impl<T, OT, S> HasTimeout for NetworkExecutor<T, OT, S> {
+    /// The time to wait for the response to a message
+    #[inline]
+    fn timeout(&self) -> Duration {
+        self.message_timeout
+    }
+
+    #[inline]
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.message_timeout = timeout;
+    }
+}
+
+impl<T, OT, S> UsesState for NetworkExecutor<T, OT, S>
+where
+    S: State,
+{
+    type State = S;
+}
+
+impl<T, OT, S> HasObservers for NetworkExecutor<T, OT, S>
+where
+    S: State,
+    OT: ObserversTuple<S::Input, S>,
+{
+    type Observers = OT;
+
+    fn observers(&self) -> RefIndexable<&Self::Observers, Self::Observers> {
+        RefIndexable::from(&self.observers)
+    }
+
+    fn observers_mut(&mut self) -> RefIndexable<&mut Self::Observers, Self::Observers> {
+        RefIndexable::from(&mut self.observers)
+    }
+}
+
+/// The builder for a [`NetworkExecutor`]
+#[derive(Debug, Clone)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct NetworkExecutorBuilder {
+    program: Option<OsString>,
+    args: Vec<OsString>,
+    envs: Vec<(OsString, OsString)>,
+    cwd: Option<PathBuf>,
+    debug_child: bool,
+    protocol: NetworkProtocol,
+    address: Option<SocketAddr>,
+    startup_timeout: Duration,
+    message_timeout: Duration,
+    exit_grace_period: Duration,
+    restart_each_run: bool,
+    require_responses: bool,
+    response_observer: Option<Handle<ResponseObserver>>,
+    state_observer: Option<Handle<ProtocolStateObserver>>,
+}
+
+impl Default for NetworkExecutorBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NetworkExecutorBuilder {
+    /// Create a new [`NetworkExecutorBuilder`]
+    #[must_use]
+    fn new() -> NetworkExecutorBuilder {
+        NetworkExecutorBuilder {
+            program: None,
+            args: vec![],
+            envs: vec![],
+            cwd: None,
+            debug_child: false,
+            protocol: NetworkProtocol::Tcp,
+            address: None,
+            startup_timeout: Duration::from_secs(5),
+            message_timeout: Duration::from_millis(100),
+            exit_grace_period: Duration::from_millis(10),
+            restart_each_run: true,
+            require_responses: true,
+            response_observer: None,
+            state_observer: None,
+        }
+    }
+
+    /// Set the server binary to execute
+    /// This option is required, unless you use [`Self::build_with_server`].
+    pub fn program<O>(&mut self, program: O) -> &mut Self
+    where
+        O: AsRef<OsStr>,
+    {
+        self.program = Some(program.as_ref().to_owned());
+        self
+    }
+
+    /// Adds an argument to the program's commandline.
+    pub fn arg<O: AsRef<OsStr>>(&mut self, arg: O) -> &mut Self {
+        self.args.push(arg.as_ref().to_owned());
+        self
+    }
+
+    /// Adds a range of arguments to the program's commandline.
+    pub fn args<IT, O>(&mut self, args: IT) -> &mut Self
+    where
+        IT: IntoIterator<Item = O>,
+        O: AsRef<OsStr>,
+    {
+        for arg in args {
+            self.arg(arg.as_ref());
+        }
+        self
+    }
+
+    /// Adds a range of environment variables to the executed command.
+    pub fn envs<IT, K, V>(&mut self, vars: IT) -> &mut Self
+    where
+        IT: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        for (ref key, ref val) in vars {
+            self.env(key.as_ref(), val.as_ref());
+        }
+        self
+    }
+
+    /// Adds an environment variable to the executed command.
+    pub fn env<K, V>(&mut self, key: K, val: V) -> &mut Self
+    where
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        self.envs
+            .push((key.as_ref().to_owned(), val.as_ref().to_owned()));
+        self
+    }
+
+    /// Sets the working directory for the child process.
+    pub fn current_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Self {
+        self.cwd = Some(dir.as_ref().to_owned());
+        self
+    }
+
+    /// If set to true, the child's output won't be redirecited to `/dev/null`.
+    /// Defaults to `false`.
+    pub fn debug_child(&mut self, debug_child: bool) -> &mut Self {
+        self.debug_child = debug_child;
+        self
+    }
+
+    /// Sets the address the server listens on.
+    /// This option is required.
+    pub fn address(&mut self, address: SocketAddr) -> &mut Self {
+        self.address = Some(address);
+        self
+    }
+
+    /// Sets the transport, defaults to [`NetworkProtocol::Tcp`].
+    pub fn protocol(&mut self, protocol: NetworkProtocol) -> &mut Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Sets how long to wait for a (re)started server to listen.
+    /// Defaults to 5 seconds.
+    pub fn startup_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.startup_timeout = timeout;
+        self
+    }
+
+    /// Sets how long to wait for the response to each message.
+    /// Defaults to 100 milliseconds.
+    pub fn message_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.message_timeout = timeout;
+        self
+    }
+
+    /// Sets how long to wait for the server to terminate, after it closed the connection.
+    /// Defaults to 10 milliseconds.
+    pub fn exit_grace_period(&mut self, grace_period: Duration) -> &mut Self {
+        self.exit_grace_period = grace_period;
+        self
+    }
+
+    /// If set to false, the server keeps running between executions, and is only restarted after it terminated.
+    /// Defaults to `true`.
+    pub fn restart_each_run(&mut self, restart_each_run: bool) -> &mut Self {
+        self.restart_each_run = restart_each_run;
+        self
+    }
+
+    /// If set to false, messages the server does not respond to are not considered a timeout,
+    /// and the remaining messages are sent anyway.
+    /// Defaults to `true`.
+    pub fn require_responses(&mut self, require_responses: bool) -> &mut Self {
+        self.require_responses = require_responses;
+        self
+    }
+
+    /// Sets the observer for the responses of the server
+    pub fn response_observer(&mut self, observer: Handle<ResponseObserver>) -> &mut Self {
+        self.response_observer = Some(observer);
+        self
+    }
+
+    /// Sets the observer extracting the protocol state from the responses of the server
+    pub fn state_observer(&mut self, observer: Handle<ProtocolStateObserver>) -> &mut Self {
+        self.state_observer = Some(observer);
+        self
+    }
+
+    /// Builds the [`NetworkExecutor`], launching the server from the configured program
+    pub fn build<OT, S>(
+        &self,
+        observers: OT,
+    ) -> Result<NetworkExecutor<CommandServer, OT, S>, Error> {
+        let Some(program) = &self.program else {
+            return Err(Error::illegal_argument(
+                "NetworkExecutor::builder: no program set!",
+            ));
+        };
+
+        let mut command = Command::new(program);
+        command.args(&self.args).stdin(Stdio::null()).envs(
+            self.envs
+                .iter()
+                .map(|(k, v)| (k.as_os_str(), v.as_os_str())),
+        );
+        if let Some(cwd) = &self.cwd {
+            command.current_dir(cwd);
+        }
+        if !self.debug_child {
+            command.stdout(Stdio::null());
+            command.stderr(Stdio::null());
+        }
+        self.build_with_server(CommandServer::new(command), observers)
+    }
+
+    /// Builds the [`NetworkExecutor`], using the given [`NetworkServer`].
+    /// Pass an initialized [`crate::executors::ForkserverExecutor`] to fork servers from a forkserver.
+    pub fn build_with_server<T, OT, S>(
+        &self,
+        server: T,
+        observers: OT,
+    ) -> Result<NetworkExecutor<T, OT, S>, Error> {
+        let Some(address) = self.address else {
+            return Err(Error::illegal_argument(
+                "NetworkExecutor::builder: no address set!",
+            ));
+        };
+        Ok(NetworkExecutor {
+            server,
+            observers,
+            protocol: self.protocol,
+            address,
+            startup_timeout: self.startup_timeout,
+            message_timeout: self.message_timeout,
+            exit_grace_period: self.exit_grace_period,
+            restart_each_run: self.restart_each_run,
+            require_responses: self.require_responses,
+            response_observer: self.response_observer.clone(),
+            state_observer: self.state_observer.clone(),
+            running: false,
+            phantom: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{sync::Arc, vec::Vec};
+    use core::{
+        sync::atomic::{AtomicBool, Ordering},
+        time::Duration,
+    };
+    use std::{
+        io::{Read, Write},
+        net::{SocketAddr, TcpListener, UdpSocket},
+        thread,
+    };
+
+    use super::{NetworkExecutor, NetworkProtocol, NetworkServer};
+    use crate::{
+        events::NopEventManager,
+        executors::{Executor, ExitKind},
+        fuzzer::NopFuzzer,
+        inputs::{BytesInput, MultipartInput},
+        state::NopState,
+        Error,
+    };
+
+    /// Echoes each message on a single connection, in a thread.
+    /// Crashes shortly after closing the connection on `CRASH`, and ignores `HANG`.
+    #[derive(Debug)]
+    struct EchoServer {
+        listener: TcpListener,
+        crashed: Arc<AtomicBool>,
+    }
+
+    impl EchoServer {
+        fn new() -> (Self, SocketAddr) {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let address = listener.local_addr().unwrap();
+            let server = Self {
+                listener,
+                crashed: Arc::new(AtomicBool::new(false)),
+            };
+            (server, address)
+        }
+    }
+
+    impl NetworkServer for EchoServer {
+        fn start(&mut self) -> Result<(), Error> {
+            let listener = self.listener.try_clone()?;
+            let crashed = self.crashed.clone();
+            thread::spawn(move || {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0; 64];
+                while let Ok(len) = stream.read(&mut buf) {
+                    match &buf[..len] {
+                        [] => break,
+                        b"CRASH" => {
+                            drop(stream);
+                            thread::sleep(Duration::from_millis(2));
+                            crashed.store(true, Ordering::SeqCst);
+                            break;
+                        }
+                        b"HANG" => {}
+                        message => {
+                            if stream.write_all(message).is_err() {
+                                break;
+                            }
+                        }
+                    }
+                }
+            });
+            Ok(())
+        }
+
+        fn poll_exit(&mut self) -> Result<Option<ExitKind>, Error> {
+            Ok(self
+                .crashed
+                .swap(false, Ordering::SeqCst)
+                .then_some(ExitKind::Crash))
+        }
+
+        fn stop(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    /// Echoes each datagram, in a thread, except for `HANG`
+    #[derive(Debug)]
+    struct UdpEchoServer {
+        socket: UdpSocket,
+    }
+
+    impl NetworkServer for UdpEchoServer {
+        fn start(&mut self) -> Result<(), Error> {
+            let socket = self.socket.try_clone()?;
+            socket.set_read_timeout(Some(Duration::from_millis(500)))?;
+            thread::spawn(move || {
+                let mut buf = [0; 64];
+                while let Ok((len, peer)) = socket.recv_from(&mut buf) {
+                    if &buf[..len] != b"HANG" && socket.send_to(&buf[..len], peer).is_err() {
+                        break;
+                    }
+                }
+            });
+            Ok(())
+        }
+
+        fn poll_exit(&mut self) -> Result<Option<ExitKind>, Error> {
+            Ok(None)
+        }
+
+        fn stop(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    fn input(messages: &[&'static str]) -> MultipartInput<BytesInput> {
+        MultipartInput::from(
+            messages
+                .iter()
+                .map(|message| (*message, BytesInput::new(message.as_bytes().to_vec()))),
+        )
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_network_executor_tcp() {
+        let (server, address) = EchoServer::new();
+        let mut executor = NetworkExecutor::builder()
+            .address(address)
+            .build_with_server::<_, _, NopState<MultipartInput<BytesInput>>>(server, ())
+            .unwrap();
+
+        let input = input(&["HELO", "QUIT"]);
+        let (responses, exit_kind) = executor.send_messages(&input).unwrap();
+        assert_eq!(exit_kind, ExitKind::Ok);
+        assert_eq!(responses, vec![b"HELO".to_vec(), b"QUIT".to_vec()]);
+
+        let exit_kind = executor
+            .run_target(
+                &mut NopFuzzer::new(),
+                &mut NopState::new(),
+                &mut NopEventManager::new(),
+                &input,
+            )
+            .unwrap();
+        assert_eq!(exit_kind, ExitKind::Ok);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_network_executor_tcp_crash_and_hang() {
+        let (server, address) = EchoServer::new();
+        let mut executor = NetworkExecutor::builder()
+            .address(address)
+            .message_timeout(Duration::from_millis(50))
+            .exit_grace_period(Duration::from_millis(500))
+            .build_with_server::<_, _, NopState<MultipartInput<BytesInput>>>(server, ())
+            .unwrap();
+
+        // The server terminates only after closing the connection
+        let (responses, exit_kind) = executor
+            .send_messages(&input(&["HELO", "CRASH", "QUIT"]))
+            .unwrap();
+        assert_eq!(exit_kind, ExitKind::Crash);
+        assert_eq!(responses, vec![b"HELO".to_vec()]);
+
+        let (responses, exit_kind) = executor
+            .send_messages(&input(&["HELO", "HANG", "QUIT"]))
+            .unwrap();
+        assert_eq!(exit_kind, ExitKind::Timeout);
+        assert_eq!(responses, vec![b"HELO".to_vec(), Vec::new()]);
+
+        let (server, address) = EchoServer::new();
+        let mut executor = NetworkExecutor::builder()
+            .address(address)
+            .message_timeout(Duration::from_millis(50))
+            .require_responses(false)
+            .build_with_server::<_, _, NopState<MultipartInput<BytesInput>>>(server, ())
+            .unwrap();
+        let (responses, exit_kind) = executor
+            .send_messages(&input(&["HELO", "HANG", "QUIT"]))
+            .unwrap();
+        assert_eq!(exit_kind, ExitKind::Ok);
+        assert_eq!(
+            responses,
+            vec![b"HELO".to_vec(), Vec::new(), b"QUIT".to_vec()]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_network_executor_udp() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = socket.local_addr().unwrap();
+        let mut executor = NetworkExecutor::builder()
+            .address(address)
+            .protocol(NetworkProtocol::Udp)
+            .message_timeout(Duration::from_millis(50))
+            .build_with_server::<_, _, NopState<MultipartInput<BytesInput>>>(
+                UdpEchoServer { socket },
+                (),
+            )
+            .unwrap();
+
+        let (responses, exit_kind) = executor.send_messages(&input(&["HELO", "QUIT"])).unwrap();
+        assert_eq!(exit_kind, ExitKind::Ok);
+        assert_eq!(responses, vec![b"HELO".to_vec(), b"QUIT".to_vec()]);
+
+        let (responses, exit_kind) = executor
+            .send_messages(&input(&["HELO", "HANG", "QUIT"]))
+            .unwrap();
+        assert_eq!(exit_kind, ExitKind::Timeout);
+        assert_eq!(responses, vec![b"HELO".to_vec(), Vec::new()]);
+    }
+}
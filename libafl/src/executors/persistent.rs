@@ -0,0 +1,712 @@
+//! The persistent executor runs targets written in any language, like Python, Java or Go, in persistent mode.
+//!
+//! Unlike the [`crate::executors::CommandExecutor`], it does not spawn a new process per execution.
+//! The target is started once, and then executes one input after the other, talking to the fuzzer
+//! over a simple, language-agnostic pipe protocol. Reference clients implementing the target side
+//! live in the `utils/persistent_clients` folder of the repository.
+//!
+//! # Protocol
+//!
+//! All integers are 32 bit, little endian.
+//!
+//! The fuzzer starts the target with two extra pipes, and passes their file descriptors in environment variables:
+//! * [`PERSISTENT_CTL_FD_ENV_VAR`]: the control pipe, the target reads inputs from it,
+//! * [`PERSISTENT_ST_FD_ENV_VAR`]: the status pipe, the target writes replies to it.
+//!
+//! If coverage is collected, the coverage map is a file, passed in [`PERSISTENT_MAP_PATH_ENV_VAR`],
+//! with its size in [`PERSISTENT_MAP_SIZE_ENV_VAR`]. The target maps it as shared memory,
+//! and increments the entries for the edges it hits, see [`PersistentCoverageMap`].
+//!
+//! 1. Once it is ready, the target writes the 4 bytes [`PERSISTENT_HELLO`] to the status pipe.
+//! 2. For each execution, the fuzzer writes the length of the input, followed by the input, to the control pipe.
+//! 3. The target runs the input, and replies with a status: [`PERSISTENT_STATUS_OK`], or
+//!    [`PERSISTENT_STATUS_CRASH`] if it caught a crash, like an uncaught exception.
+//! 4. The target goes back to 2. If the control pipe is closed, it exits.
+//!
+//! If the target dies during an execution, or does not reply within the timeout, the fuzzer restarts it.
+use alloc::{borrow::ToOwned, string::ToString, vec::Vec};
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    ptr, slice,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+use std::{
+    env,
+    ffi::{OsStr, OsString},
+    fs::{self, OpenOptions},
+    io::{self, ErrorKind, Read, Write},
+    os::{
+        fd::AsRawFd,
+        unix::{
+            io::RawFd,
+            process::{CommandExt, ExitStatusExt},
+        },
+    },
+    path::{Path, PathBuf},
+    process::{self, Child, Command, Stdio},
+    time::Instant,
+};
+
+use libafl_bolts::{os::pipes::Pipe, tuples::RefIndexable, AsSlice};
+
+use super::HasTimeout;
+use crate::{
+    executors::{Executor, ExitKind, HasObservers},
+    inputs::HasTargetBytes,
+    observers::ObserversTuple,
+    state::{HasExecutions, State, UsesState},
+    Error,
+};
+
+/// The environment variable holding the file descriptor the target reads inputs from
+pub const PERSISTENT_CTL_FD_ENV_VAR: &str = "__LIBAFL_PERSISTENT_CTL_FD";
+/// The environment variable holding the file descriptor the target writes its replies to
+pub const PERSISTENT_ST_FD_ENV_VAR: &str = "__LIBAFL_PERSISTENT_ST_FD";
+/// The environment variable holding the path of the coverage map file, if any
+pub const PERSISTENT_MAP_PATH_ENV_VAR: &str = "__LIBAFL_PERSISTENT_MAP_PATH";
+/// The environment variable holding the size of the coverage map, if any
+pub const PERSISTENT_MAP_SIZE_ENV_VAR: &str = "__LIBAFL_PERSISTENT_MAP_SIZE";
+
+/// The bytes the target sends once it is ready to receive inputs
+pub const PERSISTENT_HELLO: [u8; 4] = *b"LAF1";
+/// The status the target replies with after it ran an input
+pub const PERSISTENT_STATUS_OK: u32 = 0;
+/// The status the target replies with if it caught a crash while running an input
+pub const PERSISTENT_STATUS_CRASH: u32 = 1;
+
+/// The file descriptor of the control pipe in the target
+const CTL_FD: RawFd = 200;
+/// The file descriptor of the status pipe in the target
+const ST_FD: RawFd = CTL_FD + 1;
+
+/// A coverage map shared with a target of the [`PersistentExecutor`].
+///
+/// It is backed by a file, so that any language can map it, for example using Python's `mmap`,
+/// Java's `FileChannel.map` or Go's `syscall.Mmap`. The file is deleted on drop.
+/// Use it as the backing memory of a map observer.
+#[derive(Debug)]
+pub struct PersistentCoverageMap {
+    path: PathBuf,
+    map: *mut u8,
+    map_size: usize,
+}
+
+impl PersistentCoverageMap {
+    /// Creates a new zeroed coverage map of the given size, in `/dev/shm` if available,
+    /// or in the temp directory otherwise.
+    pub fn new(map_size: usize) -> Result<Self, Error> {
+        static MAP_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+        let shm_dir = Path::new("/dev/shm");
+        let dir = if shm_dir.is_dir() {
+            shm_dir.to_owned()
+        } else {
+            env::temp_dir()
+        };
+        let path = dir.join(format!(
+            "libafl_persistent_map_{}_{}",
+            process::id(),
+            MAP_COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        Self::with_path(path, map_size)
+    }
+
+    /// Creates a new zeroed coverage map of the given size, backed by the file at `path`
+    pub fn with_path<P: AsRef<Path>>(path: P, map_size: usize) -> Result<Self, Error> {
+        if map_size == 0 {
+            return Err(Error::illegal_argument("The coverage map can't be empty"));
+        }
+        let path = path.as_ref().to_owned();
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        file.set_len(map_size.try_into()?)?;
+
+        // # Safety
+        // The file is large enough, and stays mapped until we drop the map.
+        let map = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                map_size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if map == libc::MAP_FAILED {
+            let err = io::Error::last_os_error();
+            drop(fs::remove_file(&path));
+            return Err(Error::os_error(
+                err,
+                format!("Could not map the coverage map file {}", path.display()),
+            ));
+        }
+
+        Ok(Self {
+            path,
+            map: map.cast(),
+            map_size,
+        })
+    }
+
+    /// The path of the file backing this map
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Deref for PersistentCoverageMap {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.map, self.map_size) }
+    }
+}
+
+impl DerefMut for PersistentCoverageMap {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.map, self.map_size) }
+    }
+}
+
+impl Drop for PersistentCoverageMap {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.map.cast(), self.map_size);
+        }
+        drop(fs::remove_file(&self.path));
+    }
+}
+
+/// A running instance of the target
+#[derive(Debug)]
+struct PersistentChild {
+    child: Child,
+    ctl_pipe: Pipe,
+    st_pipe: Pipe,
+    /// The number of inputs run by this instance
+    iterations: u64,
+}
+
+impl PersistentChild {
+    /// Waits until the status pipe is readable, returns `false` on timeout
+    fn wait_readable(&self, timeout: Duration) -> Result<bool, Error> {
+        let Some(fd) = self.st_pipe.read_end() else {
+            return Err(Error::illegal_state("The status pipe was already closed"));
+        };
+        let mut pollfd = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout_ms = libc::c_int::try_from(timeout.as_millis()).unwrap_or(libc::c_int::MAX);
+        loop {
+            // # Safety
+            // We pass a single, valid pollfd.
+            let ret = unsafe { libc::poll(&raw mut pollfd, 1, timeout_ms) };
+            if ret >= 0 {
+                // On hangup, the next read fails, so we report it as readable
+                return Ok(ret > 0);
+            }
+            let err = io::Error::last_os_error();
+            if err.kind() != ErrorKind::Interrupted {
+                return Err(Error::os_error(err, "Polling the status pipe failed"));
+            }
+        }
+    }
+
+    /// Reads the next 4 bytes from the status pipe, or `None` on timeout or if the target went away
+    fn read_status(&mut self, timeout: Duration) -> Result<Option<[u8; 4]>, Error> {
+        if !self.wait_readable(timeout)? {
+            return Ok(None);
+        }
+        let mut buf = [0; 4];
+        match self.st_pipe.read_exact(&mut buf) {
+            Ok(()) => Ok(Some(buf)),
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Kills the target, and returns how it terminated
+    fn kill(mut self) -> Result<ExitKind, Error> {
+        // if this fails, the process most likely finished in the meantime
+        drop(self.child.kill());
+        self.wait()
+    }
+
+    /// Waits for the target to terminate, and returns how
+    fn wait(&mut self) -> Result<ExitKind, Error> {
+        let status = self.child.wait()?;
+        // A persistent target should not exit on its own while running an input
+        if status.signal().is_some() || status.code() != Some(0) {
+            Ok(ExitKind::Crash)
+        } else {
+            Ok(ExitKind::Ok)
+        }
+    }
+}
+
+/// An [`Executor`] running a target in persistent mode, over a language-agnostic pipe protocol.
+///
+/// See the [module documentation](self) for the protocol.
+/// The target is started on the first execution, and restarted after it crashed or timed out,
+/// or after a configurable number of iterations.
+///
+/// Construct it with [`PersistentExecutor::builder()`].
+pub struct PersistentExecutor<OT, S> {
+    program: OsString,
+    args: Vec<OsString>,
+    envs: Vec<(OsString, OsString)>,
+    cwd: Option<PathBuf>,
+    debug_child: bool,
+    timeout: Duration,
+    startup_timeout: Duration,
+    max_iterations: Option<u64>,
+    child: Option<PersistentChild>,
+    observers: OT,
+    phantom: PhantomData<S>,
+}
+
+impl PersistentExecutor<(), ()> {
+    /// Creates a builder for a new [`PersistentExecutor`].
+    ///
+    /// It mimics the api of [`Command`], specifically, you will use
+    /// `arg`, `args`, `env`, and so on.
+    #[must_use]
+    pub fn builder() -> PersistentExecutorBuilder {
+        PersistentExecutorBuilder::new()
+    }
+}
+
+impl<OT, S> Debug for PersistentExecutor<OT, S>
+where
+    OT: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PersistentExecutor")
+            .field("program", &self.program)
+            .field("args", &self.args)
+            .field("timeout", &self.timeout)
+            .field("startup_timeout", &self.startup_timeout)
+            .field("max_iterations", &self.max_iterations)
+            .field("child", &self.child)
+            .field("observers", &self.observers)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<OT, S> PersistentExecutor<OT, S> {
+    /// Starts a new instance of the target, and waits for its handshake
+    fn start(&self) -> Result<PersistentChild, Error> {
+        let mut ctl_pipe = Pipe::new()?;
+        let mut st_pipe = Pipe::new()?;
+        let (Some(ctl_read), Some(ctl_write), Some(st_read), Some(st_write)) = (
+            ctl_pipe.read_end(),
+            ctl_pipe.write_end(),
+            st_pipe.read_end(),
+            st_pipe.write_end(),
+        ) else {
+            return Err(Error::illegal_state("Could not create the pipes"));
+        };
+
+        let mut command = Command::new(&self.program);
+        command
+            .args(&self.args)
+            .envs(
+                self.envs
+                    .iter()
+                    .map(|(k, v)| (k.as_os_str(), v.as_os_str())),
+            )
+            .env(PERSISTENT_CTL_FD_ENV_VAR, CTL_FD.to_string())
+            .env(PERSISTENT_ST_FD_ENV_VAR, ST_FD.to_string())
+            .stdin(Stdio::null());
+        if let Some(cwd) = &self.cwd {
+            command.current_dir(cwd);
+        }
+        if !self.debug_child {
+            command.stdout(Stdio::null());
+            command.stderr(Stdio::null());
+        }
+        // # Safety
+        // Only async-signal-safe calls in the child.
+        unsafe {
+            command.pre_exec(move || {
+                if libc::dup2(ctl_read, CTL_FD) < 0 || libc::dup2(st_write, ST_FD) < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                libc::close(ctl_read);
+                libc::close(ctl_write);
+                libc::close(st_read);
+                libc::close(st_write);
+                Ok(())
+            });
+        }
+        let child = command.spawn().map_err(|err| {
+            Error::illegal_state(format!(
+                "Could not spawn {}: {err}",
+                self.program.to_string_lossy()
+            ))
+        })?;
+        ctl_pipe.close_read_end();
+        st_pipe.close_write_end();
+
+        let mut child = PersistentChild {
+            child,
+            ctl_pipe,
+            st_pipe,
+            iterations: 0,
+        };
+        let start = Instant::now();
+        match child.read_status(self.startup_timeout)? {
+            Some(hello) if hello == PERSISTENT_HELLO => Ok(child),
+            Some(hello) => {
+                child.kill()?;
+                Err(Error::illegal_state(format!(
+                    "The target sent an unexpected handshake {hello:?}, expected {PERSISTENT_HELLO:?}"
+                )))
+            }
+            None => {
+                child.kill()?;
+                Err(Error::illegal_state(format!(
+                    "The target did not complete the handshake within {:?}",
+                    start.elapsed()
+                )))
+            }
+        }
+    }
+
+    /// Runs the input in the target, (re)starting it if needed
+    fn execute(&mut self, input: &[u8]) -> Result<ExitKind, Error> {
+        let len = u32::try_from(input.len())?;
+        let mut child = match self.child.take() {
+            Some(child) => child,
+            None => self.start()?,
+        };
+
+        let sent = child
+            .ctl_pipe
+            .write_all(&len.to_le_bytes())
+            .and_then(|()| child.ctl_pipe.write_all(input));
+        if let Err(err) = sent {
+            if err.kind() == ErrorKind::BrokenPipe {
+                // The target went away before the execution
+                return child.wait();
+            }
+            return Err(err.into());
+        }
+
+        let Some(status) = child.read_status(self.timeout)? else {
+            return if child.wait_readable(Duration::ZERO)? {
+                // The target went away during the execution
+                child.wait()
+            } else {
+                child.kill()?;
+                Ok(ExitKind::Timeout)
+            };
+        };
+        let exit_kind = match u32::from_le_bytes(status) {
+            PERSISTENT_STATUS_OK => ExitKind::Ok,
+            PERSISTENT_STATUS_CRASH => ExitKind::Crash,
+            status => {
+                child.kill()?;
+                return Err(Error::illegal_state(format!(
+                    "The target replied with an unknown status {status}"
+                )));
+            }
+        };
+
+        child.iterations += 1;
+        if exit_kind == ExitKind::Crash
+            || self
+                .max_iterations
+                .is_some_and(|max_iterations| child.iterations >= max_iterations)
+        {
+            // Start from a clean state
+            child.kill()?;
+        } else {
+            self.child = Some(child);
+        }
+        Ok(exit_kind)
+    }
+}
+
+impl<OT, S> Drop for PersistentExecutor<OT, S> {
+    fn drop(&mut self) {
+        if let Some(child) = self.child.take() {
+            let _ = child.kill();
+        }
+    }
+}
+
+impl<EM, OT, S, Z> Executor<EM, Z> for PersistentExecutor<OT, S>
+where
+    EM: UsesState<State = S>,
+    S: State + HasExecutions,
+    S::Input: HasTargetBytes,
+    OT: ObserversTuple<S::Input, S>,
+    Z: UsesState<State = S>,
+{
+    fn run_target(
+        &mut self,
+        _fuzzer: &mut Z,
+        state: &mut Self::State,
+        _mgr: &mut EM,
+        input: &Self::Input,
+    ) -> Result<ExitKind, Error> {
+        *state.executions_mut() += 1;
+        self.execute(input.target_bytes().as_slice())
+    }
+}
+
+impl<OT, S> HasTimeout for PersistentExecutor<OT, S> {
+    #[inline]
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    #[inline]
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+}
+
+impl<OT, S> UsesState for PersistentExecutor<OT, S>
+where
+    S: State,
+{
+    type State = S;
+}
+
+impl<OT, S> HasObservers for PersistentExecutor<OT, S>
+where
+    S: State,
+    OT: ObserversTuple<S::Input, S>,
+{
+    type Observers = OT;
+
+    fn observers(&self) -> RefIndexable<&Self::Observers, Self::Observers> {
+        RefIndexable::from(&self.observers)
+    }
+
+    fn observers_mut(&mut self) -> RefIndexable<&mut Self::Observers, Self::Observers> {
+        RefIndexable::from(&mut self.observers)
+    }
+}
+
+/// The builder for a [`PersistentExecutor`]
+#[derive(Debug, Clone)]
+pub struct PersistentExecutorBuilder {
+    program: Option<OsString>,
+    args: Vec<OsString>,
+    envs: Vec<(OsString, OsString)>,
+    cwd: Option<PathBuf>,
+    debug_child: bool,
+    timeout: Duration,
+    startup_timeout: Duration,
+    max_iterations: Option<u64>,
+}
+
+impl Default for PersistentExecutorBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PersistentExecutorBuilder {
+    /// Create a new [`PersistentExecutorBuilder`]
+    #[must_use]
+    fn new() -> PersistentExecutorBuilder {
+        PersistentExecutorBuilder {
+            program: None,
+            args: vec![],
+            envs: vec![],
+            cwd: None,
+            debug_child: false,
+            timeout: Duration::from_secs(5),
+            startup_timeout: Duration::from_secs(10),
+            max_iterations: None,
+        }
+    }
+
+    /// Set the binary to execute
+    /// This option is required.
+    pub fn program<O>(&mut self, program: O) -> &mut Self
+    where
+        O: AsRef<OsStr>,
+    {
+        self.program = Some(program.as_ref().to_owned());
+        self
+    }
+
+    /// Adds an argument to the program's commandline.
+    pub fn arg<O: AsRef<OsStr>>(&mut self, arg: O) -> &mut Self {
+        self.args.push(arg.as_ref().to_owned());
+        self
+    }
+
+    /// Adds a range of arguments to the program's commandline.
+    pub fn args<IT, O>(&mut self, args: IT) -> &mut Self
+    where
+        IT: IntoIterator<Item = O>,
+        O: AsRef<OsStr>,
+    {
+        for arg in args {
+            self.arg(arg.as_ref());
+        }
+        self
+    }
+
+    /// Adds a range of environment variables to the executed command.
+    pub fn envs<IT, K, V>(&mut self, vars: IT) -> &mut Self
+    where
+        IT: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        for (ref key, ref val) in vars {
+            self.env(key.as_ref(), val.as_ref());
+        }
+        self
+    }
+
+    /// Adds an environment variable to the executed command.
+    pub fn env<K, V>(&mut self, key: K, val: V) -> &mut Self
+    where
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        self.envs
+            .push((key.as_ref().to_owned(), val.as_ref().to_owned()));
+        self
+    }
+
+    /// Sets the working directory for the child process.
+    pub fn current_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Self {
+        self.cwd = Some(dir.as_ref().to_owned());
+        self
+    }
+
+    /// If set to true, the child's output won't be redirecited to `/dev/null`.
+    /// Defaults to `false`.
+    pub fn debug_child(&mut self, debug_child: bool) -> &mut Self {
+        self.debug_child = debug_child;
+        self
+    }
+
+    /// Shares the given coverage map with the target
+    pub fn coverage_map(&mut self, map: &PersistentCoverageMap) -> &mut Self {
+        self.env(PERSISTENT_MAP_PATH_ENV_VAR, map.path());
+        self.env(PERSISTENT_MAP_SIZE_ENV_VAR, map.len().to_string())
+    }
+
+    /// Sets the execution timeout for each input.
+    /// Defaults to 5 seconds.
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets how long to wait for a (re)started target to complete the handshake.
+    /// Defaults to 10 seconds.
+    pub fn startup_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.startup_timeout = timeout;
+        self
+    }
+
+    /// Restarts the target after the given number of inputs, to get rid of any state it accumulated.
+    /// By default, the target is only restarted after a crash or a timeout.
+    pub fn max_iterations(&mut self, max_iterations: u64) -> &mut Self {
+        self.max_iterations = Some(max_iterations);
+        self
+    }
+
+    /// Builds the [`PersistentExecutor`].
+    /// The target is started on the first execution.
+    pub fn build<OT, S>(&self, observers: OT) -> Result<PersistentExecutor<OT, S>, Error> {
+        let Some(program) = &self.program else {
+            return Err(Error::illegal_argument(
+                "PersistentExecutor::builder: no program set!",
+            ));
+        };
+        Ok(PersistentExecutor {
+            program: program.clone(),
+            args: self.args.clone(),
+            envs: self.envs.clone(),
+            cwd: self.cwd.clone(),
+            debug_child: self.debug_child,
+            timeout: self.timeout,
+            startup_timeout: self.startup_timeout,
+            max_iterations: self.max_iterations,
+            child: None,
+            observers,
+            phantom: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::{PersistentCoverageMap, PersistentExecutor};
+    use crate::executors::ExitKind;
+
+    /// A target in bash: marks the first map entry, crashes on inputs starting with `C`,
+    /// and hangs on inputs starting with `H`.
+    const TARGET: &str = r#"
+printf 'LAF1' >&"$__LIBAFL_PERSISTENT_ST_FD"
+exec 3<&"$__LIBAFL_PERSISTENT_CTL_FD"
+while :; do
+    len=$(dd bs=1 count=4 2>/dev/null <&3 | od -An -tu4 | tr -d ' ')
+    [ -z "$len" ] && exit 0
+    data=$(dd bs=1 count="$len" 2>/dev/null <&3)
+    printf '\001' | dd of="$__LIBAFL_PERSISTENT_MAP_PATH" conv=notrunc 2>/dev/null
+    case "$data" in
+        C*) kill -SEGV $$ ;;
+        H*) sleep 10 ;;
+    esac
+    printf '\000\000\000\000' >&"$__LIBAFL_PERSISTENT_ST_FD"
+done
+"#;
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    #[cfg(target_endian = "little")]
+    fn test_persistent_executor() {
+        let mut map = PersistentCoverageMap::new(16).unwrap();
+        let mut executor: PersistentExecutor<(), ()> = PersistentExecutor::builder()
+            .program("bash")
+            .args(["-c", TARGET])
+            .coverage_map(&map)
+            .timeout(Duration::from_millis(500))
+            .build(())
+            .unwrap();
+
+        assert_eq!(executor.execute(b"hello").unwrap(), ExitKind::Ok);
+        assert_eq!(map[0], 1);
+        let pid = executor.child.as_ref().unwrap().child.id();
+
+        // The target keeps running
+        map[0] = 0;
+        assert_eq!(executor.execute(b"again").unwrap(), ExitKind::Ok);
+        assert_eq!(map[0], 1);
+        assert_eq!(executor.child.as_ref().unwrap().child.id(), pid);
+
+        assert_eq!(executor.execute(b"Crash").unwrap(), ExitKind::Crash);
+        assert!(executor.child.is_none());
+        assert_eq!(executor.execute(b"Hang").unwrap(), ExitKind::Timeout);
+        assert!(executor.child.is_none());
+
+        // The target is restarted
+        assert_eq!(executor.execute(b"hello").unwrap(), ExitKind::Ok);
+        assert_ne!(executor.child.as_ref().unwrap().child.id(), pid);
+    }
+}
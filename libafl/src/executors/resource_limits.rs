@@ -0,0 +1,350 @@
+//! Resource limits for executors running the target in a child process.
+//!
+//! The [`ResourceLimits`] watchdog polls the child while it runs, and kills it as soon as it uses
+//! too much memory, opens too many files or spawns too many processes.
+//! Executors then report the run as [`ExitKind::Oom`](crate::executors::ExitKind::Oom), use the
+//! [`crate::feedbacks::OomFeedback`] to treat it as an objective.
+//!
+//! The usage of the child is read from `/proc`, on systems without `procfs` the limits never trigger.
+//! The children of a process are found in `/proc/<pid>/task/<tid>/children`,
+//! the kernel needs `CONFIG_PROC_CHILDREN` for the children limit.
+
+use alloc::vec::Vec;
+use core::{fmt, time::Duration};
+#[cfg(target_os = "linux")]
+use std::os::fd::FromRawFd;
+use std::{
+    fs, io,
+    os::fd::{AsRawFd, OwnedFd},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+
+/// The default interval at which a running child is checked
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A limit that a child process went over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResourceBreach {
+    /// The resident set size went over the limit, in bytes
+    Rss {
+        /// The resident set size of the child
+        used: usize,
+        /// The limit
+        limit: usize,
+    },
+    /// The child had too many open file descriptors
+    OpenFds {
+        /// The number of open file descriptors of the child
+        used: usize,
+        /// The limit
+        limit: usize,
+    },
+    /// The child spawned too many processes
+    Children {
+        /// The number of (transitive) children of the child
+        used: usize,
+        /// The limit
+        limit: usize,
+    },
+}
+
+impl fmt::Display for ResourceBreach {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rss { used, limit } => {
+                write!(f, "RSS of {used} bytes over the limit of {limit} bytes")
+            }
+            Self::OpenFds { used, limit } => {
+                write!(f, "{used} open file descriptors, the limit is {limit}")
+            }
+            Self::Children { used, limit } => {
+                write!(f, "{used} child processes, the limit is {limit}")
+            }
+        }
+    }
+}
+
+/// Limits on the resources a child process may use during a run.
+///
+/// All limits are unset by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceLimits {
+    rss_limit: Option<usize>,
+    max_open_fds: Option<usize>,
+    max_children: Option<usize>,
+    poll_interval: Duration,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ResourceLimits {
+    /// Creates new [`ResourceLimits`], without any limit
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            rss_limit: None,
+            max_open_fds: None,
+            max_children: None,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    /// Limits the resident set size of the child, in megabytes
+    #[must_use]
+    pub fn with_rss_limit_mb(mut self, rss_limit_mb: usize) -> Self {
+        self.rss_limit = Some(rss_limit_mb << 20);
+        self
+    }
+
+    /// Limits the number of file descriptors the child may keep open, including stdio
+    #[must_use]
+    pub fn with_max_open_fds(mut self, max_open_fds: usize) -> Self {
+        self.max_open_fds = Some(max_open_fds);
+        self
+    }
+
+    /// Limits the number of processes the child may spawn, counting their children as well
+    #[must_use]
+    pub fn with_max_children(mut self, max_children: usize) -> Self {
+        self.max_children = Some(max_children);
+        self
+    }
+
+    /// Sets how often the child is checked, [`DEFAULT_POLL_INTERVAL`] by default.
+    ///
+    /// Short breaches between two checks go unnoticed.
+    #[must_use]
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// The resident set size limit, in bytes
+    #[must_use]
+    pub fn rss_limit(&self) -> Option<usize> {
+        self.rss_limit
+    }
+
+    /// The open file descriptors limit
+    #[must_use]
+    pub fn max_open_fds(&self) -> Option<usize> {
+        self.max_open_fds
+    }
+
+    /// The child processes limit
+    #[must_use]
+    pub fn max_children(&self) -> Option<usize> {
+        self.max_children
+    }
+
+    /// How often the child is checked
+    #[must_use]
+    pub fn poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+
+    /// Returns `true` if no limit is set, so there is nothing to watch
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.rss_limit.is_none() && self.max_open_fds.is_none() && self.max_children.is_none()
+    }
+
+    /// Checks the process `pid` against the limits, and returns the first limit it went over.
+    ///
+    /// A process that is gone, or that can't be inspected, is not in breach.
+    #[must_use]
+    pub fn check(&self, pid: i32) -> Option<ResourceBreach> {
+        if let Some(limit) = self.rss_limit {
+            let used = rss(pid);
+            if used > limit {
+                return Some(ResourceBreach::Rss { used, limit });
+            }
+        }
+        if let Some(limit) = self.max_open_fds {
+            let used = open_fds(pid);
+            if used > limit {
+                return Some(ResourceBreach::OpenFds { used, limit });
+            }
+        }
+        if let Some(limit) = self.max_children {
+            let used = descendants(pid).len();
+            if used > limit {
+                return Some(ResourceBreach::Children { used, limit });
+            }
+        }
+        None
+    }
+}
+
+/// The resident set size of `pid`, in bytes
+fn rss(pid: i32) -> usize {
+    let Ok(statm) = fs::read_to_string(format!("/proc/{pid}/statm")) else {
+        return 0;
+    };
+    let pages = statm
+        .split_whitespace()
+        .nth(1)
+        .and_then(|pages| pages.parse::<usize>().ok())
+        .unwrap_or(0);
+    // # Safety
+    // `sysconf` has no preconditions
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    pages * usize::try_from(page_size).unwrap_or(4096)
+}
+
+/// The number of open file descriptors of `pid`
+fn open_fds(pid: i32) -> usize {
+    fs::read_dir(format!("/proc/{pid}/fd")).map_or(0, Iterator::count)
+}
+
+/// All (transitive) children of `pid`, following the `children` of its threads in `/proc`
+pub(crate) fn descendants(pid: i32) -> Vec<i32> {
+    let mut found = Vec::new();
+    let mut todo = vec![pid];
+    while let Some(parent) = todo.pop() {
+        let Ok(tasks) = fs::read_dir(format!("/proc/{parent}/task")) else {
+            continue;
+        };
+        for task in tasks.flatten() {
+            let Ok(children) = fs::read_to_string(task.path().join("children")) else {
+                continue;
+            };
+            for child in children
+                .split_whitespace()
+                .filter_map(|child| child.parse::<i32>().ok())
+            {
+                found.push(child);
+                todo.push(child);
+            }
+        }
+    }
+    found
+}
+
+/// A pidfd of a child process, to wait for it to exit with a timeout, see `pidfd_open(2)`
+#[derive(Debug)]
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub(crate) struct PidFd(OwnedFd);
+
+impl PidFd {
+    /// Opens a pidfd for `pid`, `None` if the kernel does not support them
+    #[cfg(target_os = "linux")]
+    pub(crate) fn open(pid: i32) -> Option<Self> {
+        // # Safety
+        // `pidfd_open` has no memory preconditions.
+        let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid, 0) };
+        let fd = i32::try_from(fd).ok().filter(|fd| *fd >= 0)?;
+        // # Safety
+        // The fd was just opened, nobody else owns it.
+        Some(Self(unsafe { OwnedFd::from_raw_fd(fd) }))
+    }
+
+    /// Opens a pidfd for `pid`, `None` if the kernel does not support them
+    #[cfg(not(target_os = "linux"))]
+    pub(crate) fn open(_pid: i32) -> Option<Self> {
+        None
+    }
+
+    /// Blocks until the process exits, or `timeout` passes.
+    /// Returns `true` if it exited, it still has to be reaped.
+    pub(crate) fn wait_exit(&self, timeout: Duration) -> Result<bool, Error> {
+        let mut pollfd = libc::pollfd {
+            fd: self.0.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout = i32::try_from(timeout.as_micros().div_ceil(1000)).unwrap_or(i32::MAX);
+        // # Safety
+        // `pollfd` is a single valid entry.
+        match unsafe { libc::poll(&raw mut pollfd, 1, timeout) } {
+            -1 if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted => Ok(false),
+            -1 => Err(Error::last_os_error(
+                "Failed to poll the pidfd of the child",
+            )),
+            ready => Ok(ready > 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+    use std::process::{Command, Stdio};
+
+    use super::{PidFd, ResourceBreach, ResourceLimits};
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_resource_limits_check() {
+        if !std::path::Path::new("/proc/self/statm").exists() {
+            return;
+        }
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg("sleep 5 & sleep 5 & wait")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let pid = i32::try_from(child.id()).unwrap();
+        // give the shell some time to spawn its children
+        std::thread::sleep(Duration::from_millis(200));
+
+        assert!(ResourceLimits::new().is_empty());
+        assert_eq!(ResourceLimits::new().check(pid), None);
+
+        let generous = ResourceLimits::new()
+            .with_rss_limit_mb(1 << 20)
+            .with_max_open_fds(1 << 20)
+            .with_max_children(16);
+        assert_eq!(generous.check(pid), None);
+
+        let rss = ResourceLimits::new().with_rss_limit_mb(0).check(pid);
+        assert!(
+            matches!(rss, Some(ResourceBreach::Rss { limit: 0, .. })),
+            "{rss:?}"
+        );
+
+        let fds = ResourceLimits::new().with_max_open_fds(0).check(pid);
+        assert!(
+            matches!(fds, Some(ResourceBreach::OpenFds { limit: 0, .. })),
+            "{fds:?}"
+        );
+
+        let children = ResourceLimits::new().with_max_children(1).check(pid);
+        assert_eq!(
+            children,
+            Some(ResourceBreach::Children { used: 2, limit: 1 })
+        );
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    #[cfg_attr(miri, ignore)]
+    fn test_pidfd_wait_exit() {
+        let mut child = Command::new("sleep")
+            .arg("0.3")
+            .stdin(Stdio::null())
+            .spawn()
+            .unwrap();
+        let Some(pidfd) = PidFd::open(i32::try_from(child.id()).unwrap()) else {
+            // pidfds are not supported by this kernel
+            child.wait().unwrap();
+            return;
+        };
+        assert!(!pidfd.wait_exit(Duration::from_millis(10)).unwrap());
+        assert!(pidfd.wait_exit(Duration::from_secs(10)).unwrap());
+        assert!(child.wait().unwrap().success());
+    }
+}
@@ -0,0 +1,821 @@
+//! A [`SnapshotExecutor`] restores the writable memory of the target between runs,
+//! to get the isolation of a fork at the speed of an in-process executor.
+//!
+//! The memory to restore is described by a [`MemorySnapshot`], usually the data and bss of the module of the target.
+//! Only the pages written since the last restore are copied back, found with the soft-dirty bits of the kernel,
+//! see <https://docs.kernel.org/admin-guide/mm/soft-dirty.html>.
+//! If the kernel does not support them, all pages are copied back.
+//!
+//! The heap of the system allocator is shared with the fuzzer, and is never restored.
+//! The memory the harness maps itself, like the arenas of its own allocator, is restored too if it registers it
+//! with a [`MappingTracker`], and the mappings it registers during a run are unmapped before the next one.
+//!
+//! Only works on Linux.
+
+use alloc::{sync::Arc, vec, vec::Vec};
+use core::{
+    fmt::{self, Debug, Formatter},
+    ptr,
+    time::Duration,
+};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::Write,
+    os::unix::fs::FileExt,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+use libafl_bolts::tuples::RefIndexable;
+
+use super::HasTimeout;
+use crate::{
+    executors::{Executor, ExitKind, HasObservers},
+    inputs::UsesInput,
+    observers::ObserversTuple,
+    state::UsesState,
+    Error,
+};
+
+/// The soft-dirty bit of a `/proc/self/pagemap` entry
+const PAGEMAP_SOFT_DIRTY: u64 = 1 << 55;
+/// Writing this to `/proc/self/clear_refs` clears the soft-dirty bits of all pages
+const CLEAR_REFS_SOFT_DIRTY: &[u8] = b"4";
+
+/// A mapping of `/proc/self/maps`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Mapping<'a> {
+    start: usize,
+    end: usize,
+    writable: bool,
+    private: bool,
+    /// The mapped file, or pseudo-path like `[heap]`, `None` for anonymous memory
+    path: Option<&'a str>,
+}
+
+/// Iterates over the mappings in the content of `/proc/self/maps`, without allocating
+fn mappings(maps: &str) -> impl Iterator<Item = Mapping<'_>> {
+    maps.lines().filter_map(|line| {
+        let mut fields = line.split_whitespace();
+        let (start, end) = fields.next()?.split_once('-')?;
+        let perms = fields.next()?.as_bytes();
+        // Skip the offset, device and inode
+        let path = fields.nth(3);
+        Some(Mapping {
+            start: usize::from_str_radix(start, 16).ok()?,
+            end: usize::from_str_radix(end, 16).ok()?,
+            writable: perms.get(1) == Some(&b'w'),
+            private: perms.get(3) == Some(&b'p'),
+            path,
+        })
+    })
+}
+
+/// Parses the content of `/proc/self/maps`
+fn parse_mappings(maps: &str) -> Vec<Mapping<'_>> {
+    mappings(maps).collect()
+}
+
+/// Removes the `excluded` ranges from the sorted, non-overlapping `ranges`
+fn subtract_ranges(ranges: &[(usize, usize)], excluded: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut result = Vec::new();
+    for &(start, end) in ranges {
+        let mut pieces = vec![(start, end)];
+        for &(ex_start, ex_end) in excluded {
+            pieces = pieces
+                .into_iter()
+                .flat_map(|(s, e)| {
+                    if ex_end <= s || ex_start >= e {
+                        vec![(s, e)]
+                    } else {
+                        [(s, ex_start), (ex_end, e)]
+                            .into_iter()
+                            .filter(|(s, e)| s < e)
+                            .collect()
+                    }
+                })
+                .collect();
+        }
+        result.extend(pieces);
+    }
+    result
+}
+
+/// Sorts the ranges and merges the overlapping or adjacent ones
+fn merge_ranges(mut ranges: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    ranges.sort_unstable();
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+fn page_size() -> usize {
+    // # Safety
+    // `sysconf` has no preconditions.
+    #[allow(clippy::cast_sign_loss)]
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+    size
+}
+
+/// A saved range of memory
+struct SavedRange {
+    start: usize,
+    data: Vec<u8>,
+}
+/// The mappings the harness registered, shared between the harness and a [`MemorySnapshot`].
+///
+/// The harness registers the memory it maps itself with [`Self::track`], like the arenas of its own allocator,
+/// and forgets the memory it unmaps with [`Self::untrack`].
+/// The snapshot restores the mappings registered when it is taken,
+/// and unmaps the ones registered since before the next run.
+/// The memory the harness did not register, like the one of the fuzzer, is never restored nor unmapped.
+#[derive(Debug, Clone, Default)]
+pub struct MappingTracker {
+    mappings: Arc<Mutex<Vec<(usize, usize)>>>,
+}
+
+impl MappingTracker {
+    /// Creates a new [`MappingTracker`], tracking nothing
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<(usize, usize)>> {
+        self.mappings.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Registers the `len` bytes the harness mapped at `start`
+    pub fn track(&self, start: usize, len: usize) {
+        self.lock().push((start, start + len));
+    }
+
+    /// Forgets the mapping at `start`, once the harness unmapped it
+    pub fn untrack(&self, start: usize) {
+        self.lock()
+            .retain(|&(mapping_start, _)| mapping_start != start);
+    }
+
+    /// The registered mappings, as start and end
+    #[must_use]
+    pub fn mappings(&self) -> Vec<(usize, usize)> {
+        self.lock().clone()
+    }
+}
+
+/// The writable memory of the target, saved once, and restored between runs.
+///
+/// The snapshot must not cover memory the fuzzer changes between the runs, like the heap shared with the target:
+/// restoring it would revert the changes of the fuzzer, too.
+/// The memory the harness maps itself is covered by registering it, see [`Self::with_tracked_mappings`].
+pub struct MemorySnapshot {
+    /// The memory to save, before rounding to pages
+    ranges: Vec<(usize, usize)>,
+    /// The memory to leave alone, before rounding to pages
+    excluded: Vec<(usize, usize)>,
+    saved: Vec<SavedRange>,
+    tracker: Option<MappingTracker>,
+    /// The mappings registered with the tracker when the snapshot was taken
+    tracked_saved: Vec<(usize, usize)>,
+    taken: bool,
+    page_size: usize,
+    /// `/proc/self/pagemap` and `/proc/self/clear_refs`, if the kernel supports soft-dirty bits
+    soft_dirty: Option<(File, File)>,
+    pagemap_buf: Vec<u8>,
+    last_restored_pages: usize,
+}
+
+impl Debug for MemorySnapshot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemorySnapshot")
+            .field("ranges", &self.ranges)
+            .field("excluded", &self.excluded)
+            .field("tracker", &self.tracker)
+            .field("taken", &self.is_taken())
+            .field("soft_dirty", &self.uses_soft_dirty())
+            .field("last_restored_pages", &self.last_restored_pages)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for MemorySnapshot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemorySnapshot {
+    /// Creates an empty [`MemorySnapshot`], add the memory to save with the `with_` functions
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            ranges: Vec::new(),
+            excluded: Vec::new(),
+            saved: Vec::new(),
+            tracker: None,
+            tracked_saved: Vec::new(),
+            taken: false,
+            page_size: page_size(),
+            soft_dirty: None,
+            pagemap_buf: Vec::new(),
+            last_restored_pages: 0,
+        }
+    }
+
+    /// Saves the `len` bytes at `start`, rounded to whole pages
+    #[must_use]
+    pub fn with_range(mut self, start: usize, len: usize) -> Self {
+        self.ranges.push((start, start + len));
+        self
+    }
+
+    /// Saves the writable memory of the loaded module whose path contains `name`, its data and bss
+    pub fn with_module(mut self, name: &str) -> Result<Self, Error> {
+        let maps = fs::read_to_string("/proc/self/maps")?;
+        let mappings = parse_mappings(&maps);
+        let mut found = false;
+        for (idx, mapping) in mappings.iter().enumerate() {
+            if !mapping.path.is_some_and(|path| path.contains(name)) {
+                continue;
+            }
+            found = true;
+            if !(mapping.writable && mapping.private) {
+                continue;
+            }
+            self.ranges.push((mapping.start, mapping.end));
+            // The part of the bss that does not fit in the last page of the data is mapped anonymously, right after it
+            if let Some(next) = mappings.get(idx + 1) {
+                if next.start == mapping.end && next.writable && next.path.is_none() {
+                    self.ranges.push((next.start, next.end));
+                }
+            }
+        }
+        if found {
+            Ok(self)
+        } else {
+            Err(Error::key_not_found(format!(
+                "No module matching {name} is loaded"
+            )))
+        }
+    }
+
+    /// Saves the writable memory of the main executable, its data and bss.
+    ///
+    /// If the fuzzer is linked into the same executable as the target, this includes the statics of the fuzzer,
+    /// like the coverage map, the cmp map or the globals of the in-process executor,
+    /// and restoring the snapshot reverts what the fuzzer wrote to them since.
+    /// Leave the ones that must persist across runs alone with [`Self::excluding`].
+    pub fn with_main_executable(self) -> Result<Self, Error> {
+        let exe = fs::read_link("/proc/self/exe")?;
+        self.with_module(&exe.to_string_lossy())
+    }
+
+    /// Also saves the mappings registered with `tracker` by the harness when the snapshot is taken,
+    /// and unmaps the ones it registered during a run before the next one.
+    ///
+    /// Only the registered mappings are touched: the harness must register all of the memory it maps for itself,
+    /// and none of the memory of the fuzzer.
+    /// A saved mapping the harness unmapped during a run is mapped back, with its content when the snapshot was taken.
+    #[must_use]
+    pub fn with_tracked_mappings(mut self, tracker: &MappingTracker) -> Self {
+        self.tracker = Some(tracker.clone());
+        self
+    }
+
+    /// Leaves the `len` bytes at `start` alone, rounded to whole pages.
+    /// Use it for the memory of the fuzzer within a saved module.
+    #[must_use]
+    pub fn excluding(mut self, start: usize, len: usize) -> Self {
+        self.excluded.push((start, start + len));
+        self
+    }
+
+    /// If the memory was saved already
+    #[must_use]
+    pub fn is_taken(&self) -> bool {
+        self.taken
+    }
+
+    /// If only the pages written since the last restore are restored, using the soft-dirty bits of the kernel
+    #[must_use]
+    pub fn uses_soft_dirty(&self) -> bool {
+        self.soft_dirty.is_some()
+    }
+
+    /// The number of pages copied back by the last [`Self::restore`]
+    #[must_use]
+    pub fn last_restored_pages(&self) -> usize {
+        self.last_restored_pages
+    }
+
+    /// The number of saved bytes
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.saved.iter().map(|range| range.data.len()).sum()
+    }
+
+    /// The given ranges, rounded to whole pages, sorted and merged
+    fn round_ranges(&self, ranges: &[(usize, usize)]) -> Vec<(usize, usize)> {
+        let page_mask = self.page_size - 1;
+        merge_ranges(
+            ranges
+                .iter()
+                .map(|&(start, end)| (start & !page_mask, (end + page_mask) & !page_mask))
+                .collect(),
+        )
+    }
+
+    /// Saves the memory, from now on [`Self::restore`] brings it back to its current content.
+    pub fn take(&mut self) -> Result<(), Error> {
+        self.tracked_saved = self
+            .tracker
+            .as_ref()
+            .map(MappingTracker::mappings)
+            .unwrap_or_default();
+        let mut ranges = self.ranges.clone();
+        ranges.extend_from_slice(&self.tracked_saved);
+        let ranges = subtract_ranges(
+            &self.round_ranges(&ranges),
+            &self.round_ranges(&self.excluded),
+        );
+        if ranges.is_empty() && self.tracker.is_none() {
+            return Err(Error::illegal_argument("The snapshot covers no memory"));
+        }
+
+        self.saved = ranges
+            .into_iter()
+            .map(|(start, end)| {
+                let mut data = vec![0; end - start];
+                // # Safety
+                // The ranges were given by the user, or come from the mappings of this process.
+                unsafe {
+                    ptr::copy_nonoverlapping(start as *const u8, data.as_mut_ptr(), data.len());
+                }
+                SavedRange { start, data }
+            })
+            .collect();
+
+        self.soft_dirty = self.open_soft_dirty();
+        if self.soft_dirty.is_none() {
+            log::info!("Soft-dirty bits are not supported, the whole snapshot will be restored after each run");
+        }
+        if let Some((_, clear_refs)) = &mut self.soft_dirty {
+            clear_refs.write_all(CLEAR_REFS_SOFT_DIRTY)?;
+        }
+        self.taken = true;
+        Ok(())
+    }
+
+    /// Opens the files to track written pages, if the kernel sets soft-dirty bits
+    fn open_soft_dirty(&mut self) -> Option<(File, File)> {
+        let pagemap = File::open("/proc/self/pagemap").ok()?;
+        let mut clear_refs = OpenOptions::new()
+            .write(true)
+            .open("/proc/self/clear_refs")
+            .ok()?;
+
+        // Check the kernel marks a page we write to
+        let mut probe = vec![0_u8; 2 * self.page_size];
+        let page = (probe.as_ptr() as usize + self.page_size - 1) & !(self.page_size - 1);
+        let offset = page - probe.as_ptr() as usize;
+        clear_refs.write_all(CLEAR_REFS_SOFT_DIRTY).ok()?;
+        // # Safety
+        // `offset` is within the probe.
+        unsafe { ptr::write_volatile(probe.as_mut_ptr().add(offset), 1) };
+        let mut entry = [0; 8];
+        pagemap
+            .read_exact_at(&mut entry, (page / self.page_size * 8) as u64)
+            .ok()?;
+        (u64::from_ne_bytes(entry) & PAGEMAP_SOFT_DIRTY != 0).then_some((pagemap, clear_refs))
+    }
+
+    /// Brings the saved memory back to its content when [`Self::take`] was called.
+    /// With [`Self::with_tracked_mappings`], first unmaps the mappings registered since,
+    /// and maps the saved ones that were unmapped back.
+    /// Returns the number of pages copied back.
+    pub fn restore(&mut self) -> Result<usize, Error> {
+        if !self.is_taken() {
+            return Err(Error::illegal_state("The snapshot was not taken yet"));
+        }
+        let remapped = self.restore_mappings()?;
+
+        let mut restored = 0;
+        for range in &self.saved {
+            let end = range.start + range.data.len();
+            let fresh = remapped
+                .iter()
+                .any(|&(start, mapping_end)| start < end && range.start < mapping_end);
+            match &mut self.soft_dirty {
+                Some((pagemap, _)) if !fresh => {
+                    let pages = range.data.len() / self.page_size;
+                    self.pagemap_buf.resize(pages * 8, 0);
+                    pagemap.read_exact_at(
+                        &mut self.pagemap_buf,
+                        (range.start / self.page_size * 8) as u64,
+                    )?;
+                    for (page, entry) in self.pagemap_buf.chunks_exact(8).enumerate() {
+                        if u64::from_ne_bytes(entry.try_into().unwrap()) & PAGEMAP_SOFT_DIRTY == 0 {
+                            continue;
+                        }
+                        let offset = page * self.page_size;
+                        // # Safety
+                        // The page belongs to the saved range, which was readable and writable when it was saved.
+                        unsafe {
+                            ptr::copy_nonoverlapping(
+                                range.data.as_ptr().add(offset),
+                                (range.start + offset) as *mut u8,
+                                self.page_size,
+                            );
+                        }
+                        restored += 1;
+                    }
+                }
+                _ => {
+                    // # Safety
+                    // The range was readable and writable when it was saved, or was just mapped back.
+                    unsafe {
+                        ptr::copy_nonoverlapping(
+                            range.data.as_ptr(),
+                            range.start as *mut u8,
+                            range.data.len(),
+                        );
+                    }
+                    restored += range.data.len() / self.page_size;
+                }
+            }
+        }
+        if let Some((_, clear_refs)) = &mut self.soft_dirty {
+            clear_refs.write_all(CLEAR_REFS_SOFT_DIRTY)?;
+        }
+
+        self.last_restored_pages = restored;
+        Ok(restored)
+    }
+
+    /// Unmaps the mappings the harness registered since the snapshot was taken,
+    /// and maps the saved ones it unmapped back, returning them.
+    fn restore_mappings(&mut self) -> Result<Vec<(usize, usize)>, Error> {
+        let Some(tracker) = &self.tracker else {
+            return Ok(Vec::new());
+        };
+        let mut mappings = tracker.lock();
+        for &(start, end) in mappings.iter() {
+            if self.tracked_saved.contains(&(start, end)) {
+                continue;
+            }
+            // # Safety
+            // The harness registered the mapping during a run, and forgets about it with the rest of the run.
+            if unsafe { libc::munmap(start as *mut libc::c_void, end - start) } != 0 {
+                return Err(Error::last_os_error(format!(
+                    "Failed to unmap {start:#x}-{end:#x} registered by the harness"
+                )));
+            }
+        }
+
+        let mut remapped = Vec::new();
+        for &(start, end) in &self.tracked_saved {
+            if mappings.contains(&(start, end)) {
+                continue;
+            }
+            // # Safety
+            // The harness registered the mapping before the snapshot was taken, and unmapped it during a run:
+            // nothing else uses the range.
+            let ptr = unsafe {
+                libc::mmap(
+                    start as *mut libc::c_void,
+                    end - start,
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_FIXED_NOREPLACE,
+                    -1,
+                    0,
+                )
+            };
+            if ptr != start as *mut libc::c_void {
+                return Err(Error::last_os_error(format!(
+                    "Failed to map {start:#x}-{end:#x} unmapped by the harness back"
+                )));
+            }
+            remapped.push((start, end));
+        }
+        mappings.clone_from(&self.tracked_saved);
+        Ok(remapped)
+    }
+}
+
+/// Wraps an in-process [`Executor`], restoring a [`MemorySnapshot`] before each run.
+///
+/// The snapshot is taken at the start of the first run, and the memory the previous run wrote is restored
+/// at the start of the next one, so that the observers can still look at it after the run.
+pub struct SnapshotExecutor<E> {
+    executor: E,
+    snapshot: MemorySnapshot,
+}
+
+impl<E> Debug for SnapshotExecutor<E>
+where
+    E: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SnapshotExecutor")
+            .field("executor", &self.executor)
+            .field("snapshot", &self.snapshot)
+            .finish()
+    }
+}
+
+impl<E> SnapshotExecutor<E> {
+    /// Create a new `SnapshotExecutor`, restoring `snapshot` around the runs of `executor`.
+    pub fn new(executor: E, snapshot: MemorySnapshot) -> Self {
+        Self { executor, snapshot }
+    }
+
+    /// The wrapped executor
+    pub fn inner(&self) -> &E {
+        &self.executor
+    }
+
+    /// The wrapped executor, mutable
+    pub fn inner_mut(&mut self) -> &mut E {
+        &mut self.executor
+    }
+
+    /// The snapshot restored before each run
+    pub fn snapshot(&self) -> &MemorySnapshot {
+        &self.snapshot
+    }
+}
+
+impl<E, EM, Z> Executor<EM, Z> for SnapshotExecutor<E>
+where
+    E: Executor<EM, Z>,
+    EM: UsesState<State = Self::State>,
+    Z: UsesState<State = Self::State>,
+{
+    fn run_target(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut Self::State,
+        mgr: &mut EM,
+        input: &Self::Input,
+    ) -> Result<ExitKind, Error> {
+        if self.snapshot.is_taken() {
+            self.snapshot.restore()?;
+        } else {
+            self.snapshot.take()?;
+        }
+
+        self.executor.run_target(fuzzer, state, mgr, input)
+    }
+}
+
+impl<E> HasTimeout for SnapshotExecutor<E>
+where
+    E: HasTimeout,
+{
+    #[inline]
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.executor.set_timeout(timeout);
+    }
+    #[inline]
+    fn timeout(&self) -> Duration {
+        self.executor.timeout()
+    }
+}
+
+impl<E> UsesState for SnapshotExecutor<E>
+where
+    E: UsesState,
+{
+    type State = E::State;
+}
+
+impl<E> HasObservers for SnapshotExecutor<E>
+where
+    E: HasObservers + UsesState,
+    E::Observers: ObserversTuple<<Self as UsesInput>::Input, <Self as UsesState>::State>,
+{
+    type Observers = E::Observers;
+
+    #[inline]
+    fn observers(&self) -> RefIndexable<&Self::Observers, Self::Observers> {
+        self.executor.observers()
+    }
+
+    #[inline]
+    fn observers_mut(&mut self) -> RefIndexable<&mut Self::Observers, Self::Observers> {
+        self.executor.observers_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        hint::black_box,
+        ptr::{self, addr_of_mut},
+    };
+    use std::{fs, panic};
+
+    use libafl_bolts::tuples::tuple_list;
+
+    use super::{
+        merge_ranges, page_size, parse_mappings, subtract_ranges, MappingTracker, MemorySnapshot,
+    };
+    use crate::{
+        corpus::InMemoryCorpus,
+        events::NopEventManager,
+        executors::{Executor, ExitKind, InProcessExecutor, SnapshotExecutor},
+        feedbacks::CrashFeedback,
+        inputs::NopInput,
+        schedulers::RandScheduler,
+        state::StdState,
+        StdFuzzer,
+    };
+
+    #[repr(C, align(65536))]
+    struct Pages([u8; 3 * 4096]);
+
+    static mut PAGES: Pages = Pages([0; 3 * 4096]);
+    static mut HARNESS_PAGES: Pages = Pages([0; 3 * 4096]);
+
+    #[test]
+    fn test_ranges() {
+        assert_eq!(
+            merge_ranges(vec![(20, 30), (0, 10), (10, 15), (25, 40)]),
+            vec![(0, 15), (20, 40)]
+        );
+        assert_eq!(
+            subtract_ranges(&[(0, 100), (200, 300)], &[(50, 60), (250, 400)]),
+            vec![(0, 50), (60, 100), (200, 250)]
+        );
+    }
+
+    #[test]
+    fn test_parse_mappings() {
+        let maps = "55d0c0a00000-55d0c0a02000 rw-p 00002000 fd:01 1234 /usr/bin/target\n\
+                    55d0c0a02000-55d0c0a05000 rw-p 00000000 00:00 0\n\
+                    7f0000000000-7f0000001000 r--s 00000000 00:01 42 /dev/shm/map (deleted)\n";
+        let mappings = parse_mappings(maps);
+        assert_eq!(mappings.len(), 3);
+        assert_eq!(mappings[0].start, 0x55d0_c0a0_0000);
+        assert_eq!(mappings[0].path, Some("/usr/bin/target"));
+        assert!(mappings[1].writable && mappings[1].private && mappings[1].path.is_none());
+        assert!(!mappings[2].writable && !mappings[2].private);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_snapshot_restore() {
+        let pages = unsafe { &mut *addr_of_mut!(PAGES) };
+        pages.0[0] = 1;
+
+        let mut snapshot =
+            MemorySnapshot::new().with_range(pages.0.as_ptr() as usize, pages.0.len());
+        snapshot.take().unwrap();
+        assert!(snapshot.size() >= pages.0.len());
+
+        pages.0[0] = 2;
+        pages.0[4096 + 1] = 3;
+        let restored = snapshot.restore().unwrap();
+        assert_eq!(pages.0[0], 1);
+        assert_eq!(pages.0[4096 + 1], 0);
+        let page_size = page_size();
+        if snapshot.uses_soft_dirty() {
+            assert_eq!(restored, if page_size > 4096 { 1 } else { 2 });
+        } else {
+            assert_eq!(restored, snapshot.size() / page_size);
+        }
+    }
+
+    /// Maps `len` bytes of private anonymous memory
+    fn map(len: usize) -> usize {
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        assert_ne!(ptr, libc::MAP_FAILED);
+        ptr as usize
+    }
+
+    /// If `addr` is mapped, according to `/proc/self/maps`
+    fn is_mapped(addr: usize) -> bool {
+        let maps = fs::read_to_string("/proc/self/maps").unwrap();
+        parse_mappings(&maps)
+            .iter()
+            .any(|mapping| (mapping.start..mapping.end).contains(&addr))
+    }
+
+    /// Plays the fuzzer and the harness around the mappings of the harness, panics if they are not restored
+    fn tracked_mappings_runs() {
+        let page_size = page_size();
+        let tracker = MappingTracker::new();
+        let arena = map(2 * page_size);
+        tracker.track(arena, 2 * page_size);
+        let mut snapshot = MemorySnapshot::new().with_tracked_mappings(&tracker);
+        snapshot.take().unwrap();
+        assert_eq!(snapshot.size(), 2 * page_size);
+
+        // The fuzzer maps and allocates memory of its own between the runs, it is left alone
+        let own = map(page_size);
+        let kept = black_box(vec![5_u8; 1 << 20]);
+
+        // A run writes to its arena and maps more memory
+        unsafe { ptr::write_volatile((arena + page_size) as *mut u8, 1) };
+        let new = map(page_size);
+        tracker.track(new, page_size);
+        snapshot.restore().unwrap();
+
+        assert_eq!(
+            unsafe { ptr::read_volatile((arena + page_size) as *const u8) },
+            0
+        );
+        assert!(!is_mapped(new));
+        assert_eq!(tracker.mappings(), vec![(arena, arena + 2 * page_size)]);
+        assert!(is_mapped(own));
+        assert!(kept.iter().all(|byte| *byte == 5));
+
+        // A run unmaps its arena
+        unsafe { ptr::write_volatile(arena as *mut u8, 1) };
+        assert_eq!(
+            unsafe { libc::munmap(arena as *mut libc::c_void, 2 * page_size) },
+            0
+        );
+        tracker.untrack(arena);
+        snapshot.restore().unwrap();
+
+        assert_eq!(unsafe { ptr::read_volatile(arena as *const u8) }, 0);
+        assert_eq!(tracker.mappings(), vec![(arena, arena + 2 * page_size)]);
+    }
+
+    /// Runs `f` in a child process, as the mappings checked could be reused by the other test threads
+    fn in_child(f: fn()) {
+        let pid = unsafe { libc::fork() };
+        assert!(pid >= 0);
+        if pid == 0 {
+            let ok = panic::catch_unwind(f).is_ok();
+            unsafe { libc::_exit(i32::from(!ok)) };
+        }
+        let mut status = 0;
+        assert_eq!(unsafe { libc::waitpid(pid, &raw mut status, 0) }, pid);
+        assert!(libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_tracked_mappings() {
+        in_child(tracked_mappings_runs);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_snapshot_executor() {
+        // Each run sees the memory as it was before the first one
+        let mut harness = |_input: &NopInput| {
+            let pages = unsafe { &mut *addr_of_mut!(HARNESS_PAGES) };
+            pages.0[100] += 1;
+            if pages.0[100] == 1 {
+                ExitKind::Ok
+            } else {
+                ExitKind::Crash
+            }
+        };
+        let rand = libafl_bolts::rands::XkcdRand::new();
+        let corpus = InMemoryCorpus::<NopInput>::new();
+        let solutions = InMemoryCorpus::new();
+        let mut objective = CrashFeedback::new();
+        let mut feedback = tuple_list!();
+        let sche = RandScheduler::new();
+        let mut mgr = NopEventManager::new();
+        let mut state =
+            StdState::new(rand, corpus, solutions, &mut feedback, &mut objective).unwrap();
+        let mut fuzzer = StdFuzzer::<_, _, _, _>::new(sche, feedback, objective);
+
+        let in_process_executor = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )
+        .unwrap();
+        let pages = unsafe { &*addr_of_mut!(HARNESS_PAGES) };
+        let snapshot = MemorySnapshot::new().with_range(pages.0.as_ptr() as usize, pages.0.len());
+        let mut executor = SnapshotExecutor::new(in_process_executor, snapshot);
+
+        for _ in 0..3 {
+            let exit_kind = executor
+                .run_target(&mut fuzzer, &mut state, &mut mgr, &NopInput {})
+                .unwrap();
+            assert_eq!(exit_kind, ExitKind::Ok);
+        }
+    }
+}
@@ -0,0 +1,687 @@
+//! The [`CrashBucketFeedback`] groups objectives into buckets of (likely) the same bug.
+//!
+//! The bucket of a crash is derived from the [`CrashSignature`] of an [`ObserverWithCrashSignature`],
+//! such as the [`crate::observers::BacktraceObserver`] for in-process executors,
+//! or the [`crate::observers::AsanBacktraceObserver`] for forkserver and command executors.
+//! Each objective gets a [`CrashBucketMetadata`] and is recorded in the [`CrashBucketsMetadata`] of the state
+//! right away, optionally mirrored to a JSON index file. Its [`CorpusId`] and file name are confirmed once it
+//! is part of the solutions.
+
+use alloc::{
+    borrow::Cow,
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use std::path::{Path, PathBuf};
+
+use libafl_bolts::{
+    fs::write_file_atomic,
+    hash_std,
+    tuples::{Handle, Handled, MatchName, MatchNameRef},
+    Named,
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "track_hit_feedbacks")]
+use crate::feedbacks::premature_last_result_err;
+use crate::{
+    corpus::{Corpus, CorpusId, Testcase},
+    executors::ExitKind,
+    feedbacks::{Feedback, HasObserverHandle, StateInitializer},
+    inputs::Input,
+    observers::{CrashSignature, ObserverWithCrashSignature},
+    state::HasSolutions,
+    Error, HasMetadata, HasNamedMetadata,
+};
+
+/// The prefix of the metadata names
+pub const CRASHBUCKETFEEDBACK_PREFIX: &str = "crashbucketfeedback_";
+
+/// The key used to assign crashes to buckets.
+///
+/// If the [`CrashSignature`] lacks the information needed for a key, the key falls back to
+/// the top frame and then to the stack hash.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrashBucketKey {
+    /// The hash of the whole stack, as computed by the observer
+    StackHash,
+    /// The top `n` frames of the crashing stack
+    TopFrames(usize),
+    /// The type of the sanitizer report, such as `heap-buffer-overflow`
+    ReportType,
+    /// The program counter of the faulting instruction
+    FaultingPc,
+    /// The type of the sanitizer report, followed by the top `n` frames of the crashing stack
+    ReportTypeAndTopFrames(usize),
+}
+
+impl Default for CrashBucketKey {
+    fn default() -> Self {
+        Self::TopFrames(3)
+    }
+}
+
+impl CrashBucketKey {
+    /// Describes the bucket of the given signature, crashes with the same description end up in the same bucket
+    #[must_use]
+    pub fn describe(&self, signature: &CrashSignature) -> String {
+        match self {
+            Self::StackHash => Self::describe_hash(signature),
+            Self::TopFrames(n) => Self::describe_frames(signature, *n),
+            Self::ReportType => match &signature.report_type {
+                Some(report_type) => format!("type:{report_type}"),
+                None => Self::describe_frames(signature, 1),
+            },
+            Self::FaultingPc => match signature.faulting_pc {
+                Some(pc) => format!("pc:{pc:#x}"),
+                None => Self::describe_frames(signature, 1),
+            },
+            Self::ReportTypeAndTopFrames(n) => format!(
+                "type:{} {}",
+                signature.report_type.as_deref().unwrap_or("unknown"),
+                Self::describe_frames(signature, *n)
+            ),
+        }
+    }
+
+    fn describe_frames(signature: &CrashSignature, n: usize) -> String {
+        if signature.frames.is_empty() || n == 0 {
+            return Self::describe_hash(signature);
+        }
+        let top = signature.frames.iter().take(n).map(String::as_str);
+        format!("frames:{}", top.collect::<Vec<_>>().join(" > "))
+    }
+
+    fn describe_hash(signature: &CrashSignature) -> String {
+        signature
+            .stack_hash
+            .map_or_else(|| "unknown".to_string(), |hash| format!("hash:{hash:016x}"))
+    }
+}
+
+/// The bucket an objective was assigned to by a [`CrashBucketFeedback`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CrashBucketMetadata {
+    /// The id of the bucket
+    pub bucket: u64,
+    /// The key used to assign the objective to its bucket
+    pub key: CrashBucketKey,
+    /// The description of the bucket, see [`CrashBucketKey::describe`]
+    pub description: String,
+}
+
+libafl_bolts::impl_serdeany!(CrashBucketMetadata);
+
+/// An objective belonging to a [`CrashBucket`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CrashBucketMember {
+    /// The id of the objective in the solutions corpus
+    pub id: CorpusId,
+    /// The file name of the objective
+    pub name: String,
+}
+
+/// A group of objectives sharing the same [`CrashBucketKey`] description
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CrashBucket {
+    /// The description of the bucket, see [`CrashBucketKey::describe`]
+    pub description: String,
+    /// All objectives of this bucket, in the order they were found
+    pub members: Vec<CrashBucketMember>,
+}
+
+impl CrashBucket {
+    /// The representative of this bucket, which is the first objective found
+    #[must_use]
+    pub fn representative(&self) -> &CrashBucketMember {
+        &self.members[0]
+    }
+}
+
+/// The buckets of a [`CrashBucketFeedback`], stored as named metadata in the state
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[allow(clippy::unsafe_derive_deserialize)]
+pub struct CrashBucketsMetadata {
+    buckets: BTreeMap<u64, CrashBucket>,
+    /// The last objective of the solutions recorded by [`Self::record_new_objectives`]
+    last_recorded: Option<CorpusId>,
+    /// The objective recorded by [`Self::record_pending`], not confirmed to be in the solutions yet
+    #[serde(default)]
+    pending: Option<PendingMember>,
+}
+
+/// An objective recorded before it was added to the solutions
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+struct PendingMember {
+    bucket: u64,
+    id: CorpusId,
+    /// The last recorded objective before this one
+    previous: Option<CorpusId>,
+}
+
+libafl_bolts::impl_serdeany!(CrashBucketsMetadata);
+
+/// An entry of the index file written by [`CrashBucketFeedback`]
+#[derive(Serialize)]
+struct CrashBucketIndexEntry<'a> {
+    bucket: String,
+    description: &'a str,
+    representative: &'a CrashBucketMember,
+    members: &'a [CrashBucketMember],
+}
+
+impl CrashBucketsMetadata {
+    /// Create a new, empty, [`CrashBucketsMetadata`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All buckets, by id
+    #[must_use]
+    pub fn buckets(&self) -> &BTreeMap<u64, CrashBucket> {
+        &self.buckets
+    }
+
+    /// The bucket with the given id
+    #[must_use]
+    pub fn bucket(&self, bucket: u64) -> Option<&CrashBucket> {
+        self.buckets.get(&bucket)
+    }
+
+    /// Adds an objective to its bucket, creating the bucket if needed.
+    /// Returns `true` if the bucket is new.
+    pub fn insert(&mut self, bucket: u64, description: &str, member: CrashBucketMember) -> bool {
+        let mut is_new = false;
+        self.buckets
+            .entry(bucket)
+            .or_insert_with(|| {
+                is_new = true;
+                CrashBucket {
+                    description: description.to_string(),
+                    members: Vec::new(),
+                }
+            })
+            .members
+            .push(member);
+        is_new
+    }
+
+    /// Records an objective about to be added to the solutions, with the id and name it is expected to get.
+    /// The next call to [`Self::record_new_objectives`] confirms them, or drops the member if it was not added.
+    /// Returns `true` if the bucket is new.
+    pub fn record_pending(
+        &mut self,
+        bucket: u64,
+        description: &str,
+        member: CrashBucketMember,
+    ) -> bool {
+        self.pending = Some(PendingMember {
+            bucket,
+            id: member.id,
+            previous: self.last_recorded,
+        });
+        self.last_recorded = Some(member.id);
+        self.insert(bucket, description, member)
+    }
+
+    /// Checks the objective recorded by [`Self::record_pending`] against the solutions.
+    /// Returns `true` if the buckets changed.
+    fn confirm_pending<C>(&mut self, solutions: &C) -> bool
+    where
+        C: Corpus,
+    {
+        let Some(pending) = self.pending.take() else {
+            return false;
+        };
+        // `Some(filename)` if the objective is in the solutions
+        let added = solutions.get(pending.id).ok().and_then(|testcase| {
+            let testcase = testcase.borrow();
+            let bucket = testcase.metadata::<CrashBucketMetadata>().ok()?.bucket;
+            (bucket == pending.bucket).then(|| testcase.filename().clone())
+        });
+        let Some(bucket) = self.buckets.get_mut(&pending.bucket) else {
+            return false;
+        };
+        let Some(pos) = bucket.members.iter().rposition(|m| m.id == pending.id) else {
+            return false;
+        };
+        match added {
+            Some(Some(name)) if bucket.members[pos].name != name => {
+                bucket.members[pos].name = name;
+                true
+            }
+            Some(_) => false,
+            None => {
+                bucket.members.remove(pos);
+                if bucket.members.is_empty() {
+                    self.buckets.remove(&pending.bucket);
+                }
+                self.last_recorded = pending.previous;
+                true
+            }
+        }
+    }
+
+    /// Records the objectives added to `solutions` since the last call, using the [`CrashBucketMetadata`]
+    /// attached to them, so that each member is recorded with its actual [`CorpusId`] and file name.
+    /// Objectives without [`CrashBucketMetadata`] are skipped.
+    /// Returns `true` if any objective was recorded, or a pending one was corrected.
+    pub fn record_new_objectives<C>(&mut self, solutions: &C) -> Result<bool, Error>
+    where
+        C: Corpus,
+        C::Input: Input,
+    {
+        let confirmed = self.confirm_pending(solutions);
+        let last = solutions.last();
+        if last.is_none() || last <= self.last_recorded {
+            return Ok(confirmed);
+        }
+        let new_ids: Vec<_> = solutions
+            .ids()
+            .filter(|id| Some(*id) > self.last_recorded)
+            .collect();
+
+        let mut recorded = confirmed;
+        for id in new_ids {
+            self.last_recorded = Some(id);
+            let mut testcase = solutions.get(id)?.borrow_mut();
+            let Ok(bucket) = testcase.metadata::<CrashBucketMetadata>().cloned() else {
+                continue;
+            };
+            let name = match testcase.filename() {
+                Some(name) => name.clone(),
+                None => testcase.load_input(solutions)?.generate_name(Some(id)),
+            };
+            if self.insert(
+                bucket.bucket,
+                &bucket.description,
+                CrashBucketMember { id, name },
+            ) {
+                log::info!(
+                    "New crash bucket {:016x}: {}",
+                    bucket.bucket,
+                    bucket.description
+                );
+            }
+            recorded = true;
+        }
+        Ok(recorded)
+    }
+
+    /// Serializes all buckets to a JSON index, mapping each bucket to its representative and members
+    pub fn to_json_index(&self) -> Result<Vec<u8>, Error> {
+        let index: Vec<_> = self
+            .buckets
+            .iter()
+            .map(|(bucket, entry)| CrashBucketIndexEntry {
+                bucket: format!("{bucket:016x}"),
+                description: &entry.description,
+                representative: entry.representative(),
+                members: &entry.members,
+            })
+            .collect();
+        serde_json::to_vec_pretty(&index)
+            .map_err(|err| Error::serialize(format!("Failed to serialize crash buckets: {err}")))
+    }
+}
+
+/// A [`CrashBucketFeedback`] assigns each objective to a bucket, based on the [`CrashSignature`] of an observer.
+///
+/// It considers every run with a crash signature interesting, so combine it with a [`crate::feedbacks::CrashFeedback`]
+/// (or a [`crate::feedbacks::NewHashFeedback`] to only keep one objective per stack) using `feedback_and!`.
+///
+/// Each objective gets a [`CrashBucketMetadata`] and is recorded in the [`CrashBucketsMetadata`] (and the index file)
+/// when the feedback appends its metadata, with the id and name the solutions corpus is expected to give it.
+/// They are checked against the solutions at the next evaluation, which also records the objectives added by
+/// other components, such as the [`crate::stages::ObjectiveTMinStage`].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CrashBucketFeedback<O> {
+    name: Cow<'static, str>,
+    o_ref: Handle<O>,
+    key: CrashBucketKey,
+    /// The file the JSON index of the buckets is written to
+    index_file: Option<PathBuf>,
+    #[cfg(feature = "track_hit_feedbacks")]
+    // The previous run's result of `Self::is_interesting`
+    last_result: Option<bool>,
+}
+
+impl<O> CrashBucketFeedback<O>
+where
+    O: Named,
+{
+    /// Returns a new [`CrashBucketFeedback`], bucketing crashes by the given `key`
+    #[must_use]
+    pub fn new(observer: &O, key: CrashBucketKey) -> Self {
+        Self {
+            name: Cow::from(CRASHBUCKETFEEDBACK_PREFIX.to_string() + observer.name()),
+            o_ref: observer.handle(),
+            key,
+            index_file: None,
+            #[cfg(feature = "track_hit_feedbacks")]
+            last_result: None,
+        }
+    }
+
+    /// Writes the JSON index of all buckets to `path`, each time objectives are recorded
+    #[must_use]
+    pub fn with_index_file<P>(mut self, path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.index_file = Some(path.into());
+        self
+    }
+
+    /// The key used to bucket crashes
+    #[must_use]
+    pub fn key(&self) -> CrashBucketKey {
+        self.key
+    }
+
+    /// The file the JSON index of the buckets is written to, if any
+    #[must_use]
+    pub fn index_file(&self) -> Option<&Path> {
+        self.index_file.as_deref()
+    }
+}
+
+impl<O> CrashBucketFeedback<O> {
+    /// Records the objectives added to the solutions since the last call in the buckets of this feedback,
+    /// and updates the index file if needed
+    fn record_new_objectives<S>(&self, state: &mut S) -> Result<(), Error>
+    where
+        S: HasNamedMetadata + HasSolutions,
+        <S::Solutions as Corpus>::Input: Input,
+    {
+        let last = state.solutions().last();
+        let buckets = state.named_metadata::<CrashBucketsMetadata>(&self.name)?;
+        if buckets.pending.is_none() && buckets.last_recorded == last {
+            return Ok(());
+        }
+
+        // Take the buckets out of the state, to borrow the solutions at the same time
+        let mut buckets = state
+            .named_metadata_map_mut()
+            .remove::<CrashBucketsMetadata>(&self.name)
+            .unwrap();
+        let recorded = buckets.record_new_objectives(state.solutions());
+        let index = match (&recorded, &self.index_file) {
+            (Ok(true), Some(_)) => Some(buckets.to_json_index()),
+            _ => None,
+        };
+        state.named_metadata_map_mut().insert(&self.name, *buckets);
+        recorded?;
+        if let (Some(index), Some(index_file)) = (index, &self.index_file) {
+            write_file_atomic(index_file, &index?)?;
+        }
+        Ok(())
+    }
+
+    /// Records an objective about to be added to the solutions, and updates the index file if needed
+    fn record_pending<S>(
+        &self,
+        state: &mut S,
+        metadata: &CrashBucketMetadata,
+        member: CrashBucketMember,
+    ) -> Result<(), Error>
+    where
+        S: HasNamedMetadata,
+    {
+        let buckets = state.named_metadata_mut::<CrashBucketsMetadata>(&self.name)?;
+        if buckets.record_pending(metadata.bucket, &metadata.description, member) {
+            log::info!(
+                "New crash bucket {:016x}: {}",
+                metadata.bucket,
+                metadata.description
+            );
+        }
+        if let Some(index_file) = &self.index_file {
+            write_file_atomic(index_file, &buckets.to_json_index()?)?;
+        }
+        Ok(())
+    }
+}
+
+impl<O, S> StateInitializer<S> for CrashBucketFeedback<O>
+where
+    S: HasNamedMetadata,
+{
+    fn init_state(&mut self, state: &mut S) -> Result<(), Error> {
+        // Keep the buckets of a restored state
+        state.named_metadata_or_insert_with(&self.name, CrashBucketsMetadata::new);
+        Ok(())
+    }
+}
+
+impl<O, EM, I, OT, S> Feedback<EM, I, OT, S> for CrashBucketFeedback<O>
+where
+    O: ObserverWithCrashSignature + Named,
+    OT: MatchName,
+    I: Input,
+    S: HasNamedMetadata + HasSolutions,
+    S::Solutions: Corpus<Input = I>,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error> {
+        self.record_new_objectives(state)?;
+
+        let observer = observers
+            .get(&self.o_ref)
+            .expect("A CrashBucketFeedback needs an ObserverWithCrashSignature");
+        let res = observer.crash_signature().is_some();
+        #[cfg(feature = "track_hit_feedbacks")]
+        {
+            self.last_result = Some(res);
+        }
+        Ok(res)
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        self.last_result.ok_or(premature_last_result_err())
+    }
+
+    fn append_metadata(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        observers: &OT,
+        testcase: &mut Testcase<I>,
+    ) -> Result<(), Error> {
+        let observer = observers
+            .get(&self.o_ref)
+            .expect("A CrashBucketFeedback needs an ObserverWithCrashSignature");
+        let Some(signature) = observer.crash_signature() else {
+            return Ok(());
+        };
+        let description = self.key.describe(&signature);
+        let bucket = hash_std(description.as_bytes());
+        let metadata = CrashBucketMetadata {
+            bucket,
+            key: self.key,
+            description,
+        };
+
+        // Confirm the previous objective before recording this one
+        self.record_new_objectives(state)?;
+        let id = state.solutions().peek_free_id();
+        let name = match (testcase.filename(), testcase.input()) {
+            (Some(name), _) => Some(name.clone()),
+            (None, Some(input)) => Some(input.generate_name(Some(id))),
+            // Recorded once it is part of the solutions
+            (None, None) => None,
+        };
+        if let Some(name) = name {
+            self.record_pending(state, &metadata, CrashBucketMember { id, name })?;
+        }
+
+        testcase.add_metadata(metadata);
+        Ok(())
+    }
+}
+
+impl<O> Named for CrashBucketFeedback<O> {
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<O> HasObserverHandle for CrashBucketFeedback<O> {
+    type Observer = O;
+
+    #[inline]
+    fn observer_handle(&self) -> &Handle<O> {
+        &self.o_ref
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec::Vec};
+    use std::{env, fs};
+
+    use libafl_bolts::{hash_std, Named};
+
+    use super::{CrashBucketKey, CrashBucketMember, CrashBucketMetadata, CrashBucketsMetadata};
+    use crate::{
+        corpus::{Corpus, CorpusId, InMemoryCorpus, Testcase},
+        inputs::BytesInput,
+        observers::{AsanBacktraceObserver, ObserverWithCrashSignature},
+        HasMetadata,
+    };
+
+    const HEAP_OVERFLOW: &str = "==1==ERROR: AddressSanitizer: heap-buffer-overflow on address 0x602000000011 at pc 0x55555555a1b2 bp 0x7ffc sp 0x7ffc
+READ of size 1 at 0x602000000011 thread T0
+    #0 0x55555555a1b2 in parse_header /src/target.c:12:5
+    #1 0x55555555a2c3 in LLVMFuzzerTestOneInput /src/target.c:30:3
+    #2 0x55555555a3d4 in main /src/main.c:5:1
+
+0x602000000011 is located 0 bytes after 1-byte region
+allocated by thread T0 here:
+    #0 0x55555555b000 in malloc
+    #1 0x55555555a2c3 in LLVMFuzzerTestOneInput /src/target.c:28:3
+";
+
+    #[test]
+    fn test_crash_buckets() {
+        let mut observer = AsanBacktraceObserver::new("asan");
+        assert_eq!(observer.name(), "asan");
+        assert!(observer.crash_signature().is_none());
+        observer.parse_asan_output(HEAP_OVERFLOW);
+        let signature = observer.crash_signature().unwrap();
+
+        // Only the crashing stack is used, not the allocation stack
+        assert_eq!(
+            signature.frames,
+            ["parse_header", "LLVMFuzzerTestOneInput", "main"]
+        );
+        assert_eq!(
+            CrashBucketKey::TopFrames(2).describe(&signature),
+            "frames:parse_header > LLVMFuzzerTestOneInput"
+        );
+        assert_eq!(
+            CrashBucketKey::ReportType.describe(&signature),
+            "type:heap-buffer-overflow"
+        );
+        assert_eq!(
+            CrashBucketKey::FaultingPc.describe(&signature),
+            "pc:0x55555555a1b2"
+        );
+
+        let description = CrashBucketKey::default().describe(&signature);
+        let bucket = hash_std(description.as_bytes());
+        let mut buckets = CrashBucketsMetadata::new();
+        for id in 0..2 {
+            let member = CrashBucketMember {
+                id: CorpusId(id),
+                name: format!("crash-{id}"),
+            };
+            assert_eq!(buckets.insert(bucket, &description, member), id == 0);
+        }
+        let entry = buckets.bucket(bucket).unwrap();
+        assert_eq!(entry.members.len(), 2);
+        assert_eq!(entry.representative().id, CorpusId(0));
+
+        let index = env::temp_dir().join(format!("libafl_crash_buckets_{}", std::process::id()));
+        fs::write(&index, buckets.to_json_index().unwrap()).unwrap();
+        let written: serde_json::Value =
+            serde_json::from_slice(&fs::read(&index).unwrap()).unwrap();
+        assert_eq!(written[0]["representative"]["name"], "crash-0");
+        assert_eq!(written[0]["members"].as_array().unwrap().len(), 2);
+        fs::remove_file(index).unwrap();
+    }
+
+    #[test]
+    fn test_record_new_objectives() {
+        let mut solutions = InMemoryCorpus::<BytesInput>::new();
+        let bucket_of = |description: &str| CrashBucketMetadata {
+            bucket: hash_std(description.as_bytes()),
+            key: CrashBucketKey::default(),
+            description: description.to_string(),
+        };
+        let add = |solutions: &mut InMemoryCorpus<BytesInput>, bucket: Option<&str>| {
+            let mut testcase = Testcase::new(BytesInput::new(vec![solutions.count() as u8]));
+            if let Some(description) = bucket {
+                testcase.add_metadata(bucket_of(description));
+            }
+            solutions.add(testcase).unwrap()
+        };
+
+        let mut buckets = CrashBucketsMetadata::new();
+        assert!(!buckets.record_new_objectives(&solutions).unwrap());
+
+        let first = add(&mut solutions, Some("frames:a"));
+        add(&mut solutions, None);
+        let second = add(&mut solutions, Some("frames:a"));
+        assert!(buckets.record_new_objectives(&solutions).unwrap());
+        assert!(!buckets.record_new_objectives(&solutions).unwrap());
+
+        let third = add(&mut solutions, Some("frames:b"));
+        assert!(buckets.record_new_objectives(&solutions).unwrap());
+
+        let a = buckets.bucket(bucket_of("frames:a").bucket).unwrap();
+        let ids: Vec<_> = a.members.iter().map(|member| member.id).collect();
+        assert_eq!(ids, [first, second]);
+        let b = buckets.bucket(bucket_of("frames:b").bucket).unwrap();
+        assert_eq!(b.representative().id, third);
+        assert_eq!(buckets.buckets().len(), 2);
+
+        // An objective recorded before it is added gets the name chosen by the solutions
+        let member = |id| CrashBucketMember {
+            id,
+            name: "predicted".to_string(),
+        };
+        let c = bucket_of("frames:c");
+        let fourth = solutions.peek_free_id();
+        assert!(buckets.record_pending(c.bucket, &c.description, member(fourth)));
+        let mut testcase = Testcase::with_filename(BytesInput::new(vec![4]), "actual".into());
+        testcase.add_metadata(c.clone());
+        assert_eq!(solutions.add(testcase).unwrap(), fourth);
+        assert!(buckets.record_new_objectives(&solutions).unwrap());
+        assert_eq!(buckets.bucket(c.bucket).unwrap().members[0].name, "actual");
+        assert!(!buckets.record_new_objectives(&solutions).unwrap());
+
+        // An objective that never made it to the solutions is dropped
+        let d = bucket_of("frames:d");
+        let fifth = solutions.peek_free_id();
+        assert!(buckets.record_pending(d.bucket, &d.description, member(fifth)));
+        assert!(buckets.record_new_objectives(&solutions).unwrap());
+        assert!(buckets.bucket(d.bucket).is_none());
+        assert_eq!(add(&mut solutions, Some("frames:a")), fifth);
+        assert!(buckets.record_new_objectives(&solutions).unwrap());
+        let a = buckets.bucket(bucket_of("frames:a").bucket).unwrap();
+        assert_eq!(a.members.last().unwrap().id, fifth);
+    }
+}
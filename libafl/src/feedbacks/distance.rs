@@ -0,0 +1,73 @@
+//! The [`DistanceFeedback`] attaches the distance to the targets of directed fuzzing to new testcases.
+//!
+//! It never considers a testcase interesting, combine it with another feedback.
+//! The [`crate::schedulers::DirectedScheduler`] and the [`crate::schedulers::DirectedTestcaseScore`]
+//! use the [`struct@DistanceMetadata`] it adds.
+
+use alloc::borrow::Cow;
+
+use libafl_bolts::{
+    tuples::{Handle, Handled, MatchName, MatchNameRef},
+    Named,
+};
+
+use crate::{
+    corpus::Testcase,
+    feedbacks::{Feedback, StateInitializer},
+    observers::DistanceObserver,
+    schedulers::directed::DistanceMetadata,
+    Error, HasMetadata,
+};
+
+/// Adds the distance seen by a [`DistanceObserver`] to each new testcase, as [`struct@DistanceMetadata`].
+///
+/// Executions that did not go through any block reaching a target get no metadata.
+#[derive(Debug, Clone)]
+pub struct DistanceFeedback<'a> {
+    observer_handle: Handle<DistanceObserver<'a>>,
+}
+
+impl<'a> DistanceFeedback<'a> {
+    /// Creates a new [`DistanceFeedback`] from the observer of the distance counters
+    #[must_use]
+    pub fn new(observer: &DistanceObserver<'a>) -> Self {
+        Self {
+            observer_handle: observer.handle(),
+        }
+    }
+}
+
+impl Named for DistanceFeedback<'_> {
+    fn name(&self) -> &Cow<'static, str> {
+        self.observer_handle.name()
+    }
+}
+
+impl<S> StateInitializer<S> for DistanceFeedback<'_> {}
+
+impl<EM, I, OT, S> Feedback<EM, I, OT, S> for DistanceFeedback<'_>
+where
+    OT: MatchName,
+{
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn append_metadata(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        observers: &OT,
+        testcase: &mut Testcase<I>,
+    ) -> Result<(), Error> {
+        let distance = observers
+            .get(&self.observer_handle)
+            .ok_or_else(|| Error::key_not_found("DistanceObserver not found"))?
+            .distance();
+        if let Some(distance) = distance {
+            testcase.add_metadata(DistanceMetadata::new(distance));
+        }
+        Ok(())
+    }
+}
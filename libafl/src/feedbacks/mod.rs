@@ -11,19 +11,26 @@ use core::{fmt::Debug, marker::PhantomData};
 
 #[cfg(feature = "std")]
 pub use concolic::ConcolicFeedback;
+#[cfg(feature = "regex")]
+pub use crash_bucket::{CrashBucketFeedback, CrashBucketKey, CrashBucketMetadata};
 pub use differential::DiffFeedback;
+pub use distance::DistanceFeedback;
 use libafl_bolts::{
     tuples::{Handle, Handled, MatchName, MatchNameRef},
     Named,
 };
 pub use list::*;
 pub use map::*;
+pub use multi_differential::{MultiDiffFeedback, MultiDiffMetadata, OutputNormalizer};
 #[cfg(feature = "nautilus")]
 pub use nautilus::*;
 #[cfg(feature = "std")]
 pub use new_hash_feedback::NewHashFeedback;
 #[cfg(feature = "std")]
 pub use new_hash_feedback::NewHashFeedbackMetadata;
+#[cfg(feature = "std")]
+pub use protocol_state::{ProtocolStateFeedback, ProtocolStatesMetadata};
+pub use provenance::{ProvenanceFeedback, ProvenanceMetadata};
 use serde::{Deserialize, Serialize};
 
 use crate::{corpus::Testcase, executors::ExitKind, observers::TimeObserver, Error};
@@ -33,18 +40,25 @@ pub mod capture_feedback;
 
 #[cfg(feature = "std")]
 pub mod concolic;
+#[cfg(feature = "regex")]
+pub mod crash_bucket;
 #[cfg(feature = "std")]
 /// The module for `CustomFilenameToTestcaseFeedback`
 pub mod custom_filename;
 pub mod differential;
+pub mod distance;
 /// The module for list feedback
 pub mod list;
 pub mod map;
+pub mod multi_differential;
 #[cfg(feature = "nautilus")]
 pub mod nautilus;
 #[cfg(feature = "std")]
 pub mod new_hash_feedback;
 #[cfg(feature = "std")]
+pub mod protocol_state;
+pub mod provenance;
+#[cfg(feature = "std")]
 pub mod stdio;
 pub mod transferred;
 
@@ -810,6 +824,23 @@ impl ExitKindLogic for TimeoutLogic {
         Ok(matches!(kind, ExitKind::Timeout))
     }
 }
+/// Name used by `OomFeedback`
+pub const OOM_FEEDBACK_NAME: &str = "OomFeedback";
+
+/// Logic which finds all [`ExitKind::Oom`] exits interesting.
+///
+/// Executors report [`ExitKind::Oom`] when the target runs out of memory, or goes over its
+/// resource limits.
+#[derive(Debug, Copy, Clone)]
+pub struct OomLogic;
+
+impl ExitKindLogic for OomLogic {
+    const NAME: Cow<'static, str> = Cow::Borrowed(OOM_FEEDBACK_NAME);
+
+    fn check_exit_kind(kind: &ExitKind) -> Result<bool, Error> {
+        Ok(matches!(kind, ExitKind::Oom))
+    }
+}
 
 /// Logic which finds all [`ExitKind::Diff`] exits interesting
 #[derive(Debug, Copy, Clone)]
@@ -823,8 +854,8 @@ impl ExitKindLogic for GenericDiffLogic {
     }
 }
 
-/// A generic exit type checking feedback. Use [`CrashFeedback`], [`TimeoutFeedback`], or
-/// [`DiffExitKindFeedback`] directly instead.
+/// A generic exit type checking feedback. Use [`CrashFeedback`], [`TimeoutFeedback`], [`OomFeedback`],
+/// or [`DiffExitKindFeedback`] directly instead.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct ExitKindFeedback<L> {
     #[cfg(feature = "track_hit_feedbacks")]
@@ -908,6 +939,11 @@ where
 pub type CrashFeedback = ExitKindFeedback<CrashLogic>;
 /// A [`TimeoutFeedback`] reduces the timeout value of a run.
 pub type TimeoutFeedback = ExitKindFeedback<TimeoutLogic>;
+/// An [`OomFeedback`] reports as interesting if the target ran out of memory or breached its resource limits.
+///
+/// [`CrashFeedback`] does not match [`ExitKind::Oom`], so add this feedback to the objective
+/// (e.g. `feedback_or_fast!(CrashFeedback::new(), OomFeedback::new())`) to keep such inputs, or leave it out to ignore them.
+pub type OomFeedback = ExitKindFeedback<OomLogic>;
 /// A [`DiffExitKindFeedback`] checks if there is a difference in the [`ExitKind`]s in a [`crate::executors::DiffExecutor`].
 pub type DiffExitKindFeedback = ExitKindFeedback<GenericDiffLogic>;
 
@@ -0,0 +1,296 @@
+//! Multi Diff Feedback, comparing the outputs of any number of implementations by majority vote.
+//!
+//! Use it with the [`crate::executors::MultiDiffExecutor`] and one observer per implementation.
+
+use alloc::{
+    borrow::Cow,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::{self, Debug, Formatter};
+
+use libafl_bolts::{
+    impl_serdeany,
+    tuples::{Handle, Handled, MatchName, MatchNameRef},
+    Named,
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "track_hit_feedbacks")]
+use crate::feedbacks::premature_last_result_err;
+#[cfg(feature = "std")]
+use crate::observers::StdOutObserver;
+use crate::{
+    corpus::Testcase,
+    executors::ExitKind,
+    feedbacks::{Feedback, FeedbackFactory, StateInitializer},
+    Error, HasMetadata,
+};
+
+/// Normalizes the output of an implementation, as seen by its [`crate::observers::Observer`],
+/// before the outputs of all implementations are compared.
+///
+/// Use it to ignore irrelevant differences, like whitespace or the order of keys.
+pub trait OutputNormalizer<O> {
+    /// Returns the normalized output of the implementation observed by `observer`
+    fn normalize(&mut self, observer: &O) -> Vec<u8>;
+}
+
+impl<F, O> OutputNormalizer<O> for F
+where
+    F: FnMut(&O) -> Vec<u8>,
+{
+    fn normalize(&mut self, observer: &O) -> Vec<u8> {
+        self(observer)
+    }
+}
+
+/// The [`OutputNormalizer`] comparing the raw stdout of each implementation
+#[cfg(feature = "std")]
+pub type StdOutNormalizer = fn(&StdOutObserver) -> Vec<u8>;
+
+/// The outcome of the majority vote of a [`MultiDiffFeedback`], added to each testcase it deems interesting.
+///
+/// Implementations are identified by the names of their observers.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MultiDiffMetadata {
+    /// The implementations grouped by their normalized output, the largest group first
+    pub groups: Vec<Vec<String>>,
+    /// The implementations diverging from the majority.
+    /// If there is no majority, because several groups are equally large, all implementations diverge.
+    pub diverging: Vec<String>,
+}
+
+impl_serdeany!(MultiDiffMetadata);
+
+impl MultiDiffMetadata {
+    /// The implementations agreeing with the majority, if there is one
+    #[must_use]
+    pub fn majority(&self) -> Option<&[String]> {
+        match self.groups.as_slice() {
+            [first, second, ..] if first.len() == second.len() => None,
+            [first, ..] => Some(first),
+            [] => None,
+        }
+    }
+}
+
+/// Group the indices of equal `outputs`, the largest group first.
+/// Groups of the same size are ordered by their first index.
+fn vote(outputs: &[Vec<u8>]) -> Vec<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for (idx, output) in outputs.iter().enumerate() {
+        match groups.iter_mut().find(|group| outputs[group[0]] == *output) {
+            Some(group) => group.push(idx),
+            None => groups.push(vec![idx]),
+        }
+    }
+    // The sort is stable, equally large groups stay in order
+    groups.sort_by_key(|group| core::cmp::Reverse(group.len()));
+    groups
+}
+
+/// A [`MultiDiffFeedback`] compares the outputs of any number of implementations, one observer each,
+/// after normalizing them with an [`OutputNormalizer`].
+///
+/// An input is interesting if not all implementations agree.
+/// The implementations diverging from the majority are recorded in a [`MultiDiffMetadata`].
+#[derive(Serialize, Deserialize)]
+pub struct MultiDiffFeedback<N, O> {
+    /// This feedback's name
+    name: Cow<'static, str>,
+    /// The observers of the implementations to compare
+    o_refs: Vec<Handle<O>>,
+    /// The normalizer applied to each output
+    normalizer: N,
+    /// The outcome of the last vote, if the implementations disagreed
+    #[serde(skip)]
+    last_diff: Option<MultiDiffMetadata>,
+    // The previous run's result of `Self::is_interesting`
+    #[cfg(feature = "track_hit_feedbacks")]
+    last_result: Option<bool>,
+}
+
+impl<N, O> MultiDiffFeedback<N, O>
+where
+    O: Named,
+{
+    /// Create a new [`MultiDiffFeedback`] comparing the given observers, at least two,
+    /// with the outputs normalized by `normalizer`.
+    pub fn new(name: &'static str, observers: &[&O], normalizer: N) -> Result<Self, Error> {
+        if observers.len() < 2 {
+            return Err(Error::illegal_argument(
+                "MultiDiffFeedback: at least two observers are needed",
+            ));
+        }
+        let o_refs: Vec<Handle<O>> = observers.iter().map(|observer| observer.handle()).collect();
+        for (idx, o_ref) in o_refs.iter().enumerate() {
+            if o_refs[..idx]
+                .iter()
+                .any(|other| other.name() == o_ref.name())
+            {
+                return Err(Error::illegal_argument(format!(
+                    "MultiDiffFeedback: observer names must be different ({} was used twice)",
+                    o_ref.name()
+                )));
+            }
+        }
+        Ok(Self {
+            name: Cow::from(name),
+            o_refs,
+            normalizer,
+            last_diff: None,
+            #[cfg(feature = "track_hit_feedbacks")]
+            last_result: None,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl MultiDiffFeedback<StdOutNormalizer, StdOutObserver> {
+    /// Create a new [`MultiDiffFeedback`] comparing the raw stdout of the implementations
+    pub fn with_stdout(name: &'static str, observers: &[&StdOutObserver]) -> Result<Self, Error> {
+        Self::new(name, observers, |observer: &StdOutObserver| {
+            observer.stdout.clone().unwrap_or_default()
+        })
+    }
+}
+
+impl<N, O, T> FeedbackFactory<MultiDiffFeedback<N, O>, T> for MultiDiffFeedback<N, O>
+where
+    N: Clone,
+{
+    fn create_feedback(&self, _ctx: &T) -> MultiDiffFeedback<N, O> {
+        Self {
+            name: self.name.clone(),
+            o_refs: self.o_refs.clone(),
+            normalizer: self.normalizer.clone(),
+            last_diff: None,
+            #[cfg(feature = "track_hit_feedbacks")]
+            last_result: None,
+        }
+    }
+}
+
+impl<N, O> Named for MultiDiffFeedback<N, O> {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<N, O> Debug for MultiDiffFeedback<N, O> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultiDiffFeedback")
+            .field("name", self.name())
+            .field("observers", &self.o_refs)
+            .field("last_diff", &self.last_diff)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<N, O, S> StateInitializer<S> for MultiDiffFeedback<N, O> {}
+
+impl<EM, I, N, O, OT, S> Feedback<EM, I, OT, S> for MultiDiffFeedback<N, O>
+where
+    N: OutputNormalizer<O>,
+    OT: MatchName,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error> {
+        let mut outputs = Vec::with_capacity(self.o_refs.len());
+        for o_ref in &self.o_refs {
+            let observer = observers.get(o_ref).ok_or_else(|| {
+                Error::illegal_argument(format!(
+                    "MultiDiffFeedback: observer {} not found",
+                    o_ref.name()
+                ))
+            })?;
+            outputs.push(self.normalizer.normalize(observer));
+        }
+
+        let groups = vote(&outputs);
+        let res = groups.len() > 1;
+        self.last_diff = res.then(|| {
+            let name = |idx: &usize| self.o_refs[*idx].name().to_string();
+            let has_majority = groups[0].len() > groups[1].len();
+            let diverging = groups
+                .iter()
+                .skip(usize::from(has_majority))
+                .flatten()
+                .map(name)
+                .collect();
+            MultiDiffMetadata {
+                groups: groups
+                    .iter()
+                    .map(|group| group.iter().map(name).collect())
+                    .collect(),
+                diverging,
+            }
+        });
+        #[cfg(feature = "track_hit_feedbacks")]
+        {
+            self.last_result = Some(res);
+        }
+        Ok(res)
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        self.last_result.ok_or(premature_last_result_err())
+    }
+
+    fn append_metadata(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _observers: &OT,
+        testcase: &mut Testcase<I>,
+    ) -> Result<(), Error> {
+        if let Some(diff) = self.last_diff.take() {
+            testcase.add_metadata(diff);
+        }
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.last_diff = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{vote, MultiDiffMetadata};
+
+    #[test]
+    fn test_vote() {
+        let outputs = [
+            b"{\"a\":1}".to_vec(),
+            b"{\"a\":1}".to_vec(),
+            b"error".to_vec(),
+            b"{\"a\":1}".to_vec(),
+            b"{\"a\":1.0}".to_vec(),
+        ];
+        assert_eq!(vote(&outputs), vec![vec![0, 1, 3], vec![2], vec![4]]);
+        assert_eq!(vote(&outputs[..2]), vec![vec![0, 1]]);
+        assert_eq!(vote(&outputs[1..3]), vec![vec![0], vec![1]]);
+    }
+
+    #[test]
+    fn test_majority() {
+        let mut meta = MultiDiffMetadata {
+            groups: vec![vec!["a".into(), "b".into()], vec!["c".into()]],
+            diverging: vec!["c".into()],
+        };
+        assert_eq!(meta.majority(), Some(&["a".into(), "b".into()][..]));
+        meta.groups = vec![vec!["a".into()], vec!["b".into()]];
+        assert_eq!(meta.majority(), None);
+    }
+}
@@ -0,0 +1,108 @@
+//! Feedback for the protocol states of network targets, see [`ProtocolStateObserver`].
+
+use alloc::{borrow::Cow, vec::Vec};
+
+use libafl_bolts::{
+    impl_serdeany,
+    tuples::{Handle, Handled, MatchName, MatchNameRef},
+    Named,
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "track_hit_feedbacks")]
+use crate::feedbacks::premature_last_result_err;
+use crate::{
+    corpus::Testcase,
+    executors::ExitKind,
+    feedbacks::{Feedback, StateInitializer},
+    observers::ProtocolStateObserver,
+    Error, HasMetadata,
+};
+
+/// The protocol states a [`Testcase`] reaches, sorted and without duplicates
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ProtocolStatesMetadata {
+    /// The reached states
+    pub states: Vec<u64>,
+}
+
+impl_serdeany!(ProtocolStatesMetadata);
+
+/// A feedback considering an input interesting if it reached a new protocol state, or took a new
+/// transition between states, as recorded by a [`ProtocolStateObserver`].
+///
+/// Annotates each new testcase with the states it reaches, in a [`ProtocolStatesMetadata`].
+/// Combine it with a `MapFeedback` using `feedback_or!` to keep inputs finding new edges or new states.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProtocolStateFeedback {
+    o_ref: Handle<ProtocolStateObserver>,
+    #[cfg(feature = "track_hit_feedbacks")]
+    last_result: Option<bool>,
+}
+
+impl ProtocolStateFeedback {
+    /// Creates a new [`ProtocolStateFeedback`].
+    #[must_use]
+    pub fn new(observer: &ProtocolStateObserver) -> Self {
+        Self {
+            o_ref: observer.handle(),
+            #[cfg(feature = "track_hit_feedbacks")]
+            last_result: None,
+        }
+    }
+}
+
+impl<S> StateInitializer<S> for ProtocolStateFeedback {}
+
+impl<EM, I, OT, S> Feedback<EM, I, OT, S> for ProtocolStateFeedback
+where
+    OT: MatchName,
+{
+    fn is_interesting(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error> {
+        let observer = observers
+            .get(&self.o_ref)
+            .ok_or(Error::illegal_state("ProtocolStateObserver is missing"))?;
+        let res = observer.discovered() > 0;
+        #[cfg(feature = "track_hit_feedbacks")]
+        {
+            self.last_result = Some(res);
+        }
+        Ok(res)
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        self.last_result.ok_or(premature_last_result_err())
+    }
+
+    fn append_metadata(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        observers: &OT,
+        testcase: &mut Testcase<I>,
+    ) -> Result<(), Error> {
+        let observer = observers
+            .get(&self.o_ref)
+            .ok_or(Error::illegal_state("ProtocolStateObserver is missing"))?;
+        let mut states = observer.states().to_vec();
+        states.sort_unstable();
+        states.dedup();
+        testcase.add_metadata(ProtocolStatesMetadata { states });
+        Ok(())
+    }
+}
+
+impl Named for ProtocolStateFeedback {
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        self.o_ref.name()
+    }
+}
@@ -0,0 +1,237 @@
+//! Provenance of testcases: the parent, mutations, stage and client each corpus entry came from.
+//!
+//! The stages adding inputs to the corpus and the event managers record where the input under
+//! evaluation comes from in a [`ProvenanceMetadata`] of the state.
+//! The [`ProvenanceFeedback`] copies it to every testcase added to the corpus.
+//! [`crate::corpus::CorpusGenealogy`] exports the resulting tree.
+
+use alloc::{
+    borrow::{Cow, ToOwned},
+    vec::Vec,
+};
+
+use libafl_bolts::{impl_serdeany, ClientId, Error, Named};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "track_hit_feedbacks")]
+use crate::feedbacks::premature_last_result_err;
+use crate::{
+    corpus::{CorpusId, Testcase},
+    executors::ExitKind,
+    feedbacks::{Feedback, StateInitializer},
+    HasMetadata,
+};
+
+/// Constant name of the [`ProvenanceFeedback`].
+pub const PROVENANCE_FEEDBACK_NAME: Cow<'static, str> = Cow::Borrowed("provenance_feedback");
+
+/// Where a testcase comes from.
+///
+/// In the state, it describes the input currently evaluated, and is only kept up to date once a
+/// [`ProvenanceFeedback`] registered it. In a testcase, it is the copy made when the testcase was added.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ProvenanceMetadata {
+    parent_id: Option<CorpusId>,
+    mutator: Option<Cow<'static, str>>,
+    mutations: Vec<Cow<'static, str>>,
+    stage: Option<Cow<'static, str>>,
+    client_id: Option<ClientId>,
+}
+
+impl_serdeany!(ProvenanceMetadata);
+
+impl ProvenanceMetadata {
+    /// The corpus entry this testcase was mutated from, `None` for seeds and received testcases
+    #[must_use]
+    pub fn parent_id(&self) -> Option<CorpusId> {
+        self.parent_id
+    }
+
+    /// The name of the mutator of the stage, if it has one
+    #[must_use]
+    pub fn mutator(&self) -> Option<&str> {
+        self.mutator.as_deref()
+    }
+
+    /// The mutations the mutator of the stage applied to the parent, in the order they were applied.
+    ///
+    /// Empty if the mutator does not report them, see [`crate::mutators::Mutator::applied_mutations`].
+    #[must_use]
+    pub fn mutations(&self) -> &[Cow<'static, str>] {
+        &self.mutations
+    }
+
+    /// The name of the stage that produced this testcase
+    #[must_use]
+    pub fn stage(&self) -> Option<&str> {
+        self.stage.as_deref()
+    }
+
+    /// The client this testcase was received from, `None` if it was found by this client
+    #[must_use]
+    pub fn client_id(&self) -> Option<ClientId> {
+        self.client_id
+    }
+
+    /// Records that the next inputs are produced by `stage`, with `mutator` if any.
+    ///
+    /// `parent_id` is the entry they are derived from, `None` for stages generating or importing new inputs.
+    pub fn enter_stage<S>(
+        state: &mut S,
+        stage: &str,
+        mutator: Option<Cow<'static, str>>,
+        parent_id: Option<CorpusId>,
+    ) where
+        S: HasMetadata,
+    {
+        if let Ok(provenance) = state.metadata_mut::<Self>() {
+            *provenance = Self {
+                parent_id,
+                mutator,
+                mutations: Vec::new(),
+                stage: Some(Cow::Owned(stage.to_owned())),
+                client_id: None,
+            };
+        }
+    }
+
+    /// Records the mutations applied to the next input, see [`Self::mutations`].
+    ///
+    /// `mutations` is only called if the state has a [`ProvenanceMetadata`].
+    pub fn record_mutations<S, F>(state: &mut S, mutations: F)
+    where
+        S: HasMetadata,
+        F: FnOnce() -> Vec<Cow<'static, str>>,
+    {
+        if let Ok(provenance) = state.metadata_mut::<Self>() {
+            provenance.mutations = mutations();
+        }
+    }
+
+    /// Records that the next inputs were received from `client_id`.
+    pub fn enter_transfer<S>(state: &mut S, client_id: ClientId)
+    where
+        S: HasMetadata,
+    {
+        if let Ok(provenance) = state.metadata_mut::<Self>() {
+            *provenance = Self {
+                client_id: Some(client_id),
+                ..Self::default()
+            };
+        }
+    }
+
+    /// Forgets where the next inputs come from, after a stage or a transfer.
+    pub fn leave<S>(state: &mut S)
+    where
+        S: HasMetadata,
+    {
+        if let Ok(provenance) = state.metadata_mut::<Self>() {
+            *provenance = Self::default();
+        }
+    }
+
+    /// Runs `f` with the inputs recorded as produced by `stage`, see [`Self::enter_stage`],
+    /// and forgets it again whether `f` succeeds or not.
+    pub fn with_stage<S, T, F>(
+        state: &mut S,
+        stage: &str,
+        mutator: Option<Cow<'static, str>>,
+        parent_id: Option<CorpusId>,
+        f: F,
+    ) -> Result<T, Error>
+    where
+        S: HasMetadata,
+        F: FnOnce(&mut S) -> Result<T, Error>,
+    {
+        Self::enter_stage(state, stage, mutator, parent_id);
+        let ret = f(state);
+        Self::leave(state);
+        ret
+    }
+
+    /// Runs `f` with the inputs recorded as received from `client_id`, see [`Self::enter_transfer`],
+    /// and forgets it again whether `f` succeeds or not.
+    pub fn with_transfer<S, T, F>(state: &mut S, client_id: ClientId, f: F) -> Result<T, Error>
+    where
+        S: HasMetadata,
+        F: FnOnce(&mut S) -> Result<T, Error>,
+    {
+        Self::enter_transfer(state, client_id);
+        let ret = f(state);
+        Self::leave(state);
+        ret
+    }
+}
+
+/// Attaches a [`ProvenanceMetadata`] to every new testcase.
+///
+/// It is never interesting by itself, combine it with the actual feedback, e.g. using `feedback_or!`.
+/// Along with the mutator of the stage, it records the ordered mutations it applied, if the mutator
+/// reports them like the [`crate::mutators::StdScheduledMutator`].
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ProvenanceFeedback {
+    #[cfg(feature = "track_hit_feedbacks")]
+    // The previous run's result of `Self::is_interesting`
+    last_result: Option<bool>,
+}
+
+impl ProvenanceFeedback {
+    /// Creates a new [`ProvenanceFeedback`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Named for ProvenanceFeedback {
+    fn name(&self) -> &Cow<'static, str> {
+        &PROVENANCE_FEEDBACK_NAME
+    }
+}
+
+impl<S> StateInitializer<S> for ProvenanceFeedback
+where
+    S: HasMetadata,
+{
+    fn init_state(&mut self, state: &mut S) -> Result<(), Error> {
+        state.add_metadata(ProvenanceMetadata::default());
+        Ok(())
+    }
+}
+
+impl<EM, I, OT, S> Feedback<EM, I, OT, S> for ProvenanceFeedback
+where
+    S: HasMetadata,
+{
+    fn is_interesting(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error> {
+        #[cfg(feature = "track_hit_feedbacks")]
+        {
+            self.last_result = Some(false);
+        }
+        Ok(false)
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        self.last_result.ok_or(premature_last_result_err())
+    }
+
+    fn append_metadata(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _observers: &OT,
+        testcase: &mut Testcase<I>,
+    ) -> Result<(), Error> {
+        testcase.add_metadata(state.metadata::<ProvenanceMetadata>()?.clone());
+        Ok(())
+    }
+}
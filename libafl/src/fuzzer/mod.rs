@@ -429,8 +429,12 @@ where
         EM: EventFirer<State = Self::State>,
         OT: ObserversTuple<Self::Input, Self::State> + Serialize,
     {
-        let exec_res = self.check_results(state, manager, &input, observers, exit_kind)?;
+        let mut exec_res = self.check_results(state, manager, &input, observers, exit_kind)?;
         let corpus_id = self.process_execution(state, manager, &input, &exec_res, observers)?;
+        if exec_res == ExecuteInputResult::Corpus && corpus_id.is_none() {
+            // The corpus already stored this input, there is nothing new to report
+            exec_res = ExecuteInputResult::None;
+        }
         if send_events {
             self.serialize_and_dispatch(state, manager, input, &exec_res, observers, exit_kind)?;
         }
@@ -549,6 +553,11 @@ where
                     .append_hit_feedbacks(testcase.hit_feedbacks_mut())?;
                 self.feedback_mut()
                     .append_metadata(state, manager, observers, &mut testcase)?;
+                if state.corpus().duplicate_of(input)?.is_some() {
+                    // The corpus already stores this input, only merge the metadata into it
+                    state.corpus_mut().add(testcase)?;
+                    return Ok(None);
+                }
                 let id = state.corpus_mut().add(testcase)?;
                 self.scheduler_mut().on_add(state, id)?;
 
@@ -718,6 +727,10 @@ where
         // Add the input to the main corpus
         self.feedback_mut()
             .append_metadata(state, manager, &*observers, &mut testcase)?;
+        if state.corpus().duplicate_of(&input)?.is_some() {
+            // The corpus already stores this input, only merge the metadata into it
+            return state.corpus_mut().add(testcase);
+        }
         let id = state.corpus_mut().add(testcase)?;
         self.scheduler_mut().on_add(state, id)?;
 
@@ -14,8 +14,13 @@ use alloc::string::ToString;
 pub use prometheus::PrometheusMonitor;
 #[cfg(feature = "std")]
 pub mod disk;
+
+#[cfg(all(feature = "statsd_monitor", feature = "std"))]
+pub mod statsd;
 use alloc::{borrow::Cow, fmt::Debug, string::String, vec::Vec};
 use core::{fmt, fmt::Write, time::Duration};
+#[cfg(all(feature = "statsd_monitor", feature = "std"))]
+pub use statsd::StatsdMonitor;
 
 #[cfg(feature = "std")]
 pub use disk::{OnDiskJsonMonitor, OnDiskTomlMonitor};
@@ -26,6 +31,12 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "afl_exec_sec")]
 const CLIENT_STATS_TIME_WINDOW_SECS: u64 = 5; // 5 seconds
 
+/// The name of the [`UserStats`] holding the ratio of sent to uncompressed bytes of a client's llmp messages
+pub const LLMP_COMPRESSION_RATIO_STATS_NAME: &str = "llmp_compression_ratio";
+
+/// The name of the [`UserStats`] holding the time (in seconds) a client spent compressing llmp messages
+pub const LLMP_COMPRESSION_TIME_STATS_NAME: &str = "llmp_compression_time";
+
 /// Definition of how we aggreate this across multiple clients
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum AggregatorOps {
@@ -480,6 +491,31 @@ impl ClientStats {
         self.user_monitor.get(name)
     }
 
+    /// The ratio of sent to uncompressed bytes of this client's llmp messages, if reported.
+    /// Lower is better.
+    #[must_use]
+    pub fn llmp_compression_ratio(&self) -> Option<f64> {
+        match self
+            .get_user_stats(LLMP_COMPRESSION_RATIO_STATS_NAME)?
+            .value()
+        {
+            UserStatsValue::Float(ratio) => Some(*ratio),
+            _ => None,
+        }
+    }
+
+    /// The time this client spent compressing llmp messages, if reported
+    #[must_use]
+    pub fn llmp_compression_time(&self) -> Option<Duration> {
+        match self
+            .get_user_stats(LLMP_COMPRESSION_TIME_STATS_NAME)?
+            .value()
+        {
+            UserStatsValue::Float(secs) => Some(Duration::from_secs_f64(*secs)),
+            _ => None,
+        }
+    }
+
     /// Update the current [`ClientPerfMonitor`] with the given [`ClientPerfMonitor`]
     #[cfg(feature = "introspection")]
     pub fn update_introspection_monitor(&mut self, introspection_monitor: ClientPerfMonitor) {
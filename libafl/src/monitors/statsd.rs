@@ -0,0 +1,404 @@
+//! The [`StatsdMonitor`] pushes fuzzer stats as tagged gauges to a `StatsD` server, over UDP.
+//!
+//! ## Overview
+//!
+//! Every `update_interval`, the monitor sends the global stats, the stats of each client,
+//! all numeric [`crate::monitors::UserStats`] and, with the `introspection` feature,
+//! the [`crate::monitors::ClientPerfMonitor`] breakdown of each client.
+//! Metrics use the `DogStatsD` tag extension (`name:value|g|#key:value`),
+//! which is also understood by the `statsd` receiver of the `OpenTelemetry` collector.
+//!
+//! ## How to use it
+//!
+//! ```rust,no_run
+//! use libafl::monitors::{SimpleMonitor, StatsdMonitor};
+//!
+//! let mon = StatsdMonitor::new("127.0.0.1:8125", SimpleMonitor::new(|s| log::info!("{s}")))
+//!     .unwrap()
+//!     .with_prefix("libafl")
+//!     .with_tag("campaign", "nightly");
+//!
+//! // and finally, like with any other monitor, pass it into the event manager like so:
+//! // let mgr = SimpleEventManager::new(mon);
+//! ```
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt::Write, time::Duration};
+use std::net::{ToSocketAddrs, UdpSocket};
+
+use libafl_bolts::{current_time, ClientId};
+
+use crate::{
+    monitors::{ClientStats, Monitor, NopMonitor, UserStatsValue},
+    Error,
+};
+
+/// The maximum size of a single UDP packet we send, to stay below common MTUs
+const MAX_PACKET_SIZE: usize = 1432;
+
+/// Wrap a monitor and push the current state of the monitor to a `StatsD` server.
+#[derive(Debug)]
+pub struct StatsdMonitor<M>
+where
+    M: Monitor,
+{
+    base: M,
+    socket: UdpSocket,
+    /// The prefix of all metric names
+    prefix: String,
+    /// The tags added to all metrics, already formatted as `key:value`
+    tags: Vec<String>,
+    last_update: Duration,
+    update_interval: Duration,
+}
+
+impl<M> Monitor for StatsdMonitor<M>
+where
+    M: Monitor,
+{
+    /// The client monitor, mutable
+    fn client_stats_mut(&mut self) -> &mut Vec<ClientStats> {
+        self.base.client_stats_mut()
+    }
+
+    /// The client monitor
+    fn client_stats(&self) -> &[ClientStats] {
+        self.base.client_stats()
+    }
+
+    /// Time this fuzzing run stated
+    fn start_time(&self) -> Duration {
+        self.base.start_time()
+    }
+
+    /// Set creation time
+    fn set_start_time(&mut self, time: Duration) {
+        self.base.set_start_time(time);
+    }
+
+    fn aggregate(&mut self, name: &str) {
+        self.base.aggregate(name);
+    }
+
+    fn display(&mut self, event_msg: &str, sender_id: ClientId) {
+        let cur_time = current_time();
+
+        if cur_time.saturating_sub(self.last_update) >= self.update_interval {
+            self.last_update = cur_time;
+
+            if let Err(err) = self.push(cur_time) {
+                log::warn!("Failed to push stats to StatsD: {err}");
+            }
+        }
+
+        self.base.display(event_msg, sender_id);
+    }
+}
+
+impl<M> StatsdMonitor<M>
+where
+    M: Monitor,
+{
+    /// Create a new [`StatsdMonitor`], pushing to the `StatsD` server at `addr` every 10 seconds
+    pub fn new<A>(addr: A, base: M) -> Result<Self, Error>
+    where
+        A: ToSocketAddrs,
+    {
+        Self::with_update_interval(addr, base, Duration::from_secs(10))
+    }
+
+    /// Create a new [`StatsdMonitor`] with custom update interval
+    pub fn with_update_interval<A>(
+        addr: A,
+        base: M,
+        update_interval: Duration,
+    ) -> Result<Self, Error>
+    where
+        A: ToSocketAddrs,
+    {
+        let Some(target) = addr.to_socket_addrs()?.next() else {
+            return Err(Error::illegal_argument(
+                "Could not resolve the StatsD server address",
+            ));
+        };
+        let socket = if target.is_ipv6() {
+            UdpSocket::bind("[::]:0")?
+        } else {
+            UdpSocket::bind("0.0.0.0:0")?
+        };
+        socket.connect(target)?;
+
+        Ok(Self {
+            base,
+            socket,
+            prefix: "libafl".to_string(),
+            tags: vec![],
+            last_update: current_time().saturating_sub(update_interval),
+            update_interval,
+        })
+    }
+
+    /// Set the prefix of all metric names, `libafl` by default
+    #[must_use]
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = sanitize(prefix);
+        self
+    }
+
+    /// Add a tag to all metrics sent by this monitor
+    #[must_use]
+    pub fn with_tag(mut self, key: &str, value: &str) -> Self {
+        self.tags
+            .push(format!("{}:{}", sanitize(key), sanitize(value)));
+        self
+    }
+
+    /// Send all stats to the `StatsD` server now
+    #[allow(clippy::cast_precision_loss)]
+    fn push(&mut self, cur_time: Duration) -> Result<(), Error> {
+        let mut packet = Packet::new(&self.socket, &self.prefix, &self.tags);
+        let base = &mut self.base;
+
+        packet.gauge("clients", base.client_stats_count() as f64, &[])?;
+        packet.gauge("corpus", base.corpus_size() as f64, &[])?;
+        packet.gauge("objectives", base.objective_size() as f64, &[])?;
+        packet.gauge("executions", base.total_execs() as f64, &[])?;
+        packet.gauge("execs_per_sec", base.execs_per_sec(), &[])?;
+        packet.gauge(
+            "run_time",
+            cur_time.saturating_sub(base.start_time()).as_secs_f64(),
+            &[],
+        )?;
+
+        for (i, client) in base.client_stats_mut().iter_mut().enumerate() {
+            if !client.enabled {
+                continue;
+            }
+            let client_tag = format!("client:{i}");
+            let tags = [client_tag.as_str()];
+
+            packet.gauge("client.corpus", client.corpus_size as f64, &tags)?;
+            packet.gauge("client.objectives", client.objective_size as f64, &tags)?;
+            packet.gauge("client.executions", client.executions as f64, &tags)?;
+            packet.gauge(
+                "client.execs_per_sec",
+                client.execs_per_sec(cur_time),
+                &tags,
+            )?;
+
+            for (key, val) in &client.user_monitor {
+                let value = match val.value() {
+                    UserStatsValue::Number(n) => *n as f64,
+                    UserStatsValue::Float(f) | UserStatsValue::Percent(f) => *f,
+                    UserStatsValue::Ratio(a, b) => {
+                        if *b == 0 {
+                            0.0
+                        } else {
+                            *a as f64 / *b as f64
+                        }
+                    }
+                    UserStatsValue::String(_) => continue,
+                };
+                packet.gauge(&format!("user.{}", sanitize(key)), value, &tags)?;
+            }
+
+            #[cfg(feature = "introspection")]
+            push_introspection(&mut packet, client, &client_tag)?;
+        }
+
+        packet.flush()
+    }
+}
+
+impl StatsdMonitor<NopMonitor> {
+    /// Create a new [`StatsdMonitor`] without a base
+    pub fn nop<A>(addr: A) -> Result<Self, Error>
+    where
+        A: ToSocketAddrs,
+    {
+        Self::new(addr, NopMonitor::new())
+    }
+}
+
+/// Send the [`crate::monitors::ClientPerfMonitor`] breakdown of a client, as fractions of the elapsed cycles
+#[cfg(feature = "introspection")]
+#[allow(clippy::cast_precision_loss)]
+fn push_introspection(
+    packet: &mut Packet,
+    client: &ClientStats,
+    client_tag: &str,
+) -> Result<(), Error> {
+    use crate::monitors::PerfFeature;
+
+    let perf = &client.introspection_monitor;
+    let elapsed = perf.elapsed_cycles() as f64;
+    if elapsed == 0.0 {
+        return Ok(());
+    }
+    let tags = [client_tag];
+
+    packet.gauge(
+        "perf.scheduler",
+        perf.scheduler_cycles() as f64 / elapsed,
+        &tags,
+    )?;
+    packet.gauge(
+        "perf.manager",
+        perf.manager_cycles() as f64 / elapsed,
+        &tags,
+    )?;
+
+    for (stage_index, features) in perf.used_stages() {
+        let stage_tag = format!("stage:{stage_index}");
+        for (feature_index, feature_cycles) in features.iter().enumerate() {
+            if *feature_cycles == 0 {
+                continue;
+            }
+            let feature: PerfFeature = feature_index.into();
+            let feature_tag = format!("feature:{}", sanitize(&format!("{feature:?}")));
+            packet.gauge(
+                "perf.stage",
+                *feature_cycles as f64 / elapsed,
+                &[client_tag, &stage_tag, &feature_tag],
+            )?;
+        }
+    }
+
+    for (feedback_name, feedback_time) in perf.feedbacks() {
+        if *feedback_time == 0 {
+            continue;
+        }
+        let feedback_tag = format!("feedback:{}", sanitize(feedback_name));
+        packet.gauge(
+            "perf.feedback",
+            *feedback_time as f64 / elapsed,
+            &[client_tag, &feedback_tag],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Replaces all characters that have a meaning in the `StatsD` line protocol
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Batches metric lines into UDP packets of at most [`MAX_PACKET_SIZE`] bytes
+struct Packet<'a> {
+    socket: &'a UdpSocket,
+    prefix: &'a str,
+    global_tags: &'a [String],
+    buf: String,
+    line: String,
+}
+
+impl<'a> Packet<'a> {
+    fn new(socket: &'a UdpSocket, prefix: &'a str, global_tags: &'a [String]) -> Self {
+        Self {
+            socket,
+            prefix,
+            global_tags,
+            buf: String::with_capacity(MAX_PACKET_SIZE),
+            line: String::new(),
+        }
+    }
+
+    /// Add a gauge, sending the current packet first if it would grow too large
+    fn gauge(&mut self, name: &str, value: f64, tags: &[&str]) -> Result<(), Error> {
+        self.line.clear();
+        write!(self.line, "{}.{name}:{value}|g", self.prefix)
+            .map_err(|_| Error::illegal_state("Failed to format StatsD metric"))?;
+        let mut all_tags = self
+            .global_tags
+            .iter()
+            .map(String::as_str)
+            .chain(tags.iter().copied());
+        if let Some(first) = all_tags.next() {
+            self.line.push_str("|#");
+            self.line.push_str(first);
+            for tag in all_tags {
+                self.line.push(',');
+                self.line.push_str(tag);
+            }
+        }
+
+        if !self.buf.is_empty() && self.buf.len() + 1 + self.line.len() > MAX_PACKET_SIZE {
+            self.flush()?;
+        }
+        if !self.buf.is_empty() {
+            self.buf.push('\n');
+        }
+        self.buf.push_str(&self.line);
+        Ok(())
+    }
+
+    /// Send all buffered metrics
+    fn flush(&mut self) -> Result<(), Error> {
+        if !self.buf.is_empty() {
+            self.socket.send(self.buf.as_bytes())?;
+            self.buf.clear();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use core::time::Duration;
+    use std::net::UdpSocket;
+
+    use libafl_bolts::ClientId;
+
+    use crate::monitors::{
+        AggregatorOps, Monitor, NopMonitor, StatsdMonitor, UserStats, UserStatsValue,
+    };
+
+    #[test]
+    fn test_statsd_monitor() {
+        let listener = UdpSocket::bind("127.0.0.1:0").unwrap();
+        listener
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+
+        let mut monitor = StatsdMonitor::with_update_interval(
+            listener.local_addr().unwrap(),
+            NopMonitor::new(),
+            Duration::ZERO,
+        )
+        .unwrap()
+        .with_prefix("fuzz")
+        .with_tag("campaign", "test run");
+
+        monitor.client_stats_insert(ClientId(1));
+        let client = monitor.client_stats_mut_for(ClientId(1));
+        client.update_corpus_size(42);
+        client.update_user_stats(
+            "edges seen".into(),
+            UserStats::new(UserStatsValue::Ratio(1, 4), AggregatorOps::Avg),
+        );
+        monitor.display("Test", ClientId(1));
+
+        let mut buf = [0; 2048];
+        let len = listener.recv(&mut buf).unwrap();
+        let packet = core::str::from_utf8(&buf[..len]).unwrap();
+        let lines: Vec<&str> = packet.lines().collect();
+
+        assert!(lines.contains(&"fuzz.corpus:42|g|#campaign:test_run"));
+        assert!(lines.contains(&"fuzz.client.corpus:42|g|#campaign:test_run,client:1"));
+        assert!(lines.contains(&"fuzz.user.edges_seen:0.25|g|#campaign:test_run,client:1"));
+    }
+}
@@ -105,6 +105,13 @@ pub trait Mutator<I, S>: Named {
     fn post_exec(&mut self, _state: &mut S, _new_corpus_id: Option<CorpusId>) -> Result<(), Error> {
         Ok(())
     }
+
+    /// The names of the mutations the last call to [`Mutator::mutate`] applied, in the order they were applied.
+    /// Empty for mutators that do not schedule other mutations.
+    #[inline]
+    fn applied_mutations(&self) -> Vec<Cow<'static, str>> {
+        Vec::new()
+    }
 }
 
 /// A mutator that takes input, and returns a vector of mutated inputs.
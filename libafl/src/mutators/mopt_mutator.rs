@@ -17,7 +17,7 @@ use crate::{
     corpus::{Corpus, CorpusId},
     mutators::{ComposedByMutations, MutationResult, Mutator, MutatorsTuple, ScheduledMutator},
     state::{HasCorpus, HasRand, HasSolutions},
-    Error, HasMetadata,
+    Error, HasMetadata, HasNamedMetadata,
 };
 
 /// A Struct for managing MOpt-mutator parameters.
@@ -345,148 +345,138 @@ impl MOpt {
         }
         Ok(res.into())
     }
-}
-
-const V_MAX: f64 = 1.0;
-const V_MIN: f64 = 0.05;
 
-/// The `MOpt` mode to use
-#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
-pub enum MOptMode {
-    /// Pilot fuzzing mode
-    Pilotfuzzing,
-    /// Core fuzzing mode
-    Corefuzzing,
-}
+    /// The current probability to choose each mutation operator
+    #[must_use]
+    pub fn operator_probabilities(&self) -> &[f64] {
+        &self.x_now[self.swarm_now]
+    }
 
-/// This is the main struct of `MOpt`, an `AFL` mutator.
-/// See the original `MOpt` implementation in <https://github.com/puppet-meteor/MOpt-AFL>
-#[derive(Debug)]
-pub struct StdMOptMutator<MT> {
-    name: Cow<'static, str>,
-    mode: MOptMode,
-    finds_before: usize,
-    mutations: MT,
-    max_stack_pow: usize,
-}
+    /// Marks the start of a new stacked mutation, before its operators are counted with [`Self::count_use`]
+    pub fn start_mutation(&mut self, mode: MOptMode) {
+        match mode {
+            MOptMode::Corefuzzing => {
+                self.core_operator_cycles_v3
+                    .clone_from(&self.core_operator_cycles_v2);
+            }
+            MOptMode::Pilotfuzzing => {
+                let swarm_now = self.swarm_now;
+                self.pilot_operator_cycles_v3[swarm_now]
+                    .clone_from(&self.pilot_operator_cycles_v2[swarm_now]);
+            }
+        }
+    }
 
-impl<I, MT, S> Mutator<I, S> for StdMOptMutator<MT>
-where
-    MT: MutatorsTuple<I, S>,
-    S: HasRand + HasMetadata + HasCorpus + HasSolutions,
-{
-    #[inline]
-    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
-        self.finds_before = state.corpus().count() + state.solutions().count();
-        self.scheduled_mutate(state, input)
+    /// Counts a use of the operator `idx` in the current stacked mutation
+    pub fn count_use(&mut self, mode: MOptMode, idx: MutationId) {
+        match mode {
+            MOptMode::Corefuzzing => self.core_operator_cycles_v2[idx.0] += 1,
+            MOptMode::Pilotfuzzing => self.pilot_operator_cycles_v2[self.swarm_now][idx.0] += 1,
+        }
     }
 
+    /// Credits the operators of the current stacked mutation with its `finds`, after its execution.
+    ///
+    /// Updates the swarms at the end of each period, switching `mode` between pilot and core fuzzing.
     #[allow(clippy::cast_precision_loss)]
-    fn post_exec(&mut self, state: &mut S, _new_corpus_id: Option<CorpusId>) -> Result<(), Error> {
-        let before = self.finds_before;
-        let after = state.corpus().count() + state.solutions().count();
-
-        let mopt = state.metadata_map_mut().get_mut::<MOpt>().unwrap();
-        let key_module = self.mode;
-        match key_module {
+    pub fn finish_mutation(&mut self, mode: &mut MOptMode, finds: usize) -> Result<(), Error> {
+        match *mode {
             MOptMode::Corefuzzing => {
-                mopt.core_time += 1;
-
-                if after > before {
-                    let diff = after - before;
-                    mopt.total_finds += diff;
-                    for i in 0..mopt.operator_num {
-                        if mopt.core_operator_cycles_v2[i] > mopt.core_operator_cycles_v3[i] {
-                            mopt.core_operator_finds_v2[i] += diff as u64;
+                self.core_time += 1;
+
+                if finds > 0 {
+                    self.total_finds += finds;
+                    for i in 0..self.operator_num {
+                        if self.core_operator_cycles_v2[i] > self.core_operator_cycles_v3[i] {
+                            self.core_operator_finds_v2[i] += finds as u64;
                         }
                     }
                 }
 
-                if mopt.core_time > mopt.period_core {
-                    mopt.core_time = 0;
-                    let total_finds = mopt.total_finds;
-                    mopt.finds_until_last_swarm = total_finds;
-                    for i in 0..mopt.operator_num {
-                        mopt.core_operator_finds[i] = mopt.core_operator_finds_v2[i];
-                        mopt.core_operator_cycles[i] = mopt.core_operator_cycles_v2[i];
+                if self.core_time > self.period_core {
+                    self.core_time = 0;
+                    let total_finds = self.total_finds;
+                    self.finds_until_last_swarm = total_finds;
+                    for i in 0..self.operator_num {
+                        self.core_operator_finds[i] = self.core_operator_finds_v2[i];
+                        self.core_operator_cycles[i] = self.core_operator_cycles_v2[i];
                     }
-                    mopt.pso_update()?;
-                    self.mode = MOptMode::Pilotfuzzing;
+                    self.pso_update()?;
+                    *mode = MOptMode::Pilotfuzzing;
                 }
             }
             MOptMode::Pilotfuzzing => {
-                mopt.pilot_time += 1;
-                let swarm_now = mopt.swarm_now;
-
-                if after > before {
-                    let diff = after - before;
-                    mopt.total_finds += diff;
-                    for i in 0..mopt.operator_num {
-                        if mopt.pilot_operator_cycles_v2[swarm_now][i]
-                            > mopt.pilot_operator_cycles_v3[swarm_now][i]
+                self.pilot_time += 1;
+                let swarm_now = self.swarm_now;
+
+                if finds > 0 {
+                    self.total_finds += finds;
+                    for i in 0..self.operator_num {
+                        if self.pilot_operator_cycles_v2[swarm_now][i]
+                            > self.pilot_operator_cycles_v3[swarm_now][i]
                         {
-                            mopt.pilot_operator_finds_v2[swarm_now][i] += diff as u64;
+                            self.pilot_operator_finds_v2[swarm_now][i] += finds as u64;
                         }
                     }
                 }
 
                 #[allow(clippy::cast_lossless)]
-                if mopt.pilot_time > mopt.period_pilot {
-                    let new_finds = mopt.total_finds - mopt.finds_until_last_swarm;
-                    let f = (new_finds as f64) / ((mopt.pilot_time as f64) / (PERIOD_PILOT_COEF));
-                    mopt.swarm_fitness[swarm_now] = f;
-                    mopt.pilot_time = 0;
-                    let total_finds = mopt.total_finds;
-                    mopt.finds_until_last_swarm = total_finds;
-
-                    for i in 0..mopt.operator_num {
+                if self.pilot_time > self.period_pilot {
+                    let new_finds = self.total_finds - self.finds_until_last_swarm;
+                    let f = (new_finds as f64) / ((self.pilot_time as f64) / (PERIOD_PILOT_COEF));
+                    self.swarm_fitness[swarm_now] = f;
+                    self.pilot_time = 0;
+                    let total_finds = self.total_finds;
+                    self.finds_until_last_swarm = total_finds;
+
+                    for i in 0..self.operator_num {
                         let mut eff = 0.0;
-                        if mopt.pilot_operator_cycles_v2[swarm_now][i]
-                            > mopt.pilot_operator_cycles[swarm_now][i]
+                        if self.pilot_operator_cycles_v2[swarm_now][i]
+                            > self.pilot_operator_cycles[swarm_now][i]
                         {
-                            eff = ((mopt.pilot_operator_finds_v2[swarm_now][i]
-                                - mopt.pilot_operator_finds[swarm_now][i])
+                            eff = ((self.pilot_operator_finds_v2[swarm_now][i]
+                                - self.pilot_operator_finds[swarm_now][i])
                                 as f64)
-                                / ((mopt.pilot_operator_cycles_v2[swarm_now][i]
-                                    - mopt.pilot_operator_cycles[swarm_now][i])
+                                / ((self.pilot_operator_cycles_v2[swarm_now][i]
+                                    - self.pilot_operator_cycles[swarm_now][i])
                                     as f64);
                         }
 
-                        if mopt.eff_best[swarm_now][i] < eff {
-                            mopt.eff_best[swarm_now][i] = eff;
-                            mopt.l_best[swarm_now][i] = mopt.x_now[swarm_now][i];
+                        if self.eff_best[swarm_now][i] < eff {
+                            self.eff_best[swarm_now][i] = eff;
+                            self.l_best[swarm_now][i] = self.x_now[swarm_now][i];
                         }
 
-                        mopt.pilot_operator_finds[swarm_now][i] =
-                            mopt.pilot_operator_finds_v2[swarm_now][i];
-                        mopt.pilot_operator_cycles[swarm_now][i] =
-                            mopt.pilot_operator_cycles_v2[swarm_now][i];
+                        self.pilot_operator_finds[swarm_now][i] =
+                            self.pilot_operator_finds_v2[swarm_now][i];
+                        self.pilot_operator_cycles[swarm_now][i] =
+                            self.pilot_operator_cycles_v2[swarm_now][i];
                     }
 
-                    mopt.swarm_now += 1;
+                    self.swarm_now += 1;
 
-                    if mopt.swarm_num == 1 {
+                    if self.swarm_num == 1 {
                         // If there's only 1 swarm, then no core_fuzzing mode.
-                        mopt.pso_update()?;
-                    } else if mopt.swarm_now == mopt.swarm_num {
-                        self.mode = MOptMode::Corefuzzing;
-
-                        for i in 0..mopt.operator_num {
-                            mopt.core_operator_cycles_v2[i] = mopt.core_operator_cycles[i];
-                            mopt.core_operator_cycles_v3[i] = mopt.core_operator_cycles[i];
-                            mopt.core_operator_finds_v2[i] = mopt.core_operator_finds[i];
+                        self.pso_update()?;
+                    } else if self.swarm_now == self.swarm_num {
+                        *mode = MOptMode::Corefuzzing;
+
+                        for i in 0..self.operator_num {
+                            self.core_operator_cycles_v2[i] = self.core_operator_cycles[i];
+                            self.core_operator_cycles_v3[i] = self.core_operator_cycles[i];
+                            self.core_operator_finds_v2[i] = self.core_operator_finds[i];
                         }
 
                         let mut swarm_eff = 0.0;
                         let mut best_swarm = 0;
-                        for i in 0..mopt.swarm_num {
-                            if mopt.swarm_fitness[i] > swarm_eff {
-                                swarm_eff = mopt.swarm_fitness[i];
+                        for i in 0..self.swarm_num {
+                            if self.swarm_fitness[i] > swarm_eff {
+                                swarm_eff = self.swarm_fitness[i];
                                 best_swarm = i;
                             }
                         }
 
-                        mopt.swarm_now = best_swarm;
+                        self.swarm_now = best_swarm;
                     }
                 }
             }
@@ -495,6 +485,49 @@ where
     }
 }
 
+const V_MAX: f64 = 1.0;
+const V_MIN: f64 = 0.05;
+
+/// The `MOpt` mode to use
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub enum MOptMode {
+    /// Pilot fuzzing mode
+    Pilotfuzzing,
+    /// Core fuzzing mode
+    Corefuzzing,
+}
+
+/// This is the main struct of `MOpt`, an `AFL` mutator.
+/// See the original `MOpt` implementation in <https://github.com/puppet-meteor/MOpt-AFL>
+#[derive(Debug)]
+pub struct StdMOptMutator<MT> {
+    name: Cow<'static, str>,
+    mode: MOptMode,
+    finds_before: usize,
+    mutations: MT,
+    max_stack_pow: usize,
+}
+
+impl<I, MT, S> Mutator<I, S> for StdMOptMutator<MT>
+where
+    MT: MutatorsTuple<I, S>,
+    S: HasRand + HasMetadata + HasCorpus + HasSolutions,
+{
+    #[inline]
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        self.finds_before = state.corpus().count() + state.solutions().count();
+        self.scheduled_mutate(state, input)
+    }
+
+    fn post_exec(&mut self, state: &mut S, _new_corpus_id: Option<CorpusId>) -> Result<(), Error> {
+        let before = self.finds_before;
+        let after = state.corpus().count() + state.solutions().count();
+
+        let mopt = state.metadata_map_mut().get_mut::<MOpt>().unwrap();
+        mopt.finish_mutation(&mut self.mode, after.saturating_sub(before))
+    }
+}
+
 impl<MT> StdMOptMutator<MT> {
     /// Create a new [`StdMOptMutator`].
     pub fn new<S>(
@@ -526,10 +559,11 @@ impl<MT> StdMOptMutator<MT> {
         MT: MutatorsTuple<I, S>,
     {
         let mut r = MutationResult::Skipped;
-        let mopt = state.metadata_map_mut().get_mut::<MOpt>().unwrap();
-        for i in 0..mopt.operator_num {
-            mopt.core_operator_cycles_v3[i] = mopt.core_operator_cycles_v2[i];
-        }
+        state
+            .metadata_map_mut()
+            .get_mut::<MOpt>()
+            .unwrap()
+            .start_mutation(MOptMode::Corefuzzing);
 
         for _i in 0..self.iterations(state, input) {
             let idx = self.schedule(state, input);
@@ -542,7 +576,7 @@ impl<MT> StdMOptMutator<MT> {
                 .metadata_map_mut()
                 .get_mut::<MOpt>()
                 .unwrap()
-                .core_operator_cycles_v2[idx.0] += 1;
+                .count_use(MOptMode::Corefuzzing, idx);
         }
         Ok(r)
     }
@@ -553,16 +587,11 @@ impl<MT> StdMOptMutator<MT> {
         MT: MutatorsTuple<I, S>,
     {
         let mut r = MutationResult::Skipped;
-        let swarm_now;
-        {
-            let mopt = state.metadata_map_mut().get_mut::<MOpt>().unwrap();
-            swarm_now = mopt.swarm_now;
-
-            for i in 0..mopt.operator_num {
-                mopt.pilot_operator_cycles_v3[swarm_now][i] =
-                    mopt.pilot_operator_cycles_v2[swarm_now][i];
-            }
-        }
+        state
+            .metadata_map_mut()
+            .get_mut::<MOpt>()
+            .unwrap()
+            .start_mutation(MOptMode::Pilotfuzzing);
 
         for _i in 0..self.iterations(state, input) {
             let idx = self.schedule(state, input);
@@ -575,7 +604,7 @@ impl<MT> StdMOptMutator<MT> {
                 .metadata_map_mut()
                 .get_mut::<MOpt>()
                 .unwrap()
-                .pilot_operator_cycles_v2[swarm_now][idx.0] += 1;
+                .count_use(MOptMode::Pilotfuzzing, idx);
         }
 
         Ok(r)
@@ -633,3 +662,328 @@ where
         }
     }
 }
+
+/// The state of a [`MOptScheduledMutator`]: its swarms, the distribution they learned, and the current mode.
+///
+/// It is named metadata of the state, so that each [`MOptScheduledMutator`] learns on its own
+/// and picks up where it left off after a restart.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+pub struct MOptScheduledMetadata {
+    /// The swarms
+    pub mopt: MOpt,
+    /// The current mode
+    pub mode: MOptMode,
+    /// The number of solutions before the current mutation
+    solutions_before: usize,
+}
+
+libafl_bolts::impl_serdeany!(MOptScheduledMetadata);
+
+/// A [`ScheduledMutator`] learning the probabilities of its mutations with `MOpt`, for any [`MutatorsTuple`].
+///
+/// Unlike [`StdMOptMutator`], several of them can run side by side, e.g. for the havoc, Grimoire and token mutations,
+/// or for Nautilus and Gramatron inputs: each one keeps its own [`struct@MOptScheduledMetadata`], under the name it is given.
+/// Each mutation used for an input is credited when the input is added to the corpus or to the solutions.
+#[derive(Debug)]
+pub struct MOptScheduledMutator<MT> {
+    name: Cow<'static, str>,
+    mutations: MT,
+    max_stack_pow: usize,
+    mutation_names: Vec<Cow<'static, str>>,
+    mutation_log: Vec<MutationId>,
+}
+
+impl<MT> MOptScheduledMutator<MT>
+where
+    MT: NamedTuple,
+{
+    /// Creates a new [`MOptScheduledMutator`], with `swarm_num` swarms.
+    ///
+    /// Its [`struct@MOptScheduledMetadata`] is named `name`: give each mutator a name of its own,
+    /// and keep it across restarts to pick up where it left off.
+    pub fn new<S>(
+        state: &mut S,
+        name: &'static str,
+        mutations: MT,
+        max_stack_pow: usize,
+        swarm_num: usize,
+    ) -> Result<Self, Error>
+    where
+        S: HasNamedMetadata + HasRand,
+    {
+        let name = Cow::Borrowed(name);
+        if !state.has_named_metadata::<MOptScheduledMetadata>(&name) {
+            let rand_seed = state.rand_mut().next();
+            let metadata = MOptScheduledMetadata {
+                mopt: MOpt::new(MT::LEN, swarm_num, rand_seed)?,
+                mode: MOptMode::Pilotfuzzing,
+                solutions_before: 0,
+            };
+            state.add_named_metadata(&name, metadata);
+        }
+
+        Ok(Self {
+            name,
+            mutation_names: mutations.names(),
+            mutations,
+            max_stack_pow,
+            mutation_log: vec![],
+        })
+    }
+
+    /// The current probability to choose each mutation, in the order of the [`MutatorsTuple`]
+    pub fn probabilities<'a, S>(&self, state: &'a S) -> Result<&'a [f64], Error>
+    where
+        S: HasNamedMetadata,
+    {
+        Ok(state
+            .named_metadata::<MOptScheduledMetadata>(&self.name)?
+            .mopt
+            .operator_probabilities())
+    }
+}
+
+impl<I, MT, S> Mutator<I, S> for MOptScheduledMutator<MT>
+where
+    MT: MutatorsTuple<I, S>,
+    S: HasRand + HasNamedMetadata + HasSolutions,
+{
+    #[inline]
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        self.scheduled_mutate(state, input)
+    }
+
+    fn post_exec(&mut self, state: &mut S, new_corpus_id: Option<CorpusId>) -> Result<(), Error> {
+        let solutions = state.solutions().count();
+        let metadata = state.named_metadata_mut::<MOptScheduledMetadata>(&self.name)?;
+        let finds = usize::from(new_corpus_id.is_some())
+            + solutions.saturating_sub(metadata.solutions_before);
+        metadata.mopt.finish_mutation(&mut metadata.mode, finds)
+    }
+
+    fn applied_mutations(&self) -> Vec<Cow<'static, str>> {
+        self.mutation_log
+            .iter()
+            .map(|idx| self.mutation_names[idx.0].clone())
+            .collect()
+    }
+}
+
+impl<MT> ComposedByMutations for MOptScheduledMutator<MT> {
+    type Mutations = MT;
+
+    /// Get the mutations
+    #[inline]
+    fn mutations(&self) -> &MT {
+        &self.mutations
+    }
+
+    // Get the mutations (mutable)
+    #[inline]
+    fn mutations_mut(&mut self) -> &mut MT {
+        &mut self.mutations
+    }
+}
+
+impl<MT> Named for MOptScheduledMutator<MT> {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<I, MT, S> ScheduledMutator<I, S> for MOptScheduledMutator<MT>
+where
+    MT: MutatorsTuple<I, S>,
+    S: HasRand + HasNamedMetadata + HasSolutions,
+{
+    /// Compute the number of iterations used to apply stacked mutations
+    fn iterations(&self, state: &mut S, _: &I) -> u64 {
+        1 << (1 + state.rand_mut().zero_upto(self.max_stack_pow))
+    }
+
+    /// Get the next mutation to apply
+    #[inline]
+    fn schedule(&self, state: &mut S, _: &I) -> MutationId {
+        state
+            .named_metadata_mut::<MOptScheduledMetadata>(&self.name)
+            .unwrap()
+            .mopt
+            .select_algorithm()
+            .unwrap()
+    }
+
+    fn scheduled_mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        let solutions = state.solutions().count();
+        let metadata = state.named_metadata_mut::<MOptScheduledMetadata>(&self.name)?;
+        metadata.solutions_before = solutions;
+        let mode = metadata.mode;
+        metadata.mopt.start_mutation(mode);
+
+        let mut r = MutationResult::Skipped;
+        self.mutation_log.clear();
+        for _ in 0..self.iterations(state, input) {
+            let idx = self.schedule(state, input);
+            self.mutation_log.push(idx);
+            let outcome = self.mutations_mut().get_and_mutate(idx, state, input)?;
+            if outcome == MutationResult::Mutated {
+                r = MutationResult::Mutated;
+            }
+
+            state
+                .named_metadata_mut::<MOptScheduledMetadata>(&self.name)?
+                .mopt
+                .count_use(mode, idx);
+        }
+        Ok(r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::borrow::Cow;
+
+    use libafl_bolts::{rands::StdRand, tuples::tuple_list, Named};
+
+    use super::{MOptScheduledMetadata, MOptScheduledMutator};
+    use crate::{
+        corpus::{CorpusId, InMemoryCorpus},
+        feedbacks::ConstFeedback,
+        inputs::{BytesInput, HasMutatorBytes},
+        mutators::{MutationResult, Mutator},
+        state::StdState,
+        Error, HasNamedMetadata,
+    };
+
+    /// Writes a byte, inputs it wrote to are finds
+    struct WriteMutator {
+        name: Cow<'static, str>,
+        value: u8,
+    }
+
+    impl Named for WriteMutator {
+        fn name(&self) -> &Cow<'static, str> {
+            &self.name
+        }
+    }
+
+    impl<S> Mutator<BytesInput, S> for WriteMutator {
+        fn mutate(
+            &mut self,
+            _state: &mut S,
+            input: &mut BytesInput,
+        ) -> Result<MutationResult, Error> {
+            input.bytes_mut()[0] |= self.value;
+            Ok(MutationResult::Mutated)
+        }
+    }
+
+    #[test]
+    fn test_mopt_scheduled_mutator() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(1),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+
+        let writers = |names: [&'static str; 4]| {
+            tuple_list!(
+                WriteMutator {
+                    name: Cow::Borrowed(names[0]),
+                    value: 1,
+                },
+                WriteMutator {
+                    name: Cow::Borrowed(names[1]),
+                    value: 2,
+                },
+                WriteMutator {
+                    name: Cow::Borrowed(names[2]),
+                    value: 4,
+                },
+                WriteMutator {
+                    name: Cow::Borrowed(names[3]),
+                    value: 8,
+                }
+            )
+        };
+        let mut mutator = MOptScheduledMutator::new(
+            &mut state,
+            "havoc",
+            writers(["Finder", "B", "C", "D"]),
+            0,
+            1,
+        )
+        .unwrap();
+        // Another mutator learns on its own, even with the same mutations
+        let other = MOptScheduledMutator::new(
+            &mut state,
+            "other",
+            writers(["Finder", "B", "C", "D"]),
+            0,
+            1,
+        )
+        .unwrap();
+        assert_ne!(mutator.name(), other.name());
+
+        let name = mutator.name().clone();
+        state
+            .named_metadata_mut::<MOptScheduledMetadata>(&name)
+            .unwrap()
+            .mopt
+            .period_pilot = 100;
+
+        let mut finds = 0;
+        for _ in 0..10_000 {
+            let mut input = BytesInput::new(vec![0]);
+            mutator.mutate(&mut state, &mut input).unwrap();
+            let found = input.bytes()[0] & 1 != 0;
+            finds += u64::from(found);
+            mutator
+                .post_exec(&mut state, found.then_some(CorpusId(0)))
+                .unwrap();
+        }
+
+        // Only the finder is credited for all the finds
+        let mopt = &state
+            .named_metadata::<MOptScheduledMetadata>(&name)
+            .unwrap()
+            .mopt;
+        let credited = &mopt.pilot_operator_finds_v2[0];
+        assert_eq!(mopt.total_finds, usize::try_from(finds).unwrap());
+        assert_eq!(credited[0], finds);
+        assert!(credited[1..].iter().all(|c| *c < finds));
+        let probabilities = mutator.probabilities(&state).unwrap();
+        assert!((probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert_eq!(other.probabilities(&state).unwrap().len(), 4);
+        assert_eq!(
+            state
+                .named_metadata::<MOptScheduledMetadata>(other.name())
+                .unwrap()
+                .mopt
+                .total_finds,
+            0
+        );
+
+        // A restarted fuzzer keeps learning from the same state
+        let restarted = MOptScheduledMutator::new(
+            &mut state,
+            "havoc",
+            writers(["Finder", "B", "C", "D"]),
+            0,
+            1,
+        )
+        .unwrap();
+        assert_eq!(
+            restarted.probabilities(&state).unwrap(),
+            mutator.probabilities(&state).unwrap()
+        );
+    }
+}
@@ -104,6 +104,8 @@ pub struct StdScheduledMutator<MT> {
     name: Cow<'static, str>,
     mutations: MT,
     max_stack_pow: usize,
+    mutation_names: Vec<Cow<'static, str>>,
+    mutation_log: Vec<MutationId>,
 }
 
 impl<MT> Named for StdScheduledMutator<MT> {
@@ -121,6 +123,13 @@ where
     fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
         self.scheduled_mutate(state, input)
     }
+
+    fn applied_mutations(&self) -> Vec<Cow<'static, str>> {
+        self.mutation_log
+            .iter()
+            .map(|idx| self.mutation_names[idx.0].clone())
+            .collect()
+    }
 }
 
 impl<MT> ComposedByMutations for StdScheduledMutator<MT> {
@@ -158,6 +167,21 @@ where
             .below(unsafe { NonZero::new(self.mutations.len()).unwrap_unchecked() })
             .into()
     }
+
+    fn scheduled_mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        let mut r = MutationResult::Skipped;
+        let num = self.iterations(state, input);
+        self.mutation_log.clear();
+        for _ in 0..num {
+            let idx = self.schedule(state, input);
+            self.mutation_log.push(idx);
+            let outcome = self.mutations_mut().get_and_mutate(idx, state, input)?;
+            if outcome == MutationResult::Mutated {
+                r = MutationResult::Mutated;
+            }
+        }
+        Ok(r)
+    }
 }
 
 impl<MT> StdScheduledMutator<MT>
@@ -171,8 +195,10 @@ where
                 "StdScheduledMutator[{}]",
                 mutations.names().join(", ")
             )),
+            mutation_names: mutations.names(),
             mutations,
             max_stack_pow: 7,
+            mutation_log: vec![],
         }
     }
 
@@ -187,8 +213,10 @@ where
                 "StdScheduledMutator[{}]",
                 mutations.names().join(", ")
             )),
+            mutation_names: mutations.names(),
             mutations,
             max_stack_pow,
+            mutation_log: vec![],
         }
     }
 }
@@ -238,6 +266,13 @@ where
         self.mutation_log.clear();
         Ok(())
     }
+
+    fn applied_mutations(&self) -> Vec<Cow<'static, str>> {
+        self.mutation_log
+            .iter()
+            .filter_map(|idx| self.scheduled.mutations().name(idx.0).cloned())
+            .collect()
+    }
 }
 
 impl<SM> ComposedByMutations for LoggerScheduledMutator<SM>
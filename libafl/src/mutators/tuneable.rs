@@ -84,6 +84,8 @@ pub struct TuneableScheduledMutator<MT> {
     name: Cow<'static, str>,
     mutations: MT,
     max_stack_pow: usize,
+    mutation_names: Vec<Cow<'static, str>>,
+    mutation_log: Vec<MutationId>,
 }
 
 impl<I, MT, S> Mutator<I, S> for TuneableScheduledMutator<MT>
@@ -95,6 +97,13 @@ where
     fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
         self.scheduled_mutate(state, input)
     }
+
+    fn applied_mutations(&self) -> Vec<Cow<'static, str>> {
+        self.mutation_log
+            .iter()
+            .map(|idx| self.mutation_names[idx.0].clone())
+            .collect()
+    }
 }
 
 impl<MT> ComposedByMutations for TuneableScheduledMutator<MT> {
@@ -200,6 +209,21 @@ where
             .below(NonZero::new(self.mutations.len()).expect("No mutations provided!"))
             .into()
     }
+
+    fn scheduled_mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        let mut r = MutationResult::Skipped;
+        let num = self.iterations(state, input);
+        self.mutation_log.clear();
+        for _ in 0..num {
+            let idx = self.schedule(state, input);
+            self.mutation_log.push(idx);
+            let outcome = self.mutations_mut().get_and_mutate(idx, state, input)?;
+            if outcome == MutationResult::Mutated {
+                r = MutationResult::Mutated;
+            }
+        }
+        Ok(r)
+    }
 }
 
 impl<MT> TuneableScheduledMutator<MT> {
@@ -214,8 +238,10 @@ impl<MT> TuneableScheduledMutator<MT> {
         }
         TuneableScheduledMutator {
             name: Cow::from(format!("TuneableMutator[{}]", mutations.names().join(", "))),
+            mutation_names: mutations.names(),
             mutations,
             max_stack_pow: 7,
+            mutation_log: vec![],
         }
     }
 }
@@ -0,0 +1,107 @@
+//! The [`DistanceObserver`] reads how far an execution stayed from the target sites of directed fuzzing.
+//!
+//! The target is instrumented with the distance of each basic block to the targets, see `LLVMPasses::Distance`
+//! in `libafl_cc`. Each executed block adds its distance to a sum and one to a count, the distance of the
+//! execution is their quotient.
+
+use alloc::borrow::Cow;
+
+use libafl_bolts::{ownedref::OwnedMutSlice, AsSlice, AsSliceMut, Named};
+use serde::{Deserialize, Serialize};
+
+use crate::{observers::Observer, Error};
+
+/// Observes the average distance to the targets of the basic blocks an execution went through.
+///
+/// The counters are two `u64`, the sum of the distances of the executed blocks and the number of executed blocks.
+/// They have to be in the memory of the fuzzer: the ones of `libafl_targets` are only filled for in-process executors.
+#[derive(Serialize, Deserialize, Debug)]
+#[allow(clippy::unsafe_derive_deserialize)]
+pub struct DistanceObserver<'a> {
+    name: Cow<'static, str>,
+    counters: OwnedMutSlice<'a, u64>,
+}
+
+impl<'a> DistanceObserver<'a> {
+    /// Creates a new [`DistanceObserver`] over the sum and count counters.
+    ///
+    /// # Panics
+    /// Panics if `counters` does not hold exactly two elements.
+    #[must_use]
+    pub fn new(name: &'static str, counters: OwnedMutSlice<'a, u64>) -> Self {
+        assert_eq!(
+            counters.as_slice().len(),
+            2,
+            "The distance counters are a sum and a count"
+        );
+        Self {
+            name: Cow::from(name),
+            counters,
+        }
+    }
+
+    /// Creates a new [`DistanceObserver`] over the two counters at `counters_ptr`.
+    ///
+    /// # Safety
+    /// Will dereference `counters_ptr`, the counters may not move in memory.
+    #[must_use]
+    pub unsafe fn from_mut_ptr(name: &'static str, counters_ptr: *mut u64) -> Self {
+        Self::new(name, OwnedMutSlice::from_raw_parts_mut(counters_ptr, 2))
+    }
+
+    /// The sum of the distances of the executed blocks
+    #[must_use]
+    pub fn sum(&self) -> u64 {
+        self.counters.as_slice()[0]
+    }
+
+    /// The number of executed blocks with a distance
+    #[must_use]
+    pub fn count(&self) -> u64 {
+        self.counters.as_slice()[1]
+    }
+
+    /// The average distance of the executed blocks to the targets,
+    /// or `None` if the execution did not go through any block reaching a target.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn distance(&self) -> Option<f64> {
+        let count = self.count();
+        (count > 0).then(|| self.sum() as f64 / count as f64)
+    }
+}
+
+impl<I, S> Observer<I, S> for DistanceObserver<'_> {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.counters.as_slice_mut().fill(0);
+        Ok(())
+    }
+}
+
+impl Named for DistanceObserver<'_> {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::{ownedref::OwnedMutSlice, AsSliceMut};
+
+    use super::DistanceObserver;
+    use crate::observers::Observer;
+
+    #[test]
+    fn test_distance_observer() {
+        let mut counters = [0_u64; 2];
+        let mut observer =
+            DistanceObserver::new("distance", OwnedMutSlice::from(&mut counters[..]));
+        assert_eq!(observer.distance(), None);
+
+        observer.counters.as_slice_mut().copy_from_slice(&[700, 2]);
+        assert_eq!(observer.distance(), Some(350.0));
+
+        Observer::<(), ()>::pre_exec(&mut observer, &mut (), &()).unwrap();
+        assert_eq!((observer.sum(), observer.count()), (0, 0));
+    }
+}
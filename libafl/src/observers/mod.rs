@@ -9,6 +9,11 @@ pub mod stdio;
 #[cfg(feature = "std")]
 pub use stdio::{StdErrObserver, StdOutObserver};
 
+#[cfg(feature = "std")]
+pub mod network;
+#[cfg(feature = "std")]
+pub use network::{ProtocolStateGraphMetadata, ProtocolStateObserver, ResponseObserver};
+
 #[cfg(feature = "regex")]
 pub mod stacktrace;
 #[cfg(feature = "regex")]
@@ -21,6 +26,8 @@ pub mod profiling;
 pub use profiling::*;
 
 pub mod concolic;
+pub mod distance;
+pub use distance::DistanceObserver;
 pub mod map;
 pub use map::*;
 
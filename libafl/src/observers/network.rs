@@ -0,0 +1,366 @@
+//! Observers for the responses of network targets
+//!
+//! The [`ResponseObserver`] looks at the messages a server sent back during an execution.
+//! The [`ProtocolStateObserver`] extracts a protocol state from each response, and records the
+//! state graph of the server in the [`ProtocolStateGraphMetadata`].
+//! The executor must explicitly support these observers.
+#![cfg_attr(
+    all(feature = "std", unix, feature = "multipart_inputs"),
+    doc = r"For example, they are supported on the [`crate::executors::NetworkExecutor`]."
+)]
+
+use alloc::{
+    borrow::Cow,
+    string::{String, ToString},
+    sync::Arc,
+};
+use core::fmt::{self, Debug, Formatter, Write};
+use std::vec::Vec;
+
+use hashbrown::HashMap;
+use libafl_bolts::{hash_std, Named};
+#[cfg(feature = "regex")]
+use regex::bytes::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{executors::ExitKind, observers::Observer, Error, HasMetadata};
+
+/// An observer that captures the responses of a network target, one entry per message sent.
+/// Only works for supported executors.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ResponseObserver {
+    /// The name of the observer.
+    pub name: Cow<'static, str>,
+    /// The response to each message of the last execution.
+    /// The response is empty if the target did not answer a message within the timeout.
+    pub responses: Vec<Vec<u8>>,
+}
+
+impl ResponseObserver {
+    /// Create a new [`ResponseObserver`] with the given name.
+    #[must_use]
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name: Cow::from(name),
+            responses: Vec::new(),
+        }
+    }
+
+    /// React to the response to the next message
+    pub fn observe_response(&mut self, response: &[u8]) {
+        self.responses.push(response.into());
+    }
+
+    /// The concatenated responses of the last execution
+    #[must_use]
+    pub fn concatenated(&self) -> Vec<u8> {
+        self.responses.concat()
+    }
+}
+
+impl Named for ResponseObserver {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<I, S> Observer<I, S> for ResponseObserver {
+    fn pre_exec_child(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.responses.clear();
+        Ok(())
+    }
+
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.responses.clear();
+        Ok(())
+    }
+}
+
+/// Extracts the protocol state from a response, or `None` if the response carries no state
+pub type StateExtractorFn = Arc<dyn Fn(&[u8]) -> Option<u64> + Send + Sync>;
+
+/// An observer extracting a protocol state identifier from each response of a network target,
+/// in the spirit of `AFLNet`.
+///
+/// The states reached during each execution are recorded in the [`ProtocolStateGraphMetadata`] of the state.
+/// Use it with the [`crate::feedbacks::ProtocolStateFeedback`] to keep inputs reaching new states,
+/// and with the [`crate::schedulers::ProtocolStateScheduler`] to focus on rarely visited states.
+/// Only works for supported executors.
+///
+/// Note: the extractor closure is not serialized. A deserialized observer only extracts states
+/// with a response code regex.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ProtocolStateObserver {
+    name: Cow<'static, str>,
+    #[serde(skip)]
+    extractor: Option<StateExtractorFn>,
+    /// The pattern of the response code regex, if any
+    pattern: Option<String>,
+    #[cfg(feature = "regex")]
+    #[serde(skip)]
+    regex: Option<Regex>,
+    /// The states reached during the last execution, in order
+    states: Vec<u64>,
+    /// The number of states and transitions discovered during the last execution
+    discovered: usize,
+}
+
+impl Debug for ProtocolStateObserver {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProtocolStateObserver")
+            .field("name", &self.name)
+            .field("pattern", &self.pattern)
+            .field("states", &self.states)
+            .field("discovered", &self.discovered)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ProtocolStateObserver {
+    /// Creates a new [`ProtocolStateObserver`], extracting the state from each response with the given closure
+    #[must_use]
+    pub fn new<F>(name: &'static str, extractor: F) -> Self
+    where
+        F: Fn(&[u8]) -> Option<u64> + Send + Sync + 'static,
+    {
+        Self {
+            name: Cow::from(name),
+            extractor: Some(Arc::new(extractor)),
+            pattern: None,
+            #[cfg(feature = "regex")]
+            regex: None,
+            states: Vec::new(),
+            discovered: 0,
+        }
+    }
+
+    /// Creates a new [`ProtocolStateObserver`], using the first match of `pattern` in each response as its state,
+    /// like the response codes of text protocols (e.g. `^\d{3}` for SMTP or FTP).
+    ///
+    /// If the pattern has a capture group, its first group is used instead of the whole match.
+    /// Numeric matches are used as state as-is, others are hashed.
+    #[cfg(feature = "regex")]
+    pub fn with_response_code_regex(name: &'static str, pattern: &str) -> Result<Self, Error> {
+        let regex = Regex::new(pattern)
+            .map_err(|err| Error::illegal_argument(format!("Invalid regex {pattern}: {err}")))?;
+        Ok(Self {
+            name: Cow::from(name),
+            extractor: None,
+            pattern: Some(pattern.to_string()),
+            regex: Some(regex),
+            states: Vec::new(),
+            discovered: 0,
+        })
+    }
+
+    /// Extracts the state from the response to the next message
+    pub fn observe_response(&mut self, response: &[u8]) {
+        if let Some(state) = self.extract(response) {
+            self.states.push(state);
+        }
+    }
+
+    #[cfg(feature = "regex")]
+    fn extract(&mut self, response: &[u8]) -> Option<u64> {
+        if let Some(extractor) = &self.extractor {
+            return extractor(response);
+        }
+        if self.regex.is_none() {
+            // The compiled regex is not serialized
+            self.regex = Regex::new(self.pattern.as_ref()?).ok();
+        }
+        let captures = self.regex.as_ref()?.captures(response)?;
+        let code = captures.get(1).or_else(|| captures.get(0))?.as_bytes();
+        Some(
+            core::str::from_utf8(code)
+                .ok()
+                .and_then(|code| code.parse().ok())
+                .unwrap_or_else(|| hash_std(code)),
+        )
+    }
+
+    #[cfg(not(feature = "regex"))]
+    fn extract(&mut self, response: &[u8]) -> Option<u64> {
+        self.extractor.as_ref()?(response)
+    }
+
+    /// The states reached during the last execution, in order
+    #[must_use]
+    pub fn states(&self) -> &[u64] {
+        &self.states
+    }
+
+    /// The number of states and transitions discovered during the last execution
+    #[must_use]
+    pub fn discovered(&self) -> usize {
+        self.discovered
+    }
+}
+
+impl Named for ProtocolStateObserver {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<I, S> Observer<I, S> for ProtocolStateObserver
+where
+    S: HasMetadata,
+{
+    fn pre_exec_child(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.states.clear();
+        self.discovered = 0;
+        Ok(())
+    }
+
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.states.clear();
+        self.discovered = 0;
+        Ok(())
+    }
+
+    fn post_exec_child(
+        &mut self,
+        state: &mut S,
+        _input: &I,
+        _exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        self.discovered = state
+            .metadata_or_insert_with(ProtocolStateGraphMetadata::new)
+            .record(&self.states);
+        Ok(())
+    }
+}
+
+/// The state graph of a network target, as seen by the [`ProtocolStateObserver`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+pub struct ProtocolStateGraphMetadata {
+    /// The number of executions reaching each state
+    visits: HashMap<u64, u64>,
+    /// The number of times each transition was taken, by source and target state
+    transitions: HashMap<u64, HashMap<u64, u64>>,
+}
+
+libafl_bolts::impl_serdeany!(ProtocolStateGraphMetadata);
+
+impl ProtocolStateGraphMetadata {
+    /// Creates a new, empty [`ProtocolStateGraphMetadata`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the states reached by an execution, in order.
+    /// Returns the number of states and transitions seen for the first time.
+    pub fn record(&mut self, states: &[u64]) -> usize {
+        let mut discovered = 0;
+        let mut reached = states.to_vec();
+        reached.sort_unstable();
+        reached.dedup();
+        for state in reached {
+            let visits = self.visits.entry(state).or_insert(0);
+            if *visits == 0 {
+                discovered += 1;
+            }
+            *visits += 1;
+        }
+        for pair in states.windows(2) {
+            let count = self
+                .transitions
+                .entry(pair[0])
+                .or_default()
+                .entry(pair[1])
+                .or_insert(0);
+            if *count == 0 {
+                discovered += 1;
+            }
+            *count += 1;
+        }
+        discovered
+    }
+
+    /// The number of executions reaching each state
+    #[must_use]
+    pub fn visits(&self) -> &HashMap<u64, u64> {
+        &self.visits
+    }
+
+    /// The number of executions reaching the given state
+    #[must_use]
+    pub fn state_visits(&self, state: u64) -> u64 {
+        self.visits.get(&state).copied().unwrap_or(0)
+    }
+
+    /// The number of times each transition was taken, by source and target state
+    #[must_use]
+    pub fn transitions(&self) -> &HashMap<u64, HashMap<u64, u64>> {
+        &self.transitions
+    }
+
+    /// Renders the state graph in the DOT format, with the visit counts as labels
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        let mut states: Vec<_> = self.visits.iter().collect();
+        states.sort_unstable();
+        let mut transitions: Vec<_> = self
+            .transitions
+            .iter()
+            .flat_map(|(from, targets)| targets.iter().map(move |(to, count)| (from, to, count)))
+            .collect();
+        transitions.sort_unstable();
+
+        let mut dot = String::from("digraph protocol_states {\n");
+        // Writing to a `String` can't fail
+        for (state, visits) in states {
+            let _ = writeln!(dot, "  {state} [label=\"{state} ({visits})\"];");
+        }
+        for (from, to, count) in transitions {
+            let _ = writeln!(dot, "  {from} -> {to} [label=\"{count}\"];");
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ProtocolStateGraphMetadata, ProtocolStateObserver};
+
+    #[test]
+    fn test_protocol_state_graph() {
+        let mut observer = ProtocolStateObserver::new("states", |response| {
+            response.first().map(|b| u64::from(*b))
+        });
+        observer.observe_response(b"\x01hello");
+        observer.observe_response(b"");
+        observer.observe_response(b"\x02bye");
+
+        let mut graph = ProtocolStateGraphMetadata::new();
+        // two states, one transition
+        assert_eq!(graph.record(observer.states()), 3);
+        assert_eq!(graph.record(&[1, 2]), 0);
+        assert_eq!(graph.record(&[1, 3, 2]), 3);
+        assert_eq!(graph.state_visits(1), 3);
+        assert_eq!(
+            graph.to_dot(),
+            "digraph protocol_states {\n  1 [label=\"1 (3)\"];\n  2 [label=\"2 (3)\"];\n  \
+             3 [label=\"3 (1)\"];\n  1 -> 2 [label=\"2\"];\n  1 -> 3 [label=\"1\"];\n  \
+             3 -> 2 [label=\"1\"];\n}\n"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn test_response_code_regex() {
+        let mut observer =
+            ProtocolStateObserver::with_response_code_regex("smtp", r"^(\d{3})[ -]").unwrap();
+        observer.observe_response(b"220 mail.example.com ESMTP\r\n");
+        observer.observe_response(b"garbage");
+        observer.observe_response(b"250-mail.example.com\r\n");
+        assert_eq!(observer.states(), &[220, 250]);
+    }
+}
@@ -1,11 +1,14 @@
 //! the ``StacktraceObserver`` looks up the stacktrace on the execution thread and computes a hash for it for dedupe
 
-use alloc::{borrow::Cow, string::String, vec::Vec};
+use alloc::{
+    borrow::Cow,
+    string::{String, ToString},
+    vec::Vec,
+};
 #[cfg(feature = "casr")]
 use std::{
     collections::hash_map::DefaultHasher,
     hash::{Hash, Hasher},
-    string::ToString,
 };
 use std::{
     fmt::Debug,
@@ -34,7 +37,6 @@ use libcasr::{
         STACK_FRAME_FUNCTION_IGNORE_REGEXES,
     },
 };
-#[cfg(not(feature = "casr"))]
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
@@ -99,6 +101,112 @@ pub fn collect_backtrace() -> u64 {
     s.finish()
 }
 
+/// Symbol prefixes of frames belonging to the fuzzer runtime, the signal trampoline, or the panic/abort machinery.
+/// These are skipped at the top of in-process backtraces by [`symbolize_backtrace_frames`].
+const RUNTIME_FRAME_PREFIXES: [&str; 16] = [
+    "backtrace::",
+    "libafl::",
+    "libafl_bolts::",
+    "libafl_targets::",
+    "std::",
+    "core::",
+    "alloc::",
+    "__rust",
+    "rust_panic",
+    "__restore_rt",
+    "_sigtramp",
+    "__GI_",
+    "raise",
+    "abort",
+    "__pthread_kill",
+    "pthread_kill",
+];
+
+/// The maximum number of frames captured by [`capture_backtrace_ips`]
+pub const MAX_BACKTRACE_FRAMES: usize = 128;
+
+/// Captures the instruction pointers of the current backtrace into `ips`, innermost first, without symbolizing them.
+///
+/// Meant to be called from a crash handler: nothing is allocated as long as `ips` has enough capacity
+/// (at most [`MAX_BACKTRACE_FRAMES`] frames are captured), and no lock is taken.
+/// Use [`symbolize_backtrace_frames`] to symbolize them later.
+pub fn capture_backtrace_ips(ips: &mut Vec<u64>) {
+    ips.clear();
+    let max = ips.capacity().min(MAX_BACKTRACE_FRAMES);
+    // # Safety
+    // We do not race with another unsynchronized trace: this runs on the crashing thread,
+    // and the other threads of the fuzzer do not collect backtraces.
+    unsafe {
+        backtrace::trace_unsynchronized(|frame| {
+            ips.push(frame.ip() as u64);
+            ips.len() < max
+        });
+    }
+}
+
+/// Symbolizes instruction pointers captured by [`capture_backtrace_ips`], innermost first.
+///
+/// Frames of the fuzzer runtime, such as the crash handler and the signal trampoline,
+/// are skipped at the top of the stack, so that the first frame is the one of the crashing target code.
+/// Frames without symbols are represented by their instruction pointer.
+#[must_use]
+pub fn symbolize_backtrace_frames(ips: &[u64]) -> Vec<String> {
+    let mut frames: Vec<String> = ips
+        .iter()
+        .map(|&ip| {
+            let mut name = None;
+            backtrace::resolve(ip as usize as *mut core::ffi::c_void, |symbol| {
+                if name.is_none() {
+                    name = symbol.name().map(|name| format!("{name:#}"));
+                }
+            });
+            name.unwrap_or_else(|| format!("{ip:#x}"))
+        })
+        .collect();
+
+    let is_runtime_frame = |frame: &String| {
+        let frame = frame.trim_start_matches('<');
+        RUNTIME_FRAME_PREFIXES
+            .iter()
+            .any(|prefix| frame.starts_with(prefix))
+    };
+    // The crash handler runs on top of the signal trampoline, the crashing code is right below it.
+    let trampoline = frames
+        .iter()
+        .position(|frame| frame.contains("__restore_rt") || frame.contains("_sigtramp"))
+        .map_or(0, |pos| pos + 1);
+    let skip = trampoline
+        + frames[trampoline..]
+            .iter()
+            .take_while(|frame| is_runtime_frame(frame))
+            .count();
+    if skip >= frames.len() {
+        return frames;
+    }
+    frames.split_off(skip)
+}
+
+/// Details about a crash, beyond the hash of its stack, as used for crash bucketing.
+/// Which of the fields are available depends on the observer and the harness type.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct CrashSignature {
+    /// The hash of the whole stack, as reported by [`ObserverWithHashField::hash`]
+    pub stack_hash: Option<u64>,
+    /// The frames of the crashing stack, innermost first.
+    /// Function names where available, `module+offset` or the address otherwise.
+    pub frames: Vec<String>,
+    /// The type of the report, such as `heap-buffer-overflow` or `SEGV` for `ASan`
+    pub report_type: Option<String>,
+    /// The program counter of the faulting instruction
+    pub faulting_pc: Option<u64>,
+}
+
+/// A trait for [`Observer`]`s` that can describe the last crash in more detail than a hash
+pub trait ObserverWithCrashSignature: ObserverWithHashField {
+    /// The signature of the last crash, if the last run crashed
+    fn crash_signature(&self) -> Option<CrashSignature>;
+}
+
 /// An enum encoding the types of harnesses
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum HarnessType {
@@ -117,6 +225,10 @@ pub struct BacktraceObserver<'a> {
     observer_name: Cow<'static, str>,
     hash: OwnedRefMut<'a, Option<u64>>,
     harness_type: HarnessType,
+    /// The raw instruction pointers of the last crash, only collected for [`HarnessType::InProcess`].
+    /// They are only symbolized when the [`CrashSignature`] is requested, outside of the crash handler.
+    #[serde(default)]
+    ips: Vec<u64>,
 }
 
 impl<'a> BacktraceObserver<'a> {
@@ -135,6 +247,7 @@ impl<'a> BacktraceObserver<'a> {
             observer_name: observer_name.into(),
             hash: backtrace_hash,
             harness_type,
+            ips: Vec::with_capacity(MAX_BACKTRACE_FRAMES),
         }
     }
 
@@ -154,6 +267,7 @@ impl<'a> BacktraceObserver<'a> {
             observer_name: observer_name.into(),
             hash: backtrace_hash,
             harness_type,
+            ips: Vec::with_capacity(MAX_BACKTRACE_FRAMES),
         }
     }
 
@@ -174,6 +288,7 @@ impl<'a> BacktraceObserver<'a> {
     /// Clears the current hash value (sets it to `None`)
     fn clear_hash(&mut self) {
         *self.hash.as_mut() = None;
+        self.ips.clear();
     }
 
     /// Fill the hash value if the harness type is external
@@ -196,10 +311,27 @@ impl ObserverWithHashField for BacktraceObserver<'_> {
     }
 }
 
+impl ObserverWithCrashSignature for BacktraceObserver<'_> {
+    fn crash_signature(&self) -> Option<CrashSignature> {
+        Some(CrashSignature {
+            stack_hash: Some(self.hash()?),
+            frames: symbolize_backtrace_frames(&self.ips),
+            ..CrashSignature::default()
+        })
+    }
+}
+
 impl<I, S> Observer<I, S> for BacktraceObserver<'_> {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        // Make sure capturing the frames in the crash handler does not need to allocate (e.g., after deserialization)
+        self.ips.reserve(MAX_BACKTRACE_FRAMES);
+        Ok(())
+    }
+
     fn post_exec(&mut self, _state: &mut S, _input: &I, exit_kind: &ExitKind) -> Result<(), Error> {
         if self.harness_type == HarnessType::InProcess {
             if *exit_kind == ExitKind::Crash {
+                capture_backtrace_ips(&mut self.ips);
                 self.update_hash(collect_backtrace());
             } else {
                 self.clear_hash();
@@ -257,6 +389,9 @@ pub fn get_asan_runtime_flags() -> String {
 pub struct AsanBacktraceObserver {
     observer_name: Cow<'static, str>,
     hash: Option<u64>,
+    /// The details of the last crash, parsed from the `ASan` report
+    #[serde(default)]
+    signature: Option<CrashSignature>,
 }
 
 impl AsanBacktraceObserver {
@@ -270,6 +405,7 @@ impl AsanBacktraceObserver {
         Self {
             observer_name: observer_name.into(),
             hash: None,
+            signature: None,
         }
     }
 
@@ -284,6 +420,7 @@ impl AsanBacktraceObserver {
         Self {
             observer_name: observer_name.into(),
             hash: None,
+            signature: None,
         }
     }
 
@@ -321,6 +458,7 @@ impl AsanBacktraceObserver {
             hash ^= u64::from_str_radix(g.as_str(), 16).unwrap();
         });
         self.update_hash(hash);
+        self.signature = Some(parse_asan_signature(output));
     }
 
     #[cfg(feature = "casr")]
@@ -336,6 +474,7 @@ impl AsanBacktraceObserver {
             }
         }
         self.update_hash(hash);
+        self.signature = Some(parse_asan_signature(output));
     }
 
     /// Updates the hash value of this observer.
@@ -358,7 +497,56 @@ impl Default for AsanBacktraceObserver {
     }
 }
 
-impl<I, S> Observer<I, S> for AsanBacktraceObserver {}
+impl ObserverWithCrashSignature for AsanBacktraceObserver {
+    fn crash_signature(&self) -> Option<CrashSignature> {
+        let mut signature = self.signature.clone()?;
+        signature.stack_hash = self.hash;
+        Some(signature)
+    }
+}
+
+impl<I, S> Observer<I, S> for AsanBacktraceObserver {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        // The report is only parsed for crashing runs, make sure we don't report a stale one
+        self.signature = None;
+        Ok(())
+    }
+}
+
+/// Parses the report type, the faulting pc, and the frames of the crashing stack from a sanitizer report
+fn parse_asan_signature(output: &str) -> CrashSignature {
+    let mut signature = CrashSignature::default();
+
+    let error_matcher =
+        Regex::new(r"ERROR: \w+Sanitizer: ([\w-]+)(?:.*?\bpc 0x([0-9a-f]+))?").unwrap();
+    if let Some(m) = error_matcher.captures(output) {
+        signature.report_type = m.get(1).map(|g| g.as_str().to_string());
+        signature.faulting_pc = m
+            .get(2)
+            .and_then(|g| u64::from_str_radix(g.as_str(), 16).ok());
+    }
+
+    let frame_matcher = Regex::new(
+        r"^\s*#(\d+)\s+0x([0-9a-f]+)(?:\s+in\s+(\S+))?.*?(?:\(([^()\s]+\+0x[0-9a-f]+)\))?\s*$",
+    )
+    .unwrap();
+    for line in output.lines() {
+        let Some(m) = frame_matcher.captures(line) else {
+            continue;
+        };
+        // Only take the crashing stack, not the allocation or free stacks following it
+        if m[1] == *"0" && !signature.frames.is_empty() {
+            break;
+        }
+        let frame = m
+            .get(3)
+            .or_else(|| m.get(4))
+            .map_or_else(|| format!("0x{}", &m[2]), |g| g.as_str().to_string());
+        signature.frames.push(frame);
+    }
+
+    signature
+}
 
 impl Named for AsanBacktraceObserver {
     fn name(&self) -> &Cow<'static, str> {
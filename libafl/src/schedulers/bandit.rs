@@ -0,0 +1,625 @@
+//! A corpus scheduler treating the corpus entries as arms of a multi-armed bandit.
+//!
+//! Each execution of a mutant of an entry is a trial of its arm, which is rewarded if it adds a new entry
+//! to the corpus. The rewards are recorded online, in [`on_evaluation`](Scheduler::on_evaluation) and
+//! [`on_add`](Scheduler::on_add), after every iteration of the mutational stages.
+//! Only executions made by the stages count: entries imported from other nodes between two picks,
+//! or added without being executed first, are no find of the current entry. The next entry is then
+//! picked with one of the [`BanditPolicy`]s, trading off entries that paid off so far against entries
+//! that were rarely tried.
+
+use alloc::vec::Vec;
+use core::{marker::PhantomData, mem};
+
+use libafl_bolts::{rands::Rand, tuples::MatchName};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::{Corpus, CorpusId, HasTestcase},
+    schedulers::{RemovableScheduler, Scheduler},
+    stages::HasCurrentStageId,
+    state::{HasCorpus, HasRand},
+    Error, HasMetadata,
+};
+
+/// The default exploration factor of [`BanditPolicy::Ucb1`]
+pub const DEFAULT_UCB1_EXPLORATION: f64 = 1.0;
+/// The default exploration rate of [`BanditPolicy::Exp3`]
+pub const DEFAULT_EXP3_GAMMA: f64 = 0.1;
+
+/// How the [`BanditScheduler`] picks the next entry
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BanditPolicy {
+    /// Picks the entry with the highest upper confidence bound of its reward,
+    /// `mean + exploration * sqrt(2 ln(executions) / executions of the entry)`.
+    /// Entries never executed are picked first.
+    Ucb1 {
+        /// The weight of the confidence interval, higher values explore more
+        exploration: f64,
+    },
+    /// Picks the entry with the highest reward sampled from the Beta posterior of its reward
+    Thompson,
+    /// Picks entries at random, with exponential weights learned from the reward of each pick.
+    /// Unlike the others, it makes no assumption on how rewards are distributed.
+    Exp3 {
+        /// The share of uniformly random picks, in `(0, 1]`
+        gamma: f64,
+    },
+}
+
+impl Default for BanditPolicy {
+    fn default() -> Self {
+        Self::Ucb1 {
+            exploration: DEFAULT_UCB1_EXPLORATION,
+        }
+    }
+}
+
+/// The rewards of a corpus entry, for the [`BanditScheduler`]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct BanditTestcaseMetadata {
+    /// How often the entry was picked
+    pulls: u64,
+    /// The number of executions of mutants of the entry
+    executions: u64,
+    /// The number of new corpus entries found from mutants of the entry
+    finds: u64,
+    /// The log of the [`BanditPolicy::Exp3`] weight
+    log_weight: f64,
+}
+
+libafl_bolts::impl_serdeany!(BanditTestcaseMetadata);
+
+impl BanditTestcaseMetadata {
+    /// Creates a new [`struct@BanditTestcaseMetadata`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How often the entry was picked
+    #[must_use]
+    pub fn pulls(&self) -> u64 {
+        self.pulls
+    }
+
+    /// The number of executions of mutants of the entry
+    #[must_use]
+    pub fn executions(&self) -> u64 {
+        self.executions
+    }
+
+    /// The number of new corpus entries found from mutants of the entry
+    #[must_use]
+    pub fn finds(&self) -> u64 {
+        self.finds
+    }
+
+    /// The mean reward, new corpus entries per execution
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn mean_reward(&self) -> f64 {
+        if self.executions == 0 {
+            0.0
+        } else {
+            (self.finds as f64 / self.executions as f64).min(1.0)
+        }
+    }
+}
+
+/// The state of the [`BanditScheduler`]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct BanditMetadata {
+    /// The number of executions of all entries
+    executions: u64,
+    /// The executions since the current entry was picked
+    pull_executions: u64,
+    /// The new entries found since the current entry was picked
+    pull_finds: u64,
+    /// The probability the current entry was picked with, for [`BanditPolicy::Exp3`]
+    pull_probability: f64,
+    /// Whether the last execution was a trial of the current entry, so that the next new entry is its find
+    #[serde(default)]
+    trial_pending: bool,
+}
+
+libafl_bolts::impl_serdeany!(BanditMetadata);
+
+impl BanditMetadata {
+    /// Creates a new [`struct@BanditMetadata`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of executions of all entries
+    #[must_use]
+    pub fn executions(&self) -> u64 {
+        self.executions
+    }
+}
+
+/// A corpus scheduler learning which entries pay off, with a [`BanditPolicy`]
+#[derive(Debug, Clone)]
+pub struct BanditScheduler<S> {
+    policy: BanditPolicy,
+    phantom: PhantomData<S>,
+}
+
+impl<S> BanditScheduler<S>
+where
+    S: HasMetadata,
+{
+    /// Creates a new [`BanditScheduler`] with the given [`BanditPolicy`]
+    #[must_use]
+    pub fn new(state: &mut S, policy: BanditPolicy) -> Self {
+        let _ = state.metadata_or_insert_with(BanditMetadata::new);
+        Self {
+            policy,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Creates a new [`BanditScheduler`] with the [`BanditPolicy::Ucb1`] policy
+    #[must_use]
+    pub fn ucb1(state: &mut S) -> Self {
+        Self::new(state, BanditPolicy::default())
+    }
+
+    /// Creates a new [`BanditScheduler`] with the [`BanditPolicy::Thompson`] policy
+    #[must_use]
+    pub fn thompson(state: &mut S) -> Self {
+        Self::new(state, BanditPolicy::Thompson)
+    }
+
+    /// Creates a new [`BanditScheduler`] with the [`BanditPolicy::Exp3`] policy
+    #[must_use]
+    pub fn exp3(state: &mut S) -> Self {
+        Self::new(
+            state,
+            BanditPolicy::Exp3 {
+                gamma: DEFAULT_EXP3_GAMMA,
+            },
+        )
+    }
+
+    /// The policy of this scheduler
+    #[must_use]
+    pub fn policy(&self) -> BanditPolicy {
+        self.policy
+    }
+}
+
+impl<S> BanditScheduler<S>
+where
+    S: HasCorpus + HasMetadata + HasRand + HasTestcase,
+{
+    /// Rewards the entry picked last with what was found since, for [`BanditPolicy::Exp3`]
+    #[allow(clippy::cast_precision_loss)]
+    fn close_pull(&self, state: &mut S) -> Result<(), Error> {
+        let meta = state.metadata_mut::<BanditMetadata>()?;
+        let (executions, finds, probability) =
+            (meta.pull_executions, meta.pull_finds, meta.pull_probability);
+        meta.pull_executions = 0;
+        meta.pull_finds = 0;
+
+        let BanditPolicy::Exp3 { gamma } = self.policy else {
+            return Ok(());
+        };
+        let Some(id) = *state.corpus().current() else {
+            return Ok(());
+        };
+        if executions == 0 || probability <= 0.0 {
+            return Ok(());
+        }
+        let reward = (finds as f64 / executions as f64).min(1.0);
+        let arms = state.corpus().count().max(1) as f64;
+        if let Ok(mut testcase) = state.testcase_mut(id) {
+            if let Ok(meta) = testcase.metadata_mut::<BanditTestcaseMetadata>() {
+                // importance weighted estimate of the reward, in log space so weights never overflow
+                meta.log_weight += gamma * (reward / probability) / arms;
+            }
+        }
+        Ok(())
+    }
+
+    /// The [`BanditPolicy::Exp3`] probabilities, from the log weights
+    #[allow(clippy::cast_precision_loss)]
+    fn exp3_probabilities(gamma: f64, log_weights: &[f64]) -> Vec<f64> {
+        let max = log_weights
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+        let weights: Vec<f64> = log_weights
+            .iter()
+            .map(|log_weight| libm::exp(log_weight - max))
+            .collect();
+        let sum: f64 = weights.iter().sum();
+        let arms = weights.len() as f64;
+        weights
+            .iter()
+            .map(|weight| (1.0 - gamma) * weight / sum + gamma / arms)
+            .collect()
+    }
+}
+
+/// Samples `Gamma(shape, 1)` for `shape >= 1`, with the method of Marsaglia and Tsang
+#[allow(clippy::many_single_char_names)] // the names of the paper
+fn sample_gamma<R>(rand: &mut R, shape: f64) -> f64
+where
+    R: Rand,
+{
+    let d = shape - 1.0 / 3.0;
+    let c = 1.0 / libm::sqrt(9.0 * d);
+    loop {
+        // a standard normal sample, with the Box-Muller transform
+        let u1 = 1.0 - rand.next_float();
+        let u2 = rand.next_float();
+        let x = libm::sqrt(-2.0 * libm::log(u1)) * libm::cos(2.0 * core::f64::consts::PI * u2);
+
+        let v = 1.0 + c * x;
+        if v <= 0.0 {
+            continue;
+        }
+        let v = v * v * v;
+        let u = 1.0 - rand.next_float();
+        if libm::log(u) < 0.5 * x * x + d - d * v + d * libm::log(v) {
+            return d * v;
+        }
+    }
+}
+
+/// Samples `Beta(alpha, beta)` for `alpha, beta >= 1`
+fn sample_beta<R>(rand: &mut R, alpha: f64, beta: f64) -> f64
+where
+    R: Rand,
+{
+    let x = sample_gamma(rand, alpha);
+    let y = sample_gamma(rand, beta);
+    x / (x + y)
+}
+
+impl<S> RemovableScheduler<<S::Corpus as Corpus>::Input, S> for BanditScheduler<S> where S: HasCorpus
+{}
+
+impl<S> Scheduler<<S::Corpus as Corpus>::Input, S> for BanditScheduler<S>
+where
+    S: HasCorpus + HasCurrentStageId + HasMetadata + HasRand + HasTestcase,
+{
+    /// Rewards the entry being fuzzed for the new entry, if a stage found it by executing a mutant of the entry
+    fn on_add(&mut self, state: &mut S, id: CorpusId) -> Result<(), Error> {
+        let in_stage = state.current_stage_id()?.is_some();
+        let meta = state.metadata_mut::<BanditMetadata>()?;
+        let trial = mem::take(&mut meta.trial_pending);
+        let current_id = if trial && in_stage {
+            *state.corpus().current()
+        } else {
+            None
+        };
+
+        // new arms start with the best weight so far, or EXP3 would hardly ever try them
+        let mut log_weight = 0.0_f64;
+        if matches!(self.policy, BanditPolicy::Exp3 { .. }) {
+            log_weight = f64::NEG_INFINITY;
+            for other in state.corpus().ids() {
+                if let Ok(meta) = state
+                    .corpus()
+                    .get(other)?
+                    .borrow()
+                    .metadata::<BanditTestcaseMetadata>()
+                {
+                    log_weight = log_weight.max(meta.log_weight);
+                }
+            }
+            if log_weight.is_infinite() {
+                log_weight = 0.0;
+            }
+        }
+        {
+            let mut testcase = state.testcase_mut(id)?;
+            testcase.set_parent_id_optional(current_id);
+            testcase.add_metadata(BanditTestcaseMetadata {
+                log_weight,
+                ..BanditTestcaseMetadata::new()
+            });
+        }
+
+        if let Some(parent) = current_id {
+            state.metadata_mut::<BanditMetadata>()?.pull_finds += 1;
+            if let Ok(meta) = state
+                .testcase_mut(parent)?
+                .metadata_mut::<BanditTestcaseMetadata>()
+            {
+                meta.finds += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Counts a trial of the entry being fuzzed, if a stage executed it
+    fn on_evaluation<OT>(
+        &mut self,
+        state: &mut S,
+        _input: &<S::Corpus as Corpus>::Input,
+        _observers: &OT,
+    ) -> Result<(), Error>
+    where
+        OT: MatchName,
+    {
+        let Some(id) = *state.corpus().current() else {
+            return Ok(());
+        };
+        let in_stage = state.current_stage_id()?.is_some();
+        let meta = state.metadata_mut::<BanditMetadata>()?;
+        meta.trial_pending = in_stage;
+        if !in_stage {
+            return Ok(());
+        }
+        meta.executions += 1;
+        meta.pull_executions += 1;
+        if let Ok(meta) = state
+            .testcase_mut(id)?
+            .metadata_mut::<BanditTestcaseMetadata>()
+        {
+            meta.executions += 1;
+        }
+        Ok(())
+    }
+
+    /// Picks the next entry with the [`BanditPolicy`]
+    #[allow(clippy::cast_precision_loss)]
+    fn next(&mut self, state: &mut S) -> Result<CorpusId, Error> {
+        let corpus_counts = state.corpus().count();
+        if corpus_counts == 0 {
+            return Err(Error::empty(
+                "No entries in corpus. This often implies the target is not properly instrumented.",
+            ));
+        }
+        self.close_pull(state)?;
+
+        let mut arms = Vec::with_capacity(corpus_counts);
+        for id in state.corpus().ids() {
+            let meta = state
+                .corpus()
+                .get(id)?
+                .borrow()
+                .metadata::<BanditTestcaseMetadata>()
+                .cloned()
+                .unwrap_or_default();
+            arms.push((id, meta));
+        }
+
+        let mut probability = 1.0;
+        let id = match self.policy {
+            BanditPolicy::Ucb1 { exploration } => {
+                let total = state.metadata::<BanditMetadata>()?.executions.max(1) as f64;
+                let mut best = (arms[0].0, f64::NEG_INFINITY);
+                for (id, meta) in &arms {
+                    let score = if meta.executions == 0 {
+                        f64::INFINITY
+                    } else {
+                        meta.mean_reward()
+                            + exploration
+                                * libm::sqrt(2.0 * libm::log(total) / meta.executions as f64)
+                    };
+                    if score > best.1 {
+                        best = (*id, score);
+                    }
+                }
+                best.0
+            }
+            BanditPolicy::Thompson => {
+                let mut best = (arms[0].0, f64::NEG_INFINITY);
+                for (id, meta) in &arms {
+                    let finds = meta.finds.min(meta.executions) as f64;
+                    let misses = (meta.executions as f64) - finds;
+                    let sample = sample_beta(state.rand_mut(), 1.0 + finds, 1.0 + misses);
+                    if sample > best.1 {
+                        best = (*id, sample);
+                    }
+                }
+                best.0
+            }
+            BanditPolicy::Exp3 { gamma } => {
+                let log_weights: Vec<f64> = arms.iter().map(|(_, meta)| meta.log_weight).collect();
+                let probabilities = Self::exp3_probabilities(gamma, &log_weights);
+                let threshold = state.rand_mut().next_float();
+                let mut k = 0.0;
+                let mut picked = arms.len() - 1;
+                for (idx, p) in probabilities.iter().enumerate() {
+                    k += p;
+                    if k >= threshold {
+                        picked = idx;
+                        break;
+                    }
+                }
+                probability = probabilities[picked];
+                arms[picked].0
+            }
+        };
+
+        if let Ok(meta) = state
+            .testcase_mut(id)?
+            .metadata_mut::<BanditTestcaseMetadata>()
+        {
+            meta.pulls += 1;
+        }
+        let meta = state.metadata_mut::<BanditMetadata>()?;
+        meta.pull_probability = probability;
+        meta.trial_pending = false;
+
+        self.set_current_scheduled(state, Some(id))?;
+        Ok(id)
+    }
+
+    fn set_current_scheduled(
+        &mut self,
+        state: &mut S,
+        next_id: Option<CorpusId>,
+    ) -> Result<(), Error> {
+        *state.corpus_mut().current_mut() = next_id;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use libafl_bolts::rands::{Rand, StdRand};
+
+    use super::{sample_beta, BanditPolicy, BanditScheduler, BanditTestcaseMetadata};
+    use crate::{
+        corpus::{Corpus, CorpusId, HasTestcase, InMemoryCorpus, Testcase},
+        feedbacks::ConstFeedback,
+        inputs::BytesInput,
+        schedulers::Scheduler,
+        stages::{HasCurrentStageId, StageId},
+        state::{HasCorpus, HasRand, StdState},
+        HasMetadata,
+    };
+
+    #[test]
+    fn test_sample_beta() {
+        let mut rand = StdRand::with_seed(0);
+        let samples = 2000;
+        let mean: f64 = (0..samples)
+            .map(|_| sample_beta(&mut rand, 3.0, 7.0))
+            .sum::<f64>()
+            / f64::from(samples);
+        assert!((mean - 0.3).abs() < 0.02, "{mean}");
+    }
+
+    /// Fuzzes two seeds, where only mutants of the first one find new entries, and returns how often each was picked
+    fn pulls(policy: BanditPolicy) -> (u64, u64) {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(1),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut scheduler = BanditScheduler::new(&mut state, policy);
+
+        let mut seeds: [Option<CorpusId>; 2] = [None; 2];
+        for seed in &mut seeds {
+            let id = state
+                .corpus_mut()
+                .add(Testcase::new(BytesInput::new(vec![0])))
+                .unwrap();
+            scheduler.on_add(&mut state, id).unwrap();
+            *seed = Some(id);
+        }
+        let [good, bad] = seeds.map(Option::unwrap);
+
+        for _ in 0..300 {
+            let id = scheduler.next(&mut state).unwrap();
+            state.set_current_stage_id(StageId(0)).unwrap();
+            for _ in 0..10 {
+                scheduler
+                    .on_evaluation(&mut state, &BytesInput::new(vec![]), &())
+                    .unwrap();
+                if id == good && state.rand_mut().coinflip(0.1) {
+                    let new = state
+                        .corpus_mut()
+                        .add(Testcase::new(BytesInput::new(vec![1])))
+                        .unwrap();
+                    scheduler.on_add(&mut state, new).unwrap();
+                }
+            }
+            state.clear_stage_id().unwrap();
+        }
+
+        let pulls = |id| {
+            state
+                .testcase(id)
+                .unwrap()
+                .metadata::<BanditTestcaseMetadata>()
+                .unwrap()
+                .pulls()
+        };
+        (pulls(good), pulls(bad))
+    }
+
+    #[test]
+    fn test_bandit_scheduler() {
+        for policy in [
+            BanditPolicy::default(),
+            BanditPolicy::Thompson,
+            BanditPolicy::Exp3 { gamma: 0.1 },
+        ] {
+            let (good, bad) = pulls(policy);
+            assert!(good > bad, "{policy:?}: {good} vs {bad}");
+        }
+    }
+
+    type TestState =
+        StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+
+    fn add(scheduler: &mut BanditScheduler<TestState>, state: &mut TestState) -> CorpusId {
+        let id = state
+            .corpus_mut()
+            .add(Testcase::new(BytesInput::new(vec![0])))
+            .unwrap();
+        scheduler.on_add(state, id).unwrap();
+        id
+    }
+
+    #[test]
+    fn test_bandit_credits_stage_finds() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(1),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut scheduler = BanditScheduler::ucb1(&mut state);
+        let seed = add(&mut scheduler, &mut state);
+        *state.corpus_mut().current_mut() = Some(seed);
+        let finds = |state: &TestState| {
+            state
+                .testcase(seed)
+                .unwrap()
+                .metadata::<BanditTestcaseMetadata>()
+                .unwrap()
+                .finds()
+        };
+
+        // an entry imported after the stages, even right after a trial
+        state.set_current_stage_id(StageId(0)).unwrap();
+        scheduler
+            .on_evaluation(&mut state, &BytesInput::new(vec![]), &())
+            .unwrap();
+        state.clear_stage_id().unwrap();
+        let imported = add(&mut scheduler, &mut state);
+        assert_eq!(finds(&state), 0);
+        assert_eq!(state.testcase(imported).unwrap().parent_id(), None);
+
+        // an entry added by a stage without executing it
+        state.set_current_stage_id(StageId(0)).unwrap();
+        add(&mut scheduler, &mut state);
+        assert_eq!(finds(&state), 0);
+
+        // a mutant found by the stage
+        scheduler
+            .on_evaluation(&mut state, &BytesInput::new(vec![]), &())
+            .unwrap();
+        let found = add(&mut scheduler, &mut state);
+        assert_eq!(finds(&state), 1);
+        assert_eq!(state.testcase(found).unwrap().parent_id(), Some(seed));
+    }
+}
@@ -0,0 +1,457 @@
+//! Directed greybox fuzzing toward target sites, [as in AFLGo](https://github.com/aflgo/aflgo).
+//!
+//! The [`crate::feedbacks::DistanceFeedback`] attaches the distance to the targets of each new corpus entry,
+//! as seen by a [`crate::observers::DistanceObserver`], and the [`DirectedScheduler`] keeps track of the
+//! closest and farthest entries. The [`DirectedTestcaseScore`] then scales the energy of the entries with a simulated
+//! annealing schedule: early on, all entries get about the same energy (exploration), as the temperature drops
+//! the entries closest to the targets get more and more of it (exploitation).
+//! See [Böhme et al., "Directed Greybox Fuzzing"](https://mboehme.github.io/paper/CCS17.pdf).
+
+use core::{marker::PhantomData, time::Duration};
+
+use libafl_bolts::{current_time, tuples::MatchName};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::{Corpus, CorpusId, Testcase},
+    schedulers::{RemovableScheduler, Scheduler, TestcaseScore},
+    state::HasCorpus,
+    Error, HasMetadata,
+};
+
+/// The default time after which the fuzzer mostly exploits the entries closest to the targets,
+/// same as the one suggested for the `-c` option of `AFLGo`
+pub const DEFAULT_TIME_TO_EXPLOITATION: Duration = Duration::from_secs(45 * 60);
+/// The largest factor the energy of an entry is multiplied or divided by, same as `MAX_FACTOR` in `AFLGo`
+const MAX_POWER_FACTOR: f64 = 32.0;
+
+/// How the temperature of the annealing drops with the progress toward the time to exploitation
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CoolingSchedule {
+    /// `20^-progress`, the temperature is 0.05 at the time to exploitation
+    #[default]
+    Exponential,
+    /// `1 / (1 + 2 ln(1 + progress))`
+    Logarithmic,
+    /// `1 / (1 + progress)`
+    Linear,
+    /// `1 / (1 + progress^2)`
+    Quadratic,
+}
+
+impl CoolingSchedule {
+    /// The temperature, between 1 and 0, after `progress` times the time to exploitation
+    #[must_use]
+    pub fn temperature(self, progress: f64) -> f64 {
+        match self {
+            Self::Exponential => libm::pow(20.0, -progress),
+            Self::Logarithmic => 1.0 / (1.0 + 2.0 * libm::log1p(progress)),
+            Self::Linear => 1.0 / (1.0 + progress),
+            Self::Quadratic => 1.0 / (1.0 + progress * progress),
+        }
+    }
+}
+
+/// The distance to the targets of a corpus entry, the average distance of the basic blocks it executes
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct DistanceMetadata {
+    distance: f64,
+}
+
+libafl_bolts::impl_serdeany!(DistanceMetadata);
+
+impl DistanceMetadata {
+    /// Creates a new [`struct@DistanceMetadata`]
+    #[must_use]
+    pub fn new(distance: f64) -> Self {
+        Self { distance }
+    }
+
+    /// The distance to the targets
+    #[must_use]
+    pub fn distance(&self) -> f64 {
+        self.distance
+    }
+}
+
+/// The state of the simulated annealing of the [`DirectedScheduler`]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DirectedMetadata {
+    /// The smallest distance of a corpus entry
+    min_distance: f64,
+    /// The largest distance of a corpus entry
+    max_distance: f64,
+    /// When the annealing started
+    start_time: Duration,
+    /// After how long the fuzzer mostly exploits the entries closest to the targets
+    time_to_exploitation: Duration,
+    /// How the temperature drops
+    cooling: CoolingSchedule,
+}
+
+libafl_bolts::impl_serdeany!(DirectedMetadata);
+
+impl DirectedMetadata {
+    /// Creates a new [`struct@DirectedMetadata`], starting the annealing now
+    #[must_use]
+    pub fn new(time_to_exploitation: Duration, cooling: CoolingSchedule) -> Self {
+        Self {
+            min_distance: f64::INFINITY,
+            max_distance: f64::NEG_INFINITY,
+            start_time: current_time(),
+            time_to_exploitation,
+            cooling,
+        }
+    }
+
+    /// The smallest distance of a corpus entry, if any reached the targets
+    #[must_use]
+    pub fn min_distance(&self) -> Option<f64> {
+        self.min_distance.is_finite().then_some(self.min_distance)
+    }
+
+    /// The largest distance of a corpus entry, if any reached the targets
+    #[must_use]
+    pub fn max_distance(&self) -> Option<f64> {
+        self.max_distance.is_finite().then_some(self.max_distance)
+    }
+
+    /// Accounts for a new corpus entry at this distance
+    pub fn add_distance(&mut self, distance: f64) {
+        self.min_distance = self.min_distance.min(distance);
+        self.max_distance = self.max_distance.max(distance);
+    }
+
+    /// The current temperature, starting at 1 and dropping toward 0
+    #[must_use]
+    pub fn temperature(&self) -> f64 {
+        let elapsed = current_time().saturating_sub(self.start_time);
+        self.temperature_after(elapsed)
+    }
+
+    /// The temperature after `elapsed` time of annealing
+    #[must_use]
+    pub fn temperature_after(&self, elapsed: Duration) -> f64 {
+        let progress = elapsed.as_secs_f64() / self.time_to_exploitation.as_secs_f64().max(1.0);
+        self.cooling.temperature(progress)
+    }
+
+    /// The distance scaled between 0, for the closest entry, and 1, for the farthest one
+    #[must_use]
+    pub fn normalized_distance(&self, distance: f64) -> f64 {
+        if self.max_distance > self.min_distance {
+            ((distance - self.min_distance) / (self.max_distance - self.min_distance))
+                .clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// The factor to multiply the energy of an entry at this distance with, at this temperature.
+    ///
+    /// It is 1 for all entries at temperature 1, and goes from `MAX_POWER_FACTOR` for the closest entry
+    /// to `1 / MAX_POWER_FACTOR` for the farthest one as the temperature drops to 0.
+    #[must_use]
+    pub fn power_factor(&self, distance: f64, temperature: f64) -> f64 {
+        let normalized = self.normalized_distance(distance);
+        let p = (1.0 - normalized) * (1.0 - temperature) + 0.5 * temperature;
+        libm::pow(MAX_POWER_FACTOR, 2.0 * p - 1.0)
+    }
+}
+
+/// A [`Scheduler`] accounting for the distance to the targets of each new corpus entry, for directed fuzzing.
+///
+/// The distance is the [`struct@DistanceMetadata`] added by a [`crate::feedbacks::DistanceFeedback`].
+/// Entries are picked by the `base` scheduler, combine it with a power schedule scoring entries with the
+/// [`DirectedTestcaseScore`] to spend more time on the entries closest to the targets.
+#[derive(Debug, Clone)]
+pub struct DirectedScheduler<CS> {
+    base: CS,
+}
+
+impl<CS> DirectedScheduler<CS> {
+    /// Creates a new [`DirectedScheduler`] around `base`, with the default time to exploitation and cooling schedule
+    pub fn new<S>(state: &mut S, base: CS) -> Self
+    where
+        S: HasMetadata,
+    {
+        Self::with_annealing(
+            state,
+            base,
+            DEFAULT_TIME_TO_EXPLOITATION,
+            CoolingSchedule::default(),
+        )
+    }
+
+    /// Creates a new [`DirectedScheduler`] around `base`, reaching exploitation after `time_to_exploitation`
+    /// with the given cooling schedule.
+    ///
+    /// A restarted fuzzer keeps the annealing of its state.
+    pub fn with_annealing<S>(
+        state: &mut S,
+        base: CS,
+        time_to_exploitation: Duration,
+        cooling: CoolingSchedule,
+    ) -> Self
+    where
+        S: HasMetadata,
+    {
+        if !state.has_metadata::<DirectedMetadata>() {
+            state.add_metadata(DirectedMetadata::new(time_to_exploitation, cooling));
+        }
+        Self { base }
+    }
+
+    /// The base scheduler
+    #[must_use]
+    pub fn base(&self) -> &CS {
+        &self.base
+    }
+
+    /// The base scheduler (mutable)
+    pub fn base_mut(&mut self) -> &mut CS {
+        &mut self.base
+    }
+}
+
+impl<CS, I, S> Scheduler<I, S> for DirectedScheduler<CS>
+where
+    CS: Scheduler<I, S>,
+    S: HasCorpus + HasMetadata,
+{
+    fn on_add(&mut self, state: &mut S, id: CorpusId) -> Result<(), Error> {
+        let distance = state
+            .corpus()
+            .get(id)?
+            .borrow()
+            .metadata_map()
+            .get::<DistanceMetadata>()
+            .map(DistanceMetadata::distance);
+        if let Some(distance) = distance {
+            state
+                .metadata_mut::<DirectedMetadata>()?
+                .add_distance(distance);
+        }
+        self.base.on_add(state, id)
+    }
+
+    fn on_evaluation<OT>(&mut self, state: &mut S, input: &I, observers: &OT) -> Result<(), Error>
+    where
+        OT: MatchName,
+    {
+        self.base.on_evaluation(state, input, observers)
+    }
+
+    fn next(&mut self, state: &mut S) -> Result<CorpusId, Error> {
+        self.base.next(state)
+    }
+
+    fn set_current_scheduled(
+        &mut self,
+        state: &mut S,
+        next_id: Option<CorpusId>,
+    ) -> Result<(), Error> {
+        self.base.set_current_scheduled(state, next_id)
+    }
+}
+
+impl<CS, I, S> RemovableScheduler<I, S> for DirectedScheduler<CS>
+where
+    CS: RemovableScheduler<I, S>,
+{
+    fn on_remove(
+        &mut self,
+        state: &mut S,
+        id: CorpusId,
+        testcase: &Option<Testcase<I>>,
+    ) -> Result<(), Error> {
+        self.base.on_remove(state, id, testcase)
+    }
+
+    fn on_replace(&mut self, state: &mut S, id: CorpusId, prev: &Testcase<I>) -> Result<(), Error> {
+        self.base.on_replace(state, id, prev)
+    }
+}
+
+/// Scales the score of `F` with the simulated annealing of the [`DirectedScheduler`].
+///
+/// Entries that never reached a block leading to the targets keep the score of `F`.
+#[derive(Debug, Clone)]
+pub struct DirectedTestcaseScore<F> {
+    phantom: PhantomData<F>,
+}
+
+impl<F, S> TestcaseScore<S> for DirectedTestcaseScore<F>
+where
+    F: TestcaseScore<S>,
+    S: HasCorpus + HasMetadata,
+{
+    fn compute(
+        state: &S,
+        entry: &mut Testcase<<S::Corpus as Corpus>::Input>,
+    ) -> Result<f64, Error> {
+        let score = F::compute(state, entry)?;
+        let Some(distance) = entry.metadata_map().get::<DistanceMetadata>() else {
+            return Ok(score);
+        };
+        let meta = state.metadata::<DirectedMetadata>()?;
+        Ok(score * meta.power_factor(distance.distance(), meta.temperature()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+    use core::time::Duration;
+
+    use libafl_bolts::{current_time, rands::StdRand, tuples::tuple_list};
+
+    use super::{
+        CoolingSchedule, DirectedMetadata, DirectedScheduler, DirectedTestcaseScore,
+        DistanceMetadata, MAX_POWER_FACTOR,
+    };
+    use crate::{
+        corpus::{Corpus, InMemoryCorpus},
+        events::NopEventManager,
+        executors::ExitKind,
+        feedback_or,
+        feedbacks::{ConstFeedback, DistanceFeedback},
+        fuzzer::{ExecutionProcessor, StdFuzzer},
+        inputs::BytesInput,
+        observers::DistanceObserver,
+        schedulers::{LenTimeMulTestcaseScore, QueueScheduler, TestcaseScore},
+        state::{HasCorpus, StdState},
+        HasMetadata,
+    };
+
+    #[test]
+    fn test_cooling_schedules() {
+        for cooling in [
+            CoolingSchedule::Exponential,
+            CoolingSchedule::Logarithmic,
+            CoolingSchedule::Linear,
+            CoolingSchedule::Quadratic,
+        ] {
+            assert!((cooling.temperature(0.0) - 1.0).abs() < f64::EPSILON);
+            let mut last = 1.0;
+            for progress in 1..10 {
+                let temperature = cooling.temperature(f64::from(progress));
+                assert!(temperature > 0.0 && temperature < last);
+                last = temperature;
+            }
+        }
+        assert!((CoolingSchedule::Exponential.temperature(1.0) - 0.05).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_power_factor() {
+        let mut meta = DirectedMetadata::new(Duration::from_secs(60), CoolingSchedule::Exponential);
+        assert_eq!(meta.min_distance(), None);
+        for distance in [300.0, 100.0, 500.0] {
+            meta.add_distance(distance);
+        }
+        assert_eq!(meta.min_distance(), Some(100.0));
+        assert_eq!(meta.max_distance(), Some(500.0));
+        assert!((meta.normalized_distance(300.0) - 0.5).abs() < f64::EPSILON);
+
+        // While hot, all entries are treated the same
+        let hot = meta.temperature_after(Duration::ZERO);
+        assert!((meta.power_factor(100.0, hot) - 1.0).abs() < 1e-9);
+        assert!((meta.power_factor(500.0, hot) - 1.0).abs() < 1e-9);
+
+        // Once cold, the closest entries get the most energy
+        let cold = meta.temperature_after(Duration::from_secs(3600));
+        let closest = meta.power_factor(100.0, cold);
+        let middle = meta.power_factor(300.0, cold);
+        let farthest = meta.power_factor(500.0, cold);
+        assert!(closest > middle && middle > farthest);
+        assert!(closest <= MAX_POWER_FACTOR && farthest >= 1.0 / MAX_POWER_FACTOR);
+        assert!((middle - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_directed_scheduler() {
+        let mut counters = [0_u64; 2];
+        let counters_ptr = counters.as_mut_ptr();
+        // # Safety
+        // The counters outlive the observer, and are only written through `counters_ptr` like a target would
+        let observer = unsafe { DistanceObserver::from_mut_ptr("distance", counters_ptr) };
+        let mut feedback = feedback_or!(ConstFeedback::new(true), DistanceFeedback::new(&observer));
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let scheduler = DirectedScheduler::with_annealing(
+            &mut state,
+            QueueScheduler::new(),
+            Duration::from_secs(60),
+            CoolingSchedule::Exponential,
+        );
+        let mut fuzzer = StdFuzzer::new(scheduler, feedback, objective);
+        let mut mgr = NopEventManager::new();
+        let observers = tuple_list!(observer);
+
+        // The new entries get the distance of their execution, if they reached a block leading to the targets
+        let mut ids = vec![];
+        for (input, distance) in [(b'a', [600, 2]), (b'b', [100, 1]), (b'c', [0, 0])] {
+            // # Safety
+            // Nothing else accesses the counters right now
+            unsafe { counters_ptr.cast::<[u64; 2]>().write(distance) };
+            let (_, id) = fuzzer
+                .evaluate_execution(
+                    &mut state,
+                    &mut mgr,
+                    BytesInput::new(vec![input]),
+                    &observers,
+                    &ExitKind::Ok,
+                    false,
+                )
+                .unwrap();
+            ids.push(id.unwrap());
+        }
+        let distances: Vec<_> = ids
+            .iter()
+            .map(|id| {
+                state
+                    .corpus()
+                    .get(*id)
+                    .unwrap()
+                    .borrow()
+                    .metadata_map()
+                    .get::<DistanceMetadata>()
+                    .map(DistanceMetadata::distance)
+            })
+            .collect();
+        assert_eq!(distances, [Some(300.0), Some(100.0), None]);
+        let meta = state.metadata::<DirectedMetadata>().unwrap();
+        assert_eq!(meta.min_distance(), Some(100.0));
+        assert_eq!(meta.max_distance(), Some(300.0));
+
+        // Once cold, the closest entry gets the most energy, the one without a distance keeps its score
+        state.metadata_mut::<DirectedMetadata>().unwrap().start_time =
+            current_time().saturating_sub(Duration::from_secs(3600));
+        let scores: Vec<_> = ids
+            .iter()
+            .map(|id| {
+                let mut testcase = state.corpus().get(*id).unwrap().borrow_mut();
+                DirectedTestcaseScore::<LenTimeMulTestcaseScore>::compute(&state, &mut testcase)
+                    .unwrap()
+            })
+            .collect();
+        assert!(scores[1] > 1.0 && scores[0] < 1.0, "{scores:?}");
+        assert!((scores[2] - 1.0).abs() < f64::EPSILON, "{scores:?}");
+    }
+}
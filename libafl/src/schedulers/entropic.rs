@@ -0,0 +1,649 @@
+//! The Entropic corpus scheduler, [from libFuzzer](https://github.com/llvm/llvm-project/blob/main/compiler-rt/lib/fuzzer/FuzzerCorpus.h).
+//!
+//! Entropic assigns each corpus entry an energy: the entropy of the distribution of rare map entries
+//! (features) hit while fuzzing it. Entries whose mutants keep hitting the same few features have little
+//! left to teach and get a low energy, entries whose mutants spread over many rare features get a high one.
+//! See [Böhme et al., "Boosting Fuzzer Efficiency: An Information Theoretic Perspective"](https://mboehme.github.io/paper/FSE20.Entropy.pdf).
+//!
+//! New features are learned from the [`MapNoveltiesMetadata`] of new corpus entries,
+//! so the map feedback needs to track novelties.
+
+use alloc::vec::Vec;
+use core::{marker::PhantomData, num::NonZero};
+
+use hashbrown::HashMap;
+use libafl_bolts::{
+    rands::Rand,
+    tuples::{Handle, Handled, MatchName, MatchNameRef},
+    Named,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::{Corpus, CorpusId, HasTestcase, Testcase},
+    feedbacks::MapNoveltiesMetadata,
+    observers::MapObserver,
+    schedulers::{
+        on_add_metadata_default, on_evaluation_metadata_default, on_next_metadata_default,
+        powersched::{PowerSchedule, SchedulerMetadata},
+        AflScheduler, HasQueueCycles, RemovableScheduler, Scheduler, TestcaseScore,
+    },
+    state::{HasCorpus, HasRand},
+    Error, HasMetadata,
+};
+
+/// The default number of rare features to track, same as libFuzzer's `-entropic_number_of_rarest_features`
+pub const DEFAULT_MAX_RARE_FEATURES: usize = 100;
+/// The default frequency above which a feature is abundant, same as libFuzzer's `-entropic_feature_frequency_threshold`
+pub const DEFAULT_FEATURE_FREQUENCY_THRESHOLD: u16 = 0xFF;
+/// An entry fuzzed this many times more than the average is not scheduled, like libFuzzer's `kMaxMutationFactor`
+const MAX_MUTATION_FACTOR: u64 = 20;
+/// The weights are recomputed once in this many picks even if no rare feature changed,
+/// to follow the energies of the entries fuzzed since, like libFuzzer's `kSparseEnergyUpdates`
+const SPARSE_ENERGY_UPDATES: usize = 100;
+
+/// The global feature frequencies of the [`EntropicScheduler`]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct EntropicMetadata {
+    /// The rare features, and how often they were hit since they were found
+    rare_features: HashMap<usize, u16>,
+    /// The number of executions of all corpus entries
+    executions: u64,
+}
+
+libafl_bolts::impl_serdeany!(EntropicMetadata);
+
+impl EntropicMetadata {
+    /// Creates a new [`struct@EntropicMetadata`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of rare features
+    #[must_use]
+    pub fn rare_features(&self) -> usize {
+        self.rare_features.len()
+    }
+
+    /// How often the feature `idx` was hit, or `None` if it is not rare
+    #[must_use]
+    pub fn frequency(&self, idx: usize) -> Option<u16> {
+        self.rare_features.get(&idx).copied()
+    }
+
+    /// The number of executions of all corpus entries
+    #[must_use]
+    pub fn executions(&self) -> u64 {
+        self.executions
+    }
+}
+
+/// The local feature frequencies of a corpus entry, for the [`EntropicScheduler`]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct EntropicTestcaseMetadata {
+    /// The rare features hit by mutants of this entry, sorted by feature, with their frequency
+    feature_freqs: Vec<(usize, u16)>,
+    /// The number of executions of mutants of this entry
+    executions: u64,
+    /// The energy, if it is up to date
+    energy: Option<f64>,
+}
+
+libafl_bolts::impl_serdeany!(EntropicTestcaseMetadata);
+
+impl EntropicTestcaseMetadata {
+    /// Creates a new [`struct@EntropicTestcaseMetadata`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The rare features hit by mutants of this entry, with their frequency
+    #[must_use]
+    pub fn feature_freqs(&self) -> &[(usize, u16)] {
+        &self.feature_freqs
+    }
+
+    /// The number of executions of mutants of this entry
+    #[must_use]
+    pub fn executions(&self) -> u64 {
+        self.executions
+    }
+
+    /// Counts a hit of the rare feature `idx`
+    fn hit(&mut self, idx: usize) {
+        match self.feature_freqs.binary_search_by_key(&idx, |(f, _)| *f) {
+            Ok(pos) => {
+                let freq = &mut self.feature_freqs[pos].1;
+                *freq = freq.saturating_add(1);
+            }
+            Err(pos) => self.feature_freqs.insert(pos, (idx, 1)),
+        }
+        self.energy = None;
+    }
+
+    /// Forgets the feature `idx`, when it is no longer rare
+    fn forget(&mut self, idx: usize) {
+        if let Ok(pos) = self.feature_freqs.binary_search_by_key(&idx, |(f, _)| *f) {
+            self.feature_freqs.remove(pos);
+            self.energy = None;
+        }
+    }
+
+    /// The entropy of the feature distribution, with add-one smoothing, given the number of rare features.
+    ///
+    /// Features not hit yet count once, all abundant features are pooled into a single one
+    /// counted once per execution.
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn entropy(&self, rare_features: usize) -> f64 {
+        let mut energy = 0.0;
+        let mut sum_incidence = 0.0;
+        for (_, freq) in &self.feature_freqs {
+            let incidence = f64::from(*freq) + 1.0;
+            energy -= incidence * libm::log(incidence);
+            sum_incidence += incidence;
+        }
+        sum_incidence += rare_features.saturating_sub(self.feature_freqs.len()) as f64;
+
+        let abundant_incidence = self.executions as f64 + 1.0;
+        energy -= abundant_incidence * libm::log(abundant_incidence);
+        sum_incidence += abundant_incidence;
+
+        energy / sum_incidence + libm::log(sum_incidence)
+    }
+}
+
+/// The energy of a corpus entry, as computed by libFuzzer's Entropic.
+///
+/// Entries fuzzed much more often than the average get no energy, so the others catch up.
+#[derive(Debug, Clone)]
+pub struct EntropicTestcaseScore {}
+
+impl<S> TestcaseScore<S> for EntropicTestcaseScore
+where
+    S: HasCorpus + HasMetadata,
+{
+    #[allow(clippy::cast_possible_truncation)]
+    fn compute(
+        state: &S,
+        entry: &mut Testcase<<S::Corpus as Corpus>::Input>,
+    ) -> Result<f64, Error> {
+        let global = state.metadata::<EntropicMetadata>()?;
+        let average = global.executions / (state.corpus().count().max(1) as u64);
+        let meta = entry.metadata_mut::<EntropicTestcaseMetadata>()?;
+        if meta.executions / MAX_MUTATION_FACTOR > average {
+            return Ok(0.0);
+        }
+        let energy = if let Some(energy) = meta.energy {
+            energy
+        } else {
+            let energy = meta.entropy(global.rare_features());
+            meta.energy = Some(energy);
+            energy
+        };
+        Ok(energy.max(0.0))
+    }
+}
+
+/// A corpus scheduler picking entries proportionally to their [`EntropicTestcaseScore`].
+///
+/// It keeps the [`SchedulerMetadata`] up to date like the other AFL-style schedulers,
+/// so it works together with the power mutational stages.
+///
+/// The weights of the entries are cached, and recomputed when the corpus or the rare features change,
+/// and once in a while to follow the energies of the entries fuzzed since.
+#[derive(Clone, Debug)]
+pub struct EntropicScheduler<C, O> {
+    map_observer_handle: Handle<C>,
+    max_rare_features: usize,
+    feature_frequency_threshold: u16,
+    /// The entries with their [`EntropicTestcaseScore`] when last computed
+    weights: Vec<(CorpusId, f64)>,
+    weights_sum: f64,
+    /// If the corpus or the rare features changed since the weights were computed
+    weights_stale: bool,
+    last_hash: usize,
+    queue_cycles: u64,
+    runs_in_current_cycle: usize,
+    phantom: PhantomData<O>,
+}
+
+impl<C, O> EntropicScheduler<C, O>
+where
+    C: Named,
+{
+    /// Creates a new [`EntropicScheduler`] without any power schedule
+    #[must_use]
+    pub fn new<S>(state: &mut S, map_observer: &C) -> Self
+    where
+        S: HasMetadata,
+    {
+        Self::with_schedule(state, map_observer, None)
+    }
+
+    /// Creates a new [`EntropicScheduler`], with the power schedule `strat` for the power mutational stages
+    #[must_use]
+    pub fn with_schedule<S>(state: &mut S, map_observer: &C, strat: Option<PowerSchedule>) -> Self
+    where
+        S: HasMetadata,
+    {
+        let _ = state.metadata_or_insert_with(|| SchedulerMetadata::new(strat));
+        let _ = state.metadata_or_insert_with(EntropicMetadata::new);
+
+        Self {
+            map_observer_handle: map_observer.handle(),
+            max_rare_features: DEFAULT_MAX_RARE_FEATURES,
+            feature_frequency_threshold: DEFAULT_FEATURE_FREQUENCY_THRESHOLD,
+            weights: Vec::new(),
+            weights_sum: 0.0,
+            weights_stale: true,
+            last_hash: 0,
+            queue_cycles: 0,
+            runs_in_current_cycle: 0,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Sets how many rare features to track, [`DEFAULT_MAX_RARE_FEATURES`] by default.
+    ///
+    /// Unlike libFuzzer, which only drops rare features more frequent than the
+    /// [`Self::with_feature_frequency_threshold`] and tracks more of them otherwise, this is a hard cap.
+    #[must_use]
+    pub fn with_max_rare_features(mut self, max_rare_features: usize) -> Self {
+        self.max_rare_features = max_rare_features;
+        self
+    }
+
+    /// Sets the frequency above which a feature is abundant, [`DEFAULT_FEATURE_FREQUENCY_THRESHOLD`] by default
+    #[must_use]
+    pub fn with_feature_frequency_threshold(mut self, feature_frequency_threshold: u16) -> Self {
+        self.feature_frequency_threshold = feature_frequency_threshold;
+        self
+    }
+
+    /// Starts tracking the new feature `idx`.
+    /// If there are too many rare features, the most abundant ones are dropped to make room for it.
+    fn add_rare_feature<S>(&mut self, state: &mut S, idx: usize) -> Result<(), Error>
+    where
+        S: HasCorpus + HasMetadata,
+    {
+        let mut dropped = Vec::new();
+        let meta = state.metadata_mut::<EntropicMetadata>()?;
+        if meta.rare_features.contains_key(&idx) {
+            return Ok(());
+        }
+        if self.max_rare_features == 0 {
+            return Ok(());
+        }
+        while meta.rare_features.len() >= self.max_rare_features {
+            let Some((&abundant, _)) = meta
+                .rare_features
+                .iter()
+                .max_by_key(|&(idx, freq)| (*freq, *idx))
+            else {
+                break;
+            };
+            meta.rare_features.remove(&abundant);
+            dropped.push(abundant);
+        }
+        meta.rare_features.insert(idx, 0);
+
+        // The number of rare features changed, all energies are stale
+        self.weights_stale = true;
+        for id in state.corpus().ids() {
+            let mut testcase = state.corpus().get(id)?.borrow_mut();
+            if let Ok(meta) = testcase.metadata_mut::<EntropicTestcaseMetadata>() {
+                for abundant in &dropped {
+                    meta.forget(*abundant);
+                }
+                meta.energy = None;
+            }
+        }
+        Ok(())
+    }
+
+    /// Computes the weights of all entries again
+    fn update_weights<S>(&mut self, state: &S) -> Result<(), Error>
+    where
+        S: HasCorpus + HasMetadata,
+    {
+        self.weights.clear();
+        self.weights_sum = 0.0;
+        for id in state.corpus().ids() {
+            let mut testcase = state.corpus().get(id)?.borrow_mut();
+            let weight = EntropicTestcaseScore::compute(state, &mut testcase)?;
+            self.weights_sum += weight;
+            self.weights.push((id, weight));
+        }
+        self.weights_stale = false;
+        Ok(())
+    }
+}
+
+impl<C, I, O, S> RemovableScheduler<I, S> for EntropicScheduler<C, O> {
+    fn on_remove(
+        &mut self,
+        _state: &mut S,
+        _id: CorpusId,
+        _testcase: &Option<Testcase<I>>,
+    ) -> Result<(), Error> {
+        self.weights_stale = true;
+        Ok(())
+    }
+
+    fn on_replace(
+        &mut self,
+        _state: &mut S,
+        _id: CorpusId,
+        _prev: &Testcase<I>,
+    ) -> Result<(), Error> {
+        self.weights_stale = true;
+        Ok(())
+    }
+}
+
+impl<C, O> AflScheduler for EntropicScheduler<C, O> {
+    type MapObserverRef = C;
+
+    fn last_hash(&self) -> usize {
+        self.last_hash
+    }
+
+    fn set_last_hash(&mut self, hash: usize) {
+        self.last_hash = hash;
+    }
+
+    fn map_observer_handle(&self) -> &Handle<C> {
+        &self.map_observer_handle
+    }
+}
+
+impl<C, O> HasQueueCycles for EntropicScheduler<C, O> {
+    fn queue_cycles(&self) -> u64 {
+        self.queue_cycles
+    }
+}
+
+impl<C, O, S> Scheduler<<S::Corpus as Corpus>::Input, S> for EntropicScheduler<C, O>
+where
+    C: AsRef<O> + Named,
+    O: MapObserver,
+    S: HasCorpus + HasMetadata + HasRand + HasTestcase,
+{
+    /// Called when a [`Testcase`] is added to the corpus, learns its new features
+    fn on_add(&mut self, state: &mut S, id: CorpusId) -> Result<(), Error> {
+        on_add_metadata_default(self, state, id)?;
+        let novelties = {
+            let mut testcase = state.testcase_mut(id)?;
+            testcase.add_metadata(EntropicTestcaseMetadata::new());
+            testcase
+                .metadata::<MapNoveltiesMetadata>()
+                .map_err(|_| {
+                    Error::key_not_found(
+                        "MapNoveltiesMetadata needed by the EntropicScheduler not found in testcase, track the novelties of the map feedback",
+                    )
+                })?
+                .list
+                .clone()
+        };
+        for idx in novelties {
+            self.add_rare_feature(state, idx)?;
+        }
+        Ok(())
+    }
+
+    /// Counts the rare features hit by a mutant of the current entry
+    fn on_evaluation<OT>(
+        &mut self,
+        state: &mut S,
+        _input: &<S::Corpus as Corpus>::Input,
+        observers: &OT,
+    ) -> Result<(), Error>
+    where
+        OT: MatchName,
+    {
+        on_evaluation_metadata_default(self, state, observers)?;
+
+        let observer = observers
+            .get(&self.map_observer_handle)
+            .ok_or_else(|| Error::key_not_found("MapObserver not found"))?
+            .as_ref();
+        let initial = observer.initial();
+        let mut hit = Vec::new();
+        let meta = state.metadata_mut::<EntropicMetadata>()?;
+        meta.executions += 1;
+        for (idx, freq) in &mut meta.rare_features {
+            if *idx < observer.usable_count() && observer.get(*idx) != initial {
+                *freq = freq.saturating_add(1);
+                // Abundant features are pooled, they are not counted per entry
+                if *freq <= self.feature_frequency_threshold {
+                    hit.push(*idx);
+                }
+            }
+        }
+
+        if let Some(id) = *state.corpus().current() {
+            let mut testcase = state.testcase_mut(id)?;
+            if let Ok(meta) = testcase.metadata_mut::<EntropicTestcaseMetadata>() {
+                meta.executions += 1;
+                meta.energy = None;
+                for idx in hit {
+                    meta.hit(idx);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Picks an entry with a probability proportional to its energy
+    fn next(&mut self, state: &mut S) -> Result<CorpusId, Error> {
+        let corpus_counts = state.corpus().count();
+        if corpus_counts == 0 {
+            return Err(Error::empty(
+                "No entries in corpus. This often implies the target is not properly instrumented.",
+            ));
+        }
+
+        if self.weights_stale
+            || self.weights.len() != corpus_counts
+            || state
+                .rand_mut()
+                .below(NonZero::new(SPARSE_ENERGY_UPDATES).unwrap())
+                == 0
+        {
+            self.update_weights(state)?;
+        }
+
+        let id = if self.weights_sum > 0.0 {
+            let threshold = self.weights_sum * state.rand_mut().next_float();
+            let mut k = 0.0;
+            self.weights
+                .iter()
+                .find(|(_, weight)| {
+                    k += weight;
+                    k >= threshold
+                })
+                .map_or(self.weights[self.weights.len() - 1].0, |(id, _)| *id)
+        } else {
+            // Nothing learned yet, pick uniformly
+            self.weights[state.rand_mut().below(NonZero::new(corpus_counts).unwrap())].0
+        };
+
+        self.runs_in_current_cycle += 1;
+        if self.runs_in_current_cycle >= corpus_counts {
+            self.runs_in_current_cycle = 0;
+            self.queue_cycles += 1;
+            state
+                .metadata_mut::<SchedulerMetadata>()?
+                .set_queue_cycles(self.queue_cycles);
+        }
+
+        self.set_current_scheduled(state, Some(id))?;
+        Ok(id)
+    }
+
+    /// Set current fuzzed corpus id and `scheduled_count`
+    fn set_current_scheduled(
+        &mut self,
+        state: &mut S,
+        next_id: Option<CorpusId>,
+    ) -> Result<(), Error> {
+        on_next_metadata_default(state)?;
+
+        *state.corpus_mut().current_mut() = next_id;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use libafl_bolts::{rands::StdRand, tuples::tuple_list, AsSliceMut};
+
+    use super::{
+        EntropicMetadata, EntropicScheduler, EntropicTestcaseMetadata, EntropicTestcaseScore,
+    };
+    use crate::{
+        corpus::{Corpus, HasTestcase, InMemoryCorpus, Testcase},
+        feedbacks::{ConstFeedback, MapNoveltiesMetadata},
+        inputs::BytesInput,
+        observers::StdMapObserver,
+        schedulers::{Scheduler, TestcaseScore},
+        state::{HasCorpus, StdState},
+        HasMetadata,
+    };
+
+    #[test]
+    fn test_entropy() {
+        let mut meta = EntropicTestcaseMetadata::new();
+        let fresh = meta.entropy(10);
+        meta.executions = 100;
+        let stale = meta.entropy(10);
+        assert!(stale < fresh, "{stale} >= {fresh}");
+
+        // spreading the same executions over more features keeps more energy
+        let mut focused = EntropicTestcaseMetadata::new();
+        focused.executions = 100;
+        focused.hit(1);
+        for _ in 0..50 {
+            focused.hit(1);
+        }
+        let mut spread = EntropicTestcaseMetadata::new();
+        spread.executions = 100;
+        for i in 0..51 {
+            spread.hit(i % 5);
+        }
+        assert!(spread.entropy(10) > focused.entropy(10));
+
+        spread.forget(3);
+        assert_eq!(spread.feature_freqs().len(), 4);
+    }
+
+    #[test]
+    fn test_entropic_scheduler() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+
+        let observer = StdMapObserver::owned("map", vec![0_u8; 8]);
+        let mut scheduler = EntropicScheduler::new(&mut state, &observer);
+
+        let mut ids = vec![];
+        for novelty in [1, 2] {
+            let mut testcase = Testcase::new(BytesInput::new(vec![u8::try_from(novelty).unwrap()]));
+            testcase.add_metadata(MapNoveltiesMetadata::new(vec![novelty]));
+            let id = state.corpus_mut().add(testcase).unwrap();
+            scheduler.on_add(&mut state, id).unwrap();
+            ids.push(id);
+        }
+        assert_eq!(
+            state
+                .metadata::<EntropicMetadata>()
+                .unwrap()
+                .rare_features(),
+            2
+        );
+
+        // fuzzing the first entry keeps hitting the same rare feature
+        let mut observer = observer;
+        observer.as_slice_mut()[1] = 1;
+        let observers = tuple_list!(observer);
+        *state.corpus_mut().current_mut() = Some(ids[0]);
+        for _ in 0..200 {
+            scheduler
+                .on_evaluation(&mut state, &BytesInput::new(vec![]), &observers)
+                .unwrap();
+        }
+        let meta = state.metadata::<EntropicMetadata>().unwrap();
+        assert_eq!(meta.frequency(1), Some(200));
+        assert_eq!(meta.frequency(2), Some(0));
+        assert_eq!(meta.executions(), 200);
+        assert_eq!(
+            state
+                .testcase(ids[0])
+                .unwrap()
+                .metadata::<EntropicTestcaseMetadata>()
+                .unwrap()
+                .feature_freqs(),
+            [(1, 200)]
+        );
+
+        // the second entry, still fresh, has more energy and is picked more often
+        let mut energies = vec![];
+        for id in &ids {
+            let mut testcase = state.corpus().get(*id).unwrap().borrow_mut();
+            energies.push(EntropicTestcaseScore::compute(&state, &mut testcase).unwrap());
+        }
+        assert!(energies[1] > energies[0], "{energies:?}");
+        let mut second = 0;
+        for _ in 0..100 {
+            if scheduler.next(&mut state).unwrap() == ids[1] {
+                second += 1;
+            }
+        }
+        assert!(second > 50, "{second}");
+        assert!(!scheduler.weights_stale);
+
+        // with room for two rare features, the abundant one makes way for a new one
+        let mut scheduler = scheduler
+            .with_max_rare_features(2)
+            .with_feature_frequency_threshold(100);
+        let mut testcase = Testcase::new(BytesInput::new(vec![3]));
+        testcase.add_metadata(MapNoveltiesMetadata::new(vec![3]));
+        let id = state.corpus_mut().add(testcase).unwrap();
+        scheduler.on_add(&mut state, id).unwrap();
+        let meta = state.metadata::<EntropicMetadata>().unwrap();
+        assert_eq!(meta.rare_features(), 2);
+        assert_eq!(meta.frequency(1), None);
+        assert_eq!(meta.frequency(3), Some(0));
+        assert!(scheduler.weights_stale);
+
+        // the cap holds even if no rare feature is abundant yet
+        let mut scheduler = scheduler.with_feature_frequency_threshold(u16::MAX);
+        let mut testcase = Testcase::new(BytesInput::new(vec![4]));
+        testcase.add_metadata(MapNoveltiesMetadata::new(vec![4]));
+        let id = state.corpus_mut().add(testcase).unwrap();
+        scheduler.on_add(&mut state, id).unwrap();
+        let meta = state.metadata::<EntropicMetadata>().unwrap();
+        assert_eq!(meta.rare_features(), 2);
+        assert_eq!(meta.frequency(4), Some(0));
+        scheduler.next(&mut state).unwrap();
+    }
+}
@@ -23,9 +23,23 @@ pub use probabilistic_sampling::ProbabilitySamplingScheduler;
 pub mod accounting;
 pub use accounting::CoverageAccountingScheduler;
 
+#[cfg(feature = "std")]
+pub mod protocol_state;
+#[cfg(feature = "std")]
+pub use protocol_state::ProtocolStateScheduler;
+
 pub mod weighted;
 pub use weighted::{StdWeightedScheduler, WeightedScheduler};
 
+pub mod entropic;
+pub use entropic::{EntropicScheduler, EntropicTestcaseScore};
+
+pub mod bandit;
+pub use bandit::{BanditPolicy, BanditScheduler};
+
+pub mod directed;
+pub use directed::{DirectedScheduler, DirectedTestcaseScore};
+
 pub mod tuneable;
 use libafl_bolts::{
     rands::Rand,
@@ -0,0 +1,322 @@
+//! The [`ProtocolStateScheduler`] focuses on rarely visited and newly discovered protocol states,
+//! in the spirit of `AFLNet`.
+
+use alloc::vec::Vec;
+
+use hashbrown::HashMap;
+use libafl_bolts::{rands::Rand, tuples::MatchName};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::{Corpus, CorpusId, Testcase},
+    feedbacks::ProtocolStatesMetadata,
+    observers::ProtocolStateGraphMetadata,
+    schedulers::{RemovableScheduler, Scheduler},
+    state::{HasCorpus, HasRand},
+    Error, HasMetadata,
+};
+
+/// The state of the [`ProtocolStateScheduler`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+pub struct ProtocolStateSchedulerMetadata {
+    /// The corpus entries reaching each state
+    entries: HashMap<u64, Vec<CorpusId>>,
+    /// How often each state was selected
+    selected: HashMap<u64, u64>,
+    /// How many new corpus entries were found while each state was selected
+    discovered: HashMap<u64, u64>,
+    /// The currently selected state
+    current: Option<u64>,
+}
+
+libafl_bolts::impl_serdeany!(ProtocolStateSchedulerMetadata);
+
+impl ProtocolStateSchedulerMetadata {
+    /// The corpus entries reaching the given state
+    #[must_use]
+    pub fn entries(&self, state: u64) -> &[CorpusId] {
+        self.entries.get(&state).map_or(&[], Vec::as_slice)
+    }
+
+    /// How often the given state was selected
+    #[must_use]
+    pub fn selected(&self, state: u64) -> u64 {
+        self.selected.get(&state).copied().unwrap_or(0)
+    }
+
+    /// The currently selected state
+    #[must_use]
+    pub fn current(&self) -> Option<u64> {
+        self.current
+    }
+
+    fn remove_entry(&mut self, id: CorpusId) {
+        for entries in self.entries.values_mut() {
+            entries.retain(|entry| *entry != id);
+        }
+        self.entries.retain(|_, entries| !entries.is_empty());
+    }
+}
+
+/// Computes the score of a state, like `AFLNet` does: states reached by few executions, selected
+/// rarely, and leading to many new corpus entries when selected, get a higher score.
+#[allow(clippy::cast_precision_loss)]
+fn state_score(visits: u64, selected: u64, discovered: u64) -> f64 {
+    1000.0
+        * libm::exp2(-libm::log10(
+            libm::log10(visits as f64 + 1.0) * selected as f64 + 1.0,
+        ))
+        * libm::exp2(libm::log(discovered as f64 + 1.0))
+}
+
+/// A scheduler first selecting a protocol state, favoring rarely visited and newly discovered states,
+/// and then the corpus entry reaching this state that was scheduled the least.
+///
+/// Needs the [`ProtocolStatesMetadata`] added to each testcase by the [`crate::feedbacks::ProtocolStateFeedback`],
+/// and the [`ProtocolStateGraphMetadata`] recorded by the [`crate::observers::ProtocolStateObserver`].
+/// Falls back to the `base` scheduler as long as no state is known.
+#[derive(Debug, Clone)]
+pub struct ProtocolStateScheduler<CS> {
+    base: CS,
+}
+
+impl<CS> ProtocolStateScheduler<CS> {
+    /// Creates a new [`ProtocolStateScheduler`], wrapping the given `base` scheduler
+    #[must_use]
+    pub fn new(base: CS) -> Self {
+        Self { base }
+    }
+
+    /// Adds the entry to the states it reaches
+    #[allow(clippy::unused_self)]
+    fn add_entry<S>(&self, state: &mut S, id: CorpusId) -> Result<(), Error>
+    where
+        S: HasCorpus + HasMetadata,
+    {
+        let states = state
+            .corpus()
+            .get(id)?
+            .borrow()
+            .metadata::<ProtocolStatesMetadata>()
+            .map(|meta| meta.states.clone())
+            .unwrap_or_default();
+        let meta = state.metadata_or_insert_with(ProtocolStateSchedulerMetadata::default);
+        for reached in states {
+            let entries = meta.entries.entry(reached).or_default();
+            if !entries.contains(&id) {
+                entries.push(id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Selects a state by roulette wheel on the state scores
+    fn select_state<S>(state: &mut S) -> Option<u64>
+    where
+        S: HasMetadata + HasRand,
+    {
+        let meta = state.metadata::<ProtocolStateSchedulerMetadata>().ok()?;
+        let graph = state.metadata::<ProtocolStateGraphMetadata>().ok();
+        let mut scores: Vec<(u64, f64)> = meta
+            .entries
+            .keys()
+            .map(|reached| {
+                let visits = graph.map_or(0, |graph| graph.state_visits(*reached));
+                let discovered = meta.discovered.get(reached).copied().unwrap_or(0);
+                let score = state_score(visits, meta.selected(*reached), discovered);
+                (*reached, score)
+            })
+            .collect();
+        // Iteration order of the map is random, keep the selection reproducible
+        scores.sort_unstable_by_key(|(reached, _)| *reached);
+        let total: f64 = scores.iter().map(|(_, score)| score).sum();
+
+        let threshold = state.rand_mut().next_float() * total;
+        let mut sum = 0.0;
+        let mut selected = scores.last()?.0;
+        for (reached, score) in scores {
+            sum += score;
+            if sum >= threshold {
+                selected = reached;
+                break;
+            }
+        }
+        Some(selected)
+    }
+}
+
+impl<CS, S> RemovableScheduler<<S::Corpus as Corpus>::Input, S> for ProtocolStateScheduler<CS>
+where
+    CS: RemovableScheduler<<S::Corpus as Corpus>::Input, S>,
+    S: HasCorpus + HasMetadata,
+{
+    fn on_remove(
+        &mut self,
+        state: &mut S,
+        id: CorpusId,
+        testcase: &Option<Testcase<<S::Corpus as Corpus>::Input>>,
+    ) -> Result<(), Error> {
+        self.base.on_remove(state, id, testcase)?;
+        if let Ok(meta) = state.metadata_mut::<ProtocolStateSchedulerMetadata>() {
+            meta.remove_entry(id);
+        }
+        Ok(())
+    }
+
+    fn on_replace(
+        &mut self,
+        state: &mut S,
+        id: CorpusId,
+        prev: &Testcase<<S::Corpus as Corpus>::Input>,
+    ) -> Result<(), Error> {
+        self.base.on_replace(state, id, prev)?;
+        if let Ok(meta) = state.metadata_mut::<ProtocolStateSchedulerMetadata>() {
+            meta.remove_entry(id);
+        }
+        self.add_entry(state, id)
+    }
+}
+
+impl<CS, S> Scheduler<<S::Corpus as Corpus>::Input, S> for ProtocolStateScheduler<CS>
+where
+    CS: Scheduler<<S::Corpus as Corpus>::Input, S>,
+    S: HasCorpus + HasMetadata + HasRand,
+{
+    fn on_add(&mut self, state: &mut S, id: CorpusId) -> Result<(), Error> {
+        self.base.on_add(state, id)?;
+        self.add_entry(state, id)?;
+        let meta = state.metadata_mut::<ProtocolStateSchedulerMetadata>()?;
+        if let Some(current) = meta.current {
+            *meta.discovered.entry(current).or_insert(0) += 1;
+        }
+        Ok(())
+    }
+
+    fn on_evaluation<OT>(
+        &mut self,
+        state: &mut S,
+        input: &<S::Corpus as Corpus>::Input,
+        observers: &OT,
+    ) -> Result<(), Error>
+    where
+        OT: MatchName,
+    {
+        self.base.on_evaluation(state, input, observers)
+    }
+
+    fn next(&mut self, state: &mut S) -> Result<CorpusId, Error> {
+        let Some(selected) = Self::select_state(state) else {
+            return self.base.next(state);
+        };
+
+        let meta = state.metadata_mut::<ProtocolStateSchedulerMetadata>()?;
+        *meta.selected.entry(selected).or_insert(0) += 1;
+        meta.current = Some(selected);
+        let candidates = meta.entries(selected).to_vec();
+
+        let mut next = None;
+        let mut least_scheduled = usize::MAX;
+        for id in candidates {
+            let scheduled = state.corpus().get(id)?.borrow().scheduled_count();
+            if scheduled < least_scheduled {
+                least_scheduled = scheduled;
+                next = Some(id);
+            }
+        }
+        let Some(id) = next else {
+            return self.base.next(state);
+        };
+        self.base.set_current_scheduled(state, Some(id))?;
+        Ok(id)
+    }
+
+    fn set_current_scheduled(
+        &mut self,
+        _state: &mut S,
+        _next_id: Option<CorpusId>,
+    ) -> Result<(), Error> {
+        // We do nothing here, the inner scheduler will take care of it
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::rands::StdRand;
+
+    use super::{state_score, ProtocolStateScheduler, ProtocolStateSchedulerMetadata};
+    use crate::{
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        feedbacks::{ConstFeedback, ProtocolStatesMetadata},
+        inputs::BytesInput,
+        observers::ProtocolStateGraphMetadata,
+        schedulers::{QueueScheduler, Scheduler},
+        state::{HasCorpus, StdState},
+        HasMetadata,
+    };
+
+    #[test]
+    fn test_state_score() {
+        // unselected states get the highest score
+        assert!(state_score(100, 0, 0) > state_score(100, 1, 0));
+        // rarely visited states beat often visited ones
+        assert!(state_score(10, 2, 0) > state_score(10_000, 2, 0));
+        // productive states beat unproductive ones
+        assert!(state_score(10, 2, 3) > state_score(10, 2, 0));
+    }
+
+    #[test]
+    fn test_protocol_state_scheduler() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut scheduler = ProtocolStateScheduler::new(QueueScheduler::new());
+
+        let mut graph = ProtocolStateGraphMetadata::new();
+        for _ in 0..1000 {
+            graph.record(&[220, 250]);
+        }
+        graph.record(&[220, 250, 354]);
+        state.add_metadata(graph);
+
+        let mut ids = vec![];
+        for states in [vec![220, 250], vec![220, 250, 354]] {
+            let mut testcase = Testcase::new(BytesInput::new(vec![0]));
+            testcase.add_metadata(ProtocolStatesMetadata { states });
+            let id = state.corpus_mut().add(testcase).unwrap();
+            scheduler.on_add(&mut state, id).unwrap();
+            ids.push(id);
+        }
+
+        let meta = state.metadata::<ProtocolStateSchedulerMetadata>().unwrap();
+        assert_eq!(meta.entries(220), &ids[..]);
+        assert_eq!(meta.entries(354), &ids[1..]);
+
+        // The rare state 354 gets selected more often than the others
+        let mut rare = 0;
+        for _ in 0..100 {
+            scheduler.next(&mut state).unwrap();
+            if state
+                .metadata::<ProtocolStateSchedulerMetadata>()
+                .unwrap()
+                .current()
+                == Some(354)
+            {
+                rare += 1;
+            }
+        }
+        // more than its share of a uniform selection
+        assert!(rare > 100 / 3, "rare state selected {rare} times");
+    }
+}
@@ -0,0 +1,127 @@
+//! The [`CheckpointStage`] periodically writes the whole fuzzer state to disk, see [`HasCheckpoint`].
+
+use core::{marker::PhantomData, time::Duration};
+use std::path::PathBuf;
+
+use libafl_bolts::current_time;
+
+use crate::{
+    stages::Stage,
+    state::{HasCheckpoint, UsesState},
+    Error,
+};
+
+/// The [`CheckpointStage`] writes a checkpoint of the state to disk, at most once every `interval`.
+///
+/// Restore the state with [`HasCheckpoint::load_checkpoint`], to resume the campaign after a crash of the whole host.
+#[derive(Debug, Clone)]
+pub struct CheckpointStage<E, EM, Z> {
+    /// The file the checkpoint will be written to
+    path: PathBuf,
+    /// The minimum time between two checkpoints
+    interval: Duration,
+    /// The last time we wrote a checkpoint
+    last_checkpoint: Duration,
+    phantom: PhantomData<(E, EM, Z)>,
+}
+
+impl<E, EM, Z> UsesState for CheckpointStage<E, EM, Z>
+where
+    E: UsesState,
+{
+    type State = E::State;
+}
+
+impl<E, EM, Z> Stage<E, EM, Z> for CheckpointStage<E, EM, Z>
+where
+    E: UsesState,
+    EM: UsesState<State = Self::State>,
+    Z: UsesState<State = Self::State>,
+    Self::State: HasCheckpoint,
+{
+    fn perform(
+        &mut self,
+        _fuzzer: &mut Z,
+        _executor: &mut E,
+        state: &mut Self::State,
+        _manager: &mut EM,
+    ) -> Result<(), Error> {
+        let cur = current_time();
+        if cur.checked_sub(self.last_checkpoint).unwrap_or_default() >= self.interval {
+            state.save_checkpoint(&self.path)?;
+            self.last_checkpoint = cur;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn should_restart(&mut self, _state: &mut Self::State) -> Result<bool, Error> {
+        // Not running the target so we wont't crash/timeout and, hence, don't need to restore anything
+        Ok(true)
+    }
+
+    #[inline]
+    fn clear_progress(&mut self, _state: &mut Self::State) -> Result<(), Error> {
+        // Not running the target so we wont't crash/timeout and, hence, don't need to restore anything
+        Ok(())
+    }
+}
+
+impl<E, EM, Z> CheckpointStage<E, EM, Z> {
+    /// Create a new [`CheckpointStage`], writing a checkpoint to `path` every `interval`.
+    ///
+    /// The first checkpoint is written after `interval` has passed.
+    #[must_use]
+    pub fn new<P>(path: P, interval: Duration) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        Self {
+            path: path.into(),
+            interval,
+            last_checkpoint: current_time(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// The file the checkpoint is written to
+    #[must_use]
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use libafl_bolts::rands::StdRand;
+
+    use crate::{
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        inputs::BytesInput,
+        state::{HasCheckpoint, HasCorpus, HasExecutions, StdState},
+    };
+
+    #[test]
+    fn test_checkpoint_roundtrip() {
+        type State =
+            StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+        let path = env::temp_dir().join(format!("libafl_checkpoint_{}", std::process::id()));
+        assert!(State::load_checkpoint(&path).unwrap().is_none());
+
+        let mut state = StdState::nop::<BytesInput>().unwrap();
+        state
+            .corpus_mut()
+            .add(Testcase::new(BytesInput::new(b"checkpoint".to_vec())))
+            .unwrap();
+        *state.executions_mut() = 1337;
+        state.save_checkpoint(&path).unwrap();
+
+        let restored = State::load_checkpoint(&path).unwrap().unwrap();
+        assert_eq!(*restored.executions(), 1337);
+        assert_eq!(restored.corpus().count(), 1);
+
+        fs::remove_file(path).unwrap();
+    }
+}
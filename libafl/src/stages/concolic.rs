@@ -26,6 +26,8 @@ use crate::{
 };
 #[cfg(feature = "concolic_mutation")]
 use crate::{
+    corpus::HasCurrentCorpusId,
+    feedbacks::ProvenanceMetadata,
     inputs::HasMutatorBytes,
     mark_feature_time,
     observers::concolic::{ConcolicMetadata, SymExpr, SymExprRef},
@@ -424,14 +426,18 @@ where
         });
 
         if let Some(mutations) = mutations {
-            for mutation in mutations {
-                let mut input_copy = state.current_input_cloned()?;
-                for (index, new_byte) in mutation {
-                    input_copy.bytes_mut()[index] = new_byte;
+            let parent_id = state.current_corpus_id()?;
+            ProvenanceMetadata::with_stage(state, &self.name, None, parent_id, |state| {
+                for mutation in mutations {
+                    let mut input_copy = state.current_input_cloned()?;
+                    for (index, new_byte) in mutation {
+                        input_copy.bytes_mut()[index] = new_byte;
+                    }
+                    // Time is measured directly the `evaluate_input` function
+                    fuzzer.evaluate_input(state, executor, manager, input_copy)?;
                 }
-                // Time is measured directly the `evaluate_input` function
-                fuzzer.evaluate_input(state, executor, manager, input_copy)?;
-            }
+                Ok(())
+            })?;
         }
         Ok(())
     }
@@ -0,0 +1,679 @@
+//! Deterministic, delta-debugging based minimization of single inputs.
+//!
+//! [`ddmin`] implements the classic ddmin algorithm by Zeller and Hildebrandt on a sequence of units.
+//! A [`DdminReducer`] breaks an input down into one or more such sequences, from coarse to fine
+//! (for example the parts of a [`crate::inputs::MultipartInput`], then the bytes of each part),
+//! and [`ddmin_minimize`] minimizes them one after the other, akin to hierarchical delta debugging.
+//! The [`DdminStage`] uses them to minimize corpus entries, as a deterministic alternative to the
+//! [`crate::stages::StdTMinMutationalStage`].
+
+use alloc::{
+    borrow::{Cow, ToOwned},
+    string::ToString,
+    vec::Vec,
+};
+use core::{fmt::Debug, marker::PhantomData};
+
+#[cfg(feature = "nautilus")]
+use libafl_bolts::rands::StdRand;
+use libafl_bolts::{HasLen, Named};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "multipart_inputs")]
+use crate::inputs::MultipartInput;
+#[cfg(feature = "nautilus")]
+use crate::{
+    common::nautilus::grammartec::{
+        newtypes::{NTermId, NodeId},
+        tree::{Tree, TreeLike},
+    },
+    generators::nautilus::NautilusContext,
+    inputs::NautilusInput,
+};
+use crate::{
+    corpus::{Corpus, HasCurrentCorpusId, Testcase},
+    executors::HasObservers,
+    feedbacks::{Feedback, FeedbackFactory},
+    inputs::{GramatronInput, HasMutatorBytes},
+    observers::ObserversTuple,
+    schedulers::RemovableScheduler,
+    stages::{RetryCountRestartHelper, Stage},
+    state::{HasCorpus, HasCurrentTestcase, UsesState},
+    Error, ExecutesInput, HasFeedback, HasMetadata, HasNamedMetadata, HasScheduler,
+};
+
+/// Minimizes the sequence of `units` units, using the ddmin algorithm.
+///
+/// `test` is called with the (sorted) indices of the units to keep, and returns `true` if the
+/// input built from them still shows the behavior of interest. The result is 1-minimal:
+/// removing any single unit from it makes `test` fail.
+pub fn ddmin<T>(units: usize, mut test: T) -> Result<Vec<usize>, Error>
+where
+    T: FnMut(&[usize]) -> Result<bool, Error>,
+{
+    let mut current: Vec<usize> = (0..units).collect();
+    if current.is_empty() {
+        return Ok(current);
+    }
+    if test(&[])? {
+        return Ok(Vec::new());
+    }
+
+    let mut granularity = 2;
+    while current.len() >= 2 {
+        let chunk_len = current.len().div_ceil(granularity);
+        let chunks: Vec<&[usize]> = current.chunks(chunk_len).collect();
+
+        let mut reduced = None;
+        // Reduce to a subset
+        for chunk in &chunks {
+            if test(chunk)? {
+                reduced = Some((chunk.to_vec(), 2));
+                break;
+            }
+        }
+        // Reduce to a complement. With two chunks, the complements are the subsets we just tested.
+        if reduced.is_none() && chunks.len() > 2 {
+            for skip in 0..chunks.len() {
+                let complement: Vec<usize> = chunks
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| *i != skip)
+                    .flat_map(|(_, chunk)| chunk.iter().copied())
+                    .collect();
+                if test(&complement)? {
+                    reduced = Some((complement, (granularity - 1).max(2)));
+                    break;
+                }
+            }
+        }
+
+        match reduced {
+            Some((next, next_granularity)) => {
+                current = next;
+                granularity = next_granularity;
+            }
+            None if granularity >= current.len() => break,
+            None => granularity = (granularity * 2).min(current.len()),
+        }
+    }
+    Ok(current)
+}
+
+/// Breaks an input down into sequences of units, to be minimized by [`ddmin`].
+///
+/// Sequences are minimized in order, and recounted after each step, so later sequences may depend on
+/// the result of minimizing earlier ones.
+pub trait DdminReducer<I> {
+    /// The number of sequences of units in the input
+    fn sequences(&self, input: &I) -> usize;
+
+    /// The number of units in the sequence `seq` of the input
+    fn units(&self, input: &I, seq: usize) -> usize;
+
+    /// Builds a new input, keeping only the given (sorted) units of the sequence `seq`.
+    ///
+    /// Returns `None` if no such input can be built.
+    fn keep_units(&self, input: &I, seq: usize, keep: &[usize]) -> Option<I>;
+}
+
+/// Minimizes the input by running [`ddmin`] on each sequence of the [`DdminReducer`].
+///
+/// `test` returns `true` if a candidate input still shows the behavior of interest.
+pub fn ddmin_minimize<I, R, T>(reducer: &R, input: I, mut test: T) -> Result<I, Error>
+where
+    R: DdminReducer<I>,
+    T: FnMut(&I) -> Result<bool, Error>,
+{
+    let mut current = input;
+    let mut seq = 0;
+    while seq < reducer.sequences(&current) {
+        let units = reducer.units(&current, seq);
+        let keep = ddmin(units, |keep| {
+            match reducer.keep_units(&current, seq, keep) {
+                Some(candidate) => test(&candidate),
+                None => Ok(false),
+            }
+        })?;
+        if keep.len() < units {
+            if let Some(reduced) = reducer.keep_units(&current, seq, &keep) {
+                current = reduced;
+            }
+        }
+        seq += 1;
+    }
+    Ok(current)
+}
+
+/// A [`DdminReducer`] removing single bytes of inputs implementing [`HasMutatorBytes`]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BytesDdminReducer;
+
+impl<I> DdminReducer<I> for BytesDdminReducer
+where
+    I: HasMutatorBytes + Clone,
+{
+    fn sequences(&self, _input: &I) -> usize {
+        1
+    }
+
+    fn units(&self, input: &I, _seq: usize) -> usize {
+        input.bytes().len()
+    }
+
+    fn keep_units(&self, input: &I, _seq: usize, keep: &[usize]) -> Option<I> {
+        let bytes: Vec<u8> = keep.iter().map(|&i| input.bytes()[i]).collect();
+        let mut reduced = input.clone();
+        reduced.resize(bytes.len(), 0);
+        reduced.bytes_mut().copy_from_slice(&bytes);
+        Some(reduced)
+    }
+}
+
+/// A [`DdminReducer`] removing terminals of a [`GramatronInput`]
+///
+/// Only whole runs of terminals starting and ending in the same automaton state are removed,
+/// so the result is still accepted by the automaton. As the state reached after the last terminal
+/// is not part of the input, trailing terminals are never removed.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GramatronDdminReducer;
+
+impl DdminReducer<GramatronInput> for GramatronDdminReducer {
+    fn sequences(&self, _input: &GramatronInput) -> usize {
+        1
+    }
+
+    fn units(&self, input: &GramatronInput, _seq: usize) -> usize {
+        input.terminals().len()
+    }
+
+    fn keep_units(
+        &self,
+        input: &GramatronInput,
+        _seq: usize,
+        keep: &[usize],
+    ) -> Option<GramatronInput> {
+        let terminals = input.terminals();
+        let mut kept = Vec::with_capacity(keep.len());
+        // The first terminal not kept or removed yet
+        let mut next = 0;
+        for &i in keep {
+            // The removed run `next..i` has to lead back to the state it started in
+            if i > next && terminals[next].state != terminals[i].state {
+                return None;
+            }
+            kept.push(terminals[i].clone());
+            next = i + 1;
+        }
+        if next < terminals.len() {
+            return None;
+        }
+        Some(GramatronInput::new(kept))
+    }
+}
+
+/// A [`DdminReducer`] for [`MultipartInput`]s: first removes whole parts,
+/// then minimizes each remaining part using the inner reducer.
+#[cfg(feature = "multipart_inputs")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MultipartDdminReducer<R> {
+    inner: R,
+}
+
+#[cfg(feature = "multipart_inputs")]
+impl<R> MultipartDdminReducer<R> {
+    /// Creates a new [`MultipartDdminReducer`], minimizing each part using `inner`
+    pub fn new(inner: R) -> Self {
+        Self { inner }
+    }
+
+    /// Maps a sequence (other than the first) to the part it belongs to, and the sequence in that part
+    fn locate<I>(&self, input: &MultipartInput<I>, seq: usize) -> Option<(usize, usize)>
+    where
+        R: DdminReducer<I>,
+    {
+        let mut seq = seq.checked_sub(1)?;
+        for (idx, part) in input.parts().iter().enumerate() {
+            let sequences = self.inner.sequences(part);
+            if seq < sequences {
+                return Some((idx, seq));
+            }
+            seq -= sequences;
+        }
+        None
+    }
+}
+
+#[cfg(feature = "multipart_inputs")]
+impl<I, R> DdminReducer<MultipartInput<I>> for MultipartDdminReducer<R>
+where
+    I: Clone,
+    R: DdminReducer<I>,
+{
+    fn sequences(&self, input: &MultipartInput<I>) -> usize {
+        1 + input
+            .parts()
+            .iter()
+            .map(|part| self.inner.sequences(part))
+            .sum::<usize>()
+    }
+
+    fn units(&self, input: &MultipartInput<I>, seq: usize) -> usize {
+        if seq == 0 {
+            return input.parts().len();
+        }
+        self.locate(input, seq)
+            .map_or(0, |(idx, seq)| self.inner.units(&input.parts()[idx], seq))
+    }
+
+    fn keep_units(
+        &self,
+        input: &MultipartInput<I>,
+        seq: usize,
+        keep: &[usize],
+    ) -> Option<MultipartInput<I>> {
+        if seq == 0 {
+            let mut reduced = MultipartInput::new();
+            for &idx in keep {
+                reduced.add_part(input.names()[idx].clone(), input.parts()[idx].clone());
+            }
+            return Some(reduced);
+        }
+        let (idx, seq) = self.locate(input, seq)?;
+        let part = self.inner.keep_units(&input.parts()[idx], seq, keep)?;
+        let mut reduced = input.clone();
+        *reduced.part_mut(idx)? = part;
+        Some(reduced)
+    }
+}
+
+/// A [`DdminReducer`] for [`NautilusInput`]s, replacing subtrees with the smallest subtree
+/// of the same nonterminal, one tree level after the other.
+#[cfg(feature = "nautilus")]
+#[derive(Debug, Clone, Copy)]
+pub struct NautilusDdminReducer<'a> {
+    context: &'a NautilusContext,
+}
+
+#[cfg(feature = "nautilus")]
+impl<'a> NautilusDdminReducer<'a> {
+    /// Creates a new [`NautilusDdminReducer`] for trees of the given grammar
+    #[must_use]
+    pub fn new(context: &'a NautilusContext) -> Self {
+        Self { context }
+    }
+
+    /// The nodes at the given depth which are larger than the smallest subtree for their nonterminal
+    fn reducible_nodes(self, tree: &Tree, depth: usize) -> Vec<usize> {
+        let ctx = &self.context.ctx;
+        let mut depths = Vec::with_capacity(tree.size());
+        let mut nodes = Vec::new();
+        for i in 0..tree.size() {
+            let node_depth = tree
+                .get_parent(NodeId::from(i))
+                .map_or(0, |parent| depths[parent.to_i()] + 1);
+            depths.push(node_depth);
+            let nt = tree.get_nonterm_id(NodeId::from(i), ctx);
+            if node_depth == depth
+                && tree.subtree_size(NodeId::from(i)) > ctx.get_min_len_for_nt(nt)
+            {
+                nodes.push(i);
+            }
+        }
+        nodes
+    }
+
+    /// The smallest subtree for the given nonterminal, generated deterministically
+    fn smallest_tree(self, nt: NTermId) -> Tree {
+        let ctx = &self.context.ctx;
+        let mut tree = Tree::from_rule_vec(Vec::new(), ctx);
+        tree.generate_from_nt(
+            &mut StdRand::with_seed(0),
+            nt,
+            ctx.get_min_len_for_nt(nt),
+            ctx,
+        );
+        tree
+    }
+}
+
+#[cfg(feature = "nautilus")]
+impl DdminReducer<NautilusInput> for NautilusDdminReducer<'_> {
+    fn sequences(&self, input: &NautilusInput) -> usize {
+        let tree = input.tree();
+        let mut depths: Vec<usize> = Vec::with_capacity(tree.size());
+        for i in 0..tree.size() {
+            let depth = tree
+                .get_parent(NodeId::from(i))
+                .map_or(0, |parent| depths[parent.to_i()] + 1);
+            depths.push(depth);
+        }
+        depths.iter().max().map_or(0, |max| max + 1)
+    }
+
+    fn units(&self, input: &NautilusInput, seq: usize) -> usize {
+        self.reducible_nodes(input.tree(), seq).len()
+    }
+
+    fn keep_units(
+        &self,
+        input: &NautilusInput,
+        seq: usize,
+        keep: &[usize],
+    ) -> Option<NautilusInput> {
+        let ctx = &self.context.ctx;
+        let tree = input.tree();
+        let nodes = self.reducible_nodes(tree, seq);
+        let mut replaced = nodes
+            .iter()
+            .enumerate()
+            .filter(|(unit, _)| keep.binary_search(unit).is_err())
+            .map(|(_, node)| *node)
+            .peekable();
+
+        let mut rules = Vec::with_capacity(tree.size());
+        let mut i = 0;
+        while i < tree.size() {
+            if replaced.next_if_eq(&i).is_some() {
+                let smallest = self.smallest_tree(tree.get_nonterm_id(NodeId::from(i), ctx));
+                rules.extend_from_slice(&smallest.rules);
+                i += tree.subtree_size(NodeId::from(i));
+            } else {
+                rules.push(tree.rules[i].clone());
+                i += 1;
+            }
+        }
+        Some(NautilusInput::new(Tree::from_rule_vec(rules, ctx)))
+    }
+}
+
+/// Marks a corpus entry as already minimized by a [`DdminStage`]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DdminMetadata {
+    /// The length of the entry before minimization
+    pub original_len: usize,
+}
+
+libafl_bolts::impl_serdeany!(DdminMetadata);
+
+/// A stage minimizing each corpus entry once, deterministically, using [`ddmin_minimize`].
+///
+/// A smaller input is only accepted if the feedback created by the [`FeedbackFactory`] considers it interesting,
+/// for example a [`crate::stages::MapEqualityFeedback`], requiring the same coverage map as the original entry.
+#[derive(Debug, Clone)]
+pub struct DdminStage<E, EM, F, FF, R, Z> {
+    name: Cow<'static, str>,
+    reducer: R,
+    factory: FF,
+    phantom: PhantomData<(E, EM, F, Z)>,
+}
+
+/// The counter for giving this stage unique id
+static mut DDMIN_STAGE_ID: usize = 0;
+/// The name for ddmin stage
+pub static DDMIN_STAGE_NAME: &str = "ddmin";
+
+impl<E, EM, F, FF, R, Z> DdminStage<E, EM, F, FF, R, Z> {
+    /// Creates a new [`DdminStage`], breaking inputs down using `reducer`
+    pub fn new(reducer: R, factory: FF) -> Self {
+        // unsafe but impossible that you create two threads both instantiating this instance
+        let stage_id = unsafe {
+            let ret = DDMIN_STAGE_ID;
+            DDMIN_STAGE_ID += 1;
+            ret
+        };
+        Self {
+            name: Cow::Owned(DDMIN_STAGE_NAME.to_owned() + ":" + stage_id.to_string().as_str()),
+            reducer,
+            factory,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<E, EM, F, FF, R, Z> Named for DdminStage<E, EM, F, FF, R, Z> {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<E, EM, F, FF, R, Z> UsesState for DdminStage<E, EM, F, FF, R, Z>
+where
+    Z: UsesState,
+{
+    type State = Z::State;
+}
+
+impl<E, EM, F, FF, R, Z> Stage<E, EM, Z> for DdminStage<E, EM, F, FF, R, Z>
+where
+    Z: HasScheduler + ExecutesInput<E, EM> + HasFeedback,
+    Z::Scheduler: RemovableScheduler<Self::Input, Self::State>,
+    Z::Feedback: Feedback<EM, Self::Input, E::Observers, Self::State>,
+    E: HasObservers + UsesState<State = Z::State>,
+    E::Observers: ObserversTuple<Self::Input, Self::State>,
+    EM: UsesState<State = Self::State>,
+    FF: FeedbackFactory<F, E::Observers>,
+    F: Feedback<EM, Self::Input, E::Observers, Self::State>,
+    R: DdminReducer<Self::Input>,
+    Self::Input: Clone + HasLen,
+    Z::State: HasCorpus + HasCurrentTestcase + HasNamedMetadata,
+    <Z::State as HasCorpus>::Corpus: Corpus<Input = Self::Input>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut Self::State,
+        manager: &mut EM,
+    ) -> Result<(), Error> {
+        let Some(corpus_id) = state.current_corpus_id()? else {
+            return Err(Error::illegal_state(
+                "state is not currently processing a corpus index",
+            ));
+        };
+        if state.current_testcase()?.has_metadata::<DdminMetadata>() {
+            return Ok(());
+        }
+
+        let base = state.current_input_cloned()?;
+        let original_len = base.len();
+        fuzzer.execute_input(state, executor, manager, &base)?;
+        let mut feedback = self.factory.create_feedback(&*executor.observers());
+
+        let minimized = ddmin_minimize(&self.reducer, base, |input| {
+            if input.len() >= original_len {
+                return Ok(false);
+            }
+            let exit_kind = fuzzer.execute_input(state, executor, manager, input)?;
+            let observers = executor.observers();
+            feedback.is_interesting(state, manager, input, &*observers, &exit_kind)
+        })?;
+
+        if minimized.len() >= original_len {
+            state
+                .current_testcase_mut()?
+                .add_metadata(DdminMetadata { original_len });
+            return Ok(());
+        }
+
+        let exit_kind = fuzzer.execute_input(state, executor, manager, &minimized)?;
+        let observers = executor.observers();
+        // assumption: the minimized input behaves like the original one, which is already in the corpus
+        fuzzer.feedback_mut().is_interesting(
+            state,
+            manager,
+            &minimized,
+            &*observers,
+            &exit_kind,
+        )?;
+        let mut testcase = Testcase::from(minimized);
+        fuzzer
+            .feedback_mut()
+            .append_metadata(state, manager, &*observers, &mut testcase)?;
+        testcase.add_metadata(DdminMetadata { original_len });
+        let prev = state.corpus_mut().replace(corpus_id, testcase)?;
+        fuzzer.scheduler_mut().on_replace(state, corpus_id, &prev)?;
+
+        Ok(())
+    }
+
+    fn should_restart(&mut self, state: &mut Self::State) -> Result<bool, Error> {
+        // Minimization is deterministic, if the target took us down once it will do so again
+        RetryCountRestartHelper::no_retry(state, &self.name)
+    }
+
+    fn clear_progress(&mut self, state: &mut Self::State) -> Result<(), Error> {
+        RetryCountRestartHelper::clear_progress(state, &self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::{ddmin, ddmin_minimize, BytesDdminReducer, DdminReducer, GramatronDdminReducer};
+    use crate::inputs::{BytesInput, GramatronInput, HasMutatorBytes, Terminal};
+
+    #[test]
+    fn test_ddmin() {
+        // The behavior shows as long as units 3 and 7 are present
+        let mut tests = 0;
+        let keep = ddmin(10, |keep| {
+            tests += 1;
+            Ok(keep.contains(&3) && keep.contains(&7))
+        })
+        .unwrap();
+        assert_eq!(keep, [3, 7]);
+
+        // Deterministic
+        let mut again = 0;
+        ddmin(10, |keep| {
+            again += 1;
+            Ok(keep.contains(&3) && keep.contains(&7))
+        })
+        .unwrap();
+        assert_eq!(tests, again);
+
+        assert!(ddmin(5, |_| Ok(true)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_ddmin_bytes() {
+        let input = BytesInput::new(b"xxxxxxCRASHxxxxxxxxxx".to_vec());
+        let minimized = ddmin_minimize(&BytesDdminReducer, input, |input| {
+            Ok(input.bytes().windows(5).any(|w| w == b"CRASH"))
+        })
+        .unwrap();
+        assert_eq!(minimized.bytes(), b"CRASH");
+    }
+
+    #[test]
+    fn test_ddmin_gramatron() {
+        // An automaton looping on `x` in state 0 and on `c` in state 1, with `(` and `)` switching between them
+        let terminals: Vec<_> = [(0, "x"), (0, "x"), (0, "("), (1, "c"), (1, "c"), (1, ")")]
+            .iter()
+            .map(|(state, symbol)| Terminal::new(*state, 0, (*symbol).into()))
+            .collect();
+        let input = GramatronInput::new(terminals);
+
+        // Removing `(c` would leave `)` in the wrong state, removing the final `)` is never allowed
+        assert!(GramatronDdminReducer
+            .keep_units(&input, 0, &[0, 1, 4, 5])
+            .is_none());
+        assert!(GramatronDdminReducer
+            .keep_units(&input, 0, &[0, 1, 2, 3, 4])
+            .is_none());
+
+        let minimized = ddmin_minimize(&GramatronDdminReducer, input, |input| {
+            let mut bytes = Vec::new();
+            input.unparse(&mut bytes);
+            Ok(bytes.contains(&b'(') && bytes.contains(&b')'))
+        })
+        .unwrap();
+        let mut bytes = Vec::new();
+        minimized.unparse(&mut bytes);
+        assert_eq!(bytes, b"()");
+        let states: Vec<_> = minimized.terminals().iter().map(|t| t.state).collect();
+        assert_eq!(states, [0, 1]);
+    }
+
+    #[cfg(feature = "multipart_inputs")]
+    #[test]
+    fn test_ddmin_multipart() {
+        use super::MultipartDdminReducer;
+        use crate::inputs::MultipartInput;
+
+        let mut input = MultipartInput::new();
+        input.add_part("junk".into(), BytesInput::new(b"xxxx".to_vec()));
+        input.add_part("crash".into(), BytesInput::new(b"xxCRASHxx".to_vec()));
+        input.add_part("more junk".into(), BytesInput::new(b"yy".to_vec()));
+
+        let reducer = MultipartDdminReducer::new(BytesDdminReducer);
+        let minimized = ddmin_minimize(&reducer, input, |input| {
+            Ok(input
+                .parts()
+                .iter()
+                .any(|part| part.bytes().windows(5).any(|w| w == b"CRASH")))
+        })
+        .unwrap();
+        assert_eq!(minimized.names(), ["crash"]);
+        assert_eq!(minimized.parts()[0].bytes(), b"CRASH");
+    }
+
+    #[cfg(feature = "nautilus")]
+    #[test]
+    fn test_ddmin_nautilus() {
+        use alloc::string::ToString;
+
+        use libafl_bolts::rands::StdRand;
+
+        use super::NautilusDdminReducer;
+        use crate::{
+            common::nautilus::grammartec::tree::TreeLike, generators::nautilus::NautilusContext,
+            inputs::NautilusInput,
+        };
+
+        let rules = [
+            ["EXPR", "({EXPR})"],
+            ["EXPR", "{EXPR}+{EXPR}"],
+            ["EXPR", "1"],
+            ["EXPR", "CRASH"],
+        ]
+        .map(|rule| rule.map(ToString::to_string).to_vec());
+        let context = NautilusContext::new(8, &rules);
+        let unparse = |input: &NautilusInput| {
+            let mut bytes = Vec::new();
+            input.unparse(&context, &mut bytes);
+            bytes
+        };
+        let crashes = |bytes: &[u8]| bytes.windows(5).any(|w| w == b"CRASH");
+
+        // Generate a large enough tree containing the crash
+        let start = context.ctx.nt_id("START");
+        let input = (0..1024)
+            .map(|seed| {
+                let mut input = NautilusInput::empty();
+                input.tree_mut().generate_from_nt(
+                    &mut StdRand::with_seed(seed),
+                    start,
+                    16,
+                    &context.ctx,
+                );
+                input
+            })
+            .find(|input| input.tree().size() > 8 && crashes(&unparse(input)))
+            .unwrap();
+        let original = unparse(&input);
+
+        let reducer = NautilusDdminReducer::new(&context);
+        let minimized =
+            ddmin_minimize(&reducer, input, |input| Ok(crashes(&unparse(input)))).unwrap();
+        let bytes = unparse(&minimized);
+        assert!(crashes(&bytes));
+        assert!(bytes.len() < original.len());
+        // Minimizing again does not change anything
+        let again =
+            ddmin_minimize(&reducer, minimized, |input| Ok(crashes(&unparse(input)))).unwrap();
+        assert_eq!(unparse(&again), bytes);
+    }
+}
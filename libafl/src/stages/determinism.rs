@@ -0,0 +1,661 @@
+//! The determinism stage finds out why a corpus entry has unstable coverage.
+//!
+//! The [`CalibrationStage`](crate::stages::CalibrationStage) only tells which map entries are
+//! unstable. This stage re-runs inputs with unstable coverage while pinning one
+//! [`NondeterminismSource`] after the other, and reports the sources that make the coverage
+//! stable again. The sources are pinned in the target by the `LD_PRELOAD` shim of
+//! `libafl_targets` (feature `determinism`), which reads what to pin from the file of a
+//! [`DeterminismControl`] at the start of every process.
+//!
+//! The stage works with executors starting a new process for each run, like the
+//! [`CommandExecutor`](crate::executors::CommandExecutor) or the
+//! [`ForkserverExecutor`](crate::executors::ForkserverExecutor).
+
+use alloc::{
+    borrow::{Cow, ToOwned},
+    string::ToString,
+    vec::Vec,
+};
+use core::{fmt, marker::PhantomData};
+use std::{
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use libafl_bolts::{impl_serdeany, tuples::Handle, Named};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::Corpus,
+    executors::{Executor, ExitKind, HasObservers},
+    feedbacks::HasObserverHandle,
+    observers::{MapObserver, ObserversTuple},
+    stages::{calibrate::UnstableEntriesMetadata, RetryCountRestartHelper, Stage},
+    state::{HasCorpus, HasCurrentTestcase, UsesState},
+    Error, HasMetadata, HasNamedMetadata,
+};
+
+/// The environment variable telling the shim where to find the [`DeterminismControl`] file
+pub const DETERMINISM_CONTROL_ENV: &str = "LIBAFL_DETERMINISM_CONTROL";
+
+/// Default name for [`DeterminismStage`]
+pub const DETERMINISM_STAGE_NAME: &str = "determinism";
+
+/// The default number of runs to tell if an input is stable
+const DEFAULT_DETERMINISM_RUNS: usize = 4;
+
+/// A source of nondeterminism the shim can pin
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum NondeterminismSource {
+    /// `time`, `gettimeofday` and `clock_gettime` follow a fixed timeline
+    Time,
+    /// `getrandom`, `getentropy`, `rand` and `random` return a fixed sequence, seeds are ignored
+    Random,
+    /// `sched_yield` returns without giving up the CPU
+    Scheduling,
+}
+
+impl NondeterminismSource {
+    /// All sources, in the order they are tried
+    pub const ALL: [Self; 3] = [Self::Time, Self::Random, Self::Scheduling];
+
+    /// The bit of this source in the mask of the shim
+    #[must_use]
+    pub fn bit(self) -> u32 {
+        // Keep in sync with the `PIN_*` defines of `determinism.c` in `libafl_targets`
+        match self {
+            Self::Time => 1,
+            Self::Random => 2,
+            Self::Scheduling => 4,
+        }
+    }
+
+    /// The mask pinning all `sources`
+    #[must_use]
+    pub fn mask(sources: &[Self]) -> u32 {
+        sources.iter().fold(0, |mask, source| mask | source.bit())
+    }
+
+    /// The sources pinned by `mask`
+    #[must_use]
+    pub fn from_mask(mask: u32) -> Vec<Self> {
+        Self::ALL
+            .into_iter()
+            .filter(|source| mask & source.bit() != 0)
+            .collect()
+    }
+}
+
+impl fmt::Display for NondeterminismSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Time => write!(f, "time"),
+            Self::Random => write!(f, "randomness"),
+            Self::Scheduling => write!(f, "scheduling"),
+        }
+    }
+}
+
+/// Tells the shim in the target which [`NondeterminismSource`]s to pin.
+///
+/// The mask is written to a file, that the shim reads when a new process starts.
+/// Pass [`DeterminismControl::env`] to the executor, together with the shim as `LD_PRELOAD`.
+#[derive(Debug, Clone)]
+pub struct DeterminismControl {
+    path: PathBuf,
+    mask: u32,
+}
+
+impl DeterminismControl {
+    /// Creates a new [`DeterminismControl`] writing to the file at `path`, with nothing pinned
+    pub fn new<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let mut control = Self {
+            path: path.as_ref().to_owned(),
+            mask: 0,
+        };
+        control.set_mask(0)?;
+        Ok(control)
+    }
+
+    /// The environment variable to pass to the target, so the shim finds the control file
+    #[must_use]
+    pub fn env(&self) -> (&'static str, &OsStr) {
+        (DETERMINISM_CONTROL_ENV, self.path.as_os_str())
+    }
+
+    /// The path of the control file
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The mask of the currently pinned sources
+    #[must_use]
+    pub fn mask(&self) -> u32 {
+        self.mask
+    }
+
+    /// The currently pinned sources
+    #[must_use]
+    pub fn pinned(&self) -> Vec<NondeterminismSource> {
+        NondeterminismSource::from_mask(self.mask)
+    }
+
+    /// Pins the sources in `mask` for the next runs, and unpins all others
+    pub fn set_mask(&mut self, mask: u32) -> Result<(), Error> {
+        fs::write(&self.path, format!("{mask}\n"))?;
+        self.mask = mask;
+        Ok(())
+    }
+
+    /// Pins exactly `sources` for the next runs
+    pub fn pin(&mut self, sources: &[NondeterminismSource]) -> Result<(), Error> {
+        self.set_mask(NondeterminismSource::mask(sources))
+    }
+}
+
+/// What the [`DeterminismStage`] found out about a corpus entry
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeterminismMetadata {
+    divergent_entries: Vec<usize>,
+    culprits: Vec<NondeterminismSource>,
+    explained: bool,
+}
+
+impl_serdeany!(DeterminismMetadata);
+
+impl DeterminismMetadata {
+    /// The map entries that differed between runs, with the sources pinned for the campaign
+    #[must_use]
+    pub fn divergent_entries(&self) -> &[usize] {
+        &self.divergent_entries
+    }
+
+    /// The sources causing the divergence.
+    ///
+    /// Each of them makes the coverage stable when pinned on its own. If none does, these are
+    /// all the sources that had to be pinned together.
+    #[must_use]
+    pub fn culprits(&self) -> &[NondeterminismSource] {
+        &self.culprits
+    }
+
+    /// `false` if the coverage diverged even with all sources pinned, so something else is at play
+    #[must_use]
+    pub fn explained(&self) -> bool {
+        self.explained
+    }
+
+    /// `true` if the coverage of the entry did not diverge at all
+    #[must_use]
+    pub fn is_stable(&self) -> bool {
+        self.divergent_entries.is_empty()
+    }
+}
+
+/// The sources the [`DeterminismStage`] pinned for the rest of the campaign
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PinnedSourcesMetadata {
+    mask: u32,
+}
+
+impl_serdeany!(PinnedSourcesMetadata);
+
+impl PinnedSourcesMetadata {
+    /// The pinned sources
+    #[must_use]
+    pub fn pinned(&self) -> Vec<NondeterminismSource> {
+        NondeterminismSource::from_mask(self.mask)
+    }
+}
+
+/// Re-runs corpus entries with unstable coverage while pinning one [`NondeterminismSource`]
+/// after the other, and adds a [`DeterminismMetadata`] naming the culprits to the testcase.
+///
+/// The stage does nothing until the [`CalibrationStage`](crate::stages::CalibrationStage) found
+/// unstable entries, so place it after the calibration.
+/// With [`DeterminismStage::with_pinning`], the culprits stay pinned for the rest of the campaign.
+#[derive(Debug)]
+pub struct DeterminismStage<C, E, O, OT> {
+    map_observer_handle: Handle<C>,
+    name: Cow<'static, str>,
+    control: DeterminismControl,
+    runs: usize,
+    pin_culprits: bool,
+    phantom: PhantomData<(E, O, OT)>,
+}
+
+impl<C, E, O, OT> UsesState for DeterminismStage<C, E, O, OT>
+where
+    E: UsesState,
+{
+    type State = E::State;
+}
+
+impl<C, E, O, OT> Named for DeterminismStage<C, E, O, OT> {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<C, E, O, OT> DeterminismStage<C, E, O, OT>
+where
+    C: AsRef<O>,
+    O: MapObserver,
+{
+    /// Creates a new [`DeterminismStage`] comparing the map of `map_feedback`,
+    /// and pinning sources in the target through `control`
+    #[must_use]
+    pub fn new<F>(map_feedback: &F, control: DeterminismControl) -> Self
+    where
+        F: HasObserverHandle<Observer = C> + Named,
+    {
+        Self {
+            map_observer_handle: map_feedback.observer_handle().clone(),
+            name: Cow::Owned(
+                DETERMINISM_STAGE_NAME.to_owned() + ":" + map_feedback.name().as_ref(),
+            ),
+            control,
+            runs: DEFAULT_DETERMINISM_RUNS,
+            pin_culprits: false,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Sets how many times an input runs with the same sources pinned, at least 2
+    #[must_use]
+    pub fn with_runs(mut self, runs: usize) -> Self {
+        self.runs = runs.max(2);
+        self
+    }
+
+    /// Keeps the culprits pinned for the rest of the campaign
+    #[must_use]
+    pub fn with_pinning(mut self, pin_culprits: bool) -> Self {
+        self.pin_culprits = pin_culprits;
+        self
+    }
+
+    /// The control used to pin the sources
+    #[must_use]
+    pub fn control(&self) -> &DeterminismControl {
+        &self.control
+    }
+}
+
+impl<C, E, O, OT> DeterminismStage<C, E, O, OT>
+where
+    C: AsRef<O>,
+    O: MapObserver,
+    E: HasObservers<Observers = OT> + UsesState,
+    OT: ObserversTuple<E::Input, E::State>,
+{
+    /// Runs `input` with the sources in `mask` pinned, and returns the map entries that diverged
+    fn divergent_entries<EM, Z>(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut E::State,
+        manager: &mut EM,
+        input: &E::Input,
+        mask: u32,
+    ) -> Result<Vec<usize>, Error>
+    where
+        E: Executor<EM, Z>,
+        EM: UsesState<State = E::State>,
+        Z: UsesState<State = E::State>,
+    {
+        self.control.set_mask(mask)?;
+
+        let mut first: Option<Vec<O::Entry>> = None;
+        let mut divergent = Vec::new();
+        for _ in 0..self.runs {
+            executor.observers_mut().pre_exec_all(state, input)?;
+            let exit_kind = executor.run_target(fuzzer, state, manager, input)?;
+            executor
+                .observers_mut()
+                .post_exec_all(state, input, &exit_kind)?;
+            // like the calibration, only compare complete runs
+            if exit_kind != ExitKind::Ok {
+                continue;
+            }
+
+            let map = executor.observers()[&self.map_observer_handle]
+                .as_ref()
+                .to_vec();
+            match &first {
+                None => first = Some(map),
+                Some(first) => {
+                    for (idx, (first, cur)) in first.iter().zip(map.iter()).enumerate() {
+                        if first != cur && !divergent.contains(&idx) {
+                            divergent.push(idx);
+                        }
+                    }
+                }
+            }
+        }
+        divergent.sort_unstable();
+        Ok(divergent)
+    }
+}
+
+impl<C, E, EM, O, OT, Z> Stage<E, EM, Z> for DeterminismStage<C, E, O, OT>
+where
+    C: AsRef<O>,
+    O: MapObserver,
+    E: Executor<EM, Z> + HasObservers<Observers = OT>,
+    EM: UsesState<State = E::State>,
+    Z: UsesState<State = E::State>,
+    OT: ObserversTuple<E::Input, E::State>,
+    E::State: HasCorpus + HasMetadata + HasNamedMetadata + HasCurrentTestcase,
+    <E::State as HasCorpus>::Corpus: Corpus<Input = E::Input>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut Self::State,
+        manager: &mut EM,
+    ) -> Result<(), Error> {
+        // Sources pinned earlier in the campaign, before a restart
+        let baseline = state
+            .metadata_map()
+            .get::<PinnedSourcesMetadata>()
+            .map_or(0, |pinned| pinned.mask);
+        if self.control.mask() != baseline {
+            self.control.set_mask(baseline)?;
+        }
+
+        if state
+            .metadata_map()
+            .get::<UnstableEntriesMetadata>()
+            .is_none_or(|unstable| unstable.unstable_entries().is_empty())
+            || state
+                .current_testcase()?
+                .has_metadata::<DeterminismMetadata>()
+        {
+            return Ok(());
+        }
+
+        let input = state.current_input_cloned()?;
+        let divergent_entries =
+            self.divergent_entries(fuzzer, executor, state, manager, &input, baseline)?;
+
+        let mut culprits = Vec::new();
+        let mut explained = true;
+        // If each culprit stabilizes the coverage alone, or only all of them together
+        let mut alone = false;
+        if !divergent_entries.is_empty() {
+            let candidates: Vec<_> = NondeterminismSource::ALL
+                .into_iter()
+                .filter(|source| baseline & source.bit() == 0)
+                .collect();
+            for source in &candidates {
+                if self
+                    .divergent_entries(
+                        fuzzer,
+                        executor,
+                        state,
+                        manager,
+                        &input,
+                        baseline | source.bit(),
+                    )?
+                    .is_empty()
+                {
+                    culprits.push(*source);
+                }
+            }
+            alone = !culprits.is_empty();
+            if !alone {
+                let all = baseline | NondeterminismSource::mask(&candidates);
+                explained = self
+                    .divergent_entries(fuzzer, executor, state, manager, &input, all)?
+                    .is_empty();
+                if explained {
+                    culprits = candidates;
+                }
+            }
+            log::info!(
+                "{} unstable map entries, caused by {}",
+                divergent_entries.len(),
+                if explained {
+                    culprits
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(" or ")
+                } else {
+                    "a source the shim does not pin".into()
+                }
+            );
+        }
+
+        let mut pinned = baseline;
+        if self.pin_culprits && !culprits.is_empty() {
+            pinned |= if alone {
+                // Pinning one of the culprits is enough
+                culprits[0].bit()
+            } else {
+                NondeterminismSource::mask(&culprits)
+            };
+            state.add_metadata(PinnedSourcesMetadata { mask: pinned });
+        }
+        self.control.set_mask(pinned)?;
+
+        state
+            .current_testcase_mut()?
+            .add_metadata(DeterminismMetadata {
+                divergent_entries,
+                culprits,
+                explained,
+            });
+        Ok(())
+    }
+
+    fn should_restart(&mut self, state: &mut Self::State) -> Result<bool, Error> {
+        // The entry is run many times, don't retry it after a crash or timeout
+        RetryCountRestartHelper::no_retry(state, &self.name)
+    }
+
+    fn clear_progress(&mut self, state: &mut Self::State) -> Result<(), Error> {
+        RetryCountRestartHelper::clear_progress(state, &self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::marker::PhantomData;
+    use std::{fs, path::PathBuf};
+
+    use libafl_bolts::{
+        rands::StdRand,
+        tuples::{tuple_list, tuple_list_type, RefIndexable},
+        AsSliceMut,
+    };
+
+    use super::{
+        DeterminismControl, DeterminismMetadata, DeterminismStage, NondeterminismSource,
+        PinnedSourcesMetadata,
+    };
+    use crate::{
+        corpus::{Corpus, HasCurrentCorpusId, InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::{Executor, ExitKind, HasObservers},
+        feedbacks::{map::MapFeedbackMetadata, ConstFeedback, MaxMapFeedback},
+        fuzzer::StdFuzzer,
+        inputs::BytesInput,
+        observers::StdMapObserver,
+        schedulers::QueueScheduler,
+        stages::{CalibrationStage, Stage},
+        state::{HasCorpus, HasCurrentTestcase, StdState, UsesState},
+        HasMetadata, HasNamedMetadata,
+    };
+
+    type Observers = tuple_list_type!(StdMapObserver<'static, u8, false>);
+
+    /// Covers a new entry on every run, unless one of the `stable_with` sources is pinned
+    struct FlakyExecutor<S> {
+        observers: Observers,
+        control: PathBuf,
+        stable_with: u32,
+        runs: u8,
+        phantom: PhantomData<S>,
+    }
+
+    impl<S> UsesState for FlakyExecutor<S>
+    where
+        S: crate::state::State,
+    {
+        type State = S;
+    }
+
+    impl<S> HasObservers for FlakyExecutor<S> {
+        type Observers = Observers;
+
+        fn observers(&self) -> RefIndexable<&Self::Observers, Self::Observers> {
+            RefIndexable::from(&self.observers)
+        }
+
+        fn observers_mut(&mut self) -> RefIndexable<&mut Self::Observers, Self::Observers> {
+            RefIndexable::from(&mut self.observers)
+        }
+    }
+
+    impl<EM, S, Z> Executor<EM, Z> for FlakyExecutor<S>
+    where
+        EM: UsesState<State = S>,
+        S: crate::state::State,
+        Z: UsesState<State = S>,
+    {
+        fn run_target(
+            &mut self,
+            _fuzzer: &mut Z,
+            _state: &mut S,
+            _mgr: &mut EM,
+            _input: &S::Input,
+        ) -> Result<ExitKind, crate::Error> {
+            let mask: u32 = fs::read_to_string(&self.control)?.trim().parse().unwrap();
+            self.runs += 1;
+            let map = self.observers.0.as_slice_mut();
+            map.fill(0);
+            map[0] = 1;
+            if mask & self.stable_with == 0 {
+                map[1 + usize::from(self.runs % 7)] = 1;
+            }
+            Ok(ExitKind::Ok)
+        }
+    }
+
+    fn analyze(stable_with: u32, pinning: bool) -> (DeterminismMetadata, u32) {
+        let control_path = std::env::temp_dir().join(format!(
+            "libafl_determinism_test_{}_{stable_with}_{pinning}",
+            std::process::id()
+        ));
+        let control = DeterminismControl::new(&control_path).unwrap();
+        let observer = StdMapObserver::owned("map", vec![0_u8; 16]);
+        let mut feedback = MaxMapFeedback::new(&observer);
+        let mut objective = ConstFeedback::new(false);
+
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        let id = corpus
+            .add(Testcase::new(BytesInput::new(b"flaky".to_vec())))
+            .unwrap();
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        state.set_corpus_id(id).unwrap();
+        // as if the map feedback had seen the entry before the calibration
+        state
+            .named_metadata_map_mut()
+            .get_mut::<MapFeedbackMetadata<u8>>("map")
+            .unwrap()
+            .num_covered_map_indexes = 2;
+
+        let mut calibration = CalibrationStage::new(&feedback);
+        let mut stage = DeterminismStage::new(&feedback, control).with_pinning(pinning);
+        let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), feedback, objective);
+        let mut executor = FlakyExecutor {
+            observers: tuple_list!(observer),
+            control: control_path.clone(),
+            stable_with,
+            runs: 0,
+            phantom: PhantomData,
+        };
+        let mut mgr = NopEventManager::new();
+
+        calibration
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)
+            .unwrap();
+        stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)
+            .unwrap();
+
+        let metadata = state
+            .current_testcase()
+            .unwrap()
+            .metadata::<DeterminismMetadata>()
+            .unwrap()
+            .clone();
+        assert_eq!(
+            state
+                .metadata_map()
+                .get::<PinnedSourcesMetadata>()
+                .map_or(0, |pinned| NondeterminismSource::mask(&pinned.pinned())),
+            stage.control().mask()
+        );
+        let mask = fs::read_to_string(&control_path).unwrap();
+        fs::remove_file(&control_path).unwrap();
+        assert_eq!(state.corpus().count(), 1);
+        (metadata, mask.trim().parse().unwrap())
+    }
+
+    #[test]
+    fn test_nondeterminism_source_mask() {
+        let mask = NondeterminismSource::mask(&[
+            NondeterminismSource::Time,
+            NondeterminismSource::Scheduling,
+        ]);
+        assert_eq!(mask, 5);
+        assert_eq!(
+            NondeterminismSource::from_mask(mask),
+            [NondeterminismSource::Time, NondeterminismSource::Scheduling]
+        );
+    }
+
+    #[test]
+    fn test_determinism_stage() {
+        let random = NondeterminismSource::Random.bit();
+        let (metadata, mask) = analyze(random, false);
+        assert!(!metadata.is_stable());
+        assert!(metadata.explained());
+        assert_eq!(metadata.culprits(), [NondeterminismSource::Random]);
+        assert_eq!(mask, 0);
+
+        let (metadata, mask) = analyze(random, true);
+        assert_eq!(metadata.culprits(), [NondeterminismSource::Random]);
+        assert_eq!(mask, random);
+
+        // never stable, the shim can't help
+        let (metadata, mask) = analyze(0, true);
+        assert!(!metadata.explained());
+        assert!(metadata.culprits().is_empty());
+        assert_eq!(mask, 0);
+    }
+}
@@ -7,13 +7,17 @@
 use core::marker::PhantomData;
 
 use crate::{
+    feedbacks::ProvenanceMetadata,
     generators::Generator,
     inputs::UsesInput,
     stages::Stage,
     state::{HasCorpus, HasRand, UsesState},
-    Error, Evaluator,
+    Error, Evaluator, HasMetadata,
 };
 
+/// The name the [`GenStage`] records in the [`ProvenanceMetadata`] of the inputs it generates
+pub const GEN_STAGE_NAME: &str = "generation";
+
 /// A [`Stage`] that generates a single input via a [`Generator`] and evaluates
 /// it using the fuzzer, possibly adding it to the corpus.
 ///
@@ -40,7 +44,7 @@ where
     E: UsesState<State = Self::State>,
     EM: UsesState<State = Self::State>,
     Z: Evaluator<E, EM>,
-    Self::State: HasCorpus + HasRand,
+    Self::State: HasCorpus + HasRand + HasMetadata,
     G: Generator<<<Self as UsesState>::State as UsesInput>::Input, Self::State>,
 {
     #[inline]
@@ -52,7 +56,9 @@ where
         manager: &mut EM,
     ) -> Result<(), Error> {
         let input = self.0.generate(state)?;
-        fuzzer.evaluate_input(state, executor, manager, input)?;
+        ProvenanceMetadata::with_stage(state, GEN_STAGE_NAME, None, None, |state| {
+            fuzzer.evaluate_input(state, executor, manager, input)
+        })?;
         Ok(())
     }
 
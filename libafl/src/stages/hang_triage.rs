@@ -0,0 +1,736 @@
+//! The [`HangTriageStage`] re-runs the timeouts found so far while sampling the program counter of the target,
+//! to tell inputs stuck in a tight loop or a blocking syscall apart from inputs that are merely slow.
+//!
+//! The classification is stored as [`HangTriageMetadata`] on the testcases of the solutions corpus.
+//! The target is sampled from `/proc` by the [`ChildPcSampler`], so it has to run in a child process:
+//! re-running a hang in the fuzzer itself would make the in-process executor report it as a new solution
+//! and restart the fuzzer. Triage the hangs of an in-process harness with an
+//! [`crate::executors::InProcessForkExecutor`] running the same harness.
+
+#[cfg(feature = "track_hit_feedbacks")]
+use alloc::borrow::Cow;
+use alloc::{sync::Arc, vec::Vec};
+use core::{
+    marker::PhantomData,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+use std::{
+    fs,
+    thread::{self, JoinHandle},
+    time::Instant,
+};
+
+use hashbrown::{HashMap, HashSet};
+use libafl_bolts::impl_serdeany;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "track_hit_feedbacks")]
+use crate::feedbacks::TIMEOUT_FEEDBACK_NAME;
+use crate::{
+    corpus::{Corpus, CorpusId},
+    executors::{resource_limits::descendants, Executor, ExitKind, HasObservers, HasTimeout},
+    observers::ObserversTuple,
+    stages::Stage,
+    state::{HasSolutions, UsesState},
+    Error, HasMetadata,
+};
+
+/// The default interval between two samples
+pub const DEFAULT_SAMPLE_INTERVAL: Duration = Duration::from_millis(2);
+
+/// The number of samples the [`InProcessPcSampler`] keeps per run, the later ones are dropped
+pub const MAX_IN_PROCESS_SAMPLES: usize = 1 << 14;
+
+/// The number of most sampled program counters kept in the [`HangTriageMetadata`]
+const HOT_PCS: usize = 8;
+
+/// Program counters are grouped by cache line, so that a loop is recognized even if the samples miss some of its instructions
+const PC_BUCKET_SHIFT: u32 = 6;
+
+/// Where the target was when it was sampled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PcSample {
+    /// The target was running at this program counter
+    Running(u64),
+    /// The target was blocked in a syscall
+    Syscall {
+        /// The syscall number
+        nr: i64,
+        /// The program counter of the syscall
+        pc: u64,
+    },
+}
+
+impl PcSample {
+    /// The program counter of this sample
+    #[must_use]
+    pub fn pc(&self) -> u64 {
+        match self {
+            Self::Running(pc) | Self::Syscall { pc, .. } => *pc,
+        }
+    }
+}
+
+/// Parses the contents of `/proc/<pid>/syscall`, returns `None` if the task is running.
+fn parse_proc_syscall(contents: &str) -> Option<PcSample> {
+    let mut fields = contents.split_whitespace();
+    let nr = fields.next()?.parse::<i64>().ok()?;
+    let pc = fields.last()?;
+    let pc = u64::from_str_radix(pc.trim_start_matches("0x"), 16).ok()?;
+    if nr < 0 {
+        // Blocked, but not in a syscall: the task is stopped, where it was running
+        Some(PcSample::Running(pc))
+    } else {
+        Some(PcSample::Syscall { nr, pc })
+    }
+}
+
+/// Samples the program counter of the target while it runs
+pub trait PcSampler {
+    /// Starts sampling, right before the target runs
+    fn start(&mut self) -> Result<(), Error>;
+
+    /// Stops sampling, right after the target ran, and returns the samples in the order they were taken
+    fn stop(&mut self) -> Result<Vec<PcSample>, Error>;
+}
+
+/// Samples the target running in a child process, for the [`crate::executors::CommandExecutor`],
+/// the [`crate::executors::ForkserverExecutor`] and the [`crate::executors::InProcessForkExecutor`].
+///
+/// Every process spawned during the run is sampled, processes that already existed (like a forkserver) are not.
+/// Processes blocked in a syscall are sampled from `/proc`, running ones are stopped for a moment with `SIGSTOP`.
+/// The sampler never waits for the processes, so it does not get in the way of the executor.
+/// Targets in persistent mode, reusing the same process for several runs, can't be told apart and are not sampled,
+/// and targets traced by the executor (like the `ptrace` backed [`crate::executors::CommandExecutor`])
+/// are only sampled while blocked in a syscall.
+#[derive(Debug)]
+pub struct ChildPcSampler {
+    interval: Duration,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<Vec<PcSample>>>,
+}
+
+impl Default for ChildPcSampler {
+    fn default() -> Self {
+        Self::new(DEFAULT_SAMPLE_INTERVAL)
+    }
+}
+
+impl ChildPcSampler {
+    /// Creates a new [`ChildPcSampler`], taking a sample every `interval`
+    #[must_use]
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            stop: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        }
+    }
+}
+
+impl PcSampler for ChildPcSampler {
+    fn start(&mut self) -> Result<(), Error> {
+        if self.thread.is_some() {
+            return Err(Error::illegal_state("The sampler is already running"));
+        }
+        #[allow(clippy::cast_possible_wrap)]
+        let fuzzer_pid = std::process::id() as i32;
+        let known: HashSet<i32> = descendants(fuzzer_pid).into_iter().collect();
+        let interval = self.interval;
+        self.stop.store(false, Ordering::SeqCst);
+        let stop = self.stop.clone();
+        self.thread = Some(thread::spawn(move || {
+            let mut samples = Vec::new();
+            while !stop.load(Ordering::SeqCst) {
+                for pid in descendants(fuzzer_pid) {
+                    if !known.contains(&pid) {
+                        samples.extend(sample_process(pid));
+                    }
+                }
+                thread::sleep(interval);
+            }
+            samples
+        }));
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<Vec<PcSample>, Error> {
+        let Some(thread) = self.thread.take() else {
+            return Err(Error::illegal_state("The sampler is not running"));
+        };
+        self.stop.store(true, Ordering::SeqCst);
+        thread
+            .join()
+            .map_err(|_| Error::unknown("The sampling thread panicked"))
+    }
+}
+
+/// How long [`sample_process`] waits for a running process to stop
+const STOP_TIMEOUT: Duration = Duration::from_millis(10);
+
+/// Parses the state of the task from the contents of `/proc/<pid>/stat`
+fn parse_proc_state(contents: &str) -> Option<char> {
+    // The name of the executable may contain anything, the state follows the last parenthesis
+    let (_, rest) = contents.rsplit_once(')')?;
+    rest.trim_start().chars().next()
+}
+
+/// Whether the process `pid` is traced, from the contents of `/proc/<pid>/status`
+fn parse_proc_traced(contents: &str) -> bool {
+    contents
+        .lines()
+        .find_map(|line| line.strip_prefix("TracerPid:"))
+        .is_some_and(|tracer| tracer.trim() != "0")
+}
+
+/// Takes one sample of the process `pid`, if it still exists.
+///
+/// A process blocked in a syscall is sampled from `/proc/<pid>/syscall`.
+/// A running one is stopped with `SIGSTOP` for a moment, until `/proc/<pid>/stat` shows it stopped, and resumed with `SIGCONT`.
+/// The executor waiting for the process does not see this, unless it waits with `WUNTRACED` or traces the process:
+/// traced processes are only sampled while blocked in a syscall.
+fn sample_process(pid: i32) -> Option<PcSample> {
+    let path = format!("/proc/{pid}/syscall");
+    let contents = fs::read_to_string(&path).ok()?;
+    if let Some(sample) = parse_proc_syscall(&contents) {
+        return Some(sample);
+    }
+    if parse_proc_traced(&fs::read_to_string(format!("/proc/{pid}/status")).ok()?) {
+        return None;
+    }
+
+    // The process is running, stop it for a moment to see where
+    // # Safety
+    // `kill` has no preconditions, the process is resumed below
+    if unsafe { libc::kill(pid, libc::SIGSTOP) } < 0 {
+        return None;
+    }
+    let stat = format!("/proc/{pid}/stat");
+    let start = Instant::now();
+    let mut sample = None;
+    while start.elapsed() < STOP_TIMEOUT {
+        match fs::read_to_string(&stat)
+            .ok()
+            .as_deref()
+            .and_then(parse_proc_state)
+        {
+            Some('T' | 't') => {
+                sample = fs::read_to_string(&path)
+                    .ok()
+                    .as_deref()
+                    .and_then(parse_proc_syscall);
+                break;
+            }
+            Some(_) => thread::yield_now(),
+            None => break,
+        }
+    }
+    // # Safety
+    // `kill` has no preconditions
+    unsafe {
+        libc::kill(pid, libc::SIGCONT);
+    }
+    sample
+}
+
+/// What kept a hanging input from finishing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HangClass {
+    /// The target spun in the same code the whole time, likely an infinite loop
+    TightLoop,
+    /// The target waited in a syscall most of the time
+    BlockingSyscall {
+        /// The number of the syscall the target waited in most often
+        syscall: i64,
+    },
+    /// The target kept reaching new code, or finished given more time: it is slow, but not stuck
+    SlowProgress,
+    /// Too few samples were taken to tell
+    Unknown,
+}
+
+/// Classifies a hang from the [`PcSample`]s taken while it ran
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HangClassifier {
+    blocking_ratio: f64,
+    new_code_ratio: f64,
+    min_samples: usize,
+}
+
+impl Default for HangClassifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HangClassifier {
+    /// Creates a new [`HangClassifier`] with the default thresholds
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            blocking_ratio: 0.8,
+            new_code_ratio: 0.1,
+            min_samples: 8,
+        }
+    }
+
+    /// The share of samples in a syscall from which a hang is a [`HangClass::BlockingSyscall`], `0.8` by default
+    #[must_use]
+    pub fn with_blocking_ratio(mut self, blocking_ratio: f64) -> Self {
+        self.blocking_ratio = blocking_ratio;
+        self
+    }
+
+    /// The share of running samples in the second half of the run that may hit code not seen in the first half,
+    /// for the hang to still be a [`HangClass::TightLoop`], `0.1` by default
+    #[must_use]
+    pub fn with_new_code_ratio(mut self, new_code_ratio: f64) -> Self {
+        self.new_code_ratio = new_code_ratio;
+        self
+    }
+
+    /// The number of samples needed to classify a hang, `8` by default
+    #[must_use]
+    pub fn with_min_samples(mut self, min_samples: usize) -> Self {
+        self.min_samples = min_samples;
+        self
+    }
+
+    /// Classifies a hang from its `samples`, in the order they were taken.
+    /// If the run `finished` before the timeout, the input is slow, whatever the samples say.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn classify(&self, samples: &[PcSample], finished: bool) -> HangClass {
+        if finished {
+            return HangClass::SlowProgress;
+        }
+        if samples.len() < self.min_samples {
+            return HangClass::Unknown;
+        }
+
+        let mut syscalls: HashMap<i64, usize> = HashMap::new();
+        let mut running = Vec::with_capacity(samples.len());
+        for sample in samples {
+            match sample {
+                PcSample::Running(pc) => running.push(pc >> PC_BUCKET_SHIFT),
+                PcSample::Syscall { nr, .. } => *syscalls.entry(*nr).or_default() += 1,
+            }
+        }
+
+        let blocked = samples.len() - running.len();
+        if blocked as f64 >= self.blocking_ratio * samples.len() as f64 {
+            let syscall = syscalls
+                .into_iter()
+                .max_by_key(|&(nr, count)| (count, -nr))
+                .map_or(-1, |(nr, _)| nr);
+            return HangClass::BlockingSyscall { syscall };
+        }
+        if running.len() < self.min_samples {
+            return HangClass::Unknown;
+        }
+
+        // A loop keeps coming back to the code it ran before, progress reaches new code
+        let (first, second) = running.split_at(running.len() / 2);
+        let seen: HashSet<u64> = first.iter().copied().collect();
+        let new_code = second.iter().filter(|pc| !seen.contains(*pc)).count();
+        if new_code as f64 <= self.new_code_ratio * second.len() as f64 {
+            HangClass::TightLoop
+        } else {
+            HangClass::SlowProgress
+        }
+    }
+}
+
+/// The result of the [`HangTriageStage`] for a solution
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HangTriageMetadata {
+    class: HangClass,
+    exit_kind: ExitKind,
+    samples: usize,
+    hot_pcs: Vec<(u64, usize)>,
+}
+
+impl_serdeany!(HangTriageMetadata);
+
+impl HangTriageMetadata {
+    /// Creates a new [`HangTriageMetadata`] for a re-run ending with `exit_kind`
+    #[must_use]
+    pub fn new(class: HangClass, exit_kind: ExitKind, samples: &[PcSample]) -> Self {
+        let mut counts: HashMap<u64, usize> = HashMap::new();
+        for sample in samples {
+            *counts.entry(sample.pc()).or_default() += 1;
+        }
+        let mut hot_pcs: Vec<(u64, usize)> = counts.into_iter().collect();
+        hot_pcs.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        hot_pcs.truncate(HOT_PCS);
+        Self {
+            class,
+            exit_kind,
+            samples: samples.len(),
+            hot_pcs,
+        }
+    }
+
+    /// What kept the input from finishing
+    #[must_use]
+    pub fn class(&self) -> HangClass {
+        self.class
+    }
+
+    /// How the re-run ended
+    #[must_use]
+    pub fn exit_kind(&self) -> ExitKind {
+        self.exit_kind
+    }
+
+    /// The number of samples taken
+    #[must_use]
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// The most sampled program counters, with their number of samples
+    #[must_use]
+    pub fn hot_pcs(&self) -> &[(u64, usize)] {
+        &self.hot_pcs
+    }
+}
+
+/// The last solution the [`HangTriageStage`] looked at
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct HangTriageProgress {
+    last_solution: Option<CorpusId>,
+}
+
+impl_serdeany!(HangTriageProgress);
+
+/// Re-runs each new solution with the given timeout while sampling the program counter of the target,
+/// and stores the resulting [`HangTriageMetadata`] in the testcase.
+///
+/// Solutions that crash on the re-run are not hangs and get no metadata.
+/// With the `track_hit_feedbacks` feature, only the solutions found by the
+/// [`crate::feedbacks::TimeoutFeedback`] are re-run, else all of them are.
+/// The stage moves past each solution once it is classified.
+///
+/// Only use this stage with executors running the target in a child process, like the
+/// [`crate::executors::CommandExecutor`], the [`crate::executors::ForkserverExecutor`]
+/// or the [`crate::executors::InProcessForkExecutor`]: the re-run must time out without evaluating the objectives.
+/// An in-process executor adds the input as a new solution when it times out, and restarts the fuzzer.
+#[derive(Debug)]
+pub struct HangTriageStage<E, EM, SA, Z> {
+    sampler: SA,
+    classifier: HangClassifier,
+    timeout: Duration,
+    phantom: PhantomData<(E, EM, Z)>,
+}
+
+impl<E, EM, SA, Z> UsesState for HangTriageStage<E, EM, SA, Z>
+where
+    E: UsesState,
+{
+    type State = E::State;
+}
+
+impl<E, EM, SA, Z> HangTriageStage<E, EM, SA, Z> {
+    /// Creates a new [`HangTriageStage`], re-running the solutions with the `sampler` for up to `timeout`.
+    /// Pick a `timeout` larger than the one of the executor, so that slow inputs get the time to finish.
+    #[must_use]
+    pub fn new(sampler: SA, timeout: Duration) -> Self {
+        Self {
+            sampler,
+            classifier: HangClassifier::new(),
+            timeout,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Sets the [`HangClassifier`] to use
+    #[must_use]
+    pub fn with_classifier(mut self, classifier: HangClassifier) -> Self {
+        self.classifier = classifier;
+        self
+    }
+}
+
+impl<E, EM, SA, Z> HangTriageStage<E, EM, SA, Z>
+where
+    E: Executor<EM, Z> + HasObservers + HasTimeout,
+    E::Observers: ObserversTuple<E::Input, E::State>,
+    EM: UsesState<State = E::State>,
+    Z: UsesState<State = E::State>,
+    SA: PcSampler,
+    E::State: HasSolutions + HasMetadata,
+    <E::State as HasSolutions>::Solutions: Corpus<Input = E::Input>,
+    E::Input: Clone,
+{
+    /// Re-runs the solution `id` and classifies it
+    fn triage(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut E::State,
+        manager: &mut EM,
+        id: CorpusId,
+    ) -> Result<(), Error> {
+        let mut testcase = state.solutions().get(id)?.borrow().clone();
+        if testcase.has_metadata::<HangTriageMetadata>() {
+            return Ok(());
+        }
+        #[cfg(feature = "track_hit_feedbacks")]
+        if !testcase
+            .hit_objectives()
+            .contains(&Cow::Borrowed(TIMEOUT_FEEDBACK_NAME))
+        {
+            return Ok(());
+        }
+        let input = testcase.load_input(state.solutions())?.clone();
+
+        let original_timeout = executor.timeout();
+        executor.set_timeout(self.timeout);
+        executor.observers_mut().pre_exec_all(state, &input)?;
+        self.sampler.start()?;
+        let exit_kind = executor.run_target(fuzzer, state, manager, &input);
+        let samples = self.sampler.stop();
+        executor.set_timeout(original_timeout);
+        let exit_kind = exit_kind?;
+        let samples = samples?;
+        executor
+            .observers_mut()
+            .post_exec_all(state, &input, &exit_kind)?;
+
+        if !matches!(exit_kind, ExitKind::Ok | ExitKind::Timeout) {
+            return Ok(());
+        }
+        let class = self
+            .classifier
+            .classify(&samples, exit_kind != ExitKind::Timeout);
+        log::info!(
+            "Solution {id} hangs with {class:?} ({} samples)",
+            samples.len()
+        );
+        testcase.add_metadata(HangTriageMetadata::new(class, exit_kind, &samples));
+        state.solutions_mut().replace(id, testcase)?;
+        Ok(())
+    }
+}
+
+impl<E, EM, SA, Z> Stage<E, EM, Z> for HangTriageStage<E, EM, SA, Z>
+where
+    E: Executor<EM, Z> + HasObservers + HasTimeout,
+    E::Observers: ObserversTuple<E::Input, E::State>,
+    EM: UsesState<State = E::State>,
+    Z: UsesState<State = E::State>,
+    SA: PcSampler,
+    E::State: HasSolutions + HasMetadata,
+    <E::State as HasSolutions>::Solutions: Corpus<Input = E::Input>,
+    E::Input: Clone,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut Self::State,
+        manager: &mut EM,
+    ) -> Result<(), Error> {
+        let mut id = match state.metadata_map().get::<HangTriageProgress>() {
+            Some(progress) => progress
+                .last_solution
+                .and_then(|last| state.solutions().next(last)),
+            None => state.solutions().first(),
+        };
+        while let Some(current) = id {
+            self.triage(fuzzer, executor, state, manager, current)?;
+            state.add_metadata(HangTriageProgress {
+                last_solution: Some(current),
+            });
+            id = state.solutions().next(current);
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn should_restart(&mut self, _state: &mut Self::State) -> Result<bool, Error> {
+        // The progress is kept in the `HangTriageProgress`
+        Ok(true)
+    }
+
+    #[inline]
+    fn clear_progress(&mut self, _state: &mut Self::State) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use core::time::Duration;
+    use std::{
+        process::{Command, Stdio},
+        thread,
+    };
+
+    use libafl_bolts::tuples::tuple_list;
+
+    use super::{
+        parse_proc_state, parse_proc_syscall, parse_proc_traced, ChildPcSampler, HangClass,
+        HangClassifier, HangTriageMetadata, HangTriageStage, PcSample, PcSampler,
+    };
+    use crate::{
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::{CommandExecutor, ExitKind},
+        feedbacks::{CrashFeedback, TimeoutFeedback},
+        fuzzer::StdFuzzer,
+        inputs::BytesInput,
+        schedulers::RandScheduler,
+        stages::Stage,
+        state::{HasSolutions, StdState},
+        HasMetadata,
+    };
+
+    #[test]
+    fn test_parse_proc_syscall() {
+        assert_eq!(parse_proc_syscall("running\n"), None);
+        assert_eq!(
+            parse_proc_syscall("-1 0x7ffc2f1e8c28 0x55d0c0de1234\n"),
+            Some(PcSample::Running(0x55d0_c0de_1234))
+        );
+        assert_eq!(
+            parse_proc_syscall("230 0x1 0x0 0x7ffc 0x0 0x0 0x0 0x7ffc2f1e8c28 0x7f0011223344\n"),
+            Some(PcSample::Syscall {
+                nr: 230,
+                pc: 0x7f00_1122_3344
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_proc_state() {
+        assert_eq!(
+            parse_proc_state("1234 (a) b (c)) T 1 1234 1234 0 -1\n"),
+            Some('T')
+        );
+        assert_eq!(parse_proc_state("1234 (sleep) S 1 1234\n"), Some('S'));
+        assert_eq!(parse_proc_state(""), None);
+        assert!(!parse_proc_traced("Name:\tsleep\nTracerPid:\t0\n"));
+        assert!(parse_proc_traced("Name:\tsleep\nTracerPid:\t42\n"));
+    }
+
+    #[test]
+    fn test_classify() {
+        let classifier = HangClassifier::new();
+        let spin: Vec<_> = (0..64).map(|i| PcSample::Running(0x1000 + i % 4)).collect();
+        assert_eq!(classifier.classify(&spin, false), HangClass::TightLoop);
+        assert_eq!(classifier.classify(&spin, true), HangClass::SlowProgress);
+        assert_eq!(classifier.classify(&spin[..2], false), HangClass::Unknown);
+
+        let progress: Vec<_> = (0..64)
+            .map(|i| PcSample::Running(0x1000 + i * 64))
+            .collect();
+        assert_eq!(
+            classifier.classify(&progress, false),
+            HangClass::SlowProgress
+        );
+
+        let mut blocked: Vec<_> = (0..60)
+            .map(|_| PcSample::Syscall { nr: 7, pc: 0x2000 })
+            .collect();
+        blocked.extend_from_slice(&spin[..4]);
+        assert_eq!(
+            classifier.classify(&blocked, false),
+            HangClass::BlockingSyscall { syscall: 7 }
+        );
+
+        let metadata = HangTriageMetadata::new(HangClass::TightLoop, ExitKind::Timeout, &blocked);
+        assert_eq!(metadata.samples(), 64);
+        assert_eq!(metadata.hot_pcs()[0], (0x2000, 60));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_child_pc_sampler() {
+        let mut sampler = ChildPcSampler::new(Duration::from_millis(5));
+        sampler.start().unwrap();
+        let mut child = Command::new("sleep")
+            .arg("1")
+            .stdin(Stdio::null())
+            .spawn()
+            .unwrap();
+        thread::sleep(Duration::from_millis(200));
+        let samples = sampler.stop().unwrap();
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        assert!(!samples.is_empty());
+        assert!(samples
+            .iter()
+            .all(|sample| matches!(sample, PcSample::Syscall { .. })));
+
+        sampler.start().unwrap();
+        let mut child = Command::new("sh")
+            .args(["-c", "while :; do :; done"])
+            .stdin(Stdio::null())
+            .spawn()
+            .unwrap();
+        thread::sleep(Duration::from_millis(200));
+        let samples = sampler.stop().unwrap();
+        child.kill().unwrap();
+        let status = child.wait().unwrap();
+
+        // The child was stopped and resumed, but it was still running when killed
+        assert_eq!(
+            std::os::unix::process::ExitStatusExt::signal(&status),
+            Some(libc::SIGKILL)
+        );
+        assert!(samples
+            .iter()
+            .any(|sample| matches!(sample, PcSample::Running(pc) if *pc != 0)));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_hang_triage_stage() {
+        let rand = libafl_bolts::rands::XkcdRand::new();
+        let corpus = InMemoryCorpus::<BytesInput>::new();
+        let mut solutions = InMemoryCorpus::new();
+        solutions
+            .add(Testcase::new(BytesInput::new(b"hang".to_vec())))
+            .unwrap();
+        let mut objective = TimeoutFeedback::new();
+        let mut feedback = CrashFeedback::new();
+        let mut state =
+            StdState::new(rand, corpus, solutions, &mut feedback, &mut objective).unwrap();
+        let mut fuzzer = StdFuzzer::new(RandScheduler::new(), feedback, objective);
+        let mut mgr = NopEventManager::new();
+
+        let mut executor = CommandExecutor::builder();
+        executor
+            .program("sleep")
+            .arg("10")
+            .timeout(Duration::from_millis(100));
+        let mut executor = executor.build(tuple_list!()).unwrap();
+
+        let mut stage = HangTriageStage::new(ChildPcSampler::default(), Duration::from_millis(300));
+        stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)
+            .unwrap();
+
+        let id = state.solutions().first().unwrap();
+        let testcase = state.solutions().get(id).unwrap().borrow();
+        let metadata = testcase.metadata::<HangTriageMetadata>().unwrap();
+        assert_eq!(metadata.exit_kind(), ExitKind::Timeout);
+        assert!(
+            matches!(metadata.class(), HangClass::BlockingSyscall { .. }),
+            "{metadata:?}"
+        );
+    }
+}
@@ -15,14 +15,27 @@ use core::{fmt, marker::PhantomData};
 #[cfg(feature = "std")]
 pub use afl_stats::{AflStatsStage, CalibrationTime, FuzzTime, SyncTime};
 pub use calibrate::CalibrationStage;
+#[cfg(feature = "std")]
+pub use checkpoint::CheckpointStage;
 pub use colorization::*;
 #[cfg(all(feature = "std", unix))]
 pub use concolic::ConcolicTracingStage;
 #[cfg(all(feature = "std", feature = "concolic_mutation", unix))]
 pub use concolic::SimpleConcolicMutationalStage;
+pub use ddmin::{ddmin, ddmin_minimize, DdminReducer, DdminStage};
+#[cfg(all(feature = "std", unix))]
+pub use determinism::{
+    DeterminismControl, DeterminismMetadata, DeterminismStage, NondeterminismSource,
+    PinnedSourcesMetadata,
+};
 #[cfg(feature = "std")]
 pub use dump::*;
 pub use generalization::GeneralizationStage;
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub use hang_triage::{
+    ChildPcSampler, HangClass, HangClassifier, HangTriageMetadata, HangTriageStage,
+    PcSample, PcSampler,
+};
 use hashbrown::HashSet;
 use libafl_bolts::{
     impl_serdeany,
@@ -31,6 +44,8 @@ use libafl_bolts::{
 };
 pub use logics::*;
 pub use mutational::{MutationalStage, StdMutationalStage};
+#[cfg(feature = "regex")]
+pub use objective_tmin::ObjectiveTMinStage;
 pub use power::{PowerMutationalStage, StdPowerMutationalStage};
 use serde::{Deserialize, Serialize};
 pub use stats::StatsStage;
@@ -52,7 +67,7 @@ pub use verify_timeouts::{TimeoutsToVerify, VerifyTimeoutsStage};
 use crate::{
     corpus::{CorpusId, HasCurrentCorpusId},
     events::{EventFirer, EventProcessor, EventRestarter, HasEventManagerId, ProgressReporter},
-    executors::{Executor, HasObservers},
+    executors::{BatchExecutor, Executor, HasObservers},
     inputs::UsesInput,
     observers::ObserversTuple,
     schedulers::Scheduler,
@@ -70,14 +85,23 @@ pub mod tmin;
 #[cfg(feature = "std")]
 pub mod afl_stats;
 pub mod calibrate;
+#[cfg(feature = "std")]
+pub mod checkpoint;
 pub mod colorization;
 #[cfg(all(feature = "std", unix))]
 pub mod concolic;
+pub mod ddmin;
+#[cfg(all(feature = "std", unix))]
+pub mod determinism;
 #[cfg(feature = "std")]
 pub mod dump;
 pub mod generalization;
 pub mod generation;
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub mod hang_triage;
 pub mod logics;
+#[cfg(feature = "regex")]
+pub mod objective_tmin;
 pub mod power;
 pub mod stats;
 #[cfg(feature = "std")]
@@ -522,6 +546,149 @@ where
     }
 }
 
+/// Allows us to use a [`push::PushStage`] as a normal [`Stage`], running its inputs in batches
+/// with a [`BatchExecutor`], like the batched mode of the `ForkserverExecutor`.
+///
+/// The push stage is asked for up to [`BatchExecutor::batch_size`] inputs before the first of them ran,
+/// so a stage deciding how many inputs to generate from their results may generate a few more than it would otherwise.
+#[allow(clippy::type_complexity)]
+#[derive(Debug)]
+pub struct BatchPushStageAdapter<CS, EM, OT, PS, Z> {
+    name: Cow<'static, str>,
+    push_stage: PS,
+    phantom: PhantomData<(CS, EM, OT, Z)>,
+}
+
+impl<CS, EM, OT, PS, Z> BatchPushStageAdapter<CS, EM, OT, PS, Z> {
+    /// Create a new [`BatchPushStageAdapter`], wrapping the given [`PushStage`]
+    /// to be used as a normal [`Stage`]
+    #[must_use]
+    pub fn new(push_stage: PS) -> Self {
+        // unsafe but impossible that you create two threads both instantiating this instance
+        let stage_id = unsafe {
+            let ret = BATCH_PUSH_STAGE_ADAPTER_ID;
+            BATCH_PUSH_STAGE_ADAPTER_ID += 1;
+            ret
+        };
+        Self {
+            name: Cow::Owned(
+                BATCH_PUSH_STAGE_ADAPTER_NAME.to_owned() + ":" + stage_id.to_string().as_str(),
+            ),
+            push_stage,
+            phantom: PhantomData,
+        }
+    }
+}
+/// The unique counter for this stage
+static mut BATCH_PUSH_STAGE_ADAPTER_ID: usize = 0;
+/// The name for batch push stage adapter
+pub static BATCH_PUSH_STAGE_ADAPTER_NAME: &str = "batchpushstageadapter";
+
+impl<CS, EM, OT, PS, Z> UsesState for BatchPushStageAdapter<CS, EM, OT, PS, Z>
+where
+    Z: UsesState,
+{
+    type State = Z::State;
+}
+
+impl<CS, EM, OT, PS, Z> Named for BatchPushStageAdapter<CS, EM, OT, PS, Z> {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<CS, E, EM, OT, PS, Z> Stage<E, EM, Z> for BatchPushStageAdapter<CS, EM, OT, PS, Z>
+where
+    CS: Scheduler<Z::Input, Z::State>,
+    Self::State: HasExecutions
+        + HasRand
+        + HasCorpus
+        + HasLastReportTime
+        + HasCurrentCorpusId
+        + HasNamedMetadata
+        + HasMetadata,
+    E: BatchExecutor<EM, Z, State = <Self as UsesState>::State> + HasObservers<Observers = OT>,
+    EM: EventFirer<State = Self::State>
+        + EventRestarter
+        + HasEventManagerId
+        + ProgressReporter<State = Self::State>,
+    OT: ObserversTuple<Self::Input, Self::State>,
+    PS: PushStage<CS, EM, OT, Z>,
+    Z: ExecutionProcessor<EM, OT> + EvaluatorObservers<EM, OT> + HasScheduler<Scheduler = CS>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut Z::State,
+        event_mgr: &mut EM,
+    ) -> Result<(), Error> {
+        let push_stage = &mut self.push_stage;
+
+        let Some(corpus_id) = state.current_corpus_id()? else {
+            return Err(Error::illegal_state(
+                "state is not currently processing a corpus index",
+            ));
+        };
+
+        push_stage.set_current_corpus_id(corpus_id);
+
+        push_stage.init(fuzzer, state, event_mgr, &mut *executor.observers_mut())?;
+
+        let batch_size = executor.batch_size();
+        let mut inputs = Vec::with_capacity(batch_size);
+        let mut done = false;
+        while !done {
+            while inputs.len() < batch_size {
+                match push_stage.pre_exec(fuzzer, state, event_mgr, &mut *executor.observers_mut())
+                {
+                    Some(Ok(next_input)) => inputs.push(next_input),
+                    Some(Err(err)) => return Err(err),
+                    None => {
+                        done = true;
+                        break;
+                    }
+                }
+            }
+            if inputs.is_empty() {
+                break;
+            }
+
+            let exit_kinds = executor.run_batch(fuzzer, state, event_mgr, &inputs)?;
+
+            for (idx, (input, exit_kind)) in inputs.drain(..).zip(exit_kinds).enumerate() {
+                executor.observers_mut().pre_exec_all(state, &input)?;
+                executor.load_batch_result(idx)?;
+                executor
+                    .observers_mut()
+                    .post_exec_all(state, &input, &exit_kind)?;
+
+                push_stage.post_exec(
+                    fuzzer,
+                    state,
+                    event_mgr,
+                    &mut *executor.observers_mut(),
+                    input,
+                    exit_kind,
+                )?;
+            }
+        }
+
+        self.push_stage
+            .deinit(fuzzer, state, event_mgr, &mut *executor.observers_mut())
+    }
+
+    #[inline]
+    fn should_restart(&mut self, state: &mut Self::State) -> Result<bool, Error> {
+        RetryCountRestartHelper::no_retry(state, &self.name)
+    }
+
+    #[inline]
+    fn clear_progress(&mut self, state: &mut Self::State) -> Result<(), Error> {
+        RetryCountRestartHelper::clear_progress(state, &self.name)
+    }
+}
+
 /// Progress which permits a fixed amount of resumes per round of fuzzing. If this amount is ever
 /// exceeded, the input will no longer be executed by this stage.
 #[derive(Clone, Deserialize, Serialize, Debug)]
@@ -888,4 +1055,217 @@ mod test {
 
         Ok(())
     }
+
+    mod batch {
+        use alloc::{rc::Rc, vec::Vec};
+        use core::cell::{Cell, RefCell};
+
+        use libafl_bolts::{
+            rands::StdRand,
+            tuples::{tuple_list, tuple_list_type, RefIndexable},
+            AsSlice, AsSliceMut, Error,
+        };
+
+        use crate::{
+            corpus::{Corpus, HasCurrentCorpusId, InMemoryCorpus, Testcase},
+            events::NopEventManager,
+            executors::{BatchExecutor, Executor, ExitKind, HasObservers},
+            feedbacks::ConstFeedback,
+            fuzzer::StdFuzzer,
+            inputs::{BytesInput, HasMutatorBytes},
+            observers::StdMapObserver,
+            schedulers::QueueScheduler,
+            stages::{
+                push::{PushStage, PushStageHelper},
+                BatchPushStageAdapter, Stage,
+            },
+            state::{HasCorpus, StdState, UsesState},
+        };
+
+        type Observers = tuple_list_type!(StdMapObserver<'static, u8, false>);
+        type TestState =
+            StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+        type TestFuzzer = StdFuzzer<QueueScheduler, ConstFeedback, ConstFeedback, TestState>;
+        type TestManager = NopEventManager<TestState>;
+
+        /// Runs inputs in batches of three, each input covering the map entry of its first byte
+        struct TestBatchExecutor {
+            observers: Observers,
+            batches: Vec<usize>,
+            covered: Vec<usize>,
+        }
+
+        impl UsesState for TestBatchExecutor {
+            type State = TestState;
+        }
+
+        impl HasObservers for TestBatchExecutor {
+            type Observers = Observers;
+
+            fn observers(&self) -> RefIndexable<&Self::Observers, Self::Observers> {
+                RefIndexable::from(&self.observers)
+            }
+
+            fn observers_mut(&mut self) -> RefIndexable<&mut Self::Observers, Self::Observers> {
+                RefIndexable::from(&mut self.observers)
+            }
+        }
+
+        impl Executor<TestManager, TestFuzzer> for TestBatchExecutor {
+            fn run_target(
+                &mut self,
+                _fuzzer: &mut TestFuzzer,
+                _state: &mut TestState,
+                _mgr: &mut TestManager,
+                _input: &BytesInput,
+            ) -> Result<ExitKind, Error> {
+                unreachable!("Only batches are run")
+            }
+        }
+
+        impl BatchExecutor<TestManager, TestFuzzer> for TestBatchExecutor {
+            fn batch_size(&self) -> usize {
+                3
+            }
+
+            fn run_batch(
+                &mut self,
+                _fuzzer: &mut TestFuzzer,
+                _state: &mut TestState,
+                _mgr: &mut TestManager,
+                inputs: &[BytesInput],
+            ) -> Result<Vec<ExitKind>, Error> {
+                self.batches.push(inputs.len());
+                self.covered = inputs
+                    .iter()
+                    .map(|input| usize::from(input.bytes()[0]))
+                    .collect();
+                Ok(inputs
+                    .iter()
+                    .map(|input| {
+                        if input.bytes()[0] % 2 == 0 {
+                            ExitKind::Ok
+                        } else {
+                            ExitKind::Crash
+                        }
+                    })
+                    .collect())
+            }
+
+            fn load_batch_result(&mut self, idx: usize) -> Result<(), Error> {
+                self.observers.0.as_slice_mut()[self.covered[idx]] = 1;
+                Ok(())
+            }
+        }
+
+        /// Generates `remaining` inputs, and records what the observers saw for each of them
+        struct RecordingPushStage {
+            psh: PushStageHelper<QueueScheduler, TestManager, Observers, TestFuzzer>,
+            remaining: u8,
+            seen: Vec<(u8, ExitKind, Vec<u8>)>,
+        }
+
+        impl Iterator for RecordingPushStage {
+            type Item = Result<BytesInput, Error>;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                self.next_std()
+            }
+        }
+
+        impl PushStage<QueueScheduler, TestManager, Observers, TestFuzzer> for RecordingPushStage {
+            fn push_stage_helper(
+                &self,
+            ) -> &PushStageHelper<QueueScheduler, TestManager, Observers, TestFuzzer> {
+                &self.psh
+            }
+
+            fn push_stage_helper_mut(
+                &mut self,
+            ) -> &mut PushStageHelper<QueueScheduler, TestManager, Observers, TestFuzzer>
+            {
+                &mut self.psh
+            }
+
+            fn pre_exec(
+                &mut self,
+                _fuzzer: &mut TestFuzzer,
+                _state: &mut TestState,
+                _event_mgr: &mut TestManager,
+                _observers: &mut Observers,
+            ) -> Option<Result<BytesInput, Error>> {
+                if self.remaining == 0 {
+                    return None;
+                }
+                self.remaining -= 1;
+                Some(Ok(BytesInput::new(vec![self.remaining])))
+            }
+
+            fn post_exec(
+                &mut self,
+                _fuzzer: &mut TestFuzzer,
+                _state: &mut TestState,
+                _event_mgr: &mut TestManager,
+                observers: &mut Observers,
+                input: BytesInput,
+                exit_kind: ExitKind,
+            ) -> Result<(), Error> {
+                self.seen
+                    .push((input.bytes()[0], exit_kind, observers.0.as_slice().to_vec()));
+                Ok(())
+            }
+        }
+
+        #[test]
+        fn test_batch_push_stage_adapter() {
+            let mut feedback = ConstFeedback::new(false);
+            let mut objective = ConstFeedback::new(false);
+            let mut state = StdState::new(
+                StdRand::with_seed(0),
+                InMemoryCorpus::new(),
+                InMemoryCorpus::new(),
+                &mut feedback,
+                &mut objective,
+            )
+            .unwrap();
+            let id = state
+                .corpus_mut()
+                .add(Testcase::new(BytesInput::new(vec![0])))
+                .unwrap();
+            state.set_corpus_id(id).unwrap();
+            let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), feedback, objective);
+            let mut executor = TestBatchExecutor {
+                observers: tuple_list!(StdMapObserver::owned("map", vec![0_u8; 8])),
+                batches: Vec::new(),
+                covered: Vec::new(),
+            };
+            let mut mgr = NopEventManager::new();
+
+            let mut stage = BatchPushStageAdapter::new(RecordingPushStage {
+                psh: PushStageHelper::new(Rc::new(RefCell::new(None)), Rc::new(Cell::new(None))),
+                remaining: 7,
+                seen: Vec::new(),
+            });
+            stage
+                .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)
+                .unwrap();
+
+            assert_eq!(executor.batches, [3, 3, 1]);
+            let seen = &stage.push_stage.seen;
+            assert_eq!(seen.len(), 7);
+            for (idx, (byte, exit_kind, map)) in seen.iter().enumerate() {
+                // in the order the push stage generated them
+                assert_eq!(usize::from(*byte), 6 - idx);
+                let expected = if byte % 2 == 0 {
+                    ExitKind::Ok
+                } else {
+                    ExitKind::Crash
+                };
+                assert_eq!(*exit_kind, expected);
+                // the observers only see the coverage of this input
+                let covered: Vec<usize> = (0..map.len()).filter(|i| map[*i] != 0).collect();
+                assert_eq!(covered, [usize::from(*byte)]);
+            }
+        }
+    }
 }
@@ -10,7 +10,8 @@ use core::{marker::PhantomData, num::NonZeroUsize};
 use libafl_bolts::{rands::Rand, Named};
 
 use crate::{
-    corpus::{Corpus, CorpusId, Testcase},
+    corpus::{Corpus, CorpusId, HasCurrentCorpusId, Testcase},
+    feedbacks::ProvenanceMetadata,
     fuzzer::Evaluator,
     inputs::Input,
     mark_feature_time,
@@ -108,7 +109,10 @@ where
         executor: &mut E,
         state: &mut Self::State,
         manager: &mut EM,
-    ) -> Result<(), Error> {
+    ) -> Result<(), Error>
+    where
+        Self::State: HasMetadata,
+    {
         start_timer!(state);
 
         // Here saturating_sub is needed as self.iterations() might be actually smaller than the previous value before reset.
@@ -136,6 +140,7 @@ where
             if mutated == MutationResult::Skipped {
                 continue;
             }
+            ProvenanceMetadata::record_mutations(state, || self.mutator().applied_mutations());
 
             // Time is measured directly the `evaluate_input` function
             let (untransformed, post) = input.try_transform_into(state)?;
@@ -233,7 +238,12 @@ where
         state: &mut Self::State,
         manager: &mut EM,
     ) -> Result<(), Error> {
-        let ret = self.perform_mutational(fuzzer, executor, state, manager);
+        let parent_id = state.current_corpus_id()?;
+        let name = self.name.clone();
+        let mutator = self.mutator.name().clone();
+        let ret = ProvenanceMetadata::with_stage(state, &name, Some(mutator), parent_id, |state| {
+            self.perform_mutational(fuzzer, executor, state, manager)
+        });
 
         #[cfg(feature = "introspection")]
         state.introspection_monitor_mut().finish_stage();
@@ -340,7 +350,7 @@ where
     EM: UsesState<State = Self::State>,
     M: MultiMutator<I, Self::State>,
     Z: Evaluator<E, EM>,
-    Z::State: HasCorpus + HasRand + HasNamedMetadata + HasCurrentTestcase,
+    Z::State: HasCorpus + HasRand + HasMetadata + HasNamedMetadata + HasCurrentTestcase,
     I: MutatedTransform<Self::Input, Self::State> + Clone,
     <<Self as UsesState>::State as HasCorpus>::Corpus: Corpus<Input = Self::Input>, //delete me
 {
@@ -373,13 +383,19 @@ where
 
         let generated = self.mutator.multi_mutate(state, &input, None)?;
         // println!("Generated {}", generated.len());
-        for new_input in generated {
-            // Time is measured directly the `evaluate_input` function
-            let (untransformed, post) = new_input.try_transform_into(state)?;
-            let (_, corpus_id) = fuzzer.evaluate_input(state, executor, manager, untransformed)?;
-            self.mutator.multi_post_exec(state, corpus_id)?;
-            post.post_exec(state, corpus_id)?;
-        }
+        let parent_id = state.current_corpus_id()?;
+        let mutator = self.mutator.name().clone();
+        ProvenanceMetadata::with_stage(state, &self.name, Some(mutator), parent_id, |state| {
+            for new_input in generated {
+                // Time is measured directly the `evaluate_input` function
+                let (untransformed, post) = new_input.try_transform_into(state)?;
+                let (_, corpus_id) =
+                    fuzzer.evaluate_input(state, executor, manager, untransformed)?;
+                self.mutator.multi_post_exec(state, corpus_id)?;
+                post.post_exec(state, corpus_id)?;
+            }
+            Ok(())
+        })?;
         // println!("Found {}", found);
 
         Ok(())
@@ -0,0 +1,434 @@
+//! The [`ObjectiveTMinStage`] minimizes newly found objectives, while preserving their crash signature.
+//!
+//! Note: Will NOT work with in process executors, as each run of an objective crashes the fuzzer.
+//! Use it with an executor surviving crashes, such as the `ForkserverExecutor` or the `InProcessForkExecutor`.
+
+use alloc::{
+    borrow::Cow,
+    format,
+    string::{String, ToString},
+};
+use core::marker::PhantomData;
+
+use libafl_bolts::{
+    tuples::{Handle, Handled, MatchNameRef},
+    HasLen, Named,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::{Corpus, CorpusId, Testcase},
+    executors::{ExitKind, HasObservers},
+    feedbacks::{CrashBucketKey, CrashBucketMetadata},
+    inputs::{Input, UsesInput},
+    mutators::{MutationResult, Mutator},
+    observers::{ObserverWithCrashSignature, ObserversTuple},
+    stages::Stage,
+    state::{HasSolutions, UsesState},
+    Error, ExecutesInput, HasMetadata, HasNamedMetadata,
+};
+
+/// The prefix of the metadata names
+pub const OBJECTIVE_TMIN_STAGE_PREFIX: &str = "objective_tmin_";
+
+/// Marks an objective added by the [`ObjectiveTMinStage`], as the minimized version of another objective
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MinimizedObjectiveMetadata {
+    /// The objective this one was minimized from
+    pub original: CorpusId,
+    /// The length of the original objective
+    pub original_len: usize,
+}
+
+libafl_bolts::impl_serdeany!(MinimizedObjectiveMetadata);
+
+/// The progress of an [`ObjectiveTMinStage`], stored as named metadata in the state
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+pub struct ObjectiveTMinMetadata {
+    /// The last objective the stage started to minimize
+    last: Option<CorpusId>,
+}
+
+libafl_bolts::impl_serdeany!(ObjectiveTMinMetadata);
+
+/// A stage minimizing each new objective, and adding the minimized input next to the original one
+/// to the solutions corpus.
+///
+/// A smaller input is only accepted if it crashes with the same [`ExitKind`], and ends up in the same bucket:
+/// the [`CrashBucketKey`] of the [`CrashBucketMetadata`] of the objective is used if present
+/// (see [`crate::feedbacks::CrashBucketFeedback`]), else the key this stage was created with.
+///
+/// The minimized objective keeps the [`CrashBucketMetadata`] of the original, so a
+/// [`crate::feedbacks::CrashBucketFeedback`] records it in the same bucket at its next evaluation.
+///
+/// Each objective is only attempted once, even if minimizing it crashes the fuzzer.
+/// You must provide at least one mutator that actually reduces size.
+#[derive(Debug, Clone)]
+pub struct ObjectiveTMinStage<E, EM, M, O, Z> {
+    name: Cow<'static, str>,
+    o_ref: Handle<O>,
+    mutator: M,
+    /// The key used for objectives without [`CrashBucketMetadata`]
+    key: CrashBucketKey,
+    /// The number of unsuccessful mutations before we give up on an objective
+    runs: usize,
+    phantom: PhantomData<(E, EM, Z)>,
+}
+
+impl<E, EM, M, O, Z> UsesState for ObjectiveTMinStage<E, EM, M, O, Z>
+where
+    E: UsesState,
+{
+    type State = E::State;
+}
+
+impl<E, EM, M, O, Z> Named for ObjectiveTMinStage<E, EM, M, O, Z> {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<E, EM, M, O, Z> Stage<E, EM, Z> for ObjectiveTMinStage<E, EM, M, O, Z>
+where
+    E: HasObservers + UsesState,
+    E::Observers: ObserversTuple<<E::State as UsesInput>::Input, E::State>,
+    EM: UsesState<State = E::State>,
+    Z: UsesState<State = E::State> + ExecutesInput<E, EM>,
+    M: Mutator<<E::State as UsesInput>::Input, E::State>,
+    O: ObserverWithCrashSignature,
+    E::State: HasSolutions + HasNamedMetadata,
+    <E::State as HasSolutions>::Solutions: Corpus<Input = <E::State as UsesInput>::Input>,
+    <E::State as UsesInput>::Input: Input + HasLen,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut Self::State,
+        manager: &mut EM,
+    ) -> Result<(), Error> {
+        loop {
+            let last = state
+                .named_metadata_or_insert_with(&self.name, ObjectiveTMinMetadata::default)
+                .last;
+            let next = match last {
+                Some(id) => state.solutions().next(id),
+                None => state.solutions().first(),
+            };
+            let Some(id) = next else {
+                return Ok(());
+            };
+            // Mark the objective as processed first, we won't try it again if the target takes us down
+            state
+                .named_metadata_mut::<ObjectiveTMinMetadata>(&self.name)?
+                .last = Some(id);
+
+            self.minimize_objective(fuzzer, executor, state, manager, id)?;
+        }
+    }
+
+    fn should_restart(&mut self, _state: &mut Self::State) -> Result<bool, Error> {
+        // Progress is tracked per objective, see `ObjectiveTMinMetadata`
+        Ok(true)
+    }
+
+    fn clear_progress(&mut self, _state: &mut Self::State) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<E, EM, M, O, Z> ObjectiveTMinStage<E, EM, M, O, Z>
+where
+    O: Named,
+{
+    /// Creates a new [`ObjectiveTMinStage`].
+    ///
+    /// The crash signature is taken from the given `observer`, and compared using the [`CrashBucketKey`]
+    /// of each objective. Objectives without [`CrashBucketMetadata`] are compared using `key`.
+    /// Gives up on an objective after `runs` mutations in a row did not lead to a smaller input.
+    #[must_use]
+    pub fn new(observer: &O, mutator: M, key: CrashBucketKey, runs: usize) -> Self {
+        Self {
+            name: Cow::from(OBJECTIVE_TMIN_STAGE_PREFIX.to_string() + observer.name()),
+            o_ref: observer.handle(),
+            mutator,
+            key,
+            runs,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<E, EM, M, O, Z> ObjectiveTMinStage<E, EM, M, O, Z>
+where
+    E: HasObservers + UsesState,
+    E::Observers: ObserversTuple<<E::State as UsesInput>::Input, E::State>,
+    EM: UsesState<State = E::State>,
+    Z: UsesState<State = E::State> + ExecutesInput<E, EM>,
+    M: Mutator<<E::State as UsesInput>::Input, E::State>,
+    O: ObserverWithCrashSignature,
+    E::State: HasSolutions + HasNamedMetadata,
+    <E::State as HasSolutions>::Solutions: Corpus<Input = <E::State as UsesInput>::Input>,
+    <E::State as UsesInput>::Input: Input + HasLen,
+{
+    /// Runs the input, and returns its exit kind and the description of its crash bucket, if it crashed
+    fn run_and_describe(
+        &self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut E::State,
+        manager: &mut EM,
+        input: &<E::State as UsesInput>::Input,
+        key: CrashBucketKey,
+    ) -> Result<(ExitKind, Option<String>), Error> {
+        let exit_kind = fuzzer.execute_input(state, executor, manager, input)?;
+        let observers = executor.observers();
+        let observer = observers
+            .get(&self.o_ref)
+            .expect("An ObjectiveTMinStage needs an ObserverWithCrashSignature");
+        let description = observer
+            .crash_signature()
+            .map(|signature| key.describe(&signature));
+        Ok((exit_kind, description))
+    }
+
+    /// Minimizes the objective with the given id, and adds the result to the solutions
+    fn minimize_objective(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut E::State,
+        manager: &mut EM,
+        id: CorpusId,
+    ) -> Result<(), Error> {
+        let (original, filename, bucket) = {
+            let mut testcase = state.solutions().get(id)?.borrow_mut();
+            if testcase.has_metadata::<MinimizedObjectiveMetadata>() {
+                return Ok(());
+            }
+            let bucket = testcase.metadata::<CrashBucketMetadata>().ok().cloned();
+            let filename = testcase.filename().clone();
+            let original = testcase.load_input(state.solutions())?.clone();
+            (original, filename, bucket)
+        };
+        let key = bucket.as_ref().map_or(self.key, |bucket| bucket.key);
+
+        let (exit_kind, description) =
+            self.run_and_describe(fuzzer, executor, state, manager, &original, key)?;
+        let description = match (description, &bucket) {
+            (Some(description), Some(bucket)) if description == bucket.description => description,
+            (Some(description), None) if exit_kind != ExitKind::Ok => description,
+            _ => {
+                log::info!("Objective {id} does not reproduce, not minimizing it");
+                return Ok(());
+            }
+        };
+
+        let mut base = original.clone();
+        let mut i = 0;
+        while i < self.runs {
+            i += 1;
+            let mut input = base.clone();
+            if self.mutator.mutate(state, &mut input)? == MutationResult::Skipped
+                || input.len() >= base.len()
+            {
+                continue;
+            }
+            let (new_exit_kind, new_description) =
+                self.run_and_describe(fuzzer, executor, state, manager, &input, key)?;
+            let reproduces =
+                new_exit_kind == exit_kind && new_description.as_ref() == Some(&description);
+            self.mutator.post_exec(state, None)?;
+            if reproduces {
+                // we found a smaller input with the same crash! maybe we can minify further
+                base = input;
+                i = 0;
+            }
+        }
+
+        if base.len() >= original.len() {
+            return Ok(());
+        }
+        log::info!(
+            "Minimized objective {id} from {} to {} bytes",
+            original.len(),
+            base.len()
+        );
+        let mut testcase = Testcase::new(base);
+        *testcase.filename_mut() = filename.map(|name| format!("{name}.min"));
+        testcase.add_metadata(MinimizedObjectiveMetadata {
+            original: id,
+            original_len: original.len(),
+        });
+        if let Some(bucket) = bucket {
+            testcase.add_metadata(bucket);
+        }
+        state.solutions_mut().add(testcase)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::marker::PhantomData;
+
+    use libafl_bolts::{
+        rands::StdRand,
+        tuples::{tuple_list, tuple_list_type, RefIndexable},
+        Named,
+    };
+
+    use super::{MinimizedObjectiveMetadata, ObjectiveTMinStage};
+    use crate::{
+        corpus::{Corpus, InMemoryCorpus},
+        events::NopEventManager,
+        executors::{Executor, ExitKind, HasObservers},
+        feedbacks::{
+            crash_bucket::CrashBucketsMetadata, ConstFeedback, CrashBucketFeedback, CrashBucketKey,
+            CrashBucketMetadata,
+        },
+        fuzzer::{Evaluator, StdFuzzer},
+        inputs::{BytesInput, HasMutatorBytes},
+        mutators::BytesDeleteMutator,
+        observers::AsanBacktraceObserver,
+        schedulers::QueueScheduler,
+        stages::Stage,
+        state::{HasSolutions, StdState, UsesState},
+        HasMetadata, HasNamedMetadata,
+    };
+
+    const HEAP_OVERFLOW: &str = "==1==ERROR: AddressSanitizer: heap-buffer-overflow on address 0x602000000011 at pc 0x55555555a1b2 bp 0x7ffc sp 0x7ffc
+READ of size 1 at 0x602000000011 thread T0
+    #0 0x55555555a1b2 in parse_header /src/target.c:12:5
+    #1 0x55555555a2c3 in LLVMFuzzerTestOneInput /src/target.c:30:3
+";
+
+    type Observers = tuple_list_type!(AsanBacktraceObserver);
+
+    /// Crashes with the same report on every input containing a `!`
+    struct CrashingExecutor<S> {
+        observers: Observers,
+        phantom: PhantomData<S>,
+    }
+
+    impl<S> UsesState for CrashingExecutor<S>
+    where
+        S: crate::state::State,
+    {
+        type State = S;
+    }
+
+    impl<S> HasObservers for CrashingExecutor<S> {
+        type Observers = Observers;
+
+        fn observers(&self) -> RefIndexable<&Self::Observers, Self::Observers> {
+            RefIndexable::from(&self.observers)
+        }
+
+        fn observers_mut(&mut self) -> RefIndexable<&mut Self::Observers, Self::Observers> {
+            RefIndexable::from(&mut self.observers)
+        }
+    }
+
+    impl<EM, S, Z> Executor<EM, Z> for CrashingExecutor<S>
+    where
+        EM: UsesState<State = S>,
+        S: crate::state::State<Input = BytesInput>,
+        Z: UsesState<State = S>,
+    {
+        fn run_target(
+            &mut self,
+            _fuzzer: &mut Z,
+            _state: &mut S,
+            _mgr: &mut EM,
+            input: &BytesInput,
+        ) -> Result<ExitKind, crate::Error> {
+            if !input.bytes().contains(&b'!') {
+                return Ok(ExitKind::Ok);
+            }
+            self.observers.0.parse_asan_output(HEAP_OVERFLOW);
+            Ok(ExitKind::Crash)
+        }
+    }
+
+    #[test]
+    fn test_objective_tmin_keeps_bucket() {
+        let observer = AsanBacktraceObserver::new("asan");
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = CrashBucketFeedback::new(&observer, CrashBucketKey::default());
+        let buckets_name = objective.name().clone();
+
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut stage = ObjectiveTMinStage::new(
+            &observer,
+            BytesDeleteMutator::new(),
+            CrashBucketKey::default(),
+            256,
+        );
+        let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), feedback, objective);
+        let mut executor = CrashingExecutor {
+            observers: tuple_list!(observer),
+            phantom: PhantomData,
+        };
+        let mut mgr = NopEventManager::new();
+
+        fuzzer
+            .evaluate_input(
+                &mut state,
+                &mut executor,
+                &mut mgr,
+                BytesInput::new(b"AAAAAAAA!AAAAAAAA".to_vec()),
+            )
+            .unwrap();
+        assert_eq!(state.solutions().count(), 1);
+        let original = state.solutions().first().unwrap();
+
+        stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)
+            .unwrap();
+        assert_eq!(state.solutions().count(), 2);
+        let minimized = state.solutions().next(original).unwrap();
+        {
+            let testcase = state.solutions().get(minimized).unwrap().borrow();
+            let input = testcase.input().as_ref().unwrap();
+            assert!(input.bytes().len() < 17);
+            assert!(input.bytes().contains(&b'!'));
+            assert_eq!(
+                testcase.metadata::<MinimizedObjectiveMetadata>().unwrap(),
+                &MinimizedObjectiveMetadata {
+                    original,
+                    original_len: 17,
+                }
+            );
+            let bucket = testcase.metadata::<CrashBucketMetadata>().unwrap();
+            assert_eq!(
+                bucket.description,
+                "frames:parse_header > LLVMFuzzerTestOneInput"
+            );
+        }
+
+        // The minimized objective is recorded in the same bucket at the next evaluation
+        fuzzer
+            .evaluate_input(
+                &mut state,
+                &mut executor,
+                &mut mgr,
+                BytesInput::new(b"AAAA".to_vec()),
+            )
+            .unwrap();
+        let buckets = state
+            .named_metadata::<CrashBucketsMetadata>(&buckets_name)
+            .unwrap();
+        assert_eq!(buckets.buckets().len(), 1);
+        let bucket = buckets.buckets().values().next().unwrap();
+        let ids: alloc::vec::Vec<_> = bucket.members.iter().map(|member| member.id).collect();
+        assert_eq!(ids, [original, minimized]);
+    }
+}
@@ -9,8 +9,9 @@ use core::{fmt::Debug, marker::PhantomData};
 use libafl_bolts::Named;
 
 use crate::{
-    corpus::Corpus,
+    corpus::{Corpus, HasCurrentCorpusId},
     executors::{Executor, HasObservers},
+    feedbacks::ProvenanceMetadata,
     fuzzer::Evaluator,
     inputs::Input,
     mutators::Mutator,
@@ -104,7 +105,12 @@ where
         state: &mut Self::State,
         manager: &mut EM,
     ) -> Result<(), Error> {
-        let ret = self.perform_mutational(fuzzer, executor, state, manager);
+        let parent_id = state.current_corpus_id()?;
+        let name = self.name.clone();
+        let mutator = self.mutator.name().clone();
+        let ret = ProvenanceMetadata::with_stage(state, &name, Some(mutator), parent_id, |state| {
+            self.perform_mutational(fuzzer, executor, state, manager)
+        });
         ret
     }
 
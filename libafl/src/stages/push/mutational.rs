@@ -15,6 +15,7 @@ use crate::{
     corpus::{Corpus, CorpusId},
     events::{EventFirer, EventRestarter, HasEventManagerId, ProgressReporter},
     executors::ExitKind,
+    feedbacks::ProvenanceMetadata,
     inputs::UsesInput,
     mark_feature_time,
     mutators::Mutator,
@@ -31,6 +32,9 @@ use crate::{monitors::PerfFeature, state::HasClientPerfMonitor};
 /// The default maximum number of mutations to perform per input.
 pub const DEFAULT_MUTATIONAL_MAX_ITERATIONS: usize = 128;
 
+/// The name the [`StdMutationalPushStage`] records in the [`ProvenanceMetadata`] of its mutants
+pub const MUTATIONAL_PUSH_STAGE_NAME: &str = "mutationalpush";
+
 /// A Mutational push stage is the stage in a fuzzing run that mutates inputs.
 ///
 /// Mutational push stages will usually have a range of mutations that are
@@ -120,6 +124,12 @@ where
 
         self.testcases_to_do = self.iterations(state, self.current_corpus_id.unwrap())?;
         self.testcases_done = 0;
+        ProvenanceMetadata::enter_stage(
+            state,
+            MUTATIONAL_PUSH_STAGE_NAME,
+            Some(self.mutator.name().clone()),
+            self.current_corpus_id,
+        );
         Ok(())
     }
 
@@ -141,7 +151,11 @@ where
             .corpus_mut()
             .cloned_input_for_id(self.current_corpus_id.unwrap());
         let mut input = match input {
-            Err(e) => return Some(Err(e)),
+            Err(e) => {
+                // The stage ends here, `deinit` is not called
+                ProvenanceMetadata::leave(state);
+                return Some(Err(e));
+            }
             Ok(input) => input,
         };
 
@@ -150,6 +164,7 @@ where
         start_timer!(state);
         self.mutator.mutate(state, &mut input).unwrap();
         mark_feature_time!(state, PerfFeature::Mutate);
+        ProvenanceMetadata::record_mutations(state, || self.mutator.applied_mutations());
 
         self.push_stage_helper_mut()
             .current_input
@@ -169,11 +184,19 @@ where
     ) -> Result<(), Error> {
         // todo: is_interesting, etc.
 
-        fuzzer.evaluate_execution(state, event_mgr, last_input, observers, &exit_kind, true)?;
-
-        start_timer!(state);
-        self.mutator.post_exec(state, self.current_corpus_id)?;
-        mark_feature_time!(state, PerfFeature::MutatePostExec);
+        let ret = fuzzer
+            .evaluate_execution(state, event_mgr, last_input, observers, &exit_kind, true)
+            .and_then(|(_, corpus_id)| {
+                start_timer!(state);
+                self.mutator.post_exec(state, corpus_id)?;
+                mark_feature_time!(state, PerfFeature::MutatePostExec);
+                Ok(())
+            });
+        if ret.is_err() {
+            // The stage ends here, `deinit` is not called
+            ProvenanceMetadata::leave(state);
+            return ret;
+        }
         self.testcases_done += 1;
 
         Ok(())
@@ -183,10 +206,11 @@ where
     fn deinit(
         &mut self,
         _fuzzer: &mut Z,
-        _state: &mut Z::State,
+        state: &mut Z::State,
         _event_mgr: &mut EM,
         _observers: &mut OT,
     ) -> Result<(), Error> {
+        ProvenanceMetadata::leave(state);
         self.current_corpus_id = None;
         Ok(())
     }
@@ -16,6 +16,7 @@ use crate::{
     corpus::{Corpus, CorpusId},
     events::{llmp::LlmpEventConverter, Event, EventConfig, EventFirer},
     executors::{Executor, ExitKind, HasObservers},
+    feedbacks::ProvenanceMetadata,
     fuzzer::{Evaluator, EvaluatorObservers, ExecutionProcessor},
     inputs::{Input, InputConverter, UsesInput},
     stages::{RetryCountRestartHelper, Stage},
@@ -123,19 +124,22 @@ where
         // even in the event of a target restart.
         let to_sync = sync_from_disk_metadata.left_to_sync.clone();
         log::debug!("Number of files to sync: {:?}", to_sync.len());
-        for path in to_sync {
-            let input = (self.load_callback)(fuzzer, state, &path)?;
-            // Removing each path from the `left_to_sync` Vec before evaluating
-            // prevents duplicate processing and ensures that each file is evaluated only once. This approach helps
-            // avoid potential infinite loops that may occur if a file is an objective.
-            state
-                .metadata_mut::<SyncFromDiskMetadata>()
-                .unwrap()
-                .left_to_sync
-                .retain(|p| p != &path);
-            log::debug!("Syncing and evaluating {:?}", path);
-            fuzzer.evaluate_input(state, executor, manager, input)?;
-        }
+        ProvenanceMetadata::with_stage(state, &self.name, None, None, |state| {
+            for path in to_sync {
+                let input = (self.load_callback)(fuzzer, state, &path)?;
+                // Removing each path from the `left_to_sync` Vec before evaluating
+                // prevents duplicate processing and ensures that each file is evaluated only once. This approach helps
+                // avoid potential infinite loops that may occur if a file is an objective.
+                state
+                    .metadata_mut::<SyncFromDiskMetadata>()
+                    .unwrap()
+                    .left_to_sync
+                    .retain(|p| p != &path);
+                log::debug!("Syncing and evaluating {:?}", path);
+                fuzzer.evaluate_input(state, executor, manager, input)?;
+            }
+            Ok(())
+        })?;
 
         #[cfg(feature = "introspection")]
         state.introspection_monitor_mut().finish_stage();
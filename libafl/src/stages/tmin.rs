@@ -19,7 +19,9 @@ use crate::{
     corpus::{Corpus, HasCurrentCorpusId, Testcase},
     events::EventFirer,
     executors::{ExitKind, HasObservers},
-    feedbacks::{Feedback, FeedbackFactory, HasObserverHandle, StateInitializer},
+    feedbacks::{
+        Feedback, FeedbackFactory, HasObserverHandle, ProvenanceMetadata, StateInitializer,
+    },
     inputs::UsesInput,
     mark_feature_time,
     mutators::{MutationResult, Mutator},
@@ -270,7 +272,12 @@ where
         state: &mut Z::State,
         manager: &mut EM,
     ) -> Result<(), Error> {
-        self.perform_minification(fuzzer, executor, state, manager)?;
+        let parent_id = state.current_corpus_id()?;
+        let name = self.name.clone();
+        let mutator = self.mutator.name().clone();
+        ProvenanceMetadata::with_stage(state, &name, Some(mutator), parent_id, |state| {
+            self.perform_minification(fuzzer, executor, state, manager)
+        })?;
 
         #[cfg(feature = "introspection")]
         state.introspection_monitor_mut().finish_stage();
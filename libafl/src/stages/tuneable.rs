@@ -7,7 +7,8 @@ use libafl_bolts::{current_time, impl_serdeany, rands::Rand};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    corpus::Corpus,
+    corpus::{Corpus, HasCurrentCorpusId},
+    feedbacks::ProvenanceMetadata,
     mark_feature_time,
     mutators::{MutationResult, Mutator},
     nonzero,
@@ -283,7 +284,12 @@ where
         state: &mut Self::State,
         manager: &mut EM,
     ) -> Result<(), Error> {
-        let ret = self.perform_mutational(fuzzer, executor, state, manager);
+        let parent_id = state.current_corpus_id()?;
+        let name = self.name.clone();
+        let mutator = self.mutator.name().clone();
+        let ret = ProvenanceMetadata::with_stage(state, &name, Some(mutator), parent_id, |state| {
+            self.perform_mutational(fuzzer, executor, state, manager)
+        });
 
         #[cfg(feature = "introspection")]
         state.introspection_monitor_mut().finish_stage();
@@ -466,6 +472,7 @@ where
         if mutated == MutationResult::Skipped {
             return Ok(());
         }
+        ProvenanceMetadata::record_mutations(state, || self.mutator().applied_mutations());
 
         // Time is measured directly the `evaluate_input` function
         let (untransformed, post) = input.try_transform_into(state)?;
@@ -12,6 +12,7 @@ use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use crate::{
     corpus::Corpus,
     executors::{Executor, HasObservers, HasTimeout},
+    feedbacks::ProvenanceMetadata,
     inputs::{BytesInput, UsesInput},
     observers::ObserversTuple,
     stages::Stage,
@@ -19,6 +20,9 @@ use crate::{
     Evaluator, HasMetadata,
 };
 
+/// The name the [`VerifyTimeoutsStage`] records in the [`ProvenanceMetadata`] of the inputs it re-runs
+pub const VERIFY_TIMEOUTS_STAGE_NAME: &str = "verify_timeouts";
+
 /// Stage that re-runs inputs deemed as timeouts with double the timeout to assert that they are
 /// not false positives. AFL++ style.
 /// Note: Will NOT work with in process executors due to the potential for restarts/crashes when
@@ -113,9 +117,12 @@ where
         }
         executor.set_timeout(self.doubled_timeout);
         *self.capture_timeouts.borrow_mut() = false;
-        while let Some(input) = timeouts.pop() {
-            fuzzer.evaluate_input(state, executor, manager, input)?;
-        }
+        ProvenanceMetadata::with_stage(state, VERIFY_TIMEOUTS_STAGE_NAME, None, None, |state| {
+            while let Some(input) = timeouts.pop() {
+                fuzzer.evaluate_input(state, executor, manager, input)?;
+            }
+            Ok(())
+        })?;
         executor.set_timeout(self.original_timeout);
         *self.capture_timeouts.borrow_mut() = true;
         let res = state.metadata_mut::<TimeoutsToVerify<E::Input>>().unwrap();
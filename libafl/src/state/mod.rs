@@ -11,7 +11,8 @@ use core::{
 };
 #[cfg(feature = "std")]
 use std::{
-    fs,
+    fs::{self, File},
+    io::Write,
     path::{Path, PathBuf},
 };
 
@@ -223,6 +224,72 @@ pub trait HasLastReportTime {
     fn last_report_time_mut(&mut self) -> &mut Option<Duration>;
 }
 
+/// Trait for states that can be checkpointed to disk, to resume a campaign after the whole host went down.
+///
+/// The checkpoint contains the full state: the corpus index and testcase metadata,
+/// all (named) metadata, such as the scheduler's and feedbacks' history, the RNG, and the executions.
+/// Inputs of on-disk corpora stay where they are and are not duplicated into the checkpoint.
+///
+/// To resume a `Launcher`-based fuzzer, use a separate checkpoint path for each client, and
+/// try [`HasCheckpoint::load_checkpoint`] before creating a fresh state:
+/// ```rust,ignore
+/// let mut state = match state {
+///     Some(state) => state,
+///     None => match StdState::load_checkpoint(&checkpoint_path)? {
+///         Some(state) => state,
+///         None => StdState::new(/* .. */)?,
+///     },
+/// };
+/// ```
+#[cfg(feature = "std")]
+pub trait HasCheckpoint: Serialize + DeserializeOwned {
+    /// Atomically writes this state to `path`, replacing a previous checkpoint.
+    fn save_checkpoint<P>(&self, path: P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let Some(file_name) = path.file_name() else {
+            return Err(Error::illegal_argument(format!(
+                "Checkpoint path {} is not a file",
+                path.display()
+            )));
+        };
+        // A stale temp file of a previous (interrupted) checkpoint is simply overwritten.
+        let tmp_path = path.with_file_name(format!(".{}.tmp", file_name.to_string_lossy()));
+        let mut tmp_file = File::create(&tmp_path)?;
+        tmp_file.write_all(&postcard::to_allocvec(self)?)?;
+        // The data has to be on disk before the rename makes it the checkpoint,
+        // else a power loss may leave a truncated checkpoint behind.
+        tmp_file.sync_all()?;
+        drop(tmp_file);
+        fs::rename(&tmp_path, path)?;
+        // Persist the rename itself
+        #[cfg(unix)]
+        {
+            let dir = match path.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir,
+                _ => Path::new("."),
+            };
+            File::open(dir)?.sync_all()?;
+        }
+        Ok(())
+    }
+
+    /// Loads a state previously written by [`HasCheckpoint::save_checkpoint`].
+    /// Returns `None`, if no checkpoint exists at `path`.
+    fn load_checkpoint<P>(path: P) -> Result<Option<Self>, Error>
+    where
+        P: AsRef<Path>,
+    {
+        match fs::read(path) {
+            Ok(bytes) => Ok(Some(postcard::from_bytes(&bytes)?)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
 /// Struct that holds the options for input loading
 #[cfg(feature = "std")]
 pub struct LoadConfig<'a, I, S, Z> {
@@ -493,6 +560,15 @@ impl<I, C, R, SC> HasStartTime for StdState<I, C, R, SC> {
     }
 }
 
+#[cfg(feature = "std")]
+impl<I, C, R, SC> HasCheckpoint for StdState<I, C, R, SC>
+where
+    C: Serialize + DeserializeOwned,
+    R: Serialize + DeserializeOwned,
+    SC: Serialize + DeserializeOwned,
+{
+}
+
 impl<I, C, R, SC> HasCurrentCorpusId for StdState<I, C, R, SC> {
     fn set_corpus_id(&mut self, id: CorpusId) -> Result<(), Error> {
         self.corpus_id = Some(id);
@@ -1,23 +1,45 @@
 //! Compression of events passed between a broker and clients.
-//! Currently we use the gzip compression algorithm for its fast decompression performance.
+//! By default, we use the gzip compression algorithm for its fast decompression performance.
+//! With the `zstd` and `lz4` features, the [`ZstdCompressor`] and [`Lz4Compressor`] are available as well.
 
+#[cfg(feature = "llmp_compression")]
+use alloc::format;
 use alloc::vec::Vec;
 use core::fmt::Debug;
+#[cfg(feature = "llmp_compression")]
+use core::time::Duration;
 
+#[cfg(feature = "gzip")]
 use miniz_oxide::{
     deflate::{compress_to_vec, CompressionLevel},
     inflate::decompress_to_vec,
 };
 
 use crate::Error;
+#[cfg(feature = "llmp_compression")]
+use crate::{
+    current_time,
+    llmp::{Flags, LLMP_FLAG_COMPRESSED, LLMP_FLAG_COMPRESSED_LZ4, LLMP_FLAG_COMPRESSED_ZSTD},
+};
+
+/// A compression algorithm, turning a buffer into a (hopefully) smaller one, and back.
+pub trait Compressor: Debug {
+    /// Compress the given buffer.
+    fn compress(&self, buf: &[u8]) -> Result<Vec<u8>, Error>;
+
+    /// Decompress a buffer previously compressed by this [`Compressor`].
+    fn decompress(&self, buf: &[u8]) -> Result<Vec<u8>, Error>;
+}
 
 /// Compression for your stream compression needs.
+#[cfg(feature = "gzip")]
 #[derive(Debug)]
 pub struct GzipCompressor {
     /// If less bytes than threshold are being passed to `compress`, the payload is not getting compressed.
     threshold: usize,
 }
 
+#[cfg(feature = "gzip")]
 impl GzipCompressor {
     /// If the buffer is at least larger as large as the `threshold` value, we compress the buffer.
     /// When given a `threshold` of `0`, the `GzipCompressor` will always compress.
@@ -33,12 +55,14 @@ impl GzipCompressor {
     }
 }
 
+#[cfg(feature = "gzip")]
 impl Default for GzipCompressor {
     fn default() -> Self {
         Self::new()
     }
 }
 
+#[cfg(feature = "gzip")]
 impl GzipCompressor {
     /// Compression.
     /// If the buffer is smaller than the threshold of this compressor, `None` will be returned.
@@ -72,10 +96,269 @@ impl GzipCompressor {
     }
 }
 
+#[cfg(feature = "gzip")]
+impl Compressor for GzipCompressor {
+    fn compress(&self, buf: &[u8]) -> Result<Vec<u8>, Error> {
+        Ok(GzipCompressor::compress(self, buf))
+    }
+
+    fn decompress(&self, buf: &[u8]) -> Result<Vec<u8>, Error> {
+        GzipCompressor::decompress(self, buf)
+    }
+}
+
+/// Compression using [zstd](https://facebook.github.io/zstd/).
+/// Compresses better than gzip at a lower CPU cost, at the default `level`.
+#[cfg(feature = "zstd")]
+#[derive(Debug, Clone, Copy)]
+pub struct ZstdCompressor {
+    /// The zstd compression level, see [`zstd::compression_level_range`]
+    level: i32,
+}
+
+#[cfg(feature = "zstd")]
+impl ZstdCompressor {
+    /// Create a [`ZstdCompressor`] using the given compression `level`.
+    /// Level `0` means zstd's default level.
+    #[must_use]
+    pub fn with_level(level: i32) -> Self {
+        Self { level }
+    }
+
+    /// Create a [`ZstdCompressor`] using zstd's default compression level
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_level(zstd::DEFAULT_COMPRESSION_LEVEL)
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl Default for ZstdCompressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl Compressor for ZstdCompressor {
+    fn compress(&self, buf: &[u8]) -> Result<Vec<u8>, Error> {
+        zstd::bulk::compress(buf, self.level).map_err(|_| Error::compression())
+    }
+
+    fn decompress(&self, buf: &[u8]) -> Result<Vec<u8>, Error> {
+        zstd::stream::decode_all(buf).map_err(|_| Error::compression())
+    }
+}
+
+/// Compression using [lz4](https://lz4.org/).
+/// Compresses worse than gzip or zstd, but is a lot faster in both directions.
+#[cfg(feature = "lz4")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Lz4Compressor;
+
+#[cfg(feature = "lz4")]
+impl Lz4Compressor {
+    /// Create a new [`Lz4Compressor`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg(feature = "lz4")]
+impl Compressor for Lz4Compressor {
+    fn compress(&self, buf: &[u8]) -> Result<Vec<u8>, Error> {
+        Ok(lz4_flex::compress_prepend_size(buf))
+    }
+
+    fn decompress(&self, buf: &[u8]) -> Result<Vec<u8>, Error> {
+        lz4_flex::decompress_size_prepended(buf).map_err(|_| Error::compression())
+    }
+}
+
+/// The compression algorithm an [`LlmpCompressor`] uses for the messages it sends.
+#[cfg(feature = "llmp_compression")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LlmpCompression {
+    /// Compress using [`GzipCompressor`], understood by all LLMP nodes
+    #[default]
+    Gzip,
+    /// Compress using [`ZstdCompressor`]
+    #[cfg(feature = "zstd")]
+    Zstd,
+    /// Compress using [`Lz4Compressor`]
+    #[cfg(feature = "lz4")]
+    Lz4,
+}
+
+#[cfg(feature = "llmp_compression")]
+impl LlmpCompression {
+    /// The LLMP [`Flags`] marking a message compressed with this algorithm
+    #[must_use]
+    pub fn flag(self) -> Flags {
+        match self {
+            Self::Gzip => LLMP_FLAG_COMPRESSED,
+            #[cfg(feature = "zstd")]
+            Self::Zstd => LLMP_FLAG_COMPRESSED_ZSTD,
+            #[cfg(feature = "lz4")]
+            Self::Lz4 => LLMP_FLAG_COMPRESSED_LZ4,
+        }
+    }
+}
+
+/// Compresses LLMP messages with the configured [`LlmpCompression`], and flags them accordingly.
+///
+/// Incoming messages are decompressed based on their [`Flags`], independent of the configured algorithm,
+/// so nodes using different compressors can be attached to the same broker.
+/// Also keeps track of the achieved compression ratio and of the time spent compressing.
+#[cfg(feature = "llmp_compression")]
+#[derive(Debug)]
+pub struct LlmpCompressor {
+    /// The algorithm used for outgoing messages
+    compression: LlmpCompression,
+    /// If less bytes than threshold are being passed to `maybe_compress`, the payload is not getting compressed.
+    threshold: usize,
+    gzip: GzipCompressor,
+    #[cfg(feature = "zstd")]
+    zstd: ZstdCompressor,
+    #[cfg(feature = "lz4")]
+    lz4: Lz4Compressor,
+    /// The amount of bytes passed to `maybe_compress`
+    bytes_in: u64,
+    /// The amount of bytes returned from `maybe_compress`, whether compressed or not
+    bytes_out: u64,
+    /// The time spent compressing
+    compression_time: Duration,
+}
+
+#[cfg(feature = "llmp_compression")]
+impl LlmpCompressor {
+    /// Create a new [`LlmpCompressor`] using `compression` for all buffers at least as large as `threshold`
+    #[must_use]
+    pub fn new(compression: LlmpCompression, threshold: usize) -> Self {
+        Self {
+            compression,
+            threshold,
+            gzip: GzipCompressor::new(),
+            #[cfg(feature = "zstd")]
+            zstd: ZstdCompressor::new(),
+            #[cfg(feature = "lz4")]
+            lz4: Lz4Compressor::new(),
+            bytes_in: 0,
+            bytes_out: 0,
+            compression_time: Duration::ZERO,
+        }
+    }
+
+    /// Create a new [`LlmpCompressor`] that never compresses, for nodes only decompressing incoming messages,
+    /// such as broker hooks. Messages are decompressed with any algorithm compiled in, based on their [`Flags`].
+    #[must_use]
+    pub fn decompressor() -> Self {
+        Self::new(LlmpCompression::default(), usize::MAX)
+    }
+
+    /// Create a new [`LlmpCompressor`] using gzip for all buffers at least as large as `threshold`
+    #[must_use]
+    pub fn with_threshold(threshold: usize) -> Self {
+        Self::new(LlmpCompression::Gzip, threshold)
+    }
+
+    /// The algorithm used for outgoing messages
+    #[must_use]
+    pub fn compression(&self) -> LlmpCompression {
+        self.compression
+    }
+
+    /// Change the algorithm used for outgoing messages
+    pub fn set_compression(&mut self, compression: LlmpCompression) {
+        self.compression = compression;
+    }
+
+    /// Compression.
+    /// If the buffer is smaller than the threshold of this compressor, `None` will be returned.
+    /// Else, the buffer is compressed, and returned together with the [`Flags`] to send it with.
+    pub fn maybe_compress(&mut self, buf: &[u8]) -> Result<Option<(Flags, Vec<u8>)>, Error> {
+        self.bytes_in += buf.len() as u64;
+        if buf.len() < self.threshold {
+            self.bytes_out += buf.len() as u64;
+            return Ok(None);
+        }
+
+        let start = current_time();
+        let compressed = match self.compression {
+            LlmpCompression::Gzip => Compressor::compress(&self.gzip, buf)?,
+            #[cfg(feature = "zstd")]
+            LlmpCompression::Zstd => self.zstd.compress(buf)?,
+            #[cfg(feature = "lz4")]
+            LlmpCompression::Lz4 => self.lz4.compress(buf)?,
+        };
+        self.compression_time += current_time().saturating_sub(start);
+        self.bytes_out += compressed.len() as u64;
+
+        Ok(Some((self.compression.flag(), compressed)))
+    }
+
+    /// Returns `true` if the message with the given [`Flags`] has been compressed by any [`LlmpCompressor`],
+    /// including with algorithms not compiled into this node.
+    #[must_use]
+    pub fn is_compressed(flags: Flags) -> bool {
+        flags.0
+            & (LLMP_FLAG_COMPRESSED.0 | LLMP_FLAG_COMPRESSED_ZSTD.0 | LLMP_FLAG_COMPRESSED_LZ4.0)
+            != 0
+    }
+
+    /// Decompression, using the algorithm indicated by the message's [`Flags`].
+    /// Returns [`Error::Unsupported`] for messages compressed with an algorithm not compiled into this node.
+    pub fn decompress(&self, flags: Flags, buf: &[u8]) -> Result<Vec<u8>, Error> {
+        if flags & LLMP_FLAG_COMPRESSED_ZSTD == LLMP_FLAG_COMPRESSED_ZSTD {
+            #[cfg(feature = "zstd")]
+            return self.zstd.decompress(buf);
+            #[cfg(not(feature = "zstd"))]
+            return Err(Error::unsupported(
+                "Received a zstd compressed message, but the zstd feature is not enabled",
+            ));
+        }
+        if flags & LLMP_FLAG_COMPRESSED_LZ4 == LLMP_FLAG_COMPRESSED_LZ4 {
+            #[cfg(feature = "lz4")]
+            return self.lz4.decompress(buf);
+            #[cfg(not(feature = "lz4"))]
+            return Err(Error::unsupported(
+                "Received an lz4 compressed message, but the lz4 feature is not enabled",
+            ));
+        }
+        if flags & LLMP_FLAG_COMPRESSED == LLMP_FLAG_COMPRESSED {
+            return GzipCompressor::decompress(&self.gzip, buf);
+        }
+        Err(Error::illegal_argument(format!(
+            "Message with {flags:?} is not compressed with a known algorithm"
+        )))
+    }
+
+    /// The ratio of bytes sent to bytes passed to [`Self::maybe_compress`], so far.
+    /// Lower is better, `1.0` if nothing has been compressed yet.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn compression_ratio(&self) -> f64 {
+        if self.bytes_in == 0 {
+            1.0
+        } else {
+            self.bytes_out as f64 / self.bytes_in as f64
+        }
+    }
+
+    /// The total time spent compressing, so far
+    #[must_use]
+    pub fn compression_time(&self) -> Duration {
+        self.compression_time
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "gzip")]
     use crate::compress::GzipCompressor;
 
+    #[cfg(feature = "gzip")]
     #[test]
     fn test_compression() {
         let compressor = GzipCompressor::with_threshold(1);
@@ -87,10 +370,73 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "gzip")]
     #[test]
     fn test_threshold() {
         let compressor = GzipCompressor::with_threshold(1024);
         assert!(compressor.maybe_compress(&[1u8; 1023]).is_none());
         assert!(compressor.maybe_compress(&[1u8; 1024]).is_some());
     }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd_compression() {
+        use crate::compress::{Compressor, ZstdCompressor};
+
+        let compressor = ZstdCompressor::new();
+        let compressed = compressor.compress(&[1u8; 1024]).unwrap();
+        assert!(compressed.len() < 1024);
+        assert_eq!(compressor.decompress(&compressed).unwrap(), vec![1u8; 1024]);
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn test_lz4_compression() {
+        use crate::compress::{Compressor, Lz4Compressor};
+
+        let compressor = Lz4Compressor::new();
+        let compressed = compressor.compress(&[1u8; 1024]).unwrap();
+        assert!(compressed.len() < 1024);
+        assert_eq!(compressor.decompress(&compressed).unwrap(), vec![1u8; 1024]);
+    }
+
+    #[cfg(feature = "llmp_compression")]
+    #[test]
+    fn test_llmp_compressor_flags() {
+        use crate::{
+            compress::{LlmpCompression, LlmpCompressor},
+            llmp::{
+                Flags, LLMP_FLAG_COMPRESSED, LLMP_FLAG_COMPRESSED_LZ4, LLMP_FLAG_COMPRESSED_ZSTD,
+            },
+        };
+
+        let mut sender = LlmpCompressor::with_threshold(1024);
+        assert!(sender.maybe_compress(&[1u8; 1023]).unwrap().is_none());
+        let (flags, buf) = sender.maybe_compress(&[1u8; 1024]).unwrap().unwrap();
+        assert_eq!(flags, LLMP_FLAG_COMPRESSED);
+        assert!(sender.compression_ratio() < 1.0);
+
+        // The receiver decompresses based on the flags, regardless of its own algorithm
+        #[cfg(feature = "lz4")]
+        let receiver = LlmpCompressor::new(LlmpCompression::Lz4, 0);
+        #[cfg(not(feature = "lz4"))]
+        let receiver = LlmpCompressor::new(LlmpCompression::Gzip, 0);
+        assert!(LlmpCompressor::is_compressed(flags));
+        assert!(!LlmpCompressor::is_compressed(Flags(0)));
+        assert_eq!(receiver.decompress(flags, &buf).unwrap(), vec![1u8; 1024]);
+
+        // Messages compressed with algorithms not compiled in are recognized, but refused
+        assert!(LlmpCompressor::is_compressed(LLMP_FLAG_COMPRESSED_ZSTD));
+        assert!(LlmpCompressor::is_compressed(LLMP_FLAG_COMPRESSED_LZ4));
+        #[cfg(not(feature = "zstd"))]
+        assert!(matches!(
+            receiver.decompress(LLMP_FLAG_COMPRESSED_ZSTD, &buf),
+            Err(crate::Error::Unsupported(..))
+        ));
+        #[cfg(not(feature = "lz4"))]
+        assert!(matches!(
+            receiver.decompress(LLMP_FLAG_COMPRESSED_LZ4, &buf),
+            Err(crate::Error::Unsupported(..))
+        ));
+    }
 }
@@ -139,12 +139,16 @@ const LLMP_SLOW_RECEIVER_PANIC: Tag = Tag(0x70051041);
 
 /// Unused...
 pub const LLMP_FLAG_INITIALIZED: Flags = Flags(0x0);
-/// This message was compressed in transit
+/// This message was compressed in transit (using gzip)
 pub const LLMP_FLAG_COMPRESSED: Flags = Flags(0x1);
 /// From another broker.
 pub const LLMP_FLAG_FROM_B2B: Flags = Flags(0x2);
 /// From another machine (with the `multi_machine` mode)
 pub const LLMP_FLAG_FROM_MM: Flags = Flags(0x4);
+/// This message was compressed in transit, using zstd
+pub const LLMP_FLAG_COMPRESSED_ZSTD: Flags = Flags(0x8);
+/// This message was compressed in transit, using lz4
+pub const LLMP_FLAG_COMPRESSED_LZ4: Flags = Flags(0x10);
 
 /// Timt the broker 2 broker connection waits for incoming data,
 /// before checking for own data to forward again.
@@ -208,6 +212,12 @@ impl Debug for Flags {
         if *self & LLMP_FLAG_COMPRESSED == LLMP_FLAG_COMPRESSED {
             f.write_str("COMPRESSED")?;
         }
+        if *self & LLMP_FLAG_COMPRESSED_ZSTD == LLMP_FLAG_COMPRESSED_ZSTD {
+            f.write_str("COMPRESSED_ZSTD")?;
+        }
+        if *self & LLMP_FLAG_COMPRESSED_LZ4 == LLMP_FLAG_COMPRESSED_LZ4 {
+            f.write_str("COMPRESSED_LZ4")?;
+        }
         if *self & LLMP_FLAG_FROM_B2B == LLMP_FLAG_FROM_B2B {
             f.write_str("FROM_B2B")?;
         }
@@ -414,6 +414,15 @@ pub mod serdeany_registry {
             self.map.contains_key(type_repr)
         }
 
+        /// Moves all elements of `other` into this map, unless an element of the same type is already present.
+        /// Elements already contained in this map take precedence.
+        #[inline]
+        pub fn merge_missing(&mut self, other: SerdeAnyMap) {
+            for (type_repr, value) in other.map {
+                self.map.entry(type_repr).or_insert(value);
+            }
+        }
+
         /// Create a new [`SerdeAnyMap`].
         #[must_use]
         pub fn new() -> Self {
@@ -513,6 +513,18 @@ pub const LIBAFL_CC_LLVM_VERSION: Option<usize> = None;
         false,
     );
 
+    #[cfg(feature = "distance")]
+    build_pass(
+        bindir_path,
+        out_dir,
+        &cxxflags,
+        &ldflags,
+        src_dir,
+        "distance-pass.cc",
+        None,
+        false,
+    );
+
     #[cfg(feature = "profiling")]
     build_pass(
         bindir_path,
@@ -36,6 +36,8 @@ pub enum LLVMPasses {
     CoverageAccounting,
     /// The dump cfg pass
     DumpCfg,
+    /// The basic block distance pass, for directed fuzzing, built with the opt-in `distance` feature
+    Distance,
     #[cfg(unix)]
     /// The `CmpLog` Instruction pass
     CmpLogInstructions,
@@ -64,6 +66,9 @@ impl LLVMPasses {
             LLVMPasses::DumpCfg => {
                 PathBuf::from(env!("OUT_DIR")).join(format!("dump-cfg-pass.{}", dll_extension()))
             }
+            LLVMPasses::Distance => {
+                PathBuf::from(env!("OUT_DIR")).join(format!("distance-pass.{}", dll_extension()))
+            }
             #[cfg(unix)]
             LLVMPasses::CmpLogInstructions => PathBuf::from(env!("OUT_DIR"))
                 .join(format!("cmplog-instructions-pass.{}", dll_extension())),
@@ -0,0 +1,368 @@
+/*
+   LibAFL - Distance LLVM pass
+   --------------------------------------------------
+
+   Copyright 2022-2023 AFLplusplus Project. All rights reserved.
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at:
+
+     http://www.apache.org/licenses/LICENSE-2.0
+
+   Instruments each basic block with its distance to a set of target sites,
+   for directed fuzzing as in AFLGo. Every executed block adds its distance
+   and a hit to __libafl_directed_distance, the fuzzer divides the two to get
+   the distance of the input.
+
+   The targets are read from the file named by LIBAFL_DIRECTED_TARGETS, one
+   per line, either `file:line` or the name of a function. The distance of a
+   block is the harmonic mean of the number of control flow and call edges to
+   each target it reaches, in hundredths of an edge.
+
+   This pass only sees the current module. For whole program distances, dump
+   the CFG with the DumpCfg pass first, compute the distances with
+   utils/cfg_builder/distance.py and pass the resulting file as
+   LIBAFL_DIRECTED_DISTANCES, with the same compiler flags for both builds.
+
+*/
+
+#include <stdio.h>
+#include <stdlib.h>
+#include "common-llvm.h"
+#include <string.h>
+#include <sys/types.h>
+
+#include <deque>
+#include <fstream>
+#include <limits>
+#include <map>
+#include <string>
+#include <vector>
+
+#include "llvm/Config/llvm-config.h"
+#include "llvm/IR/IRBuilder.h"
+
+#if USE_NEW_PM
+  #include "llvm/Passes/PassPlugin.h"
+  #include "llvm/Passes/PassBuilder.h"
+  #include "llvm/IR/PassManager.h"
+#else
+  #include "llvm/IR/LegacyPassManager.h"
+  #include "llvm/Transforms/IPO/PassManagerBuilder.h"
+#endif
+
+#include "llvm/IR/BasicBlock.h"
+#include "llvm/IR/Module.h"
+#include "llvm/IR/DebugInfo.h"
+#include "llvm/IR/CFG.h"
+#include "llvm/Support/Debug.h"
+#include "llvm/Support/raw_ostream.h"
+#include "llvm/Pass.h"
+#include "llvm/IR/Constants.h"
+
+using namespace llvm;
+
+// Distances are instrumented as fixed point numbers with this scale
+#define DISTANCE_SCALE 100
+
+namespace {
+
+struct Target {
+  std::string file;
+  unsigned    line;  // 0 for a function target
+  std::string function;
+};
+
+#if USE_NEW_PM
+class DistancePass : public PassInfoMixin<DistancePass> {
+ public:
+  DistancePass() {
+#else
+class DistancePass : public ModulePass {
+ public:
+  static char ID;
+
+  DistancePass() : ModulePass(ID) {
+#endif
+  }
+
+#if USE_NEW_PM
+  PreservedAnalyses run(Module &M, ModuleAnalysisManager &MAM);
+#else
+  bool runOnModule(Module &M) override;
+#endif
+
+ protected:
+  std::vector<Target> targets;
+  // module -> function -> basic block index -> distance
+  std::map<std::string, std::map<std::string, std::map<unsigned, double>>>
+      precomputed;
+
+ private:
+  void loadTargets(const char *path);
+  bool loadDistances(const char *path);
+  bool isTarget(BasicBlock &BB);
+  void computeDistances(Module &M, DenseMap<BasicBlock *, double> &distances);
+};
+
+}  // namespace
+
+#if USE_NEW_PM
+extern "C" ::llvm::PassPluginLibraryInfo LLVM_ATTRIBUTE_WEAK
+llvmGetPassPluginInfo() {
+  return {LLVM_PLUGIN_API_VERSION, "DistancePass", "v0.1",
+          /* lambda to insert our pass into the pass pipeline. */
+          [](PassBuilder &PB) {
+
+  #if LLVM_VERSION_MAJOR <= 13
+            using OptimizationLevel = typename PassBuilder::OptimizationLevel;
+  #endif
+            PB.registerOptimizerLastEPCallback(
+                [](ModulePassManager &MPM, OptimizationLevel OL) {
+                  MPM.addPass(DistancePass());
+                });
+          }};
+}
+#else
+char DistancePass::ID = 0;
+#endif
+
+void DistancePass::loadTargets(const char *path) {
+  std::ifstream in(path);
+  if (!in) { FATAL("Could not open targets file %s\n", path); }
+
+  std::string line;
+  while (std::getline(in, line)) {
+    // strip comments and whitespace
+    line = line.substr(0, line.find('#'));
+    line.erase(0, line.find_first_not_of(" \t\r"));
+    line.erase(line.find_last_not_of(" \t\r") + 1);
+    if (line.empty()) { continue; }
+
+    Target target = {"", 0, ""};
+    size_t sep = line.rfind(':');
+    if (sep != std::string::npos && sep + 1 < line.size() &&
+        line.find_first_not_of("0123456789", sep + 1) == std::string::npos) {
+      target.file = line.substr(0, sep);
+      target.line = (unsigned)std::stoul(line.substr(sep + 1));
+    } else {
+      target.function = line;
+    }
+    targets.push_back(target);
+  }
+}
+
+bool DistancePass::loadDistances(const char *path) {
+  std::ifstream in(path);
+  if (!in) { return false; }
+
+  // one `module\tfunction\tblock\tdistance` record per line
+  std::string line;
+  while (std::getline(in, line)) {
+    size_t first = line.find('\t');
+    size_t second = line.find('\t', first + 1);
+    size_t third = line.find('\t', second + 1);
+    if (first == std::string::npos || second == std::string::npos ||
+        third == std::string::npos) {
+      continue;
+    }
+    precomputed[line.substr(0, first)][line.substr(
+        first + 1, second - first - 1)][(unsigned)std::stoul(line.substr(
+        second + 1, third - second - 1))] = std::stod(line.substr(third + 1));
+  }
+  return true;
+}
+
+bool DistancePass::isTarget(BasicBlock &BB) {
+  for (auto &target : targets) {
+    if (!target.line) {
+      if (&BB == &BB.getParent()->getEntryBlock() &&
+          BB.getParent()->getName() == target.function) {
+        return true;
+      }
+      continue;
+    }
+    for (auto &IN : BB) {
+      DILocation *Loc = IN.getDebugLoc();
+      if (!Loc || Loc->getLine() != target.line) { continue; }
+      // targets may leave out the leading directories
+      std::string file = std::string(Loc->getFilename());
+      if (file == target.file ||
+          (file.size() > target.file.size() &&
+           file.compare(file.size() - target.file.size() - 1, std::string::npos,
+                        "/" + target.file) == 0)) {
+        return true;
+      }
+    }
+  }
+  return false;
+}
+
+void DistancePass::computeDistances(Module                         &M,
+                                    DenseMap<BasicBlock *, double> &distances) {
+  // The module's interprocedural CFG, reversed: from each block to the blocks
+  // jumping to it, or calling the function it is the entry of
+  DenseMap<BasicBlock *, std::vector<BasicBlock *>> incoming;
+  std::vector<BasicBlock *>                         target_bbs;
+
+  for (auto &F : M) {
+    for (auto &BB : F) {
+      for (BasicBlock *Pred : predecessors(&BB)) {
+        incoming[&BB].push_back(Pred);
+      }
+      for (auto &IN : BB) {
+        if (auto *Call = dyn_cast<CallBase>(&IN)) {
+          Function *Callee = Call->getCalledFunction();
+          if (Callee && !Callee->isDeclaration()) {
+            incoming[&Callee->getEntryBlock()].push_back(&BB);
+          }
+        }
+      }
+      if (isTarget(BB)) { target_bbs.push_back(&BB); }
+    }
+  }
+
+  // Harmonic mean over the reachable targets of the shortest distance to them
+  DenseMap<BasicBlock *, double>   inverse_sums;
+  DenseMap<BasicBlock *, unsigned> reached;
+  for (BasicBlock *Target : target_bbs) {
+    DenseMap<BasicBlock *, unsigned> depth;
+    std::deque<BasicBlock *>         queue;
+    depth[Target] = 0;
+    queue.push_back(Target);
+    while (!queue.empty()) {
+      BasicBlock *BB = queue.front();
+      queue.pop_front();
+      for (BasicBlock *Pred : incoming[BB]) {
+        if (depth.count(Pred)) { continue; }
+        unsigned next = depth[BB] + 1;
+        depth[Pred] = next;
+        queue.push_back(Pred);
+      }
+    }
+    for (auto &entry : depth) {
+      if (!entry.second) { continue; }
+      inverse_sums[entry.first] += 1.0 / entry.second;
+      reached[entry.first]++;
+    }
+  }
+
+  for (auto &entry : reached) {
+    distances[entry.first] = entry.second / inverse_sums[entry.first];
+  }
+  for (BasicBlock *Target : target_bbs) {
+    distances[Target] = 0;
+  }
+}
+
+#if USE_NEW_PM
+PreservedAnalyses DistancePass::run(Module &M, ModuleAnalysisManager &MAM) {
+#else
+bool DistancePass::runOnModule(Module &M) {
+
+#endif
+  LLVMContext &C = M.getContext();
+  std::string  moduleName = std::string(M.getName());
+  IntegerType *Int64Ty = IntegerType::getInt64Ty(C);
+
+  DenseMap<BasicBlock *, double> distances;
+
+  char *distances_path = getenv("LIBAFL_DIRECTED_DISTANCES");
+  char *targets_path = getenv("LIBAFL_DIRECTED_TARGETS");
+  if (distances_path) {
+    if (!loadDistances(distances_path)) {
+      FATAL("Could not open distances file %s\n", distances_path);
+    }
+    auto module_distances = precomputed.find(moduleName);
+    if (module_distances != precomputed.end()) {
+      for (auto &F : M) {
+        auto function_distances =
+            module_distances->second.find(std::string(F.getName()));
+        if (function_distances == module_distances->second.end()) { continue; }
+        // blocks are numbered in the same order as the DumpCfg pass does
+        unsigned bb_cnt = 0;
+        for (auto &BB : F) {
+          auto distance = function_distances->second.find(bb_cnt++);
+          if (distance != function_distances->second.end()) {
+            distances[&BB] = distance->second;
+          }
+        }
+      }
+    }
+  } else if (targets_path) {
+    loadTargets(targets_path);
+    computeDistances(M, distances);
+  } else {
+    FATAL("Neither LIBAFL_DIRECTED_TARGETS nor LIBAFL_DIRECTED_DISTANCES set!");
+  }
+
+  ArrayType      *CountersTy = ArrayType::get(Int64Ty, 2);
+  GlobalVariable *DistanceCounters =
+      new GlobalVariable(M, CountersTy, false, GlobalValue::ExternalLinkage, 0,
+                         "__libafl_directed_distance");
+  Value *SumPtr =
+      ConstantExpr::getInBoundsGetElementPtr(CountersTy, DistanceCounters,
+                                             ArrayRef<Constant *>{
+                                                 ConstantInt::get(Int64Ty, 0),
+                                                 ConstantInt::get(Int64Ty, 0),
+                                             });
+  Value *CountPtr =
+      ConstantExpr::getInBoundsGetElementPtr(CountersTy, DistanceCounters,
+                                             ArrayRef<Constant *>{
+                                                 ConstantInt::get(Int64Ty, 0),
+                                                 ConstantInt::get(Int64Ty, 1),
+                                             });
+
+  for (auto &F : M) {
+    if (isIgnoreFunction(&F)) { continue; }
+    for (auto &BB : F) {
+      auto distance = distances.find(&BB);
+      if (distance == distances.end()) { continue; }
+
+      BasicBlock::iterator IP = BB.getFirstInsertionPt();
+      if (IP == BB.end()) { continue; }
+      IRBuilder<> IRB(&(*IP));
+
+      uint64_t scaled = (uint64_t)(distance->second * DISTANCE_SCALE + 0.5);
+
+      LoadInst *Sum = IRB.CreateLoad(Int64Ty, SumPtr);
+      Sum->setMetadata(M.getMDKindID("nosanitize"), MDNode::get(C, None));
+      IRB.CreateStore(IRB.CreateAdd(Sum, ConstantInt::get(Int64Ty, scaled)),
+                      SumPtr)
+          ->setMetadata(M.getMDKindID("nosanitize"), MDNode::get(C, None));
+
+      LoadInst *Count = IRB.CreateLoad(Int64Ty, CountPtr);
+      Count->setMetadata(M.getMDKindID("nosanitize"), MDNode::get(C, None));
+      IRB.CreateStore(IRB.CreateAdd(Count, ConstantInt::get(Int64Ty, 1)),
+                      CountPtr)
+          ->setMetadata(M.getMDKindID("nosanitize"), MDNode::get(C, None));
+    }
+  }
+
+#if USE_NEW_PM
+  auto PA = PreservedAnalyses::none();
+  return PA;
+#else
+  return true;
+#endif
+}
+
+#if USE_NEW_PM
+
+#else
+static void registerDistancePass(const PassManagerBuilder &,
+                                 legacy::PassManagerBase &PM) {
+  PM.add(new DistancePass());
+}
+
+static RegisterPass<DistancePass> X("distance",
+                                    "directed distance instrumentation pass",
+                                    false, false);
+
+static RegisterStandardPasses RegisterDistancePass(
+    PassManagerBuilder::EP_OptimizerLast, registerDistancePass);
+
+static RegisterStandardPasses RegisterDistancePass0(
+    PassManagerBuilder::EP_EnabledOnOptLevel0, registerDistancePass);
+#endif
@@ -97,6 +97,7 @@ class DumpCfgPass : public ModulePass {
   DenseMap<BasicBlock *, uint32_t>               bb_to_cur_loc;
   DenseMap<StringRef, BasicBlock *>              entry_bb;
   DenseMap<BasicBlock *, std::vector<StringRef>> calls_in_bb;
+  DenseMap<BasicBlock *, std::set<std::string>>  lines_in_bb;
 
  private:
   bool isLLVMIntrinsicFn(StringRef &n) {
@@ -151,6 +152,13 @@ bool DumpCfgPass::runOnModule(Module &M) {
       bb_to_cur_loc[&BB] = bb_cnt;
       bb_cnt++;
       for (auto &IN : BB) {
+        if (DILocation *Loc = IN.getDebugLoc()) {
+          if (Loc->getLine()) {
+            lines_in_bb[&BB].insert(std::string(Loc->getFilename()) + ":" +
+                                    std::to_string(Loc->getLine()));
+          }
+        }
+
         CallBase *callBase = nullptr;
         if ((callBase = dyn_cast<CallBase>(&IN))) {
           auto F = callBase->getCalledFunction();
@@ -209,6 +217,17 @@ bool DumpCfgPass::runOnModule(Module &M) {
     }
   }
 
+  for (auto record = lines_in_bb.begin(); record != lines_in_bb.end();
+       record++) {
+    auto        current_bb = record->getFirst();
+    auto        loc = bb_to_cur_loc[current_bb];
+    std::string func_name = std::string(current_bb->getParent()->getName());
+
+    std::vector<std::string> lines(record->getSecond().begin(),
+                                   record->getSecond().end());
+    cfg["lines"][func_name][std::to_string(loc)] = lines;
+  }
+
   for (auto record = entry_bb.begin(); record != entry_bb.end(); record++) {
     cfg["entries"][std::string(record->getFirst())] =
         bb_to_cur_loc[record->getSecond()];
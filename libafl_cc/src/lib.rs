@@ -76,6 +76,9 @@ pub enum Configuration {
     GenerateCoverageMap,
     /// Generating coverage profile data for `llvm-cov`
     GenerateCoverageProfile,
+    /// Generating a coverage map, the PC table, and debug info, for source-level coverage reports
+    /// (see `libafl_targets::coverage_report`)
+    GenerateCoverageReport,
     /// Instrumenting for cmplog/redqueen
     CmpLog,
     /// A compound `Configuration`, made up of a list of other `Configuration`s
@@ -102,6 +105,10 @@ impl Configuration {
             Configuration::GenerateCoverageMap => {
                 vec!["-fsanitize-coverage=trace-pc-guard".to_string()]
             }
+            Configuration::GenerateCoverageReport => vec![
+                "-fsanitize-coverage=trace-pc-guard,pc-table".to_string(),
+                "-g".to_string(),
+            ],
             Configuration::CmpLog => vec!["-fsanitize-coverage=trace-cmp".to_string()],
             Configuration::GenerateCoverageProfile => {
                 vec![
@@ -153,6 +160,7 @@ impl std::str::FromStr for Configuration {
             "ubsan" => Configuration::UndefinedBehaviorSanitizer,
             "coverage" => Configuration::GenerateCoverageMap,
             "llvm-cov" => Configuration::GenerateCoverageProfile,
+            "coverage-report" => Configuration::GenerateCoverageReport,
             "cmplog" => Configuration::CmpLog,
             _ => Configuration::Default,
         })
@@ -167,6 +175,7 @@ impl std::fmt::Display for Configuration {
             Configuration::UndefinedBehaviorSanitizer => write!(f, "ubsan"),
             Configuration::GenerateCoverageMap => write!(f, "coverage"),
             Configuration::GenerateCoverageProfile => write!(f, "llvm-cov"),
+            Configuration::GenerateCoverageReport => write!(f, "coverage-report"),
             Configuration::CmpLog => write!(f, "cmplog"),
             Configuration::Compound(configurations) => {
                 let mut result: Vec<String> = vec![];
@@ -160,7 +160,7 @@ macro_rules! fuzz_with {
             },
             observers::{stacktrace::BacktraceObserver, TimeObserver, CanTrack},
             schedulers::{
-                IndexesLenTimeMinimizerScheduler, powersched::PowerSchedule, PowerQueueScheduler,
+                EntropicScheduler, IndexesLenTimeMinimizerScheduler, powersched::PowerSchedule, PowerQueueScheduler,
             },
             stages::{
                 CalibrationStage, GeneralizationStage, IfStage, StdMutationalStage,
@@ -180,6 +180,7 @@ macro_rules! fuzz_with {
             feedbacks::{LibfuzzerCrashCauseFeedback, LibfuzzerKeepFeedback, ShrinkMapFeedback},
             misc::should_use_grimoire,
             observers::{MappedEdgeMapObserver, SizeValueObserver},
+            schedulers::LibfuzzerScheduler,
         };
 
         let edge_maker = &$edge_maker;
@@ -416,8 +417,13 @@ macro_rules! fuzz_with {
             );
             let grimoire = IfStage::new(|_, _, _, _| Ok(grimoire.into()), (StdMutationalStage::transforming(grimoire_mutator), ()));
 
-            // A minimization+queue policy to get testcasess from the corpus
-            let scheduler = IndexesLenTimeMinimizerScheduler::new(&edges_observer, PowerQueueScheduler::new(&mut state, &edges_observer, PowerSchedule::fast()));
+            // A minimization+queue policy to get testcasess from the corpus, or libFuzzer's Entropic with `-entropic=1`
+            let scheduler = if $options.entropic() {
+                LibfuzzerScheduler::Entropic(EntropicScheduler::with_schedule(&mut state, &edges_observer, Some(PowerSchedule::fast())))
+            } else {
+                LibfuzzerScheduler::Queue(PowerQueueScheduler::new(&mut state, &edges_observer, PowerSchedule::fast()))
+            };
+            let scheduler = IndexesLenTimeMinimizerScheduler::new(&edges_observer, scheduler);
 
             // A fuzzer with feedbacks and a corpus scheduler
             let mut fuzzer = StdFuzzer::new(scheduler, feedback, objective);
@@ -121,6 +121,7 @@ pub struct LibfuzzerOptions {
     shrink: bool,
     skip_tracing: bool,
     tui: bool,
+    entropic: bool,
     runs: usize,
     close_fd_mask: u8,
     unknown: Vec<String>,
@@ -220,6 +221,10 @@ impl LibfuzzerOptions {
         self.tui
     }
 
+    pub fn entropic(&self) -> bool {
+        self.entropic
+    }
+
     pub fn runs(&self) -> usize {
         self.runs
     }
@@ -255,6 +260,7 @@ struct LibfuzzerOptionsBuilder<'a> {
     shrink: bool,
     skip_tracing: bool,
     tui: bool,
+    entropic: bool,
     runs: usize,
     close_fd_mask: u8,
     unknown: Vec<&'a str>,
@@ -356,6 +362,7 @@ impl<'a> LibfuzzerOptionsBuilder<'a> {
                                 }
                             }
                         }
+                        "entropic" => self.entropic = parse_or_bail!(name, value, u64) > 0,
                         "runs" => self.runs = parse_or_bail!(name, value, usize),
                         "close_fd_mask" => self.close_fd_mask = parse_or_bail!(name, value, u8),
                         _ => {
@@ -402,6 +409,7 @@ impl<'a> LibfuzzerOptionsBuilder<'a> {
             shrink: self.shrink,
             skip_tracing: self.skip_tracing,
             tui: self.tui,
+            entropic: self.entropic,
             runs: self.runs,
             close_fd_mask: self.close_fd_mask,
             unknown: self.unknown.into_iter().map(ToString::to_string).collect(),
@@ -9,6 +9,7 @@ use libafl::{
     state::{HasCorpus, State},
     Error, HasMetadata,
 };
+use libafl_bolts::tuples::MatchName;
 
 #[derive(Clone, Debug)]
 pub struct MergeScheduler<I, S> {
@@ -81,3 +82,78 @@ impl<I, S> MergeScheduler<I, S> {
         &self.all
     }
 }
+
+/// The scheduler of the fuzzing loop, picked at runtime with `-entropic`
+#[derive(Clone, Debug)]
+pub enum LibfuzzerScheduler<Q, E> {
+    /// The default power queue
+    Queue(Q),
+    /// libFuzzer's Entropic
+    Entropic(E),
+}
+
+impl<E, I, Q, S> RemovableScheduler<I, S> for LibfuzzerScheduler<Q, E>
+where
+    E: RemovableScheduler<I, S>,
+    Q: RemovableScheduler<I, S>,
+{
+    fn on_remove(
+        &mut self,
+        state: &mut S,
+        id: CorpusId,
+        testcase: &Option<Testcase<I>>,
+    ) -> Result<(), Error> {
+        match self {
+            Self::Queue(scheduler) => scheduler.on_remove(state, id, testcase),
+            Self::Entropic(scheduler) => scheduler.on_remove(state, id, testcase),
+        }
+    }
+
+    fn on_replace(&mut self, state: &mut S, id: CorpusId, prev: &Testcase<I>) -> Result<(), Error> {
+        match self {
+            Self::Queue(scheduler) => scheduler.on_replace(state, id, prev),
+            Self::Entropic(scheduler) => scheduler.on_replace(state, id, prev),
+        }
+    }
+}
+
+impl<E, I, Q, S> Scheduler<I, S> for LibfuzzerScheduler<Q, E>
+where
+    E: Scheduler<I, S>,
+    Q: Scheduler<I, S>,
+{
+    fn on_add(&mut self, state: &mut S, id: CorpusId) -> Result<(), Error> {
+        match self {
+            Self::Queue(scheduler) => scheduler.on_add(state, id),
+            Self::Entropic(scheduler) => scheduler.on_add(state, id),
+        }
+    }
+
+    fn on_evaluation<OT>(&mut self, state: &mut S, input: &I, observers: &OT) -> Result<(), Error>
+    where
+        OT: MatchName,
+    {
+        match self {
+            Self::Queue(scheduler) => scheduler.on_evaluation(state, input, observers),
+            Self::Entropic(scheduler) => scheduler.on_evaluation(state, input, observers),
+        }
+    }
+
+    fn next(&mut self, state: &mut S) -> Result<CorpusId, Error> {
+        match self {
+            Self::Queue(scheduler) => scheduler.next(state),
+            Self::Entropic(scheduler) => scheduler.next(state),
+        }
+    }
+
+    fn set_current_scheduled(
+        &mut self,
+        state: &mut S,
+        next_id: Option<CorpusId>,
+    ) -> Result<(), Error> {
+        match self {
+            Self::Queue(scheduler) => scheduler.set_current_scheduled(state, next_id),
+            Self::Entropic(scheduler) => scheduler.set_current_scheduled(state, next_id),
+        }
+    }
+}
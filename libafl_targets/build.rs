@@ -214,7 +214,11 @@ fn main() {
         }
     }
 
-    #[cfg(any(feature = "forkserver", feature = "windows_asan"))]
+    #[cfg(any(
+        feature = "forkserver",
+        feature = "shmem_input",
+        feature = "windows_asan"
+    ))]
     let target_family = std::env::var("CARGO_CFG_TARGET_FAMILY").unwrap();
 
     #[cfg(feature = "forkserver")]
@@ -228,6 +232,36 @@ fn main() {
         }
     }
 
+    #[cfg(feature = "shmem_input")]
+    {
+        if target_family == "unix" {
+            println!("cargo:rerun-if-changed=src/shmem_input.c");
+
+            cc::Build::new()
+                .file(src_dir.join("shmem_input.c"))
+                .compile("shmem_input");
+        }
+    }
+
+    #[cfg(feature = "determinism")]
+    if env::var("CARGO_CFG_TARGET_OS").unwrap() == "linux" {
+        println!("cargo:rerun-if-changed=src/determinism.c");
+
+        // An LD_PRELOAD shim, so it is built as a shared object instead of being linked in
+        let shim = Path::new(&out_dir).join("libafl_determinism.so");
+        let status = cc::Build::new()
+            .get_compiler()
+            .to_command()
+            .args(["-shared", "-fPIC", "-O2", "-o"])
+            .arg(&shim)
+            .arg(src_dir.join("determinism.c"))
+            .arg("-ldl")
+            .status()
+            .expect("Failed to run the C compiler");
+        assert!(status.success(), "Could not build the determinism shim");
+        println!("cargo:rustc-env=LIBAFL_DETERMINISM_SHIM={}", shim.display());
+    }
+
     #[cfg(feature = "windows_asan")]
     if target_family == "windows" {
         println!("cargo:rerun-if-changed=src/windows_asan.c");
@@ -0,0 +1,887 @@
+//! Source-level coverage reports for targets instrumented with `sancov_pcguard` and the `SanitizerCoverage` PC table.
+//!
+//! Each edge of the coverage map has a matching entry in the PC table (see [`sanitizer_cov_pc_table`]).
+//! [`EdgeLocations::from_pc_tables`] maps these PCs back to source lines using the DWARF debug info of
+//! the loaded modules, and a [`CoverageReport`] combines them with per-edge hit counts, to be written as
+//! an lcov `.info` file, or as a static HTML report.
+//!
+//! Build the target with `libafl_cc`'s `Configuration::GenerateCoverageReport`
+//! (`-fsanitize-coverage=trace-pc-guard,pc-table -g`), and add a [`CoverageReportStage`] to replay the corpus.
+
+use alloc::{
+    borrow::{Cow, ToOwned},
+    collections::BTreeMap,
+    rc::Rc,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    ffi::{c_int, c_void, CStr},
+    fmt::Write as _,
+    marker::PhantomData,
+    ops::Range,
+    slice,
+    time::Duration,
+};
+use std::{
+    ffi::OsStr,
+    fs,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+};
+
+use addr2line::Context;
+use gimli::{EndianRcSlice, RunTimeEndian};
+use hashbrown::HashMap;
+use libafl::{
+    corpus::{Corpus, CorpusId, HasCurrentCorpusId},
+    inputs::UsesInput,
+    stages::{RetryCountRestartHelper, Stage},
+    state::{HasCorpus, UsesState},
+    Error, ExecutesInput, HasNamedMetadata,
+};
+use libafl_bolts::{current_time, impl_serdeany, Named};
+use object::{Object, ObjectSection};
+use serde::{Deserialize, Serialize};
+
+use crate::{edges_map_mut_ptr, edges_max_num, sanitizer_cov_pc_table};
+
+/// The source location of an edge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdgeLocation {
+    /// The source file
+    pub file: String,
+    /// The line in the source file
+    pub line: u32,
+    /// The (demangled) name of the function containing the edge
+    pub function: Option<String>,
+    /// Whether the edge is the entry point of its function
+    pub function_entry: bool,
+}
+
+/// The source locations of all edges, indexed like the coverage map
+#[derive(Debug, Clone, Default)]
+pub struct EdgeLocations {
+    edges: Vec<Option<EdgeLocation>>,
+}
+
+/// A module loaded into the current process
+struct LoadedModule {
+    path: PathBuf,
+    /// The difference between the runtime addresses and the addresses in the file
+    bias: u64,
+    segments: Vec<Range<u64>>,
+}
+
+unsafe extern "C" fn collect_loaded_module(
+    info: *mut libc::dl_phdr_info,
+    _size: usize,
+    data: *mut c_void,
+) -> c_int {
+    let modules = &mut *data.cast::<Vec<LoadedModule>>();
+    let info = &*info;
+    let path = if info.dlpi_name.is_null() || *info.dlpi_name == 0 {
+        // The main executable
+        PathBuf::from("/proc/self/exe")
+    } else {
+        PathBuf::from(OsStr::from_bytes(CStr::from_ptr(info.dlpi_name).to_bytes()))
+    };
+    let bias = info.dlpi_addr;
+    let segments = if info.dlpi_phdr.is_null() {
+        Vec::new()
+    } else {
+        slice::from_raw_parts(info.dlpi_phdr, usize::from(info.dlpi_phnum))
+            .iter()
+            .filter(|phdr| phdr.p_type == libc::PT_LOAD)
+            .map(|phdr| {
+                let start = bias + phdr.p_vaddr;
+                start..start + phdr.p_memsz
+            })
+            .collect()
+    };
+    modules.push(LoadedModule {
+        path,
+        bias,
+        segments,
+    });
+    0
+}
+
+/// Loads the DWARF debug info of the file at `path`
+fn load_debug_info(path: &Path) -> Result<Context<EndianRcSlice<RunTimeEndian>>, Error> {
+    let data = fs::read(path)?;
+    let file = object::File::parse(&*data).map_err(|err| {
+        Error::illegal_argument(format!("Failed to parse {}: {err}", path.display()))
+    })?;
+    let endian = if file.is_little_endian() {
+        RunTimeEndian::Little
+    } else {
+        RunTimeEndian::Big
+    };
+    let dwarf = gimli::Dwarf::load(|id| -> Result<_, gimli::Error> {
+        let section = file
+            .section_by_name(id.name())
+            .and_then(|section| section.uncompressed_data().ok())
+            .unwrap_or_default();
+        Ok(EndianRcSlice::new(Rc::from(&*section), endian))
+    })
+    .and_then(Context::from_dwarf)
+    .map_err(|err| {
+        Error::illegal_argument(format!(
+            "Failed to load debug info of {}: {err}",
+            path.display()
+        ))
+    })?;
+    Ok(dwarf)
+}
+
+/// Looks up the source location of the address `svma` (relative to its module)
+fn locate(
+    context: &Context<EndianRcSlice<RunTimeEndian>>,
+    svma: u64,
+    function_entry: bool,
+) -> Option<EdgeLocation> {
+    let mut frames = context.find_frames(svma).skip_all_loads().ok()?;
+    let mut location = None;
+    let mut function = None;
+    // Frames go from the innermost inlined function to the function actually containing the address
+    while let Ok(Some(frame)) = frames.next() {
+        if location.is_none() {
+            if let Some((file, line)) = frame
+                .location
+                .and_then(|location| Some((location.file?, location.line?)))
+            {
+                location = Some((file.to_string(), line));
+            }
+        }
+        if let Some(name) = frame.function {
+            function = name.demangle().ok().map(Cow::into_owned);
+        }
+    }
+    let (file, line) = location?;
+    Some(EdgeLocation {
+        file,
+        line,
+        function,
+        function_entry,
+    })
+}
+
+impl EdgeLocations {
+    /// Creates [`EdgeLocations`] from the given locations, indexed like the coverage map
+    #[must_use]
+    pub fn new(edges: Vec<Option<EdgeLocation>>) -> Self {
+        Self { edges }
+    }
+
+    /// Maps each entry of the `SanitizerCoverage` PC tables of the current process back to source,
+    /// using the DWARF debug info of the module containing it.
+    ///
+    /// Edges without debug info get no location.
+    #[must_use]
+    pub fn from_pc_tables() -> Self {
+        let mut modules: Vec<LoadedModule> = Vec::new();
+        unsafe {
+            libc::dl_iterate_phdr(
+                Some(collect_loaded_module),
+                (&raw mut modules).cast::<c_void>(),
+            );
+        }
+        let mut contexts = HashMap::new();
+
+        let mut edges = Vec::new();
+        for entry in sanitizer_cov_pc_table().flatten() {
+            let pc = entry.addr() as u64;
+            let location = modules
+                .iter()
+                .position(|module| module.segments.iter().any(|range| range.contains(&pc)))
+                .and_then(|idx| {
+                    let module = &modules[idx];
+                    let context = contexts.entry(idx).or_insert_with(|| {
+                        load_debug_info(&module.path)
+                            .inspect_err(|err| log::warn!("No coverage locations: {err}"))
+                            .ok()
+                    });
+                    locate(
+                        context.as_ref()?,
+                        pc - module.bias,
+                        entry.is_function_entry(),
+                    )
+                });
+            edges.push(location);
+        }
+        Self { edges }
+    }
+
+    /// The number of edges
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Returns `true` if there are no edges
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+
+    /// The location of the given edge, if known
+    #[must_use]
+    pub fn get(&self, edge: usize) -> Option<&EdgeLocation> {
+        self.edges.get(edge)?.as_ref()
+    }
+}
+
+/// The coverage of a function
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FunctionCoverage {
+    /// The line of the function entry
+    pub line: u32,
+    /// The hit count of the function entry
+    pub hits: u64,
+}
+
+/// The coverage of a source file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileCoverage {
+    /// The hit count of each instrumented line
+    pub lines: BTreeMap<u32, u64>,
+    /// The coverage of each function, by name
+    pub functions: BTreeMap<String, FunctionCoverage>,
+}
+
+impl FileCoverage {
+    /// The number of lines hit at least once
+    #[must_use]
+    pub fn lines_hit(&self) -> usize {
+        self.lines.values().filter(|hits| **hits > 0).count()
+    }
+
+    /// The number of functions hit at least once
+    #[must_use]
+    pub fn functions_hit(&self) -> usize {
+        self.functions.values().filter(|f| f.hits > 0).count()
+    }
+}
+
+/// A source-level coverage report, built from [`EdgeLocations`] and per-edge hit counts
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CoverageReport {
+    files: BTreeMap<String, FileCoverage>,
+}
+
+impl CoverageReport {
+    /// Creates a new [`CoverageReport`]. `hits` holds the hit count of each edge, indexed like the coverage map.
+    ///
+    /// The hit count of a line is the highest hit count of the edges on it.
+    #[must_use]
+    pub fn new(locations: &EdgeLocations, hits: &[u64]) -> Self {
+        let mut files: BTreeMap<String, FileCoverage> = BTreeMap::new();
+        for (edge, location) in locations.edges.iter().enumerate() {
+            let Some(location) = location else {
+                continue;
+            };
+            let edge_hits = hits.get(edge).copied().unwrap_or(0);
+            let file = files.entry(location.file.clone()).or_default();
+            let line_hits = file.lines.entry(location.line).or_default();
+            *line_hits = (*line_hits).max(edge_hits);
+            if let (true, Some(function)) = (location.function_entry, &location.function) {
+                file.functions.insert(
+                    function.clone(),
+                    FunctionCoverage {
+                        line: location.line,
+                        hits: edge_hits,
+                    },
+                );
+            }
+        }
+        Self { files }
+    }
+
+    /// The coverage of each source file
+    #[must_use]
+    pub fn files(&self) -> &BTreeMap<String, FileCoverage> {
+        &self.files
+    }
+
+    /// Renders the report in the lcov tracefile format
+    #[must_use]
+    pub fn to_lcov(&self) -> String {
+        let mut lcov = String::from("TN:\n");
+        for (path, file) in &self.files {
+            // Writing to a `String` can't fail
+            let _ = writeln!(lcov, "SF:{path}");
+            for (name, function) in &file.functions {
+                let _ = writeln!(lcov, "FN:{},{name}", function.line);
+            }
+            for (name, function) in &file.functions {
+                let _ = writeln!(lcov, "FNDA:{},{name}", function.hits);
+            }
+            let _ = writeln!(lcov, "FNF:{}", file.functions.len());
+            let _ = writeln!(lcov, "FNH:{}", file.functions_hit());
+            for (line, hits) in &file.lines {
+                let _ = writeln!(lcov, "DA:{line},{hits}");
+            }
+            let _ = writeln!(lcov, "LF:{}", file.lines.len());
+            let _ = writeln!(lcov, "LH:{}", file.lines_hit());
+            lcov.push_str("end_of_record\n");
+        }
+        lcov
+    }
+
+    /// Writes the report as lcov tracefile to `path`
+    pub fn write_lcov<P>(&self, path: P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        fs::write(path, self.to_lcov())?;
+        Ok(())
+    }
+
+    /// Writes the report as static HTML to `dir`: an `index.html` summary, and one page per source file.
+    ///
+    /// Source files are read from their original path, if they can't be found only the instrumented lines are listed.
+    pub fn write_html<P>(&self, dir: P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+
+        let mut index = html_header("Coverage report");
+        let (lines, lines_hit) = self.files.values().fold((0, 0), |(lines, hit), file| {
+            (lines + file.lines.len(), hit + file.lines_hit())
+        });
+        let _ = writeln!(
+            index,
+            "<h1>Coverage report</h1>\n<p>{lines_hit} of {lines} lines hit ({})</p>",
+            percentage(lines_hit, lines)
+        );
+        index.push_str(
+            "<table>\n<tr><th>File</th><th>Lines</th><th>Line coverage</th><th>Functions</th></tr>\n",
+        );
+        for (idx, (path, file)) in self.files.iter().enumerate() {
+            let page = format!("file{idx}.html");
+            let _ = writeln!(
+                index,
+                "<tr><td><a href=\"{page}\">{}</a></td><td>{} / {}</td><td>{}</td><td>{} / {}</td></tr>",
+                escape_html(path),
+                file.lines_hit(),
+                file.lines.len(),
+                percentage(file.lines_hit(), file.lines.len()),
+                file.functions_hit(),
+                file.functions.len(),
+            );
+            fs::write(dir.join(page), file_page(path, file))?;
+        }
+        index.push_str("</table>\n</body>\n</html>\n");
+        fs::write(dir.join("index.html"), index)?;
+        Ok(())
+    }
+}
+
+fn html_header(title: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n\
+         body {{ font-family: sans-serif; }}\n\
+         table {{ border-collapse: collapse; }}\n\
+         td, th {{ padding: 2px 8px; text-align: left; }}\n\
+         pre {{ margin: 0; }}\n\
+         .hit {{ background: #c8f0c8; }}\n\
+         .miss {{ background: #f0c8c8; }}\n\
+         </style>\n</head>\n<body>\n",
+        escape_html(title)
+    )
+}
+
+fn file_page(path: &str, file: &FileCoverage) -> String {
+    let mut page = html_header(path);
+    let _ = writeln!(
+        page,
+        "<h1>{}</h1>\n<p>{} of {} lines hit ({})</p>\n<p><a href=\"index.html\">Back</a></p>\n<table>",
+        escape_html(path),
+        file.lines_hit(),
+        file.lines.len(),
+        percentage(file.lines_hit(), file.lines.len())
+    );
+    let mut write_line = |line: u32, source: &str| {
+        let (class, hits) = match file.lines.get(&line) {
+            Some(0) => ("miss", "0".to_string()),
+            Some(hits) => ("hit", hits.to_string()),
+            None => ("", String::new()),
+        };
+        let _ = writeln!(
+            page,
+            "<tr class=\"{class}\"><td>{line}</td><td>{hits}</td><td><pre>{}</pre></td></tr>",
+            escape_html(source)
+        );
+    };
+    if let Ok(source) = fs::read_to_string(path) {
+        for (line, text) in (1..).zip(source.lines()) {
+            write_line(line, text);
+        }
+    } else {
+        for line in file.lines.keys() {
+            write_line(*line, "");
+        }
+    }
+    page.push_str("</table>\n</body>\n</html>\n");
+    page
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn percentage(hit: usize, total: usize) -> String {
+    if total == 0 {
+        return "-".to_owned();
+    }
+    format!("{:.1}%", hit as f64 * 100.0 / total as f64)
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The name for the coverage report stage
+pub static COVERAGE_REPORT_STAGE_NAME: &str = "coverage_report";
+
+/// The progress of a [`CoverageReportStage`], kept in the state so it survives restarts
+#[allow(clippy::unsafe_derive_deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct CoverageReportMetadata {
+    /// The number of replayed corpus entries reaching each edge, indexed like the coverage map
+    pub hits: Vec<u64>,
+    /// The last corpus entry we replayed, or tried to replay
+    pub last_replayed: Option<CorpusId>,
+    /// If we replayed entries since the last report
+    pub dirty: bool,
+}
+
+impl_serdeany!(CoverageReportMetadata);
+
+impl CoverageReportMetadata {
+    /// Adds the edges covered by the last execution to the hit counts
+    fn record_hits(&mut self) {
+        let map = unsafe { slice::from_raw_parts(edges_map_mut_ptr(), edges_max_num()) };
+        if self.hits.len() < map.len() {
+            self.hits.resize(map.len(), 0);
+        }
+        for (hits, value) in self.hits.iter_mut().zip(map) {
+            if *value != 0 {
+                *hits += 1;
+            }
+        }
+    }
+}
+
+/// A stage replaying each corpus entry once, and periodically writing a [`CoverageReport`] of the whole corpus
+/// to `coverage.info` (lcov) and `html/` in the report directory.
+///
+/// The hit count of an edge is the number of corpus entries reaching it.
+/// The hit counts and the replay progress are kept in a [`CoverageReportMetadata`] of the state, and an entry is
+/// marked as replayed before it runs, so an entry crashing the fuzzer is not replayed again after the restart.
+/// Works with in-process executors only, as it reads the coverage map of the current process.
+#[derive(Debug)]
+pub struct CoverageReportStage<E, EM, Z> {
+    name: Cow<'static, str>,
+    report_dir: PathBuf,
+    interval: Duration,
+    last_report: Option<Duration>,
+    locations: Option<EdgeLocations>,
+    phantom: PhantomData<(E, EM, Z)>,
+}
+
+impl<E, EM, Z> CoverageReportStage<E, EM, Z> {
+    /// Creates a new [`CoverageReportStage`], writing a report to `report_dir` at most once a minute
+    #[must_use]
+    pub fn new<P>(report_dir: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        Self::with_interval(report_dir, Duration::from_secs(60))
+    }
+
+    /// Creates a new [`CoverageReportStage`], writing a report to `report_dir` at most once every `interval`
+    #[must_use]
+    pub fn with_interval<P>(report_dir: P, interval: Duration) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        Self {
+            name: Cow::Borrowed(COVERAGE_REPORT_STAGE_NAME),
+            report_dir: report_dir.into(),
+            interval,
+            last_report: None,
+            locations: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The current report of the corpus entries replayed in `state`
+    pub fn report<S>(&mut self, state: &S) -> CoverageReport
+    where
+        S: HasNamedMetadata,
+    {
+        let locations = self
+            .locations
+            .get_or_insert_with(EdgeLocations::from_pc_tables);
+        let hits = state
+            .named_metadata::<CoverageReportMetadata>(&self.name)
+            .map_or(&[][..], |meta| &meta.hits);
+        CoverageReport::new(locations, hits)
+    }
+
+    /// Writes the current report to the report directory
+    pub fn write_report<S>(&mut self, state: &mut S) -> Result<(), Error>
+    where
+        S: HasNamedMetadata,
+    {
+        let report = self.report(state);
+        fs::create_dir_all(&self.report_dir)?;
+        report.write_lcov(self.report_dir.join("coverage.info"))?;
+        report.write_html(self.report_dir.join("html"))?;
+        if let Ok(meta) = state.named_metadata_mut::<CoverageReportMetadata>(&self.name) {
+            meta.dirty = false;
+        }
+        Ok(())
+    }
+}
+
+impl<E, EM, Z> UsesState for CoverageReportStage<E, EM, Z>
+where
+    Z: UsesState,
+{
+    type State = Z::State;
+}
+
+impl<E, EM, Z> Named for CoverageReportStage<E, EM, Z> {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<E, EM, Z> Stage<E, EM, Z> for CoverageReportStage<E, EM, Z>
+where
+    E: UsesState<State = Z::State>,
+    EM: UsesState<State = Z::State>,
+    Z: ExecutesInput<E, EM>,
+    Z::State: HasCorpus + HasNamedMetadata + HasCurrentCorpusId,
+    <Z::State as HasCorpus>::Corpus: Corpus<Input = <Z::State as UsesInput>::Input>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut Self::State,
+        manager: &mut EM,
+    ) -> Result<(), Error> {
+        loop {
+            let last_replayed = state
+                .named_metadata_or_insert_with(&self.name, CoverageReportMetadata::default)
+                .last_replayed;
+            let next = match last_replayed {
+                Some(id) if state.corpus().get(id).is_ok() => state.corpus().next(id),
+                // The entry was removed in the meantime, ids only grow so continue after it
+                Some(id) => state.corpus().ids().find(|next| *next > id),
+                None => state.corpus().first(),
+            };
+            let Some(id) = next else {
+                break;
+            };
+            // Mark the entry as replayed first, we won't replay it again if it takes us down
+            state
+                .named_metadata_mut::<CoverageReportMetadata>(&self.name)?
+                .last_replayed = Some(id);
+            let input = state.corpus().cloned_input_for_id(id)?;
+            fuzzer.execute_input(state, executor, manager, &input)?;
+            let meta = state.named_metadata_mut::<CoverageReportMetadata>(&self.name)?;
+            meta.record_hits();
+            meta.dirty = true;
+        }
+
+        let now = current_time();
+        let due = self
+            .last_report
+            .is_none_or(|last| now.saturating_sub(last) >= self.interval);
+        let dirty = state
+            .named_metadata::<CoverageReportMetadata>(&self.name)?
+            .dirty;
+        if dirty && due {
+            self.write_report(state)?;
+            self.last_report = Some(now);
+        }
+        Ok(())
+    }
+
+    fn should_restart(&mut self, state: &mut Self::State) -> Result<bool, Error> {
+        RetryCountRestartHelper::should_restart(state, &self.name, 3)
+    }
+
+    fn clear_progress(&mut self, state: &mut Self::State) -> Result<(), Error> {
+        RetryCountRestartHelper::clear_progress(state, &self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{boxed::Box, string::ToString};
+    use core::{marker::PhantomData, slice, time::Duration};
+    use std::{env, fs, sync::Once};
+
+    use libafl::{
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::{Executor, ExitKind, HasObservers},
+        feedbacks::ConstFeedback,
+        fuzzer::StdFuzzer,
+        inputs::{BytesInput, HasMutatorBytes},
+        schedulers::QueueScheduler,
+        stages::Stage,
+        state::{HasCorpus, StdState, UsesState},
+    };
+    use libafl_bolts::{rands::StdRand, serdeany::RegistryBuilder, tuples::RefIndexable};
+
+    use super::{
+        escape_html, CoverageReport, CoverageReportMetadata, CoverageReportStage, EdgeLocation,
+        EdgeLocations, FileCoverage,
+    };
+    use crate::{edges_map_mut_ptr, edges_max_num};
+
+    extern "C" {
+        fn __sanitizer_cov_pcs_init(pcs_beg: *const usize, pcs_end: *const usize);
+    }
+
+    #[inline(never)]
+    fn first_edge() -> u32 {
+        1
+    }
+
+    #[inline(never)]
+    fn second_edge() -> u32 {
+        2
+    }
+
+    /// Registers a PC table with the entries of `first_edge` and `second_edge`, like an instrumented module would
+    fn register_pc_table() {
+        static REGISTER: Once = Once::new();
+        REGISTER.call_once(|| {
+            let table: &'static [usize] = Box::leak(Box::new([
+                first_edge as fn() -> u32 as usize,
+                1,
+                second_edge as fn() -> u32 as usize,
+                1,
+            ]));
+            let range = table.as_ptr_range();
+            unsafe { __sanitizer_cov_pcs_init(range.start, range.end) };
+        });
+    }
+
+    /// The coverage of this file, and the lines of `first_edge` and `second_edge`
+    fn own_coverage(report: &CoverageReport) -> (&FileCoverage, u32, u32) {
+        let (_, file) = report
+            .files()
+            .iter()
+            .find(|(path, _)| path.ends_with("coverage_report.rs"))
+            .unwrap();
+        let line_of = |name: &str| {
+            file.functions
+                .iter()
+                .find(|(function, _)| function.ends_with(name))
+                .unwrap()
+                .1
+                .line
+        };
+        (file, line_of("first_edge"), line_of("second_edge"))
+    }
+
+    #[test]
+    fn test_from_pc_tables() {
+        register_pc_table();
+        let locations = EdgeLocations::from_pc_tables();
+        assert_eq!(locations.len(), 2);
+        let first = locations.get(0).unwrap();
+        assert!(first.file.ends_with("coverage_report.rs"));
+        assert!(first.function_entry);
+        assert!(first
+            .function
+            .as_ref()
+            .unwrap()
+            .ends_with("tests::first_edge"));
+        let second = locations.get(1).unwrap();
+        assert!(second.line > first.line);
+    }
+
+    /// Covers the first edge on every run, and the second edge for inputs containing a `b`.
+    /// Inputs containing a `c` fail, like a crash taking the fuzzer down.
+    struct EdgesExecutor<S> {
+        observers: (),
+        phantom: PhantomData<S>,
+    }
+
+    impl<S> UsesState for EdgesExecutor<S>
+    where
+        S: libafl::state::State,
+    {
+        type State = S;
+    }
+
+    impl<S> HasObservers for EdgesExecutor<S> {
+        type Observers = ();
+
+        fn observers(&self) -> RefIndexable<&Self::Observers, Self::Observers> {
+            RefIndexable::from(&self.observers)
+        }
+
+        fn observers_mut(&mut self) -> RefIndexable<&mut Self::Observers, Self::Observers> {
+            RefIndexable::from(&mut self.observers)
+        }
+    }
+
+    impl<EM, S, Z> Executor<EM, Z> for EdgesExecutor<S>
+    where
+        EM: UsesState<State = S>,
+        S: libafl::state::State<Input = BytesInput>,
+        Z: UsesState<State = S>,
+    {
+        fn run_target(
+            &mut self,
+            _fuzzer: &mut Z,
+            _state: &mut S,
+            _mgr: &mut EM,
+            input: &BytesInput,
+        ) -> Result<ExitKind, libafl::Error> {
+            if input.bytes().contains(&b'c') {
+                return Err(libafl::Error::illegal_state("crashed"));
+            }
+            let map = unsafe { slice::from_raw_parts_mut(edges_map_mut_ptr(), edges_max_num()) };
+            map.fill(0);
+            map[0] = 1;
+            if input.bytes().contains(&b'b') {
+                map[1] = 1;
+            }
+            Ok(ExitKind::Ok)
+        }
+    }
+
+    #[test]
+    fn test_coverage_report_stage() {
+        register_pc_table();
+        // # Safety
+        // No concurrency per testcase
+        unsafe {
+            RegistryBuilder::register::<CoverageReportMetadata>();
+        }
+        let report_dir =
+            env::temp_dir().join(format!("libafl_coverage_report_{}", std::process::id()));
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        for input in [b"a", b"b"] {
+            corpus
+                .add(Testcase::new(BytesInput::new(input.to_vec())))
+                .unwrap();
+        }
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), feedback, objective);
+        let mut executor = EdgesExecutor {
+            observers: (),
+            phantom: PhantomData,
+        };
+        let mut mgr = NopEventManager::new();
+        let mut stage = CoverageReportStage::with_interval(&report_dir, Duration::ZERO);
+
+        stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)
+            .unwrap();
+        let report = stage.report(&state);
+        let (file, first, second) = own_coverage(&report);
+        assert_eq!(file.lines[&first], 2);
+        assert_eq!(file.lines[&second], 1);
+        assert!(report_dir.join("coverage.info").exists());
+        assert!(report_dir.join("html").join("index.html").exists());
+
+        // Entries are only replayed once, even if the last replayed one is removed
+        let last = state.corpus().last().unwrap();
+        state.corpus_mut().remove(last).unwrap();
+        state
+            .corpus_mut()
+            .add(Testcase::new(BytesInput::new(b"bb".to_vec())))
+            .unwrap();
+        stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)
+            .unwrap();
+        let report = stage.report(&state);
+        let (file, first, second) = own_coverage(&report);
+        assert_eq!(file.lines[&first], 3);
+        assert_eq!(file.lines[&second], 2);
+        let lcov = fs::read_to_string(report_dir.join("coverage.info")).unwrap();
+        assert_eq!(lcov, report.to_lcov());
+
+        // An entry taking the fuzzer down is not replayed again by the stage after the restart,
+        // and the hits collected before are kept in the state
+        for input in [b"c", b"b"] {
+            state
+                .corpus_mut()
+                .add(Testcase::new(BytesInput::new(input.to_vec())))
+                .unwrap();
+        }
+        assert!(stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)
+            .is_err());
+        let mut stage = CoverageReportStage::with_interval(&report_dir, Duration::ZERO);
+        stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)
+            .unwrap();
+        let report = stage.report(&state);
+        let (file, first, second) = own_coverage(&report);
+        assert_eq!(file.lines[&first], 4);
+        assert_eq!(file.lines[&second], 3);
+
+        fs::remove_dir_all(report_dir).unwrap();
+    }
+
+    fn location(line: u32, function_entry: bool) -> EdgeLocation {
+        EdgeLocation {
+            file: "/src/target.c".to_string(),
+            line,
+            function: Some("parse".to_string()),
+            function_entry,
+        }
+    }
+
+    #[test]
+    fn test_lcov_report() {
+        let locations = EdgeLocations::new(vec![
+            Some(location(3, true)),
+            Some(location(4, false)),
+            None,
+            Some(location(7, false)),
+        ]);
+        let report = CoverageReport::new(&locations, &[2, 1, 5]);
+        assert_eq!(
+            report.to_lcov(),
+            "TN:\nSF:/src/target.c\nFN:3,parse\nFNDA:2,parse\nFNF:1\nFNH:1\n\
+             DA:3,2\nDA:4,1\nDA:7,0\nLF:3\nLH:2\nend_of_record\n"
+        );
+        assert_eq!(
+            escape_html("if (a < b && c > \"d\")"),
+            "if (a &lt; b &amp;&amp; c &gt; &quot;d&quot;)"
+        );
+    }
+}
@@ -0,0 +1,163 @@
+// An LD_PRELOAD shim pinning sources of nondeterminism of the target,
+// controlled by LibAFL's `DeterminismControl` (see `libafl::stages::determinism`).
+//
+// The fuzzer writes a bit mask of the sources to pin, in decimal, to the file named by
+// `LIBAFL_DETERMINISM_CONTROL`. The file is read again in every new process, so that the
+// children of a forkserver pick up the mask the fuzzer wrote before their run.
+
+#ifndef _GNU_SOURCE
+  #define _GNU_SOURCE
+#endif
+
+#include <dlfcn.h>
+#include <fcntl.h>
+#include <sched.h>
+#include <stdint.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/random.h>
+#include <sys/time.h>
+#include <sys/types.h>
+#include <time.h>
+#include <unistd.h>
+
+// Keep in sync with `DETERMINISM_CONTROL_ENV` and `NondeterminismSource` in LibAFL
+#define DETERMINISM_CONTROL_ENV "LIBAFL_DETERMINISM_CONTROL"
+#define PIN_TIME 1
+#define PIN_RANDOM 2
+#define PIN_SCHEDULING 4
+
+// The pinned clocks start at 2020-09-13T12:26:40Z and advance by 1us per call
+#define PINNED_EPOCH_SECS 1600000000ULL
+#define PINNED_TICK_NSECS 1000ULL
+#define PINNED_SEED 0x2545f4914f6cdd1dULL
+
+static pid_t    control_pid;
+static unsigned control_mask;
+static uint64_t clock_ticks;
+static uint64_t random_state;
+
+/* Reads the mask of pinned sources, once per process */
+static unsigned pinned(void) {
+  pid_t pid = getpid();
+  if (__atomic_load_n(&control_pid, __ATOMIC_ACQUIRE) == pid) {
+    return control_mask;
+  }
+
+  unsigned    mask = 0;
+  const char *path = getenv(DETERMINISM_CONTROL_ENV);
+  if (path) {
+    // plain syscalls, stdio may allocate or take locks we are called from
+    char buf[16] = {0};
+    int  fd = open(path, O_RDONLY | O_CLOEXEC);
+    if (fd >= 0) {
+      ssize_t len = read(fd, buf, sizeof(buf) - 1);
+      close(fd);
+      if (len > 0) { mask = (unsigned)strtoul(buf, NULL, 10); }
+    }
+  }
+
+  control_mask = mask;
+  __atomic_store_n(&clock_ticks, 0, __ATOMIC_RELAXED);
+  __atomic_store_n(&random_state, PINNED_SEED, __ATOMIC_RELAXED);
+  __atomic_store_n(&control_pid, pid, __ATOMIC_RELEASE);
+  return mask;
+}
+
+/* The pinned clock, in nanoseconds since the epoch */
+static uint64_t pinned_now(void) {
+  uint64_t ticks = __atomic_fetch_add(&clock_ticks, 1, __ATOMIC_RELAXED);
+  return PINNED_EPOCH_SECS * 1000000000ULL + ticks * PINNED_TICK_NSECS;
+}
+
+/* xorshift64, deterministic for a given sequence of calls */
+static uint64_t pinned_random(void) {
+  uint64_t x = __atomic_load_n(&random_state, __ATOMIC_RELAXED);
+  x ^= x << 13;
+  x ^= x >> 7;
+  x ^= x << 17;
+  __atomic_store_n(&random_state, x, __ATOMIC_RELAXED);
+  return x;
+}
+
+static void pinned_fill(void *buf, size_t len) {
+  uint8_t *bytes = buf;
+  while (len) {
+    uint64_t r = pinned_random();
+    size_t   n = len < sizeof(r) ? len : sizeof(r);
+    memcpy(bytes, &r, n);
+    bytes += n;
+    len -= n;
+  }
+}
+
+/* The next definition of `name`, usually the one of the libc */
+#define REAL(name)                                                \
+  ({                                                              \
+    static __typeof__(&name) real_##name;                         \
+    if (!real_##name) {                                           \
+      real_##name = (__typeof__(&name))dlsym(RTLD_NEXT, #name);   \
+    }                                                             \
+    real_##name;                                                  \
+  })
+
+time_t time(time_t *tloc) {
+  if (!(pinned() & PIN_TIME)) { return REAL(time)(tloc); }
+  time_t now = (time_t)(pinned_now() / 1000000000ULL);
+  if (tloc) { *tloc = now; }
+  return now;
+}
+
+int gettimeofday(struct timeval *tv, void *tz) {
+  if (!(pinned() & PIN_TIME)) { return REAL(gettimeofday)(tv, tz); }
+  uint64_t now = pinned_now();
+  tv->tv_sec = (time_t)(now / 1000000000ULL);
+  tv->tv_usec = (suseconds_t)((now % 1000000000ULL) / 1000);
+  return 0;
+}
+
+int clock_gettime(clockid_t clockid, struct timespec *tp) {
+  if (!(pinned() & PIN_TIME)) { return REAL(clock_gettime)(clockid, tp); }
+  // all clocks follow the same pinned timeline
+  uint64_t now = pinned_now();
+  tp->tv_sec = (time_t)(now / 1000000000ULL);
+  tp->tv_nsec = (long)(now % 1000000000ULL);
+  return 0;
+}
+
+ssize_t getrandom(void *buf, size_t buflen, unsigned int flags) {
+  if (!(pinned() & PIN_RANDOM)) { return REAL(getrandom)(buf, buflen, flags); }
+  pinned_fill(buf, buflen);
+  return (ssize_t)buflen;
+}
+
+int getentropy(void *buf, size_t buflen) {
+  if (!(pinned() & PIN_RANDOM)) { return REAL(getentropy)(buf, buflen); }
+  pinned_fill(buf, buflen);
+  return 0;
+}
+
+int rand(void) {
+  if (!(pinned() & PIN_RANDOM)) { return REAL(rand)(); }
+  return (int)(pinned_random() >> 33);
+}
+
+long random(void) {
+  if (!(pinned() & PIN_RANDOM)) { return REAL(random)(); }
+  return (long)(pinned_random() >> 33);
+}
+
+void srand(unsigned int seed) {
+  // a pinned generator ignores the seed, it is often derived from the time or the pid
+  if (!(pinned() & PIN_RANDOM)) { REAL(srand)(seed); }
+}
+
+void srandom(unsigned int seed) {
+  if (!(pinned() & PIN_RANDOM)) { REAL(srandom)(seed); }
+}
+
+int sched_yield(void) {
+  // a pinned yield keeps running the current thread instead of letting the kernel pick another one
+  if (!(pinned() & PIN_SCHEDULING)) { return REAL(sched_yield)(); }
+  return 0;
+}
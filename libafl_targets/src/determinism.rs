@@ -0,0 +1,13 @@
+//! An `LD_PRELOAD` shim pinning the sources of nondeterminism of a target, for `LibAFL`'s
+//! `DeterminismStage`.
+//!
+//! Preload [`DETERMINISM_SHIM_PATH`] in the target and pass the `DeterminismControl` of the stage
+//! in the environment, then the stage decides for each run if `time`, `clock_gettime`,
+//! `gettimeofday`, `getrandom`, `getentropy`, `rand`, `random` and `sched_yield` behave
+//! normally or return pinned values.
+//!
+//! The shim only affects dynamically linked calls, statically linked targets and raw syscalls
+//! are out of its reach.
+
+/// The path of the shim, built with the crate. Set it as `LD_PRELOAD` of the target.
+pub const DETERMINISM_SHIM_PATH: &str = env!("LIBAFL_DETERMINISM_SHIM");
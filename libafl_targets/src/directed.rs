@@ -0,0 +1,24 @@
+//! The distance counters of directed fuzzing, filled by targets instrumented with `LLVMPasses::Distance`
+//! from `libafl_cc`.
+//!
+//! The counters are a static of the instrumented program, they are not shared with the fuzzer like the coverage map.
+//! Only executors running the target in the fuzzer process, like the `InProcessExecutor`, see them:
+//! with the `InProcessForkExecutor`, the `ForkserverExecutor` or the `CommandExecutor`,
+//! the target fills its own copy and the [`DistanceObserver`] always reads zeros.
+
+use libafl::observers::DistanceObserver;
+
+/// The sum of the distances to the targets of the executed basic blocks, and the number of executed basic blocks.
+#[no_mangle]
+#[allow(non_upper_case_globals)]
+pub static mut __libafl_directed_distance: [u64; 2] = [0; 2];
+pub use __libafl_directed_distance as DIRECTED_DISTANCE;
+
+/// Gets a new [`DistanceObserver`] over the [`DIRECTED_DISTANCE`] counters, for in-process executors.
+///
+/// # Safety
+/// The observer points to the mutable static counters, they may only be written by the target.
+#[must_use]
+pub unsafe fn directed_distance_observer(name: &'static str) -> DistanceObserver<'static> {
+    DistanceObserver::from_mut_ptr(name, (&raw mut DIRECTED_DISTANCE).cast::<u64>())
+}
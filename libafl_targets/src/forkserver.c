@@ -44,6 +44,13 @@
 #define FS_NEW_OPT_MAPSIZE 0x1
 #define FS_NEW_OPT_SHDMEM_FUZZ 0x2
 #define FS_NEW_OPT_AUTODICT 0x800
+// LibAFL extension: several children in flight, see `batch_forkserver_loop`
+#define FS_NEW_OPT_BATCH 0x10000
+
+// Batched mode, set by LibAFL's `ForkserverExecutor`
+#define BATCH_SIZE_ENV_VAR "__LIBAFL_FS_BATCH_SIZE"
+#define BATCH_MAP_ENV_VAR "__LIBAFL_FS_BATCH_MAP_ID"
+#define BATCH_INPUT_ENV_VAR "__LIBAFL_FS_BATCH_INPUT_ID"
 
 /* Reporting options */
 #define FS_OPT_ENABLED 0x80000001
@@ -81,6 +88,13 @@ static void (*old_sigterm_handler)(int) = 0;
 
 static uint8_t is_persistent;
 
+static uint32_t batch_size;
+static int     *batch_pids;
+static uint8_t *batch_area;
+static size_t   batch_area_stride;
+static uint8_t *batch_input;
+static size_t   batch_input_stride;
+
 void __afl_set_persistent_mode(uint8_t mode) {
   is_persistent = mode;
 }
@@ -104,6 +118,10 @@ static void at_exit(int signal) {
     child_pid = -1;
   }
 
+  for (uint32_t i = 0; batch_pids && i < batch_size; i++) {
+    if (batch_pids[i] > 0) { kill(batch_pids[i], SIGKILL); }
+  }
+
   _exit(0);
 }
 
@@ -214,6 +232,130 @@ static void map_input_shared_memory() {
   }
 }
 
+/* Maps the shared memory in `env_var`, sized by `<env_var>_SIZE`. */
+
+static uint8_t *map_shm_from_env(const char *env_var, size_t *size) {
+  char  size_var[64];
+  char *id_str = getenv(env_var);
+  char *size_str;
+
+  snprintf(size_var, sizeof(size_var), "%s_SIZE", env_var);
+  size_str = getenv(size_var);
+  if (!id_str || !size_str) { return NULL; }
+  *size = strtoul(size_str, NULL, 10);
+
+  uint8_t *map = NULL;
+#ifdef USEMMAP
+  int shm_fd = shm_open(id_str, O_RDWR, DEFAULT_PERMISSION);
+  if (shm_fd == -1) { return NULL; }
+  map = mmap(0, *size, PROT_READ | PROT_WRITE, MAP_SHARED, shm_fd, 0);
+  close(shm_fd);
+  if (map == MAP_FAILED) { return NULL; }
+#else
+  map = (uint8_t *)shmat(atoi(id_str), NULL, 0);
+  if (map == (void *)-1) { return NULL; }
+#endif
+  return map;
+}
+
+/* Batched mode setup, returns 0 if the fuzzer did not ask for it. */
+
+static int map_batch_shared_memory(void) {
+  char  *size_str = getenv(BATCH_SIZE_ENV_VAR);
+  size_t area_size, input_size;
+
+  if (!size_str) { return 0; }
+  batch_size = strtoul(size_str, NULL, 10);
+  if (!batch_size) { return 0; }
+
+  batch_area = map_shm_from_env(BATCH_MAP_ENV_VAR, &area_size);
+  if (!batch_area) {
+    perror("Could not access batch coverage shared memory");
+    send_forkserver_error(FS_ERROR_SHM_OPEN);
+    exit(1);
+  }
+  batch_area_stride = area_size / batch_size;
+
+  if (__afl_sharedmem_fuzzing) {
+    batch_input = map_shm_from_env(BATCH_INPUT_ENV_VAR, &input_size);
+    if (!batch_input) {
+      perror("Could not access batch input shared memory");
+      send_forkserver_error(FS_ERROR_SHM_OPEN);
+      exit(1);
+    }
+    batch_input_stride = input_size / batch_size;
+  }
+
+  batch_pids = calloc(batch_size, sizeof(int));
+  if (!batch_pids) {
+    write_error("calloc");
+    exit(1);
+  }
+  return 1;
+}
+
+/* Batched fork server loop: the fuzzer sends the number of inputs it
+   prepared, we fork one child per input, each with its own coverage map and
+   input slot, and report the wait statuses in the order of the slots.
+   Returns in the children only. */
+
+static void batch_forkserver_loop(void (*old_sigchld_handler)(int)) {
+  while (1) {
+    uint32_t count;
+    int      status;
+
+    if (read(FORKSRV_FD, &count, 4) != 4) { _exit(1); }
+
+    if (!count || count > batch_size) {
+      write_error("invalid batch size from fuzzer");
+      _exit(1);
+    }
+
+    for (uint32_t i = 0; i < count; i++) {
+      batch_pids[i] = fork();
+      if (batch_pids[i] < 0) {
+        write_error("fork");
+        _exit(1);
+      }
+
+      if (!batch_pids[i]) {
+        signal(SIGCHLD, old_sigchld_handler);
+        signal(SIGTERM, old_sigterm_handler);
+
+        close(FORKSRV_FD);
+        close(FORKSRV_FD + 1);
+
+        __afl_area_ptr = batch_area + i * batch_area_stride;
+        if (batch_input) {
+          __afl_fuzz_len = (uint32_t *)(batch_input + i * batch_input_stride);
+          __afl_fuzz_ptr = batch_input + i * batch_input_stride + sizeof(uint32_t);
+        }
+        free(batch_pids);
+        batch_pids = NULL;
+        return;
+      }
+    }
+
+    if (write(FORKSRV_FD + 1, batch_pids, count * 4) != (ssize_t)(count * 4)) {
+      write_error("write to afl-fuzz");
+      _exit(1);
+    }
+
+    for (uint32_t i = 0; i < count; i++) {
+      if (waitpid(batch_pids[i], &status, 0) < 0) {
+        write_error("waitpid");
+        _exit(1);
+      }
+      batch_pids[i] = 0;
+
+      if (write(FORKSRV_FD + 1, &status, 4) != 4) {
+        write_error("writing to afl-fuzz");
+        _exit(1);
+      }
+    }
+  }
+}
+
 /* Fork server logic. */
 
 void __afl_start_forkserver(void) {
@@ -240,6 +382,7 @@ void __afl_start_forkserver(void) {
   void (*old_sigchld_handler)(int) = signal(SIGCHLD, SIG_DFL);
 
   int autotokens_on = __token_start != NULL && __token_stop != NULL;
+  int batch_on = 0;
 
   /* Phone home and tell the parent that we're OK. If parent isn't there,
      assume we're not running in forkserver mode and just execute program. */
@@ -254,9 +397,12 @@ void __afl_start_forkserver(void) {
     _exit(1);
   }
 
+  batch_on = map_batch_shared_memory();
+
   status = FS_NEW_OPT_MAPSIZE;
   if (__afl_sharedmem_fuzzing) { status |= FS_NEW_OPT_SHDMEM_FUZZ; }
   if (autotokens_on) { status |= FS_NEW_OPT_AUTODICT; }
+  if (batch_on) { status |= FS_NEW_OPT_BATCH; }
 
   if (write(FORKSRV_FD + 1, msg, 4) != 4) { _exit(1); }
 
@@ -295,7 +441,12 @@ void __afl_start_forkserver(void) {
   status = version;
   if (write(FORKSRV_FD + 1, msg, 4) != 4) { _exit(1); }
 
-  if (__afl_sharedmem_fuzzing) { map_input_shared_memory(); }
+  if (__afl_sharedmem_fuzzing && !batch_on) { map_input_shared_memory(); }
+
+  if (batch_on) {
+    batch_forkserver_loop(old_sigchld_handler);
+    return;
+  }
 
   while (1) {
     int status;
@@ -122,6 +122,13 @@ pub use cmps::*;
 #[cfg(feature = "std")]
 pub mod drcov;
 
+#[cfg(all(
+    feature = "coverage_report",
+    any(target_os = "linux", target_os = "android"),
+    any(feature = "sancov_pcguard_edges", feature = "sancov_pcguard_hitcounts")
+))]
+pub mod coverage_report;
+
 #[cfg(all(windows, feature = "std", feature = "windows_asan"))]
 pub mod windows_asan;
 #[cfg(all(windows, feature = "std", feature = "windows_asan"))]
@@ -131,3 +138,18 @@ pub use windows_asan::*;
 pub mod forkserver;
 #[cfg(all(unix, feature = "forkserver"))]
 pub use forkserver::*;
+
+#[cfg(all(unix, feature = "shmem_input"))]
+pub mod shmem_input;
+#[cfg(all(unix, feature = "shmem_input"))]
+pub use shmem_input::*;
+
+#[cfg(feature = "directed")]
+pub mod directed;
+#[cfg(feature = "directed")]
+pub use directed::*;
+
+#[cfg(all(target_os = "linux", feature = "determinism"))]
+pub mod determinism;
+#[cfg(all(target_os = "linux", feature = "determinism"))]
+pub use determinism::*;
@@ -324,7 +324,11 @@ unsafe extern "C" fn __sanitizer_cov_pcs_init(pcs_beg: *const usize, pcs_end: *c
 
     let pc_tables_ptr = &raw mut PC_TABLES;
     let pc_tables = &mut *pc_tables_ptr;
-    pc_tables.push(slice::from_raw_parts(pcs_beg as *const PcTableEntry, len));
+    // Each entry is made of two `usize`s, the pc and the flags
+    pc_tables.push(slice::from_raw_parts(
+        pcs_beg as *const PcTableEntry,
+        len / 2,
+    ));
 }
 
 /// An entry to the `sanitizer_cov` `pc_table`
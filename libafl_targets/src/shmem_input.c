@@ -0,0 +1,80 @@
+#include "common.h"
+
+#include "android-ashmem.h"
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <unistd.h>
+#ifndef USEMMAP
+  #include <sys/shm.h>
+#else
+  #include <sys/mman.h>
+  #include <sys/stat.h>
+  #include <fcntl.h>
+#endif
+
+// Keep in sync with `SHM_INPUT_ENV_VAR` in LibAFL's `CommandExecutor`
+#define SHM_INPUT_ENV_VAR "__LIBAFL_SHM_INPUT_ID"
+#define SHM_INPUT_SIZE_ENV_VAR "__LIBAFL_SHM_INPUT_ID_SIZE"
+#define SHM_INPUT_HDR_SIZE 4
+#define DEFAULT_PERMISSION 0600
+
+static uint8_t *libafl_shmem_input_ptr;
+static size_t   libafl_shmem_input_size;
+
+/* Map the shared memory the fuzzer delivers inputs in.
+   Returns 0 on success, -1 if no shared memory was passed or mapping it failed. */
+int libafl_shmem_input_map(void) {
+  if (libafl_shmem_input_ptr) { return 0; }
+
+  char *id_str = getenv(SHM_INPUT_ENV_VAR);
+  char *size_str = getenv(SHM_INPUT_SIZE_ENV_VAR);
+
+  if (!id_str || !size_str) { return -1; }
+
+  size_t   size = strtoull(size_str, NULL, 10);
+  uint8_t *map = NULL;
+
+  if (size < SHM_INPUT_HDR_SIZE) { return -1; }
+
+#ifdef USEMMAP
+  int shm_fd = shm_open(id_str, O_RDONLY, DEFAULT_PERMISSION);
+  if (shm_fd == -1) {
+    perror("Could not open the input shared memory");
+    return -1;
+  }
+
+  map = (uint8_t *)mmap(0, size, PROT_READ, MAP_SHARED, shm_fd, 0);
+  close(shm_fd);
+  if (map == MAP_FAILED) { map = NULL; }
+#else
+  map = (uint8_t *)shmat(atoi(id_str), NULL, SHM_RDONLY);
+  if (map == (void *)-1) { map = NULL; }
+#endif
+
+  if (!map) {
+    perror("Could not map the input shared memory");
+    return -1;
+  }
+
+  libafl_shmem_input_ptr = map;
+  libafl_shmem_input_size = size;
+  return 0;
+}
+
+/* The current input, or NULL if the shared memory is not mapped.
+   Its length is written to `len`. */
+const uint8_t *libafl_shmem_input(size_t *len) {
+  if (libafl_shmem_input_map() != 0) {
+    *len = 0;
+    return NULL;
+  }
+
+  uint32_t input_len = *(volatile uint32_t *)libafl_shmem_input_ptr;
+  if (input_len > libafl_shmem_input_size - SHM_INPUT_HDR_SIZE) {
+    input_len = libafl_shmem_input_size - SHM_INPUT_HDR_SIZE;
+  }
+
+  *len = input_len;
+  return libafl_shmem_input_ptr + SHM_INPUT_HDR_SIZE;
+}
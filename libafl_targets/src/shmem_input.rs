@@ -0,0 +1,45 @@
+//! Reading inputs delivered via shared memory, by the `CommandExecutor` of `LibAFL`
+//! with `InputLocation::SharedMemory`.
+
+extern "C" {
+    /// Map the shared memory the fuzzer delivers inputs in.
+    fn libafl_shmem_input_map() -> i32;
+    /// The current input, or null if the shared memory is not mapped.
+    fn libafl_shmem_input(len: *mut usize) -> *const u8;
+}
+
+/// Map the shared memory the fuzzer delivers inputs in.
+/// Calling this is optional, [`shmem_input`] maps it on first use.
+///
+/// Returns `false` if the target was not started with an input shared memory,
+/// or if mapping it failed.
+///
+/// # Note
+///
+/// The function's logic is written in C and this code is a wrapper.
+#[must_use]
+pub fn map_shmem_input() -> bool {
+    unsafe { libafl_shmem_input_map() == 0 }
+}
+
+/// The input delivered by the fuzzer via shared memory,
+/// or `None` if the target was not started with an input shared memory.
+///
+/// The shared memory is mapped on first use.
+///
+/// # Note
+///
+/// The function's logic is written in C and this code is a wrapper.
+#[must_use]
+pub fn shmem_input() -> Option<&'static [u8]> {
+    let mut len = 0;
+    let ptr = unsafe { libafl_shmem_input(&raw mut len) };
+    if ptr.is_null() {
+        None
+    } else {
+        // # Safety
+        // The C code made sure the input is within the mapped shared memory,
+        // which stays mapped for the lifetime of the process.
+        Some(unsafe { core::slice::from_raw_parts(ptr, len) })
+    }
+}
@@ -0,0 +1,98 @@
+#!/usr/bin/python3
+
+import os
+import networkx as nx
+import sys
+
+from build import build_graphs, load_cfgs
+
+
+def parse_targets(path):
+    lines = set()
+    functions = set()
+    for line in open(path):
+        line = line.split("#")[0].strip()
+        if not line:
+            continue
+        file, sep, lineno = line.rpartition(":")
+        if sep and lineno.isdigit():
+            lines.add((file, int(lineno)))
+        else:
+            functions.add(line)
+    return lines, functions
+
+
+def matches(location, lines):
+    file, _, lineno = location.rpartition(":")
+    for target_file, target_line in lines:
+        # targets may leave out the leading directories
+        if int(lineno) == target_line and (
+            file == target_file or file.endswith("/" + target_file)
+        ):
+            return True
+    return False
+
+
+if len(sys.argv) < 2:
+    sys.exit("Usage: CFG_OUTPUT_PATH=<directory> python3 distance.py <targets> [output]")
+
+if "CFG_OUTPUT_PATH" not in os.environ:
+    sys.exit("CFG_OUTPUT_PATH not set")
+
+cfg = load_cfgs(os.environ["CFG_OUTPUT_PATH"])
+target_lines, target_functions = parse_targets(sys.argv[1])
+output = sys.argv[2] if len(sys.argv) > 2 else "distances.txt"
+
+G, _ = build_graphs(cfg)
+nodes = {
+    (data["module"], data["function"], data["block"]): node
+    for node, data in G.nodes(data=True)
+}
+
+# build_graphs only links calls within a module, link the calls to the other modules too
+entries = dict()
+for mname, module in cfg.items():
+    for fname, entry in module["entries"].items():
+        if (mname, fname, entry) in nodes:
+            entries.setdefault(fname, nodes[(mname, fname, entry)])
+for mname, module in cfg.items():
+    for fname, calls in module.get("calls", {}).items():
+        for idx, target_fns in calls.items():
+            for target_fn in target_fns:
+                if target_fn in entries:
+                    G.add_edge(nodes[(mname, fname, int(idx))], entries[target_fn])
+
+targets = set()
+for mname, module in cfg.items():
+    for fname, blocks in module.get("lines", {}).items():
+        for idx, locations in blocks.items():
+            if any(matches(location, target_lines) for location in locations):
+                targets.add(nodes[(mname, fname, int(idx))])
+for fname in target_functions:
+    if fname in entries:
+        targets.add(entries[fname])
+
+if not targets:
+    sys.exit("No basic block matches the targets")
+
+# The distance of a block is the harmonic mean of its shortest distances to the targets it reaches
+inverse_sums = dict()
+reached = dict()
+R = G.reverse(copy=False)
+for target in targets:
+    for node, depth in nx.single_source_shortest_path_length(R, target).items():
+        if depth:
+            inverse_sums[node] = inverse_sums.get(node, 0.0) + 1.0 / depth
+            reached[node] = reached.get(node, 0) + 1
+
+distances = {node: reached[node] / inverse_sums[node] for node in reached}
+for target in targets:
+    distances[target] = 0.0
+
+with open(output, "w") as f:
+    for node, distance in sorted(distances.items()):
+        data = G.nodes[node]
+        module = data["module"][: -len(".cfg")]
+        f.write(f"{module}\t{data['function']}\t{data['block']}\t{distance}\n")
+
+print(f"{len(targets)} target blocks, {len(distances)} blocks reaching them, written to {output}")
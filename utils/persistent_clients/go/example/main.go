@@ -0,0 +1,27 @@
+// An example target for LibAFL's PersistentExecutor: panics on inputs starting with "bug".
+package main
+
+import (
+	"bytes"
+	"log"
+
+	libaflpersistent "github.com/AFLplusplus/LibAFL/utils/persistent_clients/go"
+)
+
+func harness(data []byte) {
+	if len(data) > 0 && data[0] == 'b' {
+		libaflpersistent.Hit(1)
+		if bytes.HasPrefix(data, []byte("bu")) {
+			libaflpersistent.Hit(2)
+			if bytes.HasPrefix(data, []byte("bug")) {
+				panic("found the bug")
+			}
+		}
+	}
+}
+
+func main() {
+	if err := libaflpersistent.Run(harness); err != nil {
+		log.Fatal(err)
+	}
+}
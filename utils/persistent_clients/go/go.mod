@@ -0,0 +1,3 @@
+module github.com/AFLplusplus/LibAFL/utils/persistent_clients/go
+
+go 1.18
@@ -0,0 +1,116 @@
+// Package libaflpersistent is the reference client for LibAFL's PersistentExecutor.
+//
+// It runs a Go harness in persistent mode: the fuzzer sends inputs over a pipe,
+// and Run executes them one after the other in the same process.
+// Go has no runtime hook for coverage, so report it manually with Hit.
+package libaflpersistent
+
+import (
+	"encoding/binary"
+	"errors"
+	"fmt"
+	"io"
+	"os"
+	"runtime/debug"
+	"strconv"
+	"syscall"
+)
+
+const (
+	ctlFdEnvVar   = "__LIBAFL_PERSISTENT_CTL_FD"
+	stFdEnvVar    = "__LIBAFL_PERSISTENT_ST_FD"
+	mapPathEnvVar = "__LIBAFL_PERSISTENT_MAP_PATH"
+	mapSizeEnvVar = "__LIBAFL_PERSISTENT_MAP_SIZE"
+
+	statusOk    uint32 = 0
+	statusCrash uint32 = 1
+)
+
+var hello = []byte("LAF1")
+
+// The coverage map shared with the fuzzer, nil if it did not pass one
+var coverage []byte
+
+// Hit reports a hit of the edge id to the fuzzer.
+func Hit(id uint32) {
+	if len(coverage) > 0 {
+		coverage[id%uint32(len(coverage))]++
+	}
+}
+
+func envFile(name string, envVar string) (*os.File, error) {
+	fd, err := strconv.Atoi(os.Getenv(envVar))
+	if err != nil {
+		return nil, fmt.Errorf("%s is not set, is the target run by LibAFL's PersistentExecutor? %w", envVar, err)
+	}
+	return os.NewFile(uintptr(fd), name), nil
+}
+
+func mapCoverage() error {
+	path := os.Getenv(mapPathEnvVar)
+	if path == "" {
+		return nil
+	}
+	size, err := strconv.Atoi(os.Getenv(mapSizeEnvVar))
+	if err != nil {
+		return fmt.Errorf("invalid %s: %w", mapSizeEnvVar, err)
+	}
+	file, err := os.OpenFile(path, os.O_RDWR, 0)
+	if err != nil {
+		return err
+	}
+	defer file.Close()
+	coverage, err = syscall.Mmap(int(file.Fd()), 0, size, syscall.PROT_READ|syscall.PROT_WRITE, syscall.MAP_SHARED)
+	return err
+}
+
+// Runs the harness, reporting panics as crashes
+func runOne(harness func([]byte), data []byte) (status uint32) {
+	defer func() {
+		if r := recover(); r != nil {
+			fmt.Fprintf(os.Stderr, "panic: %v\n%s", r, debug.Stack())
+			status = statusCrash
+		}
+	}()
+	harness(data)
+	return statusOk
+}
+
+// Run executes harness on each input sent by the fuzzer, until it closes the control pipe.
+// Panics in the harness are reported as crashes.
+func Run(harness func([]byte)) error {
+	ctl, err := envFile("libafl-ctl", ctlFdEnvVar)
+	if err != nil {
+		return err
+	}
+	st, err := envFile("libafl-st", stFdEnvVar)
+	if err != nil {
+		return err
+	}
+	if err := mapCoverage(); err != nil {
+		return err
+	}
+
+	if _, err := st.Write(hello); err != nil {
+		return err
+	}
+	var header [4]byte
+	var reply [4]byte
+	for {
+		if _, err := io.ReadFull(ctl, header[:]); err != nil {
+			if errors.Is(err, io.EOF) {
+				return nil
+			}
+			return err
+		}
+		data := make([]byte, binary.LittleEndian.Uint32(header[:]))
+		if _, err := io.ReadFull(ctl, data); err != nil {
+			return err
+		}
+
+		binary.LittleEndian.PutUint32(reply[:], runOne(harness, data))
+		if _, err := st.Write(reply[:]); err != nil {
+			return err
+		}
+	}
+}
@@ -0,0 +1,18 @@
+"""An example target for LibAFL's `PersistentExecutor`: crashes on inputs containing `"bug"` keys."""
+
+import json
+
+import libafl_persistent
+
+
+def harness(data: bytes):
+    try:
+        value = json.loads(data)
+    except ValueError:
+        return
+    if isinstance(value, dict) and "bug" in value:
+        raise RuntimeError("found the bug")
+
+
+if __name__ == "__main__":
+    libafl_persistent.run(harness)
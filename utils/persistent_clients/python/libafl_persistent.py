@@ -0,0 +1,149 @@
+"""Reference client for LibAFL's `PersistentExecutor`.
+
+Runs a Python harness in persistent mode: the fuzzer sends inputs over a pipe,
+and this client runs them one after the other in the same process.
+Coverage is collected by tracing the executed lines, see `EdgeTracer`,
+or reported manually with `hit`.
+
+    import libafl_persistent
+
+    def harness(data: bytes):
+        json.loads(data)
+
+    libafl_persistent.run(harness)
+"""
+
+import mmap
+import os
+import struct
+import sys
+import traceback
+import zlib
+
+CTL_FD_ENV_VAR = "__LIBAFL_PERSISTENT_CTL_FD"
+ST_FD_ENV_VAR = "__LIBAFL_PERSISTENT_ST_FD"
+MAP_PATH_ENV_VAR = "__LIBAFL_PERSISTENT_MAP_PATH"
+MAP_SIZE_ENV_VAR = "__LIBAFL_PERSISTENT_MAP_SIZE"
+
+HELLO = b"LAF1"
+STATUS_OK = 0
+STATUS_CRASH = 1
+
+
+class CoverageMap:
+    """The coverage map shared with the fuzzer, if it passed one."""
+
+    def __init__(self):
+        path = os.environ.get(MAP_PATH_ENV_VAR)
+        self._map = None
+        self._size = 0
+        if path:
+            self._size = int(os.environ[MAP_SIZE_ENV_VAR])
+            with open(path, "r+b") as f:
+                self._map = mmap.mmap(f.fileno(), self._size)
+
+    @property
+    def enabled(self):
+        return self._map is not None
+
+    def hit(self, idx):
+        """Increments the entry for `idx`, wrapping around like AFL does."""
+        if self._map is not None:
+            idx %= self._size
+            self._map[idx] = (self._map[idx] + 1) & 0xFF
+
+
+_coverage = None
+
+
+def hit(idx):
+    """Reports a hit of the edge `idx` to the fuzzer, for manual instrumentation."""
+    if _coverage is not None:
+        _coverage.hit(idx)
+
+
+class EdgeTracer:
+    """Reports the transitions between executed lines as edges, AFL-style.
+
+    The location ids are derived from the file name and line number,
+    so they stay the same when the fuzzer restarts the target.
+    """
+
+    def __init__(self, coverage):
+        self._coverage = coverage
+        self._ids = {}
+        self._prev = 0
+
+    def _location(self, code, lineno):
+        key = (code, lineno)
+        loc = self._ids.get(key)
+        if loc is None:
+            loc = zlib.crc32(f"{code.co_filename}:{lineno}".encode())
+            self._ids[key] = loc
+        return loc
+
+    def _trace(self, frame, event, _arg):
+        if event == "line":
+            loc = self._location(frame.f_code, frame.f_lineno)
+            self._coverage.hit(self._prev ^ loc)
+            self._prev = loc >> 1
+        return self._trace
+
+    def start(self):
+        self._prev = 0
+        sys.settrace(self._trace)
+
+    def stop(self):
+        sys.settrace(None)
+
+
+def _read_exact(f, length):
+    """Reads exactly `length` bytes, or returns `None` if the fuzzer went away."""
+    buf = bytearray()
+    while len(buf) < length:
+        chunk = f.read(length - len(buf))
+        if not chunk:
+            return None
+        buf += chunk
+    return bytes(buf)
+
+
+def run(harness, trace=True):
+    """Runs `harness` on each input sent by the fuzzer, until it closes the control pipe.
+
+    Exceptions raised by the harness are reported as crashes.
+    With `trace`, the executed lines are reported as coverage, which slows down the execution.
+    """
+    global _coverage
+
+    ctl = os.fdopen(int(os.environ[CTL_FD_ENV_VAR]), "rb", buffering=0)
+    st = os.fdopen(int(os.environ[ST_FD_ENV_VAR]), "wb", buffering=0)
+    _coverage = CoverageMap()
+    tracer = EdgeTracer(_coverage) if trace and _coverage.enabled else None
+
+    st.write(HELLO)
+    while True:
+        header = _read_exact(ctl, 4)
+        if header is None:
+            return
+        (length,) = struct.unpack("<I", header)
+        data = _read_exact(ctl, length)
+        if data is None:
+            return
+
+        error = None
+        if tracer is not None:
+            tracer.start()
+        try:
+            harness(data)
+        except Exception as err:
+            error = err
+        finally:
+            if tracer is not None:
+                tracer.stop()
+
+        status = STATUS_OK
+        if error is not None:
+            status = STATUS_CRASH
+            traceback.print_exception(type(error), error, error.__traceback__)
+        st.write(struct.pack("<I", status))